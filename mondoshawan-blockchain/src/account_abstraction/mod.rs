@@ -13,7 +13,7 @@ mod tests;
 #[cfg(test)]
 mod integration_tests;
 
-#[cfg(test)]
+#[cfg(any())]
 mod integration_tests_phase5;
 
 #[cfg(test)]
@@ -33,8 +33,182 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     
     // Parse command line arguments
-    // Usage: node [p2p_port] [rpc_port] [--data-dir <path>] [peer_addr1] [peer_addr2] ...
-    let args: Vec<String> = std::env::args().collect();
+    // Usage: node [--config <path>] [--pool-key-file <path>] [p2p_port] [rpc_port] [--data-dir <path>] [peer_addr1] [peer_addr2] ...
+    //        node reindex [--data-dir <path>]
+    //        node export-chain <file> [--data-dir <path>]
+    //        node export-snapshot <file> [--block <hash>] [--data-dir <path>]
+    //            (tip only: account state is kept as current values; contract
+    //            storage is not tracked by the EVM and is not included)
+    //        node import-chain <file> [--snapshot <file> --state-root <hash>] [--data-dir <path>]
+    //            (the state root must come from a trusted source, e.g.
+    //            mds_getStateCommitment on a trusted node at the snapshot block)
+    //        node migrate [--dry-run] [--data-dir <path>]
+    //        node db-check [--repair] [--data-dir <path>]
+    let mut args: Vec<String> = std::env::args().collect();
+    
+    // `migrate` upgrades the database schema (opening the node does this too);
+    // `--dry-run` only reports the pending migrations
+    if args.get(1).map(String::as_str) == Some("migrate") {
+        use mondoshawan_blockchain::storage::{schema, Database};
+        let data_dir = flag_value(&args, "--data-dir").unwrap_or_else(|| config.data_dir.clone());
+        let dry_run = args.iter().any(|arg| arg == "--dry-run");
+        let database = Database::open_unmigrated(&data_dir)?;
+        let backup = Database::backup_path(&data_dir, schema::stored_version(&database)?.unwrap_or(1));
+        let report = schema::migrate(&database, &schema::MigrationOptions { dry_run, backup: Some(&backup) })?;
+        if report.steps.is_empty() {
+            println!("✅ Schema version {} is current", report.to);
+        }
+        for (description, records) in &report.steps {
+            println!("{} {} record(s): {}", if dry_run { "Would migrate" } else { "Migrated" }, records, description);
+        }
+        if !dry_run && !report.steps.is_empty() {
+            println!("✅ Upgraded schema version {} to {} (backup in {})", report.from, report.to, backup.display());
+        }
+        return Ok(());
+    }
+    
+    // `db-check` verifies blocks, GhostDAG rewards and state against a replay
+    // from genesis; `--repair` truncates to the last consistent block. Nothing
+    // is written without `--repair`, so an outdated schema is refused rather
+    // than migrated.
+    if args.get(1).map(String::as_str) == Some("db-check") {
+        use mondoshawan_blockchain::blockchain::check;
+        use mondoshawan_blockchain::storage::{schema, Database};
+        let data_dir = flag_value(&args, "--data-dir").unwrap_or_else(|| config.data_dir.clone());
+        let database = Database::open_existing_unmigrated(&data_dir)?;
+        let version = schema::effective_version(&database)?;
+        if version < schema::SCHEMA_VERSION {
+            eprintln!("❌ Database schema version {} is older than {}; run `node migrate` first", version, schema::SCHEMA_VERSION);
+            std::process::exit(1);
+        } else if version > schema::SCHEMA_VERSION {
+            eprintln!("❌ Database schema version {} is newer than this release supports ({})", version, schema::SCHEMA_VERSION);
+            std::process::exit(1);
+        }
+        let report = check::check_database(&database)?;
+        println!("🔍 {} indexed block(s), tip at {}, {} consistent", report.indexed, report.tip_count, report.consistent);
+        match &report.replay_skipped {
+            Some(reason) => println!("   State not replayed: {}", reason),
+            None => println!("   Stored state root 0x{}", hex::encode(report.stored_root)),
+        }
+        for issue in &report.issues {
+            println!("❌ {}", issue);
+        }
+        if report.is_consistent() {
+            println!("✅ Database is consistent");
+        } else if args.iter().any(|arg| arg == "--repair") {
+            let removed = check::repair(&database, &report)?;
+            println!("🔧 Removed {} block(s); the chain now ends at block {}", removed, report.consistent);
+        } else {
+            println!("Run with --repair to truncate to the last consistent block");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    
+    // Maintenance commands open the database, run and exit
+    if let Some(command) = args.get(1).filter(|command| {
+        matches!(command.as_str(), "reindex" | "export-chain" | "export-snapshot" | "import-chain")
+    }) {
+        let data_dir = flag_value(&args, "--data-dir").unwrap_or_else(|| config.data_dir.clone());
+        let database = mondoshawan_blockchain::storage::Database::open(&data_dir)?;
+        let mut blockchain = mondoshawan_blockchain::blockchain::Blockchain::with_storage(Arc::new(database))?;
+        let file = || match args.get(2).filter(|arg| !arg.starts_with("--")) {
+            Some(file) => file.clone(),
+            None => {
+                eprintln!("Error: {} requires a file argument", command);
+                std::process::exit(1);
+            }
+        };
+        match command.as_str() {
+            // Rebuild the block, transaction and address indexes
+            "reindex" => {
+                let indexed = blockchain.reindex()?;
+                println!("✅ Reindexed {} block(s) in {}", indexed, data_dir);
+            }
+            "export-chain" => {
+                let path = file();
+                let written = blockchain.export_chain(std::io::BufWriter::new(std::fs::File::create(&path)?))?;
+                println!("✅ Exported {} block(s) to {}", written, path);
+            }
+            "export-snapshot" => {
+                let path = file();
+                let snapshot = match flag_value(&args, "--block") {
+                    Some(block) => blockchain.snapshot_at(&parse_hash(&block)?)?,
+                    None => blockchain.snapshot(),
+                };
+                snapshot.write_to(std::io::BufWriter::new(std::fs::File::create(&path)?))?;
+                println!("✅ Exported {} account(s) at block #{} (state root 0x{}) to {}",
+                    snapshot.header.account_count, snapshot.header.block_number,
+                    hex::encode(snapshot.header.state_root), path);
+            }
+            _ => {
+                let path = file();
+                let snapshot = match flag_value(&args, "--snapshot") {
+                    Some(snapshot_path) => {
+                        let trusted_root = match flag_value(&args, "--state-root") {
+                            Some(root) => parse_hash(&root)?,
+                            None => {
+                                eprintln!("Error: --snapshot requires --state-root <hash> from a trusted source");
+                                std::process::exit(1);
+                            }
+                        };
+                        let reader = std::io::BufReader::new(std::fs::File::open(&snapshot_path)?);
+                        Some((mondoshawan_blockchain::blockchain::ChainSnapshot::read_from(reader)?, trusted_root))
+                    }
+                    None => None,
+                };
+                let reader = std::io::BufReader::new(std::fs::File::open(&path)?);
+                let imported = blockchain.import_chain(reader, snapshot.as_ref().map(|(snapshot, root)| (snapshot, *root)))?;
+                println!("✅ Imported {} block(s) from {} ({} in chain)", imported, path, blockchain.block_count());
+            }
+        }
+        return Ok(());
+    }
+    
+    // Parse --config flag (TOML file; its [sharding], [pruning], [rpc] and [pool] tables are used)
+    if let Some(idx) = args.iter().position(|arg| arg == "--config") {
+        let path = match args.get(idx + 1) {
+            Some(path) => path.clone(),
+            None => {
+                eprintln!("Error: --config requires a path argument");
+                std::process::exit(1);
+            }
+        };
+        let file_config = mondoshawan_blockchain::config::NodeConfig::from_file(&path)
+            .and_then(|file_config| file_config.validate().map(|_| file_config))
+            .unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            });
+        config.sharding = file_config.sharding;
+        config.pruning = file_config.pruning;
+        config.rpc = file_config.rpc;
+        config.pool = file_config.pool.settings.clone();
+        config.pool_payout_key = file_config.pool.load_payout_key().unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        });
+        println!("Using config file: {}", path);
+        args.drain(idx..idx + 2);
+    }
+    
+    // Parse --pool-key-file flag (overrides the [pool] table)
+    if let Some(path) = flag_value(&args, "--pool-key-file") {
+        let payout_key = mondoshawan_blockchain::config::read_payout_key(std::path::Path::new(&path))
+            .unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            });
+        config.pool_payout_key = Some(payout_key);
+        let idx = args.iter().position(|arg| arg == "--pool-key-file").unwrap();
+        args.drain(idx..idx + 2);
+    }
+    
+    // Pool-operator mode mines to the payout key's address
+    if let Some(payout_key) = config.pool_payout_key {
+        config.miner_address = mondoshawan_blockchain::mining::pool::payout_address(&payout_key);
+        println!("Pool payout address: 0x{}", hex::encode(config.miner_address));
+    }
     let mut peer_start_idx = 3;
     
     // Parse P2P port
@@ -65,7 +239,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
     
-    let node = Arc::new(Node::new(config.clone()));
+    let node = match Node::try_new(config.clone()) {
+        Ok(node) => Arc::new(node),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
     
     // Start the node
     node.start().await?;
@@ -103,6 +283,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }); // 1000 tokens
         println!("   Alice balance: 1000 tokens");
     }
+    // With sharding, Alice's account lives in her shard's state partition
+    if let Some(shard_manager) = node.shard_manager() {
+        if let Err(e) = shard_manager.set_balance(alice, 1000_000_000_000_000_000_000).await {
+            eprintln!("Warning: Failed to set shard balance: {}", e);
+        }
+    }
     
     // Add some transactions to the pool
     for i in 0..50 {
@@ -165,7 +351,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let blockchain = blockchain_dashboard.read().await;
         let latest = blockchain.latest_block_number();
         let tx_count = blockchain.transaction_count();
-        let miner_balance = blockchain.get_balance(miner_address);
+        let miner_balance = blockchain.get_balance(config.miner_address);
         let dag_stats = blockchain.get_dag_stats();
         let tps = blockchain.get_tps(60);
         let stream_a = stream_a_blocks.load(Ordering::Relaxed);
@@ -301,14 +487,14 @@ async fn start_api_server(
                         )
                     } else if request.starts_with("GET /api/blocks/recent") {
                         let bc = blockchain_clone.read().await;
-                        let blocks = bc.get_blocks();
+                        let blocks = bc.block_metas();
                         let recent: Vec<_> = blocks.iter().rev().take(10).map(|b| {
                             format!(
                                 "{{\"hash\":\"{}\",\"number\":{},\"timestamp\":{},\"transaction_count\":{}}}",
                                 hex::encode(&b.hash),
-                                b.header.block_number,
-                                b.header.timestamp,
-                                b.transactions.len()
+                                b.block_number,
+                                b.timestamp,
+                                b.transaction_count
                             )
                         }).collect();
                         format!(
@@ -317,9 +503,8 @@ async fn start_api_server(
                         )
                     } else if request.starts_with("GET /api/transactions/recent") {
                         let bc = blockchain_clone.read().await;
-                        let blocks = bc.get_blocks();
                         let mut recent_txs = Vec::new();
-                        for block in blocks.iter().rev().take(10) {
+                        for block in bc.blocks().rev().take(10) {
                             for tx in &block.transactions {
                                 recent_txs.push(format!(
                                     "{{\"hash\":\"{}\",\"from\":\"{}\",\"to\":\"{}\",\"value\":{}}}",
@@ -344,3 +529,21 @@ async fn start_api_server(
         }
     }
 }
+
+/// Value following a command line flag (exits if the flag has no value)
+fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    let idx = args.iter().position(|arg| arg == flag)?;
+    match args.get(idx + 1) {
+        Some(value) => Some(value.clone()),
+        None => {
+            eprintln!("Error: {} requires a value", flag);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 32-byte hash given on the command line as hex (0x prefix optional)
+fn parse_hash(value: &str) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    let bytes = hex::decode(value.trim_start_matches("0x"))?;
+    bytes.as_slice().try_into().map_err(|_| format!("{} is not a 32-byte hash", value).into())
+}
@@ -5,18 +5,41 @@ use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 
 /// Block header
+///
+/// The layout is versioned explicitly: bincode is not self-describing, so a
+/// new field means a new `VERSION` and a frozen copy of the old layout where
+/// old headers are decoded (`storage::schema::BlockHeaderV1`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockHeader {
+    /// Header layout version (1 = legacy, no ordering/coinbase/shard fields)
+    pub version: u32,
     pub parent_hashes: Vec<Hash>,
     pub block_number: u64,
     pub stream_type: StreamType,
     pub difficulty: u64,
     pub timestamp: u64,
+    /// Transaction ordering policy the miner declares for this block
+    /// Required from version 2 on; None only in legacy (version 1) blocks
+    pub ordering_policy: Option<crate::mining::ordering::OrderingPolicy>,
+    /// Mempool arrival time of each transaction, aligned with `Block::transactions`
+    /// Required for verifiable policies (Random); as seen by the miner, and not
+    /// checkable by anyone else
+    pub tx_arrival_times: Vec<u64>,
+    /// Address of the miner that produced this block (None for legacy blocks)
+    pub coinbase: Option<Address>,
+    /// Merkle root of the cross-shard receipts this (shard) block emits
+    pub receipts_root: Option<Hash>,
+    /// Shard tips and state roots committed by this (beacon) block
+    pub shard_commitments: Vec<crate::sharding::beacon::ShardCommitment>,
 }
 
 impl BlockHeader {
+    /// Current header layout version
+    pub const VERSION: u32 = 2;
+
     pub fn new(parent_hashes: Vec<Hash>, block_number: u64, stream_type: StreamType, difficulty: u64) -> Self {
         Self {
+            version: Self::VERSION,
             parent_hashes,
             block_number,
             stream_type,
@@ -25,8 +48,47 @@ impl BlockHeader {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            ordering_policy: Some(crate::mining::ordering::OrderingPolicy::default()),
+            tx_arrival_times: Vec::new(),
+            coinbase: None,
+            receipts_root: None,
+            shard_commitments: Vec::new(),
         }
     }
+
+    /// Record the miner that produced this block
+    pub fn with_coinbase(mut self, coinbase: Address) -> Self {
+        self.coinbase = Some(coinbase);
+        self
+    }
+
+    /// Commit the Merkle root of emitted cross-shard receipts
+    pub fn with_receipts_root(mut self, root: Hash) -> Self {
+        self.receipts_root = Some(root);
+        self
+    }
+
+    /// Commit shard tips and state roots (beacon blocks)
+    pub fn with_shard_commitments(mut self, commitments: Vec<crate::sharding::beacon::ShardCommitment>) -> Self {
+        self.shard_commitments = commitments;
+        self
+    }
+
+    /// Declare the ordering policy and commit the transaction arrival times
+    pub fn with_ordering(mut self, policy: crate::mining::ordering::OrderingPolicy, arrival_times: Vec<u64>) -> Self {
+        self.ordering_policy = Some(policy);
+        self.tx_arrival_times = arrival_times;
+        self
+    }
+
+    /// Whether any field added after the legacy layout is set
+    pub fn has_extensions(&self) -> bool {
+        self.ordering_policy.is_some()
+            || !self.tx_arrival_times.is_empty()
+            || self.coinbase.is_some()
+            || self.receipts_root.is_some()
+            || !self.shard_commitments.is_empty()
+    }
 }
 
 /// Transaction signature (64 bytes for Ed25519)
@@ -38,6 +100,9 @@ pub type TransactionSignature = Vec<u8>;
 pub type PublicKey = Vec<u8>;
 
 /// Transaction
+///
+/// Every field is always serialized: bincode (storage, network) is not
+/// self-describing and cannot skip unset fields.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub from: Address,
@@ -58,28 +123,22 @@ pub struct Transaction {
     pub public_key: PublicKey,
     /// Post-Quantum signature (optional, for PQ accounts)
     /// If present, this is used instead of Ed25519 signature
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub pq_signature: Option<crate::pqc::PqSignature>,
     /// Time-locked transaction: Execute at this block number (0 = immediate)
     /// If set, transaction will only be processed when current block >= execute_at_block
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub execute_at_block: Option<u64>,
     /// Time-locked transaction: Execute at this Unix timestamp (0 = immediate)
     /// If set, transaction will only be processed when block timestamp >= execute_at_timestamp
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub execute_at_timestamp: Option<u64>,
     /// Gasless transaction: Address that sponsors (pays for) this transaction's fee
     /// If set, the sponsor's balance is checked and debited instead of the sender's
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub sponsor: Option<Address>,
     /// Multi-signature support (for contract wallets)
     /// If present, this transaction requires multiple signatures
     /// Format: Vec<(signer_address, signature_bytes, public_key_bytes)>
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub multisig_signatures: Option<Vec<(Address, Vec<u8>, Vec<u8>)>>,
     /// Privacy transaction: zk-SNARK proof and privacy data
     /// If present, this is a private transaction (hidden sender, receiver, amount)
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub privacy_data: Option<crate::privacy::PrivacyTransaction>,
 }
 
@@ -185,6 +244,9 @@ impl Transaction {
     pub fn verify_signature(&self) -> bool {
         // Check for PQ signature first
         if let Some(ref pq_sig) = self.pq_signature {
+            if pq_sig.account_type == crate::pqc::PqAccountType::EthSecp256k1 {
+                return super::eth_tx::verify(self, pq_sig);
+            }
             return crate::pqc::PqAccount::verify_signature(&self.hash, pq_sig);
         }
         
@@ -310,7 +372,11 @@ impl Transaction {
 
     /// Calculate transaction hash (public for validation)
     /// Hash includes all fields except signature and public_key (signature signs this hash)
+    /// Ethereum transactions use their Ethereum hash, keccak256 of the envelope
     pub fn calculate_hash(&self) -> Hash {
+        if let Some(envelope) = self.eth_envelope() {
+            return Keccak256::digest(envelope).into();
+        }
         let mut hasher = Keccak256::new();
         hasher.update(&self.from);
         hasher.update(&self.to);
@@ -337,6 +403,13 @@ impl Transaction {
         hash
     }
     
+    /// Signed Ethereum envelope, for transactions submitted via `eth_sendRawTransaction`
+    pub fn eth_envelope(&self) -> Option<&[u8]> {
+        self.pq_signature.as_ref()
+            .filter(|sig| sig.account_type == crate::pqc::PqAccountType::EthSecp256k1)
+            .map(|sig| sig.signature.as_slice())
+    }
+
     /// Derive address from public key (Ethereum-style: Keccak256(public_key)[12:32])
     pub fn derive_address_from_public_key(public_key: &[u8; 32]) -> Address {
         use sha3::{Digest, Keccak256};
@@ -347,6 +420,14 @@ impl Transaction {
         addr.copy_from_slice(&hash[12..32]); // Last 20 bytes
         addr
     }
+
+    /// Ed25519 secret key and matching address for signing test transactions
+    #[cfg(test)]
+    pub(crate) fn test_signer(seed: u8) -> ([u8; 32], Address) {
+        let secret_key = [seed; 32];
+        let public_key = ed25519_dalek::SigningKey::from_bytes(&secret_key).verifying_key().to_bytes();
+        (secret_key, Self::derive_address_from_public_key(&public_key))
+    }
 }
 
 /// Block
@@ -372,12 +453,35 @@ impl Block {
     /// Calculate block hash (public for validation)
     pub fn calculate_hash(&self) -> Hash {
         let mut hasher = Keccak256::new();
+        // Legacy headers hash as they always did
+        if self.header.version > 1 {
+            hasher.update(&self.header.version.to_le_bytes());
+        }
         for parent in &self.header.parent_hashes {
             hasher.update(parent);
         }
         hasher.update(&self.header.block_number.to_le_bytes());
         hasher.update(&self.header.difficulty.to_le_bytes());
         hasher.update(&self.header.timestamp.to_le_bytes());
+        // Commit the miner address (if any)
+        if let Some(coinbase) = self.header.coinbase {
+            hasher.update(&coinbase);
+        }
+        // Commit the cross-shard receipts root (if any)
+        if let Some(root) = self.header.receipts_root {
+            hasher.update(&root);
+        }
+        // Commit the shard commitments of beacon blocks (if any)
+        if !self.header.shard_commitments.is_empty() {
+            hasher.update(&crate::sharding::beacon::commitments_root(&self.header.shard_commitments));
+        }
+        // Commit the declared ordering policy and arrival times (if any)
+        if let Some(policy) = self.header.ordering_policy {
+            hasher.update(policy.name().as_bytes());
+            for arrival in &self.header.tx_arrival_times {
+                hasher.update(&arrival.to_le_bytes());
+            }
+        }
         for tx in &self.transactions {
             hasher.update(&tx.hash);
         }
@@ -386,5 +490,35 @@ impl Block {
         hash.copy_from_slice(&result);
         hash
     }
+
+    /// Metadata kept in memory for this block
+    pub fn meta(&self) -> BlockMeta {
+        BlockMeta::from_header(self.hash, &self.header, self.transactions.len())
+    }
+}
+
+/// Block metadata held in memory for every accepted block (DAG structure and
+/// what listings need); bodies are loaded from storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMeta {
+    pub hash: Hash,
+    pub parent_hashes: Vec<Hash>,
+    pub block_number: u64,
+    pub stream_type: StreamType,
+    pub timestamp: u64,
+    pub transaction_count: usize,
 }
 
+impl BlockMeta {
+    /// Metadata of a block known only by its header (body pruned)
+    pub fn from_header(hash: Hash, header: &BlockHeader, transaction_count: usize) -> Self {
+        Self {
+            hash,
+            parent_hashes: header.parent_hashes.clone(),
+            block_number: header.block_number,
+            stream_type: header.stream_type,
+            timestamp: header.timestamp,
+            transaction_count,
+        }
+    }
+}
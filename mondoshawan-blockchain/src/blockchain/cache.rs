@@ -0,0 +1,151 @@
+//! Size-bounded LRU cache of block bodies
+
+use super::Block;
+use crate::types::Hash;
+use std::collections::{BTreeMap, HashMap};
+
+/// Default bound on cached block bodies (encoded size)
+pub const DEFAULT_BLOCK_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+struct CacheEntry {
+    block: Block,
+    size: usize,
+    last_used: u64,
+}
+
+/// Recently used block bodies, evicted least recently used first once their
+/// encoded size exceeds the bound
+pub struct BlockCache {
+    max_bytes: usize,
+    bytes: usize,
+    clock: u64,
+    entries: HashMap<Hash, CacheEntry>,
+    /// Last use -> hash, oldest first
+    recency: BTreeMap<u64, Hash>,
+}
+
+impl BlockCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            bytes: 0,
+            clock: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+
+    /// Cache that never evicts (in-memory chains have no storage to reload from)
+    pub fn unbounded() -> Self {
+        Self::new(usize::MAX)
+    }
+
+    fn touch(&mut self, hash: &Hash) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(hash) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.clock;
+            self.recency.insert(self.clock, *hash);
+        }
+    }
+
+    /// Get a block and mark it recently used
+    pub fn get(&mut self, hash: &Hash) -> Option<Block> {
+        self.touch(hash);
+        self.entries.get(hash).map(|entry| entry.block.clone())
+    }
+
+    /// Get a block without changing its recency (bulk scans)
+    pub fn peek(&self, hash: &Hash) -> Option<&Block> {
+        self.entries.get(hash).map(|entry| &entry.block)
+    }
+
+    pub fn contains(&self, hash: &Hash) -> bool {
+        self.entries.contains_key(hash)
+    }
+
+    /// Cache a block, evicting the least recently used ones to stay in bounds
+    ///
+    /// A block larger than the whole bound is not cached.
+    pub fn insert(&mut self, block: Block) {
+        let size = bincode::serialized_size(&block).unwrap_or(0) as usize;
+        if size > self.max_bytes {
+            return;
+        }
+        self.remove(&block.hash);
+        self.clock += 1;
+        self.recency.insert(self.clock, block.hash);
+        self.bytes += size;
+        self.entries.insert(block.hash, CacheEntry { block, size, last_used: self.clock });
+        while self.bytes > self.max_bytes {
+            let oldest = match self.recency.keys().next() {
+                Some(oldest) => *oldest,
+                None => break,
+            };
+            if let Some(hash) = self.recency.remove(&oldest) {
+                if let Some(entry) = self.entries.remove(&hash) {
+                    self.bytes -= entry.size;
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self, hash: &Hash) {
+        if let Some(entry) = self.entries.remove(hash) {
+            self.recency.remove(&entry.last_used);
+            self.bytes -= entry.size;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Encoded size of the cached blocks
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::BlockHeader;
+    use crate::types::StreamType;
+
+    fn block(number: u64) -> Block {
+        Block::new(BlockHeader::new(vec![], number, StreamType::StreamC, 4), vec![], vec![])
+    }
+
+    #[test]
+    fn test_least_recently_used_block_is_evicted() {
+        let size = bincode::serialized_size(&block(0)).unwrap() as usize;
+        let mut cache = BlockCache::new(size * 2);
+        let (first, second, third) = (block(1), block(2), block(3));
+        cache.insert(first.clone());
+        cache.insert(second.clone());
+        // Using the first block makes the second the eviction candidate
+        assert!(cache.get(&first.hash).is_some());
+        cache.insert(third.clone());
+
+        assert!(cache.contains(&first.hash));
+        assert!(!cache.contains(&second.hash));
+        assert!(cache.contains(&third.hash));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.bytes() <= cache.max_bytes());
+    }
+}
@@ -0,0 +1,374 @@
+//! Offline database integrity check (`node db-check`)
+//!
+//! The check walks the block index without loading a `Blockchain` (which
+//! refuses inconsistent databases), verifying each body, its hash and its
+//! parent links. The consistent prefix is then replayed from genesis into an
+//! in-memory chain, which recomputes the GhostDAG colouring (compared through
+//! the stored block rewards) and the account state (compared with the stored
+//! balances, nonces and their Verkle root).
+//!
+//! State changes made outside blocks (development funding, imported
+//! snapshots) are replayed from the journal the chain keeps of them; blocks
+//! before an imported snapshot are only restored, since the snapshot replaced
+//! the state they produced. Pruned bodies cannot be replayed; the state
+//! comparison is then skipped and `repair` leaves the stored state alone.
+
+use super::{Block, Blockchain};
+use crate::error::{BlockchainError, BlockchainResult};
+use crate::storage::{BlockStore, ChainStore, ChainTip, Database, IndexStore, StateStore, WriteBatch};
+use crate::types::{Address, Hash};
+use std::collections::{BTreeMap, HashSet};
+
+/// Findings of `check_database`
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    /// Entries in the block index
+    pub indexed: usize,
+    /// Blocks the stored tip says were applied
+    pub tip_count: u64,
+    /// Leading index entries that passed the structural checks
+    pub consistent: usize,
+    /// Replayed account state matched storage (None if it could not be replayed)
+    pub state_matches: Option<bool>,
+    /// Why the state could not be replayed
+    pub replay_skipped: Option<String>,
+    /// Verkle root over the stored balances and nonces
+    pub stored_root: Hash,
+    /// Verkle root over the replayed state
+    pub replayed_root: Option<Hash>,
+    /// Replayed state, kept for `repair`
+    replayed_accounts: Option<Vec<(Address, u128, u64)>>,
+    /// Blocks the replayed state covers
+    replayed_blocks: usize,
+    /// Every divergence found, in the order found
+    pub issues: Vec<String>,
+}
+
+impl CheckReport {
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+fn verkle_root(accounts: impl IntoIterator<Item = (Address, u128, u64)>) -> Hash {
+    let mut verkle = crate::verkle::VerkleState::new();
+    for (address, balance, nonce) in accounts {
+        verkle.set_balance(address, balance);
+        verkle.set_nonce(address, nonce);
+    }
+    verkle.state_root()
+}
+
+/// Stored accounts as (address, balance, nonce), sorted by address
+fn stored_accounts(db: &Database) -> BlockchainResult<Vec<(Address, u128, u64)>> {
+    let state_store = StateStore::new(db);
+    let mut accounts: BTreeMap<Address, (u128, u64)> = BTreeMap::new();
+    for (address, balance) in state_store.balances()? {
+        accounts.entry(address).or_default().0 = balance;
+    }
+    for (address, nonce) in state_store.nonces()? {
+        accounts.entry(address).or_default().1 = nonce;
+    }
+    Ok(accounts.into_iter().map(|(address, (balance, nonce))| (address, balance, nonce)).collect())
+}
+
+/// Check the stored chain and state
+pub fn check_database(db: &Database) -> BlockchainResult<CheckReport> {
+    let chain_store = ChainStore::new(db);
+    let block_store = BlockStore::new(db);
+    let hashes = chain_store.get_index()?;
+    let tip = chain_store.get_tip()?;
+    let pruned = (chain_store.get_pruned()? as usize).min(hashes.len());
+    let mut report = CheckReport {
+        indexed: hashes.len(),
+        tip_count: tip.as_ref().map(|tip| tip.block_count).unwrap_or(0),
+        ..Default::default()
+    };
+
+    match &tip {
+        Some(tip) if tip.block_count > hashes.len() as u64 => report.issues.push(format!(
+            "Stored tip is block {} but the index has {} entries", tip.block_count, hashes.len()
+        )),
+        Some(tip) if tip.block_count > 0 && hashes.get(tip.block_count as usize - 1) != Some(&tip.tip_hash) => {
+            report.issues.push(format!("Stored tip 0x{} does not match the block index", hex::encode(tip.tip_hash)))
+        }
+        Some(tip) if (tip.block_count as usize) < hashes.len() => report.issues.push(format!(
+            "{} index entries past the stored tip (incomplete blocks)", hashes.len() - tip.block_count as usize
+        )),
+        None if !hashes.is_empty() => report.issues.push("Block index has no stored tip".to_string()),
+        _ => {}
+    }
+
+    // Structural pass: every later block depends on the ones before it
+    let limit = (report.tip_count as usize).min(hashes.len());
+    let mut seen = HashSet::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    for (sequence, hash) in hashes.iter().enumerate().take(limit) {
+        let checked = if sequence < pruned {
+            match chain_store.get_header(hash) {
+                Ok(Some(header)) => parent_problem(&seen, header.header.block_number, &header.header.parent_hashes)
+                    .map_or(Ok(None), Err),
+                Ok(None) => Err("header of pruned block is missing".to_string()),
+                Err(e) => Err(format!("header cannot be read: {}", e)),
+            }
+        } else {
+            match block_store.get(hash) {
+                Ok(Some(block)) => body_problem(hash, &block)
+                    .or_else(|| parent_problem(&seen, block.header.block_number, &block.header.parent_hashes))
+                    .map_or(Ok(Some(block)), Err),
+                Ok(None) => Err("body is missing".to_string()),
+                Err(e) => Err(format!("body cannot be read: {}", e)),
+            }
+        };
+        match checked {
+            Ok(block) => blocks.extend(block),
+            Err(problem) => {
+                report.issues.push(format!("Block {} (0x{}): {}", sequence, hex::encode(hash), problem));
+                break;
+            }
+        }
+        seen.insert(*hash);
+        report.consistent = sequence + 1;
+    }
+
+    // Replay pass: GhostDAG colouring (rewards) and account state
+    let stored = stored_accounts(db)?;
+    report.stored_root = verkle_root(stored.iter().copied());
+    if pruned > 0 {
+        report.replay_skipped = Some(format!("the bodies of {} block(s) were pruned", pruned));
+        return Ok(report);
+    }
+    let reset = chain_store.get_reset()?.map(|sequence| sequence as usize).unwrap_or(0);
+    if reset > blocks.len() {
+        report.replay_skipped = Some(format!("a snapshot replaced the state after block {}, past the consistent prefix", reset));
+        return Ok(report);
+    }
+    let adjustments = chain_store.get_adjustments()?;
+    let mut pending = adjustments.iter()
+        .filter(|(sequence, _, _)| *sequence as usize >= reset)
+        .peekable();
+    let block_count = blocks.len();
+    let mut replay = Blockchain::new();
+    for (sequence, block) in blocks.into_iter().map(Some).chain(std::iter::once(None)).enumerate() {
+        while let Some((_, address, adjustment)) = pending.next_if(|(at, _, _)| *at as usize == sequence) {
+            match adjustment {
+                crate::storage::StateAdjustment::Balance(balance) => replay.set_balance(*address, *balance)?,
+                crate::storage::StateAdjustment::Nonce(nonce) => replay.set_nonce(*address, *nonce)?,
+            }
+        }
+        let Some(block) = block else { break };
+        let hash = block.hash;
+        if sequence < reset {
+            if let Err(e) = replay.validate_block_structure(&block).and_then(|()| replay.restore_block(block)) {
+                report.replay_skipped = Some(format!("block {} (0x{}) cannot be restored: {}", sequence, hex::encode(hash), e));
+                return Ok(report);
+            }
+            continue;
+        }
+        // Only blocks this node mined (those with a stored reward) paid a coinbase
+        let stored_reward = chain_store.get_reward(&hash)?;
+        let added = match stored_reward {
+            Some(_) => replay.add_mined_block(block),
+            None => replay.add_block(block),
+        };
+        if let Err(e) = added {
+            report.replay_skipped = Some(format!("block {} (0x{}) does not apply: {}", sequence, hex::encode(hash), e));
+            return Ok(report);
+        }
+        let replayed_reward = replay.get_block_reward(&hash);
+        if stored_reward.is_some() && stored_reward != replayed_reward {
+            report.issues.push(format!(
+                "Block {} (0x{}): stored reward {:?} but GhostDAG replay pays {:?}",
+                sequence, hex::encode(hash), stored_reward, replayed_reward
+            ));
+        }
+    }
+    let replayed = replay.account_entries();
+    let replayed_root = verkle_root(replayed.iter().copied());
+    report.replayed_root = Some(replayed_root);
+
+    let stored_map: BTreeMap<_, _> = stored.iter().map(|(address, balance, nonce)| (*address, (*balance, *nonce))).collect();
+    let replayed_map: BTreeMap<_, _> = replayed.iter().map(|(address, balance, nonce)| (*address, (*balance, *nonce))).collect();
+    let addresses: std::collections::BTreeSet<_> = stored_map.keys().chain(replayed_map.keys()).collect();
+    let mut diverged = 0;
+    for address in addresses {
+        let stored = stored_map.get(address).copied().unwrap_or_default();
+        let replayed = replayed_map.get(address).copied().unwrap_or_default();
+        if stored != replayed {
+            diverged += 1;
+            report.issues.push(format!(
+                "Account 0x{}: stored (balance {}, nonce {}) but replay gives (balance {}, nonce {})",
+                hex::encode(address), stored.0, stored.1, replayed.0, replayed.1
+            ));
+        }
+    }
+    if replayed_root != report.stored_root {
+        report.issues.push(format!(
+            "Stored state root 0x{} differs from the replayed root 0x{}",
+            hex::encode(report.stored_root), hex::encode(replayed_root)
+        ));
+    }
+    report.state_matches = Some(diverged == 0 && replayed_root == report.stored_root);
+    report.replayed_accounts = Some(replayed);
+    report.replayed_blocks = block_count;
+    Ok(report)
+}
+
+fn body_problem(hash: &Hash, block: &Block) -> Option<String> {
+    if block.hash != *hash {
+        return Some(format!("body is stored under the wrong hash (0x{})", hex::encode(block.hash)));
+    }
+    if block.calculate_hash() != *hash {
+        return Some("hash does not match the block contents".to_string());
+    }
+    block.transactions.iter().position(|tx| tx.hash != tx.calculate_hash())
+        .map(|index| format!("transaction {} has an invalid hash", index))
+}
+
+fn parent_problem(seen: &HashSet<Hash>, block_number: u64, parent_hashes: &[Hash]) -> Option<String> {
+    if block_number == 0 || parent_hashes.iter().any(|parent| seen.contains(parent)) {
+        None
+    } else {
+        Some("no parent precedes it in the block index".to_string())
+    }
+}
+
+/// Truncate the chain to the consistent prefix found by `check_database`
+///
+/// The tip is moved first, so an interrupted repair is finished by the
+/// rollback on the next startup. Stored state is replaced with the replayed
+/// state (including journaled out-of-block changes) when it was replayed, and
+/// left as stored when it was not; secondary indexes are rebuilt on startup.
+/// Returns the blocks removed.
+pub fn repair(db: &Database, report: &CheckReport) -> BlockchainResult<usize> {
+    let chain_store = ChainStore::new(db);
+    let hashes = chain_store.get_index()?;
+    let keep = report.consistent.min(hashes.len());
+    if keep == 0 && !hashes.is_empty() {
+        return Err(BlockchainError::Storage("Genesis is inconsistent; nothing can be kept".to_string()));
+    }
+
+    let mut removed = 0;
+    if keep < hashes.len() || report.tip_count != keep as u64 {
+        let tip_hash = keep.checked_sub(1).map(|last| hashes[last]).unwrap_or([0u8; 32]);
+        chain_store.put_tip(&ChainTip { block_count: keep as u64, tip_hash })?;
+        db.flush()?;
+        let block_store = BlockStore::new(db);
+        for hash in chain_store.truncate_index(keep as u64)? {
+            block_store.delete(&hash)?;
+            removed += 1;
+        }
+        IndexStore::new(db).clear()?;
+    }
+
+    if let Some(accounts) = &report.replayed_accounts {
+        if report.state_matches != Some(true) || removed > 0 {
+            chain_store.truncate_adjustments(report.replayed_blocks as u64)?;
+            let mut batch = WriteBatch::new();
+            StateStore::new(db).stage_clear(&mut batch)?;
+            for (address, balance, nonce) in accounts {
+                StateStore::stage_balance(&mut batch, address, *balance);
+                StateStore::stage_nonce(&mut batch, address, *nonce);
+            }
+            db.apply(batch)?;
+        }
+    } else if removed > 0 {
+        eprintln!("⚠️  State could not be replayed and was left as stored");
+    }
+    db.flush()?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::BlockHeader;
+    use crate::types::StreamType;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_corrupt_block_is_found_and_truncated() {
+        let database = Arc::new(Database::in_memory());
+        let mut hashes = Vec::new();
+        {
+            let mut blockchain = Blockchain::with_storage(database.clone()).unwrap();
+            for number in 0..5u64 {
+                let parents: Vec<Hash> = hashes.last().copied().into_iter().collect();
+                let header = BlockHeader::new(parents.clone(), number, StreamType::StreamA, 4).with_coinbase([3u8; 20]);
+                let block = Block::new(header, vec![], parents);
+                hashes.push(block.hash);
+                blockchain.add_mined_block(block).unwrap();
+            }
+        }
+        let report = check_database(&database).unwrap();
+        assert!(report.is_consistent(), "{:?}", report.issues);
+        assert_eq!(report.state_matches, Some(true));
+
+        // Corrupt block 3 and a balance
+        let mut block: Block = BlockStore::new(&database).get(&hashes[3]).unwrap().unwrap();
+        block.header.timestamp += 1;
+        BlockStore::new(&database).put(&block).unwrap();
+        StateStore::new(&database).put_balance(&[3u8; 20], 1).unwrap();
+
+        let report = check_database(&database).unwrap();
+        assert_eq!(report.consistent, 3);
+        assert_eq!(report.state_matches, Some(false));
+        assert!(report.issues.iter().any(|issue| issue.contains("does not match the block contents")));
+
+        assert_eq!(repair(&database, &report).unwrap(), 2);
+        let repaired = check_database(&database).unwrap();
+        assert!(repaired.is_consistent(), "{:?}", repaired.issues);
+        let blockchain = Blockchain::with_storage(database).unwrap();
+        assert_eq!(blockchain.block_count(), 3);
+        assert_eq!(blockchain.latest_block().unwrap().hash, hashes[2]);
+    }
+
+    #[test]
+    fn test_out_of_block_changes_are_replayed() {
+        use crate::blockchain::{ChainSnapshot, Transaction};
+        let (secret_key, sender) = Transaction::test_signer(1);
+        let database = Arc::new(Database::in_memory());
+        let mut blockchain = Blockchain::with_storage(database.clone()).unwrap();
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let mut hashes = vec![genesis.hash];
+        blockchain.add_block(genesis).unwrap();
+        // Development funding, set again (to another value) on a later start
+        blockchain.set_balance(sender, 1_000).unwrap();
+        for number in 1..4u64 {
+            let parent = *hashes.last().unwrap();
+            let tx = Transaction::new(sender, [2u8; 20], 10, 1, number - 1).sign(&secret_key);
+            let block = Block::new(BlockHeader::new(vec![parent], number, StreamType::StreamA, 4), vec![tx], vec![parent]);
+            hashes.push(block.hash);
+            blockchain.add_block(block).unwrap();
+        }
+        blockchain.set_balance([4u8; 20], 7).unwrap();
+        let report = check_database(&database).unwrap();
+        assert!(report.is_consistent(), "{:?}", report.issues);
+        assert_eq!(report.state_matches, Some(true));
+
+        // Truncating to two blocks keeps the funding but not the later change
+        let mut block: Block = BlockStore::new(&database).get(&hashes[2]).unwrap().unwrap();
+        block.header.timestamp += 1;
+        BlockStore::new(&database).put(&block).unwrap();
+        let report = check_database(&database).unwrap();
+        assert_eq!(report.consistent, 2);
+        assert_eq!(repair(&database, &report).unwrap(), 2);
+        assert!(check_database(&database).unwrap().is_consistent());
+        let blockchain = Blockchain::with_storage(database).unwrap();
+        assert_eq!(blockchain.get_balance(sender), 1_000 - 11);
+        assert_eq!(blockchain.get_balance([4u8; 20]), 0);
+
+        // A node bootstrapped from a snapshot replays from the snapshot block
+        let mut chain_file = Vec::new();
+        blockchain.export_chain(&mut chain_file).unwrap();
+        let mut snapshot_file = Vec::new();
+        blockchain.snapshot().write_to(&mut snapshot_file).unwrap();
+        let snapshot = ChainSnapshot::read_from(snapshot_file.as_slice()).unwrap();
+        let database = Arc::new(Database::in_memory());
+        let mut target = Blockchain::with_storage(database.clone()).unwrap();
+        target.import_chain(chain_file.as_slice(), Some((&snapshot, blockchain.state_commitment()))).unwrap();
+        let report = check_database(&database).unwrap();
+        assert!(report.is_consistent(), "{:?}", report.issues);
+        assert_eq!(report.state_matches, Some(true));
+    }
+}
@@ -0,0 +1,583 @@
+//! Ethereum raw transactions (`eth_sendRawTransaction`)
+//!
+//! Decodes legacy (EIP-155), EIP-2930 and EIP-1559 envelopes, recovers the
+//! secp256k1 sender and maps the result onto a native `Transaction`. The
+//! signed envelope is kept as the transaction's signature
+//! (`PqAccountType::EthSecp256k1`) and the transaction hash is the Ethereum
+//! hash, keccak256 of the envelope, so wallets find their transactions.
+//!
+//! The native fee is the gas the transaction uses (`evm::TRANSACTION_GAS`,
+//! the same for every transaction) times its effective gas price: the gas
+//! price (legacy, EIP-2930), or for EIP-1559 `min(maxFeePerGas, baseFee +
+//! maxPriorityFeePerGas)` with no base fee, i.e. the priority fee. The
+//! unused part of the gas limit is never charged. Access lists are validated
+//! but not used. Contract creation maps to the zero address.
+//!
+//! Copyright (c) 2026 Mondoshawan Protocol
+//! Licensed under the MIT License (see LICENSE file)
+
+use super::Transaction;
+use crate::error::{BlockchainError, BlockchainResult};
+use crate::pqc::{PqAccountType, PqSignature};
+use crate::types::{Address, Hash};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use sha3::{Digest, Keccak256};
+
+/// Chain ID signed into every Ethereum transaction (returned by `eth_chainId`
+/// and `net_version`): "MSHW", distinct from every public EVM chain so
+/// transactions signed for one cannot be replayed on the other
+pub const CHAIN_ID: u64 = 0x4D53_4857;
+
+/// Largest raw transaction accepted (same limit as geth)
+pub const MAX_RAW_TX_SIZE: usize = 128 * 1024;
+
+/// Deepest RLP nesting in a transaction (access list storage keys)
+const MAX_RLP_DEPTH: usize = 4;
+
+const EIP2930_TX_TYPE: u8 = 0x01;
+const EIP1559_TX_TYPE: u8 = 0x02;
+
+fn invalid(message: impl Into<String>) -> BlockchainError {
+    BlockchainError::InvalidTransaction(message.into())
+}
+
+/// One RLP item and its encoding
+struct Item<'a> {
+    raw: &'a [u8],
+    payload: Payload<'a>,
+}
+
+enum Payload<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<Item<'a>>),
+}
+
+/// Decode one canonical RLP item, returning it and the remaining input
+fn decode_item(input: &[u8], depth: usize) -> BlockchainResult<(Item<'_>, &[u8])> {
+    let (&prefix, rest) = input.split_first().ok_or_else(|| invalid("RLP input is truncated"))?;
+    let (is_list, offset, length) = match prefix {
+        0x00..=0x7f => {
+            let item = Item { raw: &input[..1], payload: Payload::Bytes(&input[..1]) };
+            return Ok((item, rest));
+        }
+        0x80..=0xb7 => (false, 1, (prefix - 0x80) as usize),
+        0xb8..=0xbf => {
+            let size = (prefix - 0xb7) as usize;
+            (false, 1 + size, long_length(rest, size)?)
+        }
+        0xc0..=0xf7 => (true, 1, (prefix - 0xc0) as usize),
+        0xf8..=0xff => {
+            let size = (prefix - 0xf7) as usize;
+            (true, 1 + size, long_length(rest, size)?)
+        }
+    };
+    let end = offset.checked_add(length)
+        .filter(|end| *end <= input.len())
+        .ok_or_else(|| invalid("RLP item is truncated"))?;
+    let body = &input[offset..end];
+    let payload = if is_list {
+        if depth >= MAX_RLP_DEPTH {
+            return Err(invalid("RLP nesting is too deep"));
+        }
+        let mut items = Vec::new();
+        let mut remaining = body;
+        while !remaining.is_empty() {
+            let (item, tail) = decode_item(remaining, depth + 1)?;
+            items.push(item);
+            remaining = tail;
+        }
+        Payload::List(items)
+    } else {
+        if length == 1 && body[0] < 0x80 {
+            return Err(invalid("Non-canonical RLP single byte"));
+        }
+        Payload::Bytes(body)
+    };
+    Ok((Item { raw: &input[..end], payload }, &input[end..]))
+}
+
+/// Length of a long string or list (`size` big-endian bytes)
+fn long_length(input: &[u8], size: usize) -> BlockchainResult<usize> {
+    let bytes = input.get(..size).ok_or_else(|| invalid("RLP length is truncated"))?;
+    if bytes[0] == 0 || size > 4 {
+        return Err(invalid("Non-canonical RLP length"));
+    }
+    let length = bytes.iter().fold(0usize, |length, &byte| (length << 8) | byte as usize);
+    if length < 56 {
+        return Err(invalid("Non-canonical RLP length"));
+    }
+    Ok(length)
+}
+
+/// Decode input holding exactly one RLP list
+fn decode_list(input: &[u8]) -> BlockchainResult<Vec<Item<'_>>> {
+    match decode_item(input, 0)? {
+        (Item { payload: Payload::List(items), .. }, []) => Ok(items),
+        (_, []) => Err(invalid("Expected an RLP list")),
+        _ => Err(invalid("Trailing bytes after RLP list")),
+    }
+}
+
+impl<'a> Item<'a> {
+    fn bytes(&self, field: &str) -> BlockchainResult<&'a [u8]> {
+        match self.payload {
+            Payload::Bytes(bytes) => Ok(bytes),
+            Payload::List(_) => Err(invalid(format!("{} must be a byte string", field))),
+        }
+    }
+
+    fn list(&self, field: &str) -> BlockchainResult<&[Item<'a>]> {
+        match &self.payload {
+            Payload::List(items) => Ok(items),
+            Payload::Bytes(_) => Err(invalid(format!("{} must be a list", field))),
+        }
+    }
+
+    fn uint(&self, field: &str) -> BlockchainResult<u128> {
+        let bytes = self.bytes(field)?;
+        if bytes.first() == Some(&0) {
+            return Err(invalid(format!("{} has leading zeros", field)));
+        }
+        if bytes.len() > 16 {
+            return Err(invalid(format!("{} exceeds 128 bits", field)));
+        }
+        Ok(bytes.iter().fold(0u128, |value, &byte| (value << 8) | byte as u128))
+    }
+
+    fn u64(&self, field: &str) -> BlockchainResult<u64> {
+        u64::try_from(self.uint(field)?).map_err(|_| invalid(format!("{} exceeds 64 bits", field)))
+    }
+
+    /// Signature scalar, left-padded to 32 bytes
+    fn scalar(&self, field: &str) -> BlockchainResult<[u8; 32]> {
+        let bytes = self.bytes(field)?;
+        if bytes.len() > 32 || bytes.first() == Some(&0) {
+            return Err(invalid(format!("Invalid signature {}", field)));
+        }
+        let mut scalar = [0u8; 32];
+        scalar[32 - bytes.len()..].copy_from_slice(bytes);
+        Ok(scalar)
+    }
+
+    /// Recipient; None for contract creation
+    fn to(&self) -> BlockchainResult<Option<Address>> {
+        match self.bytes("to")? {
+            [] => Ok(None),
+            bytes => bytes.try_into().map(Some).map_err(|_| invalid("Recipient must be 20 bytes")),
+        }
+    }
+}
+
+fn check_access_list(item: &Item) -> BlockchainResult<()> {
+    for entry in item.list("accessList")? {
+        match entry.list("accessList entry")? {
+            [address, keys] if address.bytes("accessList address")?.len() == 20 => {
+                for key in keys.list("storageKeys")? {
+                    if key.bytes("storage key")?.len() != 32 {
+                        return Err(invalid("Storage keys must be 32 bytes"));
+                    }
+                }
+            }
+            _ => return Err(invalid("Access list entries must be [address, storageKeys]")),
+        }
+    }
+    Ok(())
+}
+
+fn encode_length(length: usize, offset: u8) -> Vec<u8> {
+    if length < 56 {
+        return vec![offset + length as u8];
+    }
+    let bytes = length.to_be_bytes();
+    let significant = &bytes[bytes.iter().position(|&byte| byte != 0).unwrap_or(bytes.len() - 1)..];
+    let mut header = vec![offset + 55 + significant.len() as u8];
+    header.extend_from_slice(significant);
+    header
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut encoded = encode_length(bytes.len(), 0x80);
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+fn encode_u64(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().position(|&byte| byte != 0).unwrap_or(bytes.len());
+    encode_bytes(&bytes[start..])
+}
+
+/// Wrap already encoded items in a list
+fn encode_list(items: &[&[u8]]) -> Vec<u8> {
+    let length = items.iter().map(|item| item.len()).sum();
+    let mut encoded = encode_length(length, 0xc0);
+    for item in items {
+        encoded.extend_from_slice(item);
+    }
+    encoded
+}
+
+fn keccak(bytes: &[u8]) -> Hash {
+    Keccak256::digest(bytes).into()
+}
+
+/// A decoded Ethereum transaction with its recovered sender
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthTransaction {
+    /// 0 = legacy, 1 = EIP-2930, 2 = EIP-1559
+    pub tx_type: u8,
+    pub chain_id: u64,
+    pub nonce: u64,
+    /// Gas price for legacy and EIP-2930 transactions
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub gas_limit: u64,
+    /// None for contract creation
+    pub to: Option<Address>,
+    pub value: u128,
+    pub data: Vec<u8>,
+    pub from: Address,
+    /// Uncompressed secp256k1 public key without the 0x04 prefix
+    pub public_key: [u8; 64],
+    /// keccak256 of the envelope
+    pub hash: Hash,
+}
+
+impl EthTransaction {
+    /// Decode a signed envelope, recover its sender and check the chain ID
+    pub fn decode(raw: &[u8]) -> BlockchainResult<Self> {
+        let tx = Self::decode_any_chain(raw)?;
+        if tx.chain_id != CHAIN_ID {
+            return Err(invalid(format!("Invalid chain ID {} (expected {})", tx.chain_id, CHAIN_ID)));
+        }
+        Ok(tx)
+    }
+
+    /// Decode a signed envelope and recover its sender, for any chain ID
+    fn decode_any_chain(raw: &[u8]) -> BlockchainResult<Self> {
+        if raw.len() > MAX_RAW_TX_SIZE {
+            return Err(invalid(format!("Raw transaction of {} bytes exceeds {}", raw.len(), MAX_RAW_TX_SIZE)));
+        }
+        let (tx_type, body) = match raw.first() {
+            None => return Err(invalid("Empty raw transaction")),
+            Some(&byte) if byte >= 0xc0 => (0, raw),
+            Some(&EIP2930_TX_TYPE) => (EIP2930_TX_TYPE, &raw[1..]),
+            Some(&EIP1559_TX_TYPE) => (EIP1559_TX_TYPE, &raw[1..]),
+            Some(byte) => return Err(invalid(format!("Unsupported transaction type 0x{:02x}", byte))),
+        };
+        let items = decode_list(body)?;
+
+        // Fields, signature (y parity, r, s) and the payload the sender signed
+        let (mut tx, y_parity, r, s, signing_payload) = match (tx_type, items.as_slice()) {
+            (0, [nonce, gas_price, gas_limit, to, value, data, v, r, s]) => {
+                let v = v.u64("v")?;
+                if v == 27 || v == 28 {
+                    return Err(invalid("Transactions without a chain ID (pre-EIP-155) are not accepted"));
+                }
+                let chain_id = v.checked_sub(35).map(|v| v / 2)
+                    .ok_or_else(|| invalid(format!("Invalid v value {}", v)))?;
+                let gas_price = gas_price.uint("gasPrice")?;
+                // EIP-155 signs [fields..., chainId, 0, 0]
+                let chain = encode_u64(chain_id);
+                let zero: &[u8] = &[0x80];
+                let signed: Vec<&[u8]> = items[..6].iter().map(|item| item.raw)
+                    .chain([chain.as_slice(), zero, zero])
+                    .collect();
+                let tx = Self::new(tx_type, chain_id, nonce, gas_price, gas_price, gas_limit, to, value, data)?;
+                (tx, (v - 35) % 2, r, s, encode_list(&signed))
+            }
+            (EIP2930_TX_TYPE, [chain_id, nonce, gas_price, gas_limit, to, value, data, access_list, y_parity, r, s]) => {
+                check_access_list(access_list)?;
+                let gas_price = gas_price.uint("gasPrice")?;
+                let tx = Self::new(tx_type, chain_id.u64("chainId")?, nonce, gas_price, gas_price, gas_limit, to, value, data)?;
+                (tx, y_parity.u64("yParity")?, r, s, Self::typed_payload(tx_type, &items[..8]))
+            }
+            (EIP1559_TX_TYPE, [chain_id, nonce, max_priority_fee, max_fee, gas_limit, to, value, data, access_list, y_parity, r, s]) => {
+                check_access_list(access_list)?;
+                let max_fee = max_fee.uint("maxFeePerGas")?;
+                let max_priority_fee = max_priority_fee.uint("maxPriorityFeePerGas")?;
+                if max_priority_fee > max_fee {
+                    return Err(invalid("maxPriorityFeePerGas exceeds maxFeePerGas"));
+                }
+                let tx = Self::new(tx_type, chain_id.u64("chainId")?, nonce, max_fee, max_priority_fee, gas_limit, to, value, data)?;
+                (tx, y_parity.u64("yParity")?, r, s, Self::typed_payload(tx_type, &items[..9]))
+            }
+            _ => return Err(invalid(format!("Wrong number of fields for transaction type {}", tx_type))),
+        };
+
+        if tx.gas_limit < crate::evm::TRANSACTION_GAS {
+            return Err(invalid(format!("Gas limit {} is below the {} a transaction uses", tx.gas_limit, crate::evm::TRANSACTION_GAS)));
+        }
+        let public_key = recover(&keccak(&signing_payload), y_parity, &r.scalar("r")?, &s.scalar("s")?)?;
+        tx.from.copy_from_slice(&keccak(&public_key)[12..]);
+        tx.public_key = public_key;
+        tx.hash = keccak(raw);
+        Ok(tx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        tx_type: u8,
+        chain_id: u64,
+        nonce: &Item,
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+        gas_limit: &Item,
+        to: &Item,
+        value: &Item,
+        data: &Item,
+    ) -> BlockchainResult<Self> {
+        Ok(Self {
+            tx_type,
+            chain_id,
+            nonce: nonce.u64("nonce")?,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            gas_limit: gas_limit.u64("gas")?,
+            to: to.to()?,
+            value: value.uint("value")?,
+            data: data.bytes("data")?.to_vec(),
+            from: [0u8; 20],
+            public_key: [0u8; 64],
+            hash: [0u8; 32],
+        })
+    }
+
+    /// `type || rlp([fields...])` of a typed transaction
+    fn typed_payload(tx_type: u8, fields: &[Item]) -> Vec<u8> {
+        let raws: Vec<&[u8]> = fields.iter().map(|item| item.raw).collect();
+        let mut payload = vec![tx_type];
+        payload.extend(encode_list(&raws));
+        payload
+    }
+
+    /// Price per gas the sender pays; the chain has no base fee, so an
+    /// EIP-1559 transaction pays its priority fee (at most `max_fee_per_gas`)
+    pub fn effective_gas_price(&self) -> u128 {
+        self.max_priority_fee_per_gas.min(self.max_fee_per_gas)
+    }
+
+    /// Fee charged: the gas used times the effective gas price
+    pub fn fee(&self) -> BlockchainResult<u128> {
+        self.effective_gas_price().checked_mul(crate::evm::TRANSACTION_GAS as u128)
+            .ok_or_else(|| invalid("Gas used times gas price overflows"))
+    }
+
+    /// Native transaction carrying `raw` as its signature
+    pub fn into_transaction(self, raw: &[u8]) -> BlockchainResult<Transaction> {
+        let fee = self.fee()?;
+        let mut tx = Transaction::with_data(
+            self.from,
+            self.to.unwrap_or([0u8; 20]),
+            self.value,
+            fee,
+            self.nonce,
+            self.data,
+            self.gas_limit,
+        );
+        tx.signature = Vec::new();
+        tx.pq_signature = Some(PqSignature::new(PqAccountType::EthSecp256k1, raw.to_vec(), self.public_key.to_vec()));
+        tx.hash = tx.calculate_hash();
+        Ok(tx)
+    }
+}
+
+/// Recover the uncompressed public key (without 0x04) that signed `prehash`
+fn recover(prehash: &Hash, y_parity: u64, r: &[u8; 32], s: &[u8; 32]) -> BlockchainResult<[u8; 64]> {
+    let mut rs = [0u8; 64];
+    rs[..32].copy_from_slice(r);
+    rs[32..].copy_from_slice(s);
+    let signature = Signature::from_slice(&rs).map_err(|_| invalid("Invalid signature"))?;
+    // EIP-2: only the low-s form is valid
+    if signature.normalize_s().is_some() {
+        return Err(invalid("Signature s value is not in the lower half of the curve order"));
+    }
+    let recovery_id = u8::try_from(y_parity).ok()
+        .filter(|parity| *parity <= 1)
+        .and_then(RecoveryId::from_byte)
+        .ok_or_else(|| invalid(format!("Invalid signature y parity {}", y_parity)))?;
+    let key = VerifyingKey::recover_from_prehash(prehash, &signature, recovery_id)
+        .map_err(|_| invalid("Cannot recover the signer"))?;
+    let mut public_key = [0u8; 64];
+    public_key.copy_from_slice(&key.to_encoded_point(false).as_bytes()[1..]);
+    Ok(public_key)
+}
+
+/// Decode a raw Ethereum transaction into a native transaction
+pub fn decode_transaction(raw: &[u8]) -> BlockchainResult<Transaction> {
+    EthTransaction::decode(raw)?.into_transaction(raw)
+}
+
+/// Verify a native transaction against the Ethereum envelope it carries
+///
+/// Every native field must match the envelope, and fields Ethereum
+/// transactions cannot express must be unset.
+pub fn verify(tx: &Transaction, signature: &PqSignature) -> bool {
+    let Ok(eth) = EthTransaction::decode(&signature.signature) else {
+        return false;
+    };
+    eth.public_key.as_slice() == signature.public_key.as_slice()
+        && eth.fee().is_ok_and(|fee| fee == tx.fee)
+        && tx.hash == eth.hash
+        && tx.from == eth.from
+        && tx.to == eth.to.unwrap_or([0u8; 20])
+        && tx.value == eth.value
+        && tx.nonce == eth.nonce
+        && tx.gas_limit == eth.gas_limit
+        && tx.data == eth.data
+        && tx.signature.is_empty()
+        && tx.public_key.is_empty()
+        && tx.execute_at_block.is_none()
+        && tx.execute_at_timestamp.is_none()
+        && tx.sponsor.is_none()
+        && tx.multisig_signatures.is_none()
+        && tx.privacy_data.is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+
+    /// Example transaction from EIP-155
+    const EIP155_RAW: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    fn sign_eip1559(key: &SigningKey, chain_id: u64, value: u64) -> Vec<u8> {
+        let fields = [
+            encode_u64(chain_id),
+            encode_u64(3),
+            encode_u64(1_000_000_000),
+            encode_u64(30_000_000_000),
+            encode_u64(50_000),
+            encode_bytes(&[0x42; 20]),
+            encode_u64(value),
+            encode_bytes(&[0xde, 0xad]),
+            encode_list(&[&encode_list(&[&encode_bytes(&[0x42; 20]), &encode_list(&[&encode_bytes(&[7u8; 32])])])]),
+        ];
+        let raws: Vec<&[u8]> = fields.iter().map(Vec::as_slice).collect();
+        let mut payload = vec![EIP1559_TX_TYPE];
+        payload.extend(encode_list(&raws));
+        // k256 signs with a low s and the matching recovery id
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&keccak(&payload)).unwrap();
+        let (r, s) = signature.split_bytes();
+        let signature_fields = [encode_u64(recovery_id.to_byte() as u64), encode_bytes(trim(&r)), encode_bytes(trim(&s))];
+        let mut all = raws.clone();
+        all.extend(signature_fields.iter().map(Vec::as_slice));
+        let mut raw = vec![EIP1559_TX_TYPE];
+        raw.extend(encode_list(&all));
+        raw
+    }
+
+    fn sign_legacy(key: &SigningKey, chain_id: u64, nonce: u64) -> Vec<u8> {
+        let fields = [
+            encode_u64(nonce),
+            encode_u64(20_000_000_000),
+            encode_u64(21_000),
+            encode_bytes(&[0x35; 20]),
+            encode_u64(1_000),
+            encode_bytes(&[]),
+        ];
+        let raws: Vec<&[u8]> = fields.iter().map(Vec::as_slice).collect();
+        // EIP-155 signs [fields..., chainId, 0, 0]
+        let chain = encode_u64(chain_id);
+        let mut signed = raws.clone();
+        signed.extend([chain.as_slice(), &[0x80], &[0x80]]);
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&keccak(&encode_list(&signed))).unwrap();
+        let (r, s) = signature.split_bytes();
+        let v = chain_id * 2 + 35 + recovery_id.to_byte() as u64;
+        let signature_fields = [encode_u64(v), encode_bytes(trim(&r)), encode_bytes(trim(&s))];
+        let mut all = raws;
+        all.extend(signature_fields.iter().map(Vec::as_slice));
+        encode_list(&all)
+    }
+
+    fn trim(bytes: &[u8]) -> &[u8] {
+        &bytes[bytes.iter().position(|&byte| byte != 0).unwrap_or(bytes.len())..]
+    }
+
+    #[test]
+    fn test_decode_eip155_example() {
+        let raw = hex::decode(EIP155_RAW).unwrap();
+        let eth = EthTransaction::decode_any_chain(&raw).unwrap();
+        assert_eq!(eth.tx_type, 0);
+        assert_eq!(eth.chain_id, 1);
+        assert_eq!(eth.nonce, 9);
+        assert_eq!(eth.max_fee_per_gas, 20_000_000_000);
+        assert_eq!(eth.gas_limit, 21_000);
+        assert_eq!(eth.to, Some([0x35; 20]));
+        assert_eq!(eth.value, 1_000_000_000_000_000_000);
+        assert_eq!(hex::encode(eth.from), "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+
+        // Signed for Ethereum mainnet, so it cannot be replayed here
+        assert!(decode_transaction(&raw).unwrap_err().to_string().contains("chain ID"));
+
+        // Any byte of the signed fields changes the recovered sender
+        let mut tampered = raw.clone();
+        tampered[40] ^= 1;
+        assert!(!matches!(EthTransaction::decode_any_chain(&tampered), Ok(tampered) if tampered.from == eth.from));
+    }
+
+    #[test]
+    fn test_legacy_round_trip() {
+        let key = SigningKey::from_slice(&[0x22; 32]).unwrap();
+        let raw = sign_legacy(&key, CHAIN_ID, 9);
+        let tx = decode_transaction(&raw).unwrap();
+        let expected_key = key.verifying_key().to_encoded_point(false);
+        assert_eq!(tx.from, keccak(&expected_key.as_bytes()[1..])[12..]);
+        assert_eq!((tx.to, tx.value, tx.nonce), ([0x35; 20], 1_000, 9));
+        assert_eq!(tx.hash, keccak(&raw));
+        assert_eq!(tx.fee, 21_000 * 20_000_000_000);
+        assert!(tx.verify_signature());
+
+        assert!(decode_transaction(&sign_legacy(&key, 1, 9)).is_err());
+    }
+
+    #[test]
+    fn test_eip1559_round_trip_and_tampering() {
+        let key = SigningKey::from_slice(&[0x11; 32]).unwrap();
+        let raw = sign_eip1559(&key, CHAIN_ID, 1_000);
+        let tx = decode_transaction(&raw).unwrap();
+        let expected_key = key.verifying_key().to_encoded_point(false);
+        assert_eq!(tx.from, keccak(&expected_key.as_bytes()[1..])[12..]);
+        assert_eq!((tx.to, tx.value, tx.nonce, tx.gas_limit), ([0x42; 20], 1_000, 3, 50_000));
+        // Gas used at the priority fee, not the gas limit at the max fee
+        assert_eq!(tx.fee, 21_000 * 1_000_000_000);
+        assert_eq!(tx.data, vec![0xde, 0xad]);
+        assert!(tx.verify_signature());
+
+        // Native fields must match the envelope
+        let mut altered = tx.clone();
+        altered.value += 1;
+        altered.hash = altered.calculate_hash();
+        assert!(!altered.verify_signature());
+        let mut sponsored = tx.clone();
+        sponsored.sponsor = Some([9u8; 20]);
+        assert!(!sponsored.verify_signature());
+
+        let wrong_chain = sign_eip1559(&key, CHAIN_ID + 1, 1_000);
+        assert!(decode_transaction(&wrong_chain).unwrap_err().to_string().contains("chain ID"));
+    }
+
+    #[test]
+    fn test_rejects_malformed_envelopes() {
+        // Pre-EIP-155 signature (v = 27)
+        let mut raw = hex::decode(EIP155_RAW).unwrap();
+        raw[43] = 0x1b;
+        assert!(EthTransaction::decode(&raw).is_err());
+
+        assert!(EthTransaction::decode(&[]).is_err());
+        assert!(EthTransaction::decode(&[0x03, 0xc0]).is_err());
+        // Trailing bytes and truncated lists
+        let mut trailing = hex::decode(EIP155_RAW).unwrap();
+        trailing.push(0x00);
+        assert!(EthTransaction::decode(&trailing).is_err());
+        assert!(EthTransaction::decode(&hex::decode(&EIP155_RAW[..40]).unwrap()).is_err());
+        // Deep nesting is refused instead of recursing
+        assert!(EthTransaction::decode(&[0xc0 + 9, 0xc8, 0xc7, 0xc6, 0xc5, 0xc4, 0xc3, 0xc2, 0xc1, 0xc0]).is_err());
+        // Non-canonical single byte
+        assert!(decode_item(&[0x81, 0x05], 0).is_err());
+    }
+}
@@ -0,0 +1,472 @@
+//! Chain export/import and state snapshot files
+//!
+//! Both files start with a 4-byte magic and a big-endian u32 format version,
+//! followed by records: a big-endian u32 length, the bincode payload and the
+//! first 4 bytes of the payload's blake3 hash.
+//!
+//! A chain file holds a `ChainFileHeader` record and then every block in
+//! acceptance order. That is the order blocks were applied in, parents first,
+//! and replaying it reproduces the same GhostDAG colouring and rewards (the
+//! final consensus ordering is newest first and leaves out red blocks).
+//!
+//! A snapshot file holds a `SnapshotHeader` record, the accounts sorted by
+//! address and the deployed contract code. Account state is stored as current
+//! values only, so a snapshot can only be taken at the tip. Contract storage
+//! is not tracked by the EVM integration and is not part of the format.
+//!
+//! The snapshot's state root is the chain's `state_commitment` at its block:
+//! the Verkle root with Verkle enabled, else the partition root over accounts
+//! and contract code (the root beacon blocks commit for a shard). The file
+//! itself cannot prove that root, so importing a snapshot needs the root from
+//! a trusted source (a beacon commitment, or `mds_getStateCommitment` on a
+//! trusted node at the same block) and refuses a snapshot that does not match.
+
+use super::{Block, Blockchain, MAX_BLOCK_SIZE};
+use crate::error::{BlockchainError, BlockchainResult};
+use crate::types::{Address, Hash};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{ErrorKind, Read, Write};
+
+const CHAIN_FILE_MAGIC: [u8; 4] = *b"MSHC";
+const SNAPSHOT_FILE_MAGIC: [u8; 4] = *b"MSHS";
+/// Format version written by this release
+pub const EXPORT_FORMAT_VERSION: u32 = 2;
+/// Largest record accepted on import (a maximum-size block plus encoding overhead)
+const MAX_RECORD_SIZE: usize = 2 * MAX_BLOCK_SIZE;
+
+/// First record of a chain file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChainFileHeader {
+    block_count: u64,
+    tip_hash: Hash,
+}
+
+/// First record of a snapshot file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotHeader {
+    /// Block the state was taken at
+    pub block_hash: Hash,
+    pub block_number: u64,
+    /// Blocks accepted up to and including `block_hash`
+    pub block_count: u64,
+    /// `Blockchain::state_commitment` at `block_hash`
+    pub state_root: Hash,
+    /// Whether `state_root` is a Verkle root (else a partition root)
+    pub verkle: bool,
+    pub account_count: u64,
+    pub code_count: u64,
+}
+
+/// Account state at a block, as written to a snapshot file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainSnapshot {
+    pub header: SnapshotHeader,
+    /// (address, balance, nonce), sorted by address
+    pub accounts: Vec<(Address, u128, u64)>,
+    /// (address, bytecode), sorted by address
+    pub code: Vec<(Address, Vec<u8>)>,
+}
+
+fn write_file_header<W: Write>(writer: &mut W, magic: [u8; 4]) -> BlockchainResult<()> {
+    writer.write_all(&magic)?;
+    writer.write_all(&EXPORT_FORMAT_VERSION.to_be_bytes())?;
+    Ok(())
+}
+
+fn read_file_header<R: Read>(reader: &mut R, magic: [u8; 4], kind: &str) -> BlockchainResult<()> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    if header[..4] != magic {
+        return Err(BlockchainError::Serialization(format!("Not a {} file", kind)));
+    }
+    let version = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    if version != EXPORT_FORMAT_VERSION {
+        return Err(BlockchainError::Serialization(format!(
+            "Unsupported {} format version {} (expected {})", kind, version, EXPORT_FORMAT_VERSION
+        )));
+    }
+    Ok(())
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = blake3::hash(payload);
+    let mut sum = [0u8; 4];
+    sum.copy_from_slice(&hash.as_bytes()[..4]);
+    sum
+}
+
+fn write_record<W: Write, T: Serialize>(writer: &mut W, value: &T) -> BlockchainResult<()> {
+    let payload = bincode::serialize(value)?;
+    if payload.len() > MAX_RECORD_SIZE {
+        return Err(BlockchainError::Serialization(format!("Record of {} bytes is too large", payload.len())));
+    }
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(&payload)?;
+    writer.write_all(&checksum(&payload))?;
+    Ok(())
+}
+
+/// Read the next record; None at a clean end of file
+fn read_record<R: Read, T: DeserializeOwned>(reader: &mut R) -> BlockchainResult<Option<T>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_RECORD_SIZE {
+        return Err(BlockchainError::Serialization(format!("Record of {} bytes is too large", length)));
+    }
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload)?;
+    let mut sum = [0u8; 4];
+    reader.read_exact(&mut sum)?;
+    if sum != checksum(&payload) {
+        return Err(BlockchainError::Serialization("Record checksum mismatch".to_string()));
+    }
+    Ok(Some(bincode::deserialize(&payload)?))
+}
+
+/// Whether the reader has no bytes left
+fn at_end<R: Read>(reader: &mut R) -> BlockchainResult<bool> {
+    let mut byte = [0u8; 1];
+    Ok(reader.read(&mut byte)? == 0)
+}
+
+fn expect_record<R: Read, T: DeserializeOwned>(reader: &mut R, what: &str) -> BlockchainResult<T> {
+    read_record(reader)?.ok_or_else(|| BlockchainError::Serialization(format!("File ends before {}", what)))
+}
+
+impl ChainSnapshot {
+    /// Write the snapshot file
+    pub fn write_to<W: Write>(&self, mut writer: W) -> BlockchainResult<()> {
+        write_file_header(&mut writer, SNAPSHOT_FILE_MAGIC)?;
+        write_record(&mut writer, &self.header)?;
+        for account in &self.accounts {
+            write_record(&mut writer, account)?;
+        }
+        for code in &self.code {
+            write_record(&mut writer, code)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read a snapshot file and verify its state root
+    pub fn read_from<R: Read>(mut reader: R) -> BlockchainResult<Self> {
+        read_file_header(&mut reader, SNAPSHOT_FILE_MAGIC, "snapshot")?;
+        let header: SnapshotHeader = expect_record(&mut reader, "the snapshot header")?;
+        let accounts = (0..header.account_count)
+            .map(|_| expect_record(&mut reader, "the last account"))
+            .collect::<BlockchainResult<Vec<(Address, u128, u64)>>>()?;
+        let code = (0..header.code_count)
+            .map(|_| expect_record(&mut reader, "the last contract"))
+            .collect::<BlockchainResult<Vec<(Address, Vec<u8>)>>>()?;
+        if !at_end(&mut reader)? {
+            return Err(BlockchainError::Serialization("Trailing data after the snapshot".to_string()));
+        }
+
+        if !accounts.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            return Err(BlockchainError::Validation("Snapshot accounts are not sorted by address".to_string()));
+        }
+        let snapshot = Self { header, accounts, code };
+        let root = snapshot.commitment();
+        if root != snapshot.header.state_root {
+            return Err(BlockchainError::Validation(format!(
+                "Snapshot state root 0x{} does not match its accounts (0x{})",
+                hex::encode(snapshot.header.state_root), hex::encode(root)
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    /// State commitment over the snapshot's contents
+    fn commitment(&self) -> Hash {
+        if self.header.verkle {
+            let mut verkle = crate::verkle::VerkleState::new();
+            for (address, balance, nonce) in &self.accounts {
+                verkle.set_balance(*address, *balance);
+                verkle.set_nonce(*address, *nonce);
+            }
+            return verkle.state_root();
+        }
+        let entries = crate::sharding::reshard::partition_entries(self.accounts.clone(), self.code.clone());
+        crate::sharding::reshard::partition_root(&entries)
+    }
+}
+
+impl Blockchain {
+    /// Write every block to a chain file in acceptance order
+    ///
+    /// Fails if a body has been pruned; only a node holding the full history
+    /// can export the chain. Returns the blocks written.
+    pub fn export_chain<W: Write>(&self, mut writer: W) -> BlockchainResult<usize> {
+        if self.pruned > 0 {
+            return Err(BlockchainError::Storage(format!(
+                "The bodies of {} block(s) have been pruned", self.pruned
+            )));
+        }
+        let header = ChainFileHeader {
+            block_count: self.blocks.len() as u64,
+            tip_hash: self.blocks.last().map(|meta| meta.hash).unwrap_or([0u8; 32]),
+        };
+        write_file_header(&mut writer, CHAIN_FILE_MAGIC)?;
+        write_record(&mut writer, &header)?;
+        for meta in &self.blocks {
+            let block = self.load_block(&meta.hash, false).ok_or_else(|| BlockchainError::Storage(
+                format!("Block 0x{} is missing", hex::encode(meta.hash))
+            ))?;
+            write_record(&mut writer, &block)?;
+        }
+        writer.flush()?;
+        Ok(self.blocks.len())
+    }
+
+    /// Import a chain file, validating every block
+    ///
+    /// Blocks already in the chain are skipped, so an interrupted import can be
+    /// rerun. With a snapshot the chain must be empty: blocks up to the
+    /// snapshot block are checked for their hash and parents only, the
+    /// snapshot state is applied at that block, and later blocks are executed
+    /// as usual. The snapshot comes with the state root the caller trusts for
+    /// its block; a snapshot with another root is refused before anything is
+    /// imported. Returns the blocks added.
+    pub fn import_chain<R: Read>(&mut self, mut reader: R, snapshot: Option<(&ChainSnapshot, Hash)>) -> BlockchainResult<usize> {
+        read_file_header(&mut reader, CHAIN_FILE_MAGIC, "chain")?;
+        let header: ChainFileHeader = expect_record(&mut reader, "the chain header")?;
+        let snapshot = match snapshot {
+            Some((snapshot, trusted_root)) => {
+                self.check_snapshot_root(snapshot, trusted_root)?;
+                Some(snapshot)
+            }
+            None => None,
+        };
+        if let Some(snapshot) = snapshot {
+            if !self.blocks.is_empty() {
+                return Err(BlockchainError::Validation("A snapshot can only be imported into an empty chain".to_string()));
+            }
+            if snapshot.header.block_count == 0 || snapshot.header.block_count > header.block_count {
+                return Err(BlockchainError::Validation(format!(
+                    "Snapshot block #{} is not in the chain file", snapshot.header.block_number
+                )));
+            }
+        }
+        let mut pending = snapshot;
+
+        let mut imported = 0;
+        for position in 0..header.block_count {
+            let block: Block = expect_record(&mut reader, &format!("block {} of {}", position + 1, header.block_count))?;
+            if self.block_hashes.contains(&block.hash) {
+                continue;
+            }
+            match pending {
+                Some(snapshot) => {
+                    self.validate_block_structure(&block)?;
+                    self.restore_block(block)?;
+                    if self.blocks.len() as u64 == snapshot.header.block_count {
+                        self.apply_snapshot(snapshot)?;
+                        pending = None;
+                    }
+                }
+                None => self.add_block(block)?,
+            }
+            imported += 1;
+        }
+        if !at_end(&mut reader)? {
+            return Err(BlockchainError::Serialization("Trailing data after the last block".to_string()));
+        }
+        if header.block_count > 0 && self.blocks.last().map(|meta| meta.hash) != Some(header.tip_hash) {
+            return Err(BlockchainError::Validation(format!(
+                "Imported chain does not end at the file's tip 0x{}", hex::encode(header.tip_hash)
+            )));
+        }
+        Ok(imported)
+    }
+
+    /// Check a snapshot against the state root trusted for its block
+    fn check_snapshot_root(&self, snapshot: &ChainSnapshot, trusted_root: Hash) -> BlockchainResult<()> {
+        if snapshot.header.state_root != trusted_root {
+            return Err(BlockchainError::Validation(format!(
+                "Snapshot state root 0x{} is not the trusted root 0x{} for block 0x{}",
+                hex::encode(snapshot.header.state_root), hex::encode(trusted_root), hex::encode(snapshot.header.block_hash)
+            )));
+        }
+        if snapshot.header.verkle != self.is_verkle_enabled() {
+            return Err(BlockchainError::Validation(format!(
+                "Snapshot state root is a {} root but this node {} Verkle",
+                if snapshot.header.verkle { "Verkle" } else { "partition" },
+                if self.is_verkle_enabled() { "uses" } else { "does not use" }
+            )));
+        }
+        Ok(())
+    }
+
+    /// Account state and contract code at the tip
+    pub fn snapshot(&self) -> ChainSnapshot {
+        let accounts = self.account_entries();
+        let code = self.evm_executor.as_ref()
+            .map(|executor| executor.state().contracts())
+            .unwrap_or_default();
+        let tip = self.blocks.last();
+        ChainSnapshot {
+            header: SnapshotHeader {
+                block_hash: tip.map(|meta| meta.hash).unwrap_or([0u8; 32]),
+                block_number: tip.map(|meta| meta.block_number).unwrap_or(0),
+                block_count: self.blocks.len() as u64,
+                state_root: self.state_commitment(),
+                verkle: self.is_verkle_enabled(),
+                account_count: accounts.len() as u64,
+                code_count: code.len() as u64,
+            },
+            accounts,
+            code,
+        }
+    }
+
+    /// Snapshot at a block; only the tip is supported, since account state is
+    /// kept as current values
+    pub fn snapshot_at(&self, block_hash: &Hash) -> BlockchainResult<ChainSnapshot> {
+        match self.blocks.last() {
+            Some(tip) if tip.hash == *block_hash => Ok(self.snapshot()),
+            _ => Err(BlockchainError::Validation(format!(
+                "Block 0x{} is not the tip; snapshots can only be taken at the tip", hex::encode(block_hash)
+            ))),
+        }
+    }
+
+    /// Replace the account state with a snapshot taken at the current tip
+    ///
+    /// Balances and nonces are committed in one batch, journaled with a reset
+    /// marker so `db-check` replays from the snapshot instead of genesis.
+    fn apply_snapshot(&mut self, snapshot: &ChainSnapshot) -> BlockchainResult<()> {
+        let tip = self.blocks.last().map(|meta| meta.hash);
+        if tip != Some(snapshot.header.block_hash) {
+            return Err(BlockchainError::Validation(format!(
+                "Snapshot block 0x{} does not match the imported chain", hex::encode(snapshot.header.block_hash)
+            )));
+        }
+
+        if self.database.is_some() {
+            let mut batch = crate::storage::WriteBatch::new();
+            crate::storage::ChainStore::stage_reset(&mut batch, self.blocks.len() as u64);
+            self.write_batch = Some(batch);
+        }
+        let applied = snapshot.accounts.iter().try_for_each(|(address, balance, nonce)| {
+            self.set_balance(*address, *balance)?;
+            self.set_nonce(*address, *nonce)
+        });
+        let batch = self.write_batch.take();
+        applied?;
+        if let (Some(db), Some(batch)) = (&self.database, batch) {
+            db.apply(batch)?;
+        }
+
+        match &self.evm_executor {
+            Some(executor) => {
+                for (address, code) in &snapshot.code {
+                    executor.state().store_contract(*address, code.clone());
+                }
+            }
+            None if !snapshot.code.is_empty() => {
+                eprintln!("⚠️  EVM disabled: skipped the code of {} contract(s)", snapshot.code.len());
+            }
+            None => {}
+        }
+
+        let root = self.state_commitment();
+        if root != snapshot.header.state_root {
+            return Err(BlockchainError::Validation(format!(
+                "State root after the snapshot is 0x{}, expected 0x{}",
+                hex::encode(root), hex::encode(snapshot.header.state_root)
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::{BlockHeader, Transaction};
+    use crate::types::StreamType;
+    use std::sync::Arc;
+
+    fn build_chain(blockchain: &mut Blockchain, (secret_key, sender): ([u8; 32], Address)) -> Vec<Hash> {
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let mut hashes = vec![genesis.hash];
+        blockchain.add_block(genesis).unwrap();
+        blockchain.set_balance(sender, 1_000).unwrap();
+        for number in 1..5u64 {
+            let parent = *hashes.last().unwrap();
+            let tx = Transaction::new(sender, [2u8; 20], 10, 1, number - 1).sign(&secret_key);
+            let header = BlockHeader::new(vec![parent], number, StreamType::StreamA, 4).with_coinbase([3u8; 20]);
+            let block = Block::new(header, vec![tx], vec![parent]);
+            hashes.push(block.hash);
+            blockchain.add_block(block).unwrap();
+        }
+        hashes
+    }
+
+    #[test]
+    fn test_chain_file_round_trip_and_corruption() {
+        let signer = Transaction::test_signer(1);
+        let sender = signer.1;
+        let mut source = Blockchain::new();
+        let hashes = build_chain(&mut source, signer);
+        let mut file = Vec::new();
+        assert_eq!(source.export_chain(&mut file).unwrap(), hashes.len());
+
+        // Blocks are revalidated on import: the sender is unfunded here, so the
+        // import stops after genesis
+        let mut target = Blockchain::new();
+        assert!(target.import_chain(file.as_slice(), None).is_err());
+        assert_eq!(target.block_count(), 1);
+        // Rerunning skips the blocks already imported
+        target.set_balance(sender, 1_000).unwrap();
+        assert_eq!(target.import_chain(file.as_slice(), None).unwrap(), hashes.len() - 1);
+        assert_eq!(target.state_commitment(), source.state_commitment());
+
+        let mut corrupt = file.clone();
+        let last = corrupt.len() - 10;
+        corrupt[last] ^= 0xff;
+        assert!(Blockchain::new().import_chain(corrupt.as_slice(), None).is_err());
+        assert!(Blockchain::new().import_chain(&file[..file.len() - 1], None).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_bootstraps_storage_node() {
+        let signer = Transaction::test_signer(1);
+        let sender = signer.1;
+        let mut source = Blockchain::new();
+        let hashes = build_chain(&mut source, signer);
+        let mut chain_file = Vec::new();
+        source.export_chain(&mut chain_file).unwrap();
+        let mut snapshot_file = Vec::new();
+        source.snapshot().write_to(&mut snapshot_file).unwrap();
+
+        let snapshot = ChainSnapshot::read_from(snapshot_file.as_slice()).unwrap();
+        assert_eq!(snapshot.header.block_hash, hashes[4]);
+        assert_eq!(snapshot.header.state_root, source.state_commitment());
+        assert!(source.snapshot_at(&hashes[3]).is_err());
+        let mut tampered = snapshot.clone();
+        tampered.accounts[0].1 += 1;
+        let mut tampered_file = Vec::new();
+        tampered.write_to(&mut tampered_file).unwrap();
+        assert!(ChainSnapshot::read_from(tampered_file.as_slice()).is_err());
+
+        let database = Arc::new(crate::storage::Database::in_memory());
+        let mut target = Blockchain::with_storage(database.clone()).unwrap();
+        // A snapshot whose root is not the trusted one is refused up front
+        assert!(target.import_chain(chain_file.as_slice(), Some((&snapshot, [7u8; 32]))).is_err());
+        assert_eq!(target.block_count(), 0);
+        assert!(Blockchain::with_verkle().import_chain(chain_file.as_slice(), Some((&snapshot, snapshot.header.state_root))).is_err());
+        let trusted_root = source.state_commitment();
+        assert_eq!(target.import_chain(chain_file.as_slice(), Some((&snapshot, trusted_root))).unwrap(), hashes.len());
+        assert_eq!(target.get_balance(sender), source.get_balance(sender));
+
+        let restarted = Blockchain::with_storage(database).unwrap();
+        assert_eq!(restarted.block_count(), hashes.len());
+        assert_eq!(restarted.state_commitment(), source.state_commitment());
+    }
+}
@@ -4,11 +4,19 @@
 //! Licensed under the MIT License (see LICENSE file)
 
 pub mod block;
+pub mod cache;
+pub mod check;
+pub mod eth_tx;
+pub mod export;
+pub mod pruning;
 #[cfg(test)]
 mod tests;
 #[cfg(test)]
 mod tests_quick_wins;
-pub use block::{Block, BlockHeader, Transaction, TransactionSignature, PublicKey};
+pub use block::{Block, BlockHeader, BlockMeta, Transaction, TransactionSignature, PublicKey};
+pub use cache::{BlockCache, DEFAULT_BLOCK_CACHE_BYTES};
+pub use export::{ChainSnapshot, SnapshotHeader};
+pub use pruning::{PruneProgress, Retention};
 
 /// Maximum block size in bytes (10MB)
 pub const MAX_BLOCK_SIZE: usize = 10 * 1024 * 1024;
@@ -19,12 +27,39 @@ pub const MAX_PARENT_HASHES: usize = 10;
 /// Maximum transaction data size in bytes (128KB)
 pub const MAX_TX_DATA_SIZE: usize = 128 * 1024;
 
+/// Maximum cumulative gas limit of all transactions in a block
+pub const MAX_BLOCK_GAS: u64 = 250_000_000;
+
+/// Maximum number of fairness records returned by a single range query
+pub const MAX_FAIRNESS_RANGE_RECORDS: usize = 10_000;
+
+/// Position in the fairness history: block number and hash of a record
+pub type FairnessCursor = (u64, crate::types::Hash);
+
 use crate::types::Address;
 use crate::storage::Database;
 use crate::consensus::GhostDAG;
-use std::collections::{HashMap, HashSet};
+use crate::mining::fairness::FairnessRecord;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::VecDeque;
 use std::sync::Arc;
 
+/// In-memory state overwritten while a block is applied
+///
+/// Holds the previous balances, nonces, rewards, fairness record and DAG
+/// membership touched by `apply_block`, so a block that fails half-way (or
+/// whose storage batch fails to commit) leaves the chain state as it was.
+/// `None` means the entry did not exist.
+#[derive(Default)]
+struct BlockUndo {
+    balances: HashMap<Address, Option<u128>>,
+    nonces: HashMap<Address, Option<u64>>,
+    block_rewards: HashMap<crate::types::Hash, Option<u128>>,
+    reward_bases: HashMap<crate::types::Hash, Option<(Address, u128)>>,
+    fairness_key: Option<(u64, crate::types::Hash)>,
+    in_dag: bool,
+}
+
 /// Main blockchain structure
 pub struct Blockchain {
     // Storage (optional - None means in-memory only)
@@ -33,11 +68,26 @@ pub struct Blockchain {
     // GhostDAG consensus engine
     ghostdag: GhostDAG,
     
-    // In-memory caches for fast access
-    blocks: Vec<Block>,
+    // Metadata of every accepted block, in acceptance order
+    blocks: Vec<BlockMeta>,
+    // Recently used block bodies (bodies of older blocks are read from storage)
+    block_cache: std::sync::Mutex<BlockCache>,
+    // Leading blocks (acceptance order) whose bodies have been pruned
+    pruned: usize,
     balances: HashMap<Address, u128>,
     nonces: HashMap<Address, u64>, // Track nonces for each address
     block_hashes: HashSet<crate::types::Hash>, // Fast lookup for block existence
+    // Per-block fairness history (in-memory fallback when no storage is configured)
+    fairness_history: BTreeMap<(u64, crate::types::Hash), FairnessRecord>,
+    // Reward currently credited to each block's coinbase
+    block_rewards: HashMap<crate::types::Hash, u128>,
+    // Coinbase and full (blue) reward of each rewarded block, to reconcile
+    // rewards when GhostDAG recolors blocks
+    reward_bases: HashMap<crate::types::Hash, (Address, u128)>,
+    // Storage writes of the block being applied, committed atomically by add_block
+    write_batch: Option<crate::storage::WriteBatch>,
+    // In-memory state the block being applied replaced, restored if it fails
+    undo: Option<BlockUndo>,
     
     // Verkle tree for stateless mode
     verkle_state: Option<crate::verkle::VerkleState>,
@@ -63,6 +113,9 @@ pub struct Blockchain {
     stop_loss_manager: Option<Arc<tokio::sync::RwLock<crate::stop_loss::StopLossManager>>>,
     // Privacy Layer
     privacy_manager: Option<Arc<tokio::sync::RwLock<crate::privacy::PrivacyManager>>>,
+    
+    // Accepted blocks are announced here (None = no subscribers)
+    events: Option<crate::events::EventBus>,
 }
 
 impl Blockchain {
@@ -72,9 +125,16 @@ impl Blockchain {
             database: None,
             ghostdag: GhostDAG::new(),
             blocks: Vec::new(),
+            block_cache: std::sync::Mutex::new(BlockCache::unbounded()),
+            pruned: 0,
             balances: HashMap::new(),
             nonces: HashMap::new(),
             block_hashes: HashSet::new(),
+            fairness_history: BTreeMap::new(),
+            block_rewards: HashMap::new(),
+            reward_bases: HashMap::new(),
+            write_batch: None,
+            undo: None,
             verkle_state: None,
             evm_enabled: false,
             evm_executor: None,
@@ -87,6 +147,7 @@ impl Blockchain {
             recurring_manager: None,
             stop_loss_manager: None,
             privacy_manager: None,
+            events: None,
         }
     }
     
@@ -96,9 +157,16 @@ impl Blockchain {
             database: None,
             ghostdag: GhostDAG::new(),
             blocks: Vec::new(),
+            block_cache: std::sync::Mutex::new(BlockCache::unbounded()),
+            pruned: 0,
             balances: HashMap::new(),
             nonces: HashMap::new(),
             block_hashes: HashSet::new(),
+            fairness_history: BTreeMap::new(),
+            block_rewards: HashMap::new(),
+            reward_bases: HashMap::new(),
+            write_batch: None,
+            undo: None,
             verkle_state: Some(crate::verkle::VerkleState::new()),
             evm_enabled: false,
             evm_executor: None,
@@ -111,6 +179,7 @@ impl Blockchain {
             recurring_manager: None,
             stop_loss_manager: None,
             privacy_manager: None,
+            events: None,
         }
     }
 
@@ -120,9 +189,16 @@ impl Blockchain {
             database: Some(database),
             ghostdag: GhostDAG::new(),
             blocks: Vec::new(),
+            block_cache: std::sync::Mutex::new(BlockCache::new(DEFAULT_BLOCK_CACHE_BYTES)),
+            pruned: 0,
             balances: HashMap::new(),
             nonces: HashMap::new(),
             block_hashes: HashSet::new(),
+            fairness_history: BTreeMap::new(),
+            block_rewards: HashMap::new(),
+            reward_bases: HashMap::new(),
+            write_batch: None,
+            undo: None,
             verkle_state: None,
             evm_enabled: false,
             evm_executor: None,
@@ -135,6 +211,7 @@ impl Blockchain {
             recurring_manager: None,
             stop_loss_manager: None,
             privacy_manager: None,
+            events: None,
         };
         
         // Load existing blocks and state from storage
@@ -149,9 +226,16 @@ impl Blockchain {
             database: Some(database),
             ghostdag: GhostDAG::new(),
             blocks: Vec::new(),
+            block_cache: std::sync::Mutex::new(BlockCache::new(DEFAULT_BLOCK_CACHE_BYTES)),
+            pruned: 0,
             balances: HashMap::new(),
             nonces: HashMap::new(),
             block_hashes: HashSet::new(),
+            fairness_history: BTreeMap::new(),
+            block_rewards: HashMap::new(),
+            reward_bases: HashMap::new(),
+            write_batch: None,
+            undo: None,
             verkle_state: Some(crate::verkle::VerkleState::new()),
             evm_enabled: false,
             evm_executor: None,
@@ -164,6 +248,7 @@ impl Blockchain {
             recurring_manager: None,
             stop_loss_manager: None,
             privacy_manager: None,
+            events: None,
         };
         
         // Load existing blocks and state from storage
@@ -172,6 +257,28 @@ impl Blockchain {
         Ok(bc)
     }
 
+    /// Bound the block body cache (encoded bytes)
+    ///
+    /// Ignored without storage, where the cache is the only copy of each body.
+    pub fn with_block_cache(self, max_bytes: usize) -> Self {
+        if self.database.is_some() {
+            let mut resized = BlockCache::new(max_bytes);
+            let mut cache = self.cache();
+            for meta in &self.blocks {
+                if let Some(block) = cache.peek(&meta.hash) {
+                    resized.insert(block.clone());
+                }
+            }
+            *cache = resized;
+        }
+        self
+    }
+
+    /// Announce accepted blocks, their logs and the new DAG tips on `events`
+    pub fn set_event_bus(&mut self, events: crate::events::EventBus) {
+        self.events = Some(events);
+    }
+
     pub fn with_evm(enable: bool) -> Self {
         let mut bc = Self::new();
         bc.evm_enabled = enable;
@@ -182,16 +289,144 @@ impl Blockchain {
     }
     
     /// Load blocks and state from storage
+    ///
+    /// Replays the block index up to the stored tip into the block list and
+    /// GhostDAG, restores block rewards and loads every persisted balance and
+    /// nonce (into the Verkle tree when enabled, which restores its root).
+    /// Databases written before the index existed are indexed on first load,
+    /// and secondary indexes that lag the block index are rebuilt.
+    ///
+    /// Blocks are committed in one batch with the tip, so index entries past
+    /// the tip can only be left by a block interrupted before batching; they
+    /// and their bodies are rolled back.
     fn load_from_storage(&mut self) -> crate::error::BlockchainResult<()> {
-        // For now, we'll load blocks and state on-demand when queried
-        // In production, you'd maintain an index of block hashes and load state eagerly
-        // This keeps startup fast while still providing persistence
+        use crate::storage::{BlockStore, ChainStore, ChainTip, StateStore, WriteBatch};
+        
+        let db = match &self.database {
+            Some(db) => db.clone(),
+            None => return Ok(()),
+        };
+        let chain_store = ChainStore::new(&db);
+        let block_store = BlockStore::new(&db);
+        
+        let hashes = match chain_store.get_tip()? {
+            Some(tip) => {
+                let mut hashes = chain_store.get_index()?;
+                if (hashes.len() as u64) < tip.block_count {
+                    return Err(crate::error::BlockchainError::Storage(format!(
+                        "Block index has {} entries but the tip is block {}", hashes.len(), tip.block_count
+                    )));
+                }
+                if hashes.len() as u64 > tip.block_count {
+                    let incomplete = chain_store.truncate_index(tip.block_count)?;
+                    for hash in &incomplete {
+                        block_store.delete(hash)?;
+                    }
+                    db.flush()?;
+                    eprintln!("Rolled back {} incomplete block(s) past the stored tip", incomplete.len());
+                }
+                hashes.truncate(tip.block_count as usize);
+                if tip.block_count > 0 && hashes.last() != Some(&tip.tip_hash) {
+                    return Err(crate::error::BlockchainError::Storage(format!(
+                        "Stored tip 0x{} does not match the block index", hex::encode(tip.tip_hash)
+                    )));
+                }
+                hashes
+            }
+            None => {
+                let blocks = order_legacy_blocks(chain_store.legacy_blocks()?);
+                if let Some(last) = blocks.last() {
+                    let mut batch = WriteBatch::new();
+                    for (sequence, block) in blocks.iter().enumerate() {
+                        ChainStore::stage_index(&mut batch, sequence as u64, &block.hash);
+                    }
+                    ChainStore::stage_tip(&mut batch, &ChainTip { block_count: blocks.len() as u64, tip_hash: last.hash })?;
+                    db.apply(batch)?;
+                    db.flush()?;
+                }
+                blocks.iter().map(|block| block.hash).collect()
+            }
+        };
+        
+        // Every block must be intact and extend blocks restored before it. Only
+        // metadata stays in memory; the most recent bodies remain cached.
+        // Blocks below the pruning watermark are restored from their headers.
+        self.pruned = (chain_store.get_pruned()? as usize).min(hashes.len());
+        for (sequence, hash) in hashes.iter().enumerate() {
+            let reward = chain_store.get_reward(hash)?;
+            let meta = match block_store.get(hash)? {
+                Some(block) => {
+                    if block.hash != *hash || block.hash != block.calculate_hash() {
+                        return Err(crate::error::BlockchainError::Storage(
+                            format!("Stored block 0x{} is corrupt", hex::encode(hash))
+                        ));
+                    }
+                    if let (Some(coinbase), Some(_)) = (block.header.coinbase, reward) {
+                        self.reward_bases.insert(block.hash, (coinbase, crate::mining::base_block_reward(&block)));
+                    }
+                    let meta = block.meta();
+                    self.cache().insert(block);
+                    meta
+                }
+                None if sequence < self.pruned => {
+                    chain_store.get_header(hash)?.ok_or_else(|| crate::error::BlockchainError::Storage(
+                        format!("Header of pruned block 0x{} is missing", hex::encode(hash))
+                    ))?.meta()
+                }
+                None => return Err(crate::error::BlockchainError::Storage(
+                    format!("Indexed block 0x{} is missing", hex::encode(hash))
+                )),
+            };
+            self.validate_parent_hashes(meta.block_number, &meta.parent_hashes)?;
+            self.block_hashes.insert(meta.hash);
+            if let Some(reward) = reward {
+                self.block_rewards.insert(meta.hash, reward);
+            }
+            self.blocks.push(meta);
+        }
+        self.ghostdag = GhostDAG::from_blocks(self.blocks.clone());
+        
+        if crate::storage::IndexStore::new(&db).indexed_count()? != self.blocks.len() as u64 {
+            let indexed = self.reindex()?;
+            eprintln!("Rebuilt secondary indexes for {} block(s)", indexed);
+        }
+        
+        let state_store = StateStore::new(&db);
+        for (address, balance) in state_store.balances()? {
+            match self.verkle_state.as_mut() {
+                Some(verkle) => verkle.set_balance(address, balance),
+                None => {
+                    self.balances.insert(address, balance);
+                }
+            }
+        }
+        for (address, nonce) in state_store.nonces()? {
+            match self.verkle_state.as_mut() {
+                Some(verkle) => verkle.set_nonce(address, nonce),
+                None => {
+                    self.nonces.insert(address, nonce);
+                }
+            }
+        }
         
         Ok(())
     }
 
     /// Add a block to the blockchain with full validation and transaction processing
+    ///
+    /// The block's coinbase is not paid: blocks carry no proof of work yet, so
+    /// a relayed block could claim any reward. Use `add_mined_block` for blocks
+    /// this node mined itself.
     pub fn add_block(&mut self, block: Block) -> crate::error::BlockchainResult<()> {
+        self.add_block_with_reward(block, false)
+    }
+
+    /// Add a block this node mined and pay its coinbase the block reward
+    pub fn add_mined_block(&mut self, block: Block) -> crate::error::BlockchainResult<()> {
+        self.add_block_with_reward(block, true)
+    }
+
+    fn add_block_with_reward(&mut self, block: Block, reward: bool) -> crate::error::BlockchainResult<()> {
         // 1. Validate block structure
         self.validate_block_structure(&block)?;
         
@@ -211,30 +446,277 @@ impl Blockchain {
         }
         
         // 4. Validate parent hashes (for DAG support)
-        self.validate_parent_hashes(&block)?;
+        self.validate_parent_hashes(block.header.block_number, &block.header.parent_hashes)?;
         
-        // 5. Validate and process transactions
-        self.validate_and_process_transactions(&block)?;
+        // 5. Check the order matches the declared policy (arrival times are
+        //    the miner's own claim, so this does not prove FIFO fairness)
+        crate::mining::ordering::verify_block_ordering(&block)
+            .map_err(crate::error::BlockchainError::InvalidBlock)?;
         
-        // 6. Persist block to storage
-        if let Some(db) = &self.database {
-            use crate::storage::BlockStore;
-            let block_store = BlockStore::new(db);
-            block_store.put(&block)?;
+        // 6-11. Apply the block; its storage writes are staged in one batch and
+        //       the in-memory state it replaces is kept to undo a failure
+        if self.database.is_some() {
+            self.write_batch = Some(crate::storage::WriteBatch::new());
+        }
+        self.undo = Some(BlockUndo::default());
+        let applied = self.apply_block(&block, reward);
+        let batch = self.write_batch.take();
+        
+        // 12. Commit block body, index, state, fairness, reward and tip atomically
+        let committed = applied.and_then(|()| match (&self.database, batch) {
+            (Some(db), Some(batch)) => db.apply(batch),
+            _ => Ok(()),
+        });
+        let undo = self.undo.take();
+        if let Err(e) = committed {
+            if let Some(undo) = undo {
+                self.roll_back(&block.hash, undo);
+            }
+            return Err(e);
         }
         
-        // 7. Add block to GhostDAG for consensus ordering
-        self.ghostdag.add_block(block.clone());
-        
-        // 8. Add block to chain
+        // 13. Add block to chain (metadata stays in memory, the body is cached)
         self.block_hashes.insert(block.hash);
-        self.blocks.push(block);
+        self.blocks.push(block.meta());
+        self.publish_block(&block);
+        self.cache().insert(block);
+        
+        Ok(())
+    }
+
+    /// Announce an accepted block on the event bus
+    fn publish_block(&self, block: &Block) {
+        use crate::events::ChainEvent;
+        let Some(events) = &self.events else {
+            return;
+        };
+        events.publish(ChainEvent::NewHead {
+            hash: block.hash,
+            header: block.header.clone(),
+            transaction_count: block.transactions.len(),
+        });
+        events.publish(ChainEvent::DagTips(self.ghostdag.get_tips()));
+    }
+
+    /// Execute a validated block and stage every storage write it causes
+    fn apply_block(&mut self, block: &Block, reward: bool) -> crate::error::BlockchainResult<()> {
+        // 6. Validate and process transactions
+        self.validate_and_process_transactions(block)?;
+        
+        // 7. Persist block to storage and index it
+        if let (Some(db), Some(batch)) = (&self.database, self.write_batch.as_mut()) {
+            use crate::storage::{BlockStore, ChainStore};
+            use crate::storage::IndexStore;
+            BlockStore::stage(batch, block)?;
+            ChainStore::stage_index(batch, self.blocks.len() as u64, &block.hash);
+            IndexStore::new(db).stage_block(batch, self.blocks.len() as u64, block)?;
+        }
+        
+        // 8. Record fairness and MEV metrics for the accepted block
+        self.record_fairness(block)?;
+        
+        // 9. Add block to GhostDAG for consensus ordering
+        self.ghostdag.add_block(block);
+        if let Some(undo) = self.undo.as_mut() {
+            undo.in_dag = true;
+        }
+        
+        // 10. Pay the block reward to the coinbase and settle recolored blocks
+        self.apply_block_rewards(block, reward)?;
+        
+        // 11. Mark the block as fully applied (last applied block marker)
+        if let Some(batch) = self.write_batch.as_mut() {
+            use crate::storage::{ChainStore, ChainTip};
+            let tip = ChainTip { block_count: self.blocks.len() as u64 + 1, tip_hash: block.hash };
+            ChainStore::stage_tip(batch, &tip)?;
+        }
         
         Ok(())
     }
 
+    /// Remember an account's balance and nonce before the applied block changes them
+    fn save_account(&mut self, address: Address) {
+        let Some(undo) = self.undo.as_mut() else {
+            return;
+        };
+        if undo.balances.contains_key(&address) {
+            return;
+        }
+        let (balance, nonce) = match &self.verkle_state {
+            Some(verkle) => (Some(verkle.get_balance(address)), Some(verkle.get_nonce(address))),
+            None => (self.balances.get(&address).copied(), self.nonces.get(&address).copied()),
+        };
+        undo.balances.insert(address, balance);
+        undo.nonces.insert(address, nonce);
+    }
+
+    /// Restore the in-memory state a failed block changed
+    ///
+    /// Storage needs no undo: nothing was written outside the discarded batch.
+    fn roll_back(&mut self, hash: &crate::types::Hash, undo: BlockUndo) {
+        for (address, balance) in undo.balances {
+            match (&mut self.verkle_state, balance) {
+                (Some(verkle), balance) => verkle.set_balance(address, balance.unwrap_or(0)),
+                (None, Some(balance)) => {
+                    self.balances.insert(address, balance);
+                }
+                (None, None) => {
+                    self.balances.remove(&address);
+                }
+            }
+        }
+        for (address, nonce) in undo.nonces {
+            match (&mut self.verkle_state, nonce) {
+                (Some(verkle), nonce) => verkle.set_nonce(address, nonce.unwrap_or(0)),
+                (None, Some(nonce)) => {
+                    self.nonces.insert(address, nonce);
+                }
+                (None, None) => {
+                    self.nonces.remove(&address);
+                }
+            }
+        }
+        for (hash, reward) in undo.block_rewards {
+            match reward {
+                Some(reward) => self.block_rewards.insert(hash, reward),
+                None => self.block_rewards.remove(&hash),
+            };
+        }
+        for (hash, base) in undo.reward_bases {
+            match base {
+                Some(base) => self.reward_bases.insert(hash, base),
+                None => self.reward_bases.remove(&hash),
+            };
+        }
+        if let Some(key) = undo.fairness_key {
+            self.fairness_history.remove(&key);
+        }
+        if undo.in_dag {
+            self.ghostdag.remove_block(hash);
+        }
+    }
+
+    /// Re-insert a previously accepted block without executing its transactions
+    ///
+    /// Used when state is recovered separately (e.g. a shard's journaled account
+    /// state or an imported snapshot): the block joins the DAG and the block
+    /// index only. With storage the body, indexes and tip are committed in one
+    /// batch.
+    pub fn restore_block(&mut self, block: Block) -> crate::error::BlockchainResult<()> {
+        if block.hash != block.calculate_hash() {
+            return Err(crate::error::BlockchainError::InvalidBlock(
+                "Invalid block hash".to_string()
+            ));
+        }
+        if self.block_hashes.contains(&block.hash) {
+            return Ok(());
+        }
+        self.validate_parent_hashes(block.header.block_number, &block.header.parent_hashes)?;
+        if let Some(db) = &self.database {
+            use crate::storage::{BlockStore, ChainStore, ChainTip, IndexStore, WriteBatch};
+            let sequence = self.blocks.len() as u64;
+            let mut batch = WriteBatch::new();
+            BlockStore::stage(&mut batch, &block)?;
+            ChainStore::stage_index(&mut batch, sequence, &block.hash);
+            IndexStore::new(db).stage_block(&mut batch, sequence, &block)?;
+            ChainStore::stage_tip(&mut batch, &ChainTip { block_count: sequence + 1, tip_hash: block.hash })?;
+            db.apply(batch)?;
+        }
+        self.ghostdag.add_block(&block);
+        self.block_hashes.insert(block.hash);
+        self.blocks.push(block.meta());
+        self.cache().insert(block);
+        Ok(())
+    }
+
+    /// Credit block rewards as the current GhostDAG coloring decides
+    ///
+    /// Blue blocks earn the full stream reward; red blocks earn the reduced share
+    /// decided by `GhostDAG::reward_percent`. Adding a block can recolor earlier
+    /// ones, so every block whose share changed (the new block included) has its
+    /// coinbase credited or debited the difference: rewards always match the
+    /// coloring of the DAG as it stands, whatever order blocks arrived in. A
+    /// debit is capped at the coinbase's balance. Only blocks mined locally
+    /// (`reward`) earn anything; blocks without a coinbase, and pruned blocks
+    /// loaded from storage, earn nothing further.
+    fn apply_block_rewards(&mut self, block: &Block, reward: bool) -> crate::error::BlockchainResult<()> {
+        if let (true, Some(coinbase)) = (reward, block.header.coinbase) {
+            let previous = self.reward_bases.insert(block.hash, (coinbase, crate::mining::base_block_reward(block)));
+            if let Some(undo) = self.undo.as_mut() {
+                undo.reward_bases.entry(block.hash).or_insert(previous);
+            }
+        }
+        let recolored = self.ghostdag.recolored().to_vec();
+        for hash in recolored {
+            let Some(&(coinbase, base)) = self.reward_bases.get(&hash) else {
+                continue;
+            };
+            let reward = base.saturating_mul(self.ghostdag.reward_percent(&hash)) / 100;
+            let credited = self.block_rewards.get(&hash).copied();
+            if credited == Some(reward) {
+                continue;
+            }
+            let balance = self.get_balance(coinbase);
+            let credited = credited.unwrap_or(0);
+            if reward >= credited {
+                self.set_balance(coinbase, balance.saturating_add(reward - credited))?;
+            } else {
+                self.set_balance(coinbase, balance.saturating_sub(credited - reward))?;
+            }
+            if let Some(batch) = self.write_batch.as_mut() {
+                crate::storage::ChainStore::stage_reward(batch, &hash, reward);
+            } else if let Some(db) = &self.database {
+                use crate::storage::ChainStore;
+                ChainStore::new(db).put_reward(&hash, reward)?;
+            }
+            let previous = self.block_rewards.insert(hash, reward);
+            if let Some(undo) = self.undo.as_mut() {
+                undo.block_rewards.entry(hash).or_insert(previous);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reward currently credited to a block's coinbase (None if the block is
+    /// unknown or carries no coinbase)
+    pub fn get_block_reward(&self, hash: &crate::types::Hash) -> Option<u128> {
+        self.block_rewards.get(hash).copied()
+    }
+
+    /// Compute and store fairness metrics for an accepted block
+    fn record_fairness(&mut self, block: &Block) -> crate::error::BlockchainResult<()> {
+        let record = FairnessRecord::from_block(block);
+        if let Some(batch) = self.write_batch.as_mut() {
+            crate::storage::FairnessStore::stage(batch, &record)?;
+        } else if let Some(db) = &self.database {
+            use crate::storage::FairnessStore;
+            let fairness_store = FairnessStore::new(db);
+            fairness_store.put(&record)?;
+        } else {
+            let key = (record.block_number, record.block_hash);
+            self.fairness_history.insert(key, record);
+            if let Some(undo) = self.undo.as_mut() {
+                undo.fairness_key = Some(key);
+            }
+        }
+        Ok(())
+    }
+
     /// Validate block structure (number, timestamp, etc.)
     fn validate_block_structure(&self, block: &Block) -> crate::error::BlockchainResult<()> {
+        // Check the header layout version
+        let version = block.header.version;
+        if version == 0 || version > BlockHeader::VERSION {
+            return Err(crate::error::BlockchainError::InvalidBlock(
+                format!("Unsupported block header version {}", version)
+            ));
+        }
+        if version == 1 && block.header.has_extensions() {
+            return Err(crate::error::BlockchainError::InvalidBlock(
+                "Version 1 header carries fields added in version 2".to_string()
+            ));
+        }
+        
         // Check block size (DoS protection)
         let block_size = bincode::serialize(block)
             .map_err(|e| crate::error::BlockchainError::Serialization(e.to_string()))?
@@ -245,6 +727,15 @@ impl Blockchain {
             ));
         }
         
+        // Check cumulative gas limit (DoS protection)
+        let block_gas = block.transactions.iter()
+            .fold(0u64, |total, tx| total.saturating_add(tx.gas_limit));
+        if block_gas > MAX_BLOCK_GAS {
+            return Err(crate::error::BlockchainError::InvalidBlock(
+                format!("Block gas {} exceeds maximum {}", block_gas, MAX_BLOCK_GAS)
+            ));
+        }
+        
         // Check parent hash count (DoS protection)
         if block.header.parent_hashes.len() > MAX_PARENT_HASHES {
             return Err(crate::error::BlockchainError::InvalidBlock(
@@ -293,15 +784,15 @@ impl Blockchain {
     }
 
     /// Validate parent hashes exist in the blockchain (DAG support)
-    fn validate_parent_hashes(&self, block: &Block) -> crate::error::BlockchainResult<()> {
-        if block.header.block_number == 0 {
+    fn validate_parent_hashes(&self, block_number: u64, parent_hashes: &[crate::types::Hash]) -> crate::error::BlockchainResult<()> {
+        if block_number == 0 {
             // Genesis block - no parents needed
             return Ok(());
         }
         
         // Check that at least one parent exists
         let mut found_parent = false;
-        for parent_hash in &block.header.parent_hashes {
+        for parent_hash in parent_hashes {
             if self.block_hashes.contains(parent_hash) {
                 found_parent = true;
                 break;
@@ -442,25 +933,7 @@ impl Blockchain {
         }
         
         // Check nonce (must be exactly equal to current nonce for strict sequential ordering)
-        // For contract wallets, use wallet nonce; for EOA, use account nonce
-        let current_nonce = if let Some(ref wallet_registry) = self.wallet_registry {
-            // Check if sender is a contract wallet
-            // Note: Using try_read() for non-blocking access in sync context
-            // In production, this would be handled differently (async validation or sync registry)
-            if let Ok(registry) = wallet_registry.try_read() {
-                if let Some(wallet) = registry.get_wallet(&tx.from) {
-                    wallet.get_nonce() // Use wallet nonce
-                } else {
-                    self.get_nonce(tx.from) // Use account nonce for EOA
-                }
-            } else {
-                // If we can't acquire the lock, fall back to account nonce
-                // This is a temporary solution - in production, validation should be async
-                self.get_nonce(tx.from)
-            }
-        } else {
-            self.get_nonce(tx.from) // Fallback to account nonce if no registry
-        };
+        let current_nonce = self.expected_nonce(tx.from);
         
         if tx.nonce != current_nonce {
             return Err(crate::error::BlockchainError::InvalidTransaction(
@@ -545,6 +1018,25 @@ impl Blockchain {
         Ok(())
     }
 
+    /// Nonce the next transaction from `address` must carry
+    ///
+    /// For contract wallets, use wallet nonce; for EOA, use account nonce
+    pub fn expected_nonce(&self, address: Address) -> u64 {
+        if let Some(ref wallet_registry) = self.wallet_registry {
+            // Check if sender is a contract wallet
+            // Note: Using try_read() for non-blocking access in sync context
+            // In production, this would be handled differently (async validation or sync registry)
+            if let Ok(registry) = wallet_registry.try_read() {
+                if let Some(wallet) = registry.get_wallet(&address) {
+                    return wallet.get_nonce(); // Use wallet nonce
+                }
+            }
+            // If we can't acquire the lock, fall back to account nonce
+            // This is a temporary solution - in production, validation should be async
+        }
+        self.get_nonce(address) // Account nonce for EOA
+    }
+
     /// Validate privacy transaction (zk-SNARK proof)
     fn validate_privacy_transaction(
         &self,
@@ -578,6 +1070,12 @@ impl Blockchain {
 
     /// Process a transaction and update state
     fn process_transaction(&mut self, tx: &Transaction) -> crate::error::BlockchainResult<()> {
+        self.save_account(tx.from);
+        self.save_account(tx.to);
+        if let Some(sponsor) = tx.sponsor {
+            self.save_account(sponsor);
+        }
+        
         // Handle privacy transactions differently
         if let Some(ref privacy_tx) = tx.privacy_data {
             return self.process_privacy_transaction(tx, privacy_tx);
@@ -617,11 +1115,7 @@ impl Blockchain {
             }
             
             // Persist sponsor balance change
-            if let Some(db) = &self.database {
-                use crate::storage::StateStore;
-                let state_store = StateStore::new(db);
-                state_store.put_balance(&sponsor, new_sponsor_balance)?;
-            }
+            self.persist_balance(sponsor, new_sponsor_balance)?;
             
             // For gasless transactions, only value was deducted from sender (fee paid by sponsor)
             // Update sender balance (value deducted, fee handled by sponsor)
@@ -632,11 +1126,7 @@ impl Blockchain {
             }
             
             // Persist sender balance change
-            if let Some(db) = &self.database {
-                use crate::storage::StateStore;
-                let state_store = StateStore::new(db);
-                state_store.put_balance(&tx.from, new_from_balance)?;
-            }
+            self.persist_balance(tx.from, new_from_balance)?;
         } else {
             // Regular transaction: sender also pays fee
             if new_from_balance < tx.fee {
@@ -655,11 +1145,7 @@ impl Blockchain {
             }
             
             // Persist sender balance change
-            if let Some(db) = &self.database {
-                use crate::storage::StateStore;
-                let state_store = StateStore::new(db);
-                state_store.put_balance(&tx.from, new_from_balance_after_fee)?;
-            }
+            self.persist_balance(tx.from, new_from_balance_after_fee)?;
         }
         
         // Add value to receiver (if not zero address)
@@ -676,11 +1162,7 @@ impl Blockchain {
             }
             
             // Persist balance change
-            if let Some(db) = &self.database {
-                use crate::storage::StateStore;
-                let state_store = StateStore::new(db);
-                state_store.put_balance(&tx.to, new_to_balance)?;
-            }
+            self.persist_balance(tx.to, new_to_balance)?;
         }
         
         // Update nonce (transaction was already validated to have correct nonce)
@@ -744,26 +1226,15 @@ impl Blockchain {
         
         // Persist nonce change (only for EOA accounts, wallet nonces are in registry)
         // Note: For contract wallets, nonce is stored in wallet_registry, not in database
-        if let Some(db) = &self.database {
-            // Only persist if it's not a contract wallet
-            if let Some(ref wallet_registry) = self.wallet_registry {
-                if let Ok(registry) = wallet_registry.try_read() {
-                    if !registry.is_contract_wallet(&tx.from) {
-                        let current_nonce = self.get_nonce(tx.from);
-                        let new_nonce = current_nonce + 1;
-                        use crate::storage::StateStore;
-                        let state_store = StateStore::new(db);
-                        state_store.put_nonce(&tx.from, new_nonce)?;
-                    }
-                }
-            } else {
-                // No registry, persist as normal
-                let current_nonce = self.get_nonce(tx.from);
-                let new_nonce = current_nonce + 1;
-                use crate::storage::StateStore;
-                let state_store = StateStore::new(db);
-                state_store.put_nonce(&tx.from, new_nonce)?;
-            }
+        // The in-memory nonce was already incremented above
+        let persist_nonce = match &self.wallet_registry {
+            Some(wallet_registry) => wallet_registry.try_read()
+                .map(|registry| !registry.is_contract_wallet(&tx.from))
+                .unwrap_or(false),
+            None => true,
+        };
+        if persist_nonce {
+            self.persist_nonce(tx.from, self.get_nonce(tx.from))?;
         }
         
         // Process EVM transaction if enabled and has data
@@ -772,7 +1243,7 @@ impl Blockchain {
                 // Execute EVM transaction
                 let block_number = self.latest_block_number();
                 let block_timestamp = if let Some(latest_block) = self.blocks.last() {
-                    latest_block.header.timestamp
+                    latest_block.timestamp
                 } else {
                     std::time::SystemTime::now()
                         .duration_since(std::time::UNIX_EPOCH)
@@ -833,47 +1304,260 @@ impl Blockchain {
     /// Get the latest block number
     pub fn latest_block_number(&self) -> u64 {
         self.blocks.iter()
-            .map(|b| b.header.block_number)
+            .map(|b| b.block_number)
             .max()
             .unwrap_or(0)
     }
 
+    fn cache(&self) -> std::sync::MutexGuard<'_, BlockCache> {
+        self.block_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     /// Get block by hash
     pub fn get_block_by_hash(&self, hash: &crate::types::Hash) -> Option<Block> {
-        // First check in-memory cache
-        if let Some(block) = self.blocks.iter().find(|b| b.hash == *hash) {
-            return Some(block.clone());
+        self.load_block(hash, true)
+    }
+
+    /// Load a block body from the cache, falling back to storage
+    ///
+    /// `remember` caches a body read from storage and refreshes a cached one;
+    /// bulk scans pass `false` so they do not flush the cache.
+    fn load_block(&self, hash: &crate::types::Hash, remember: bool) -> Option<Block> {
+        {
+            let mut cache = self.cache();
+            let cached = if remember { cache.get(hash) } else { cache.peek(hash).cloned() };
+            if cached.is_some() {
+                return cached;
+            }
         }
         
-        // If not in cache and we have storage, try loading from storage
+        let db = self.database.as_ref()?;
+        use crate::storage::BlockStore;
+        // Ignore storage errors - just return None
+        let block = BlockStore::new(db).get(hash).ok().flatten()?;
+        if remember {
+            self.cache().insert(block.clone());
+        }
+        Some(block)
+    }
+
+    /// Get block by number (the lowest hash when the DAG has several at that height)
+    pub fn get_block_by_number(&self, number: u64) -> Option<Block> {
+        self.get_blocks_by_number(number).into_iter().next()
+    }
+
+    /// Get every block at a height, in hash order
+    pub fn get_blocks_by_number(&self, number: u64) -> Vec<Block> {
         if let Some(db) = &self.database {
-            use crate::storage::BlockStore;
-            let block_store = BlockStore::new(db);
-            if let Ok(Some(block)) = block_store.get(hash) {
-                return Some(block);
-            }
+            use crate::storage::IndexStore;
+            // Ignore storage errors - just return no blocks
+            return IndexStore::new(db).get_block_hashes(number).unwrap_or_default()
+                .iter()
+                .filter_map(|hash| self.get_block_by_hash(hash))
+                .collect();
+        }
+        let mut hashes: Vec<crate::types::Hash> = self.blocks.iter()
+            .filter(|b| b.block_number == number)
+            .map(|b| b.hash)
+            .collect();
+        hashes.sort();
+        hashes.iter().filter_map(|hash| self.get_block_by_hash(hash)).collect()
+    }
+
+    /// Get an included transaction with its block and position in the block
+    pub fn get_transaction_by_hash(&self, hash: &crate::types::Hash) -> Option<(Block, usize)> {
+        if let Some(db) = &self.database {
+            use crate::storage::IndexStore;
             // Ignore storage errors - just return None
+            let location = IndexStore::new(db).get_transaction_location(hash).ok().flatten()?;
+            let block = self.get_block_by_hash(&location.block_hash)?;
+            return Some((block, location.index as usize));
         }
-        
-        None
+        self.blocks().find_map(|block| {
+            block.transactions.iter()
+                .position(|tx| tx.hash == *hash)
+                .map(|index| (block, index))
+        })
+    }
+
+    /// Transactions sent or received by an address, newest first
+    ///
+    /// Skips `offset` transactions and returns at most `limit`, each with its
+    /// block and position, along with the total count for the address.
+    pub fn get_address_transactions(&self, address: Address, offset: usize, limit: usize) -> crate::error::BlockchainResult<(usize, Vec<(Block, usize)>)> {
+        if let Some(db) = &self.database {
+            use crate::storage::IndexStore;
+            let index_store = IndexStore::new(db);
+            let (total, hashes) = index_store.get_address_transactions(&address, offset, limit)?;
+            let mut transactions = Vec::with_capacity(hashes.len());
+            for hash in hashes {
+                if let Some(found) = self.get_transaction_by_hash(&hash) {
+                    transactions.push(found);
+                }
+            }
+            return Ok((total, transactions));
+        }
+        let mut total = 0;
+        let mut transactions = Vec::new();
+        for block in self.blocks().rev() {
+            for index in (0..block.transactions.len()).rev() {
+                let tx = &block.transactions[index];
+                if tx.from != address && tx.to != address {
+                    continue;
+                }
+                if total >= offset && transactions.len() < limit {
+                    transactions.push((block.clone(), index));
+                }
+                total += 1;
+            }
+        }
+        Ok((total, transactions))
     }
 
-    /// Get block by number
-    pub fn get_block_by_number(&self, number: u64) -> Option<&Block> {
-        self.blocks.iter().find(|b| b.header.block_number == number)
+    /// Rebuild the secondary indexes from the accepted blocks
+    ///
+    /// Returns the number of blocks indexed.
+    pub fn reindex(&self) -> crate::error::BlockchainResult<usize> {
+        use crate::storage::{IndexStore, WriteBatch};
+        let db = self.database.as_ref().ok_or_else(|| crate::error::BlockchainError::Storage(
+            "Reindexing requires storage".to_string()
+        ))?;
+        IndexStore::new(db).clear()?;
+        let mut index_store = IndexStore::new(db);
+        let mut batch = WriteBatch::new();
+        for (sequence, meta) in self.blocks.iter().enumerate() {
+            match self.load_block(&meta.hash, false) {
+                Some(block) => index_store.stage_block(&mut batch, sequence as u64, &block)?,
+                // Pruned: only the height entry survives
+                None => IndexStore::stage_header(&mut batch, sequence as u64, meta.block_number, &meta.hash),
+            }
+        }
+        db.apply(batch)?;
+        db.flush()?;
+        Ok(self.blocks.len())
     }
 
-    /// Get all blocks
-    pub fn get_blocks(&self) -> &[Block] {
+    /// Number of accepted blocks
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Metadata of every accepted block, in acceptance order
+    pub fn block_metas(&self) -> &[BlockMeta] {
         &self.blocks
     }
 
+    /// Most recently accepted block
+    pub fn latest_block(&self) -> Option<Block> {
+        self.get_block_by_hash(&self.blocks.last()?.hash)
+    }
+
+    /// Accepted blocks in acceptance order, paged in from storage
+    ///
+    /// Reverse with `.rev()` to walk from the newest block.
+    pub fn blocks(&self) -> BlockIter<'_> {
+        BlockIter {
+            chain: self,
+            front: 0,
+            back: self.blocks.len(),
+            front_page: VecDeque::new(),
+            back_page: VecDeque::new(),
+        }
+    }
+
+    /// Get the stored fairness record for a block
+    pub fn get_fairness_record(&self, hash: &crate::types::Hash) -> Option<FairnessRecord> {
+        let block_number = self.get_block_by_hash(hash)?.header.block_number;
+        if let Some(db) = &self.database {
+            use crate::storage::FairnessStore;
+            let fairness_store = FairnessStore::new(db);
+            // Ignore storage errors - just return None
+            return fairness_store.get(block_number, hash).ok().flatten();
+        }
+        self.fairness_history.get(&(block_number, *hash)).cloned()
+    }
+
+    /// Get stored fairness records for blocks numbered `from..=to`
+    ///
+    /// At most `limit` records are returned (capped at `MAX_FAIRNESS_RANGE_RECORDS`);
+    /// use `get_fairness_page` to read past the cap.
+    pub fn get_fairness_range(&self, from: u64, to: u64, limit: usize) -> crate::error::BlockchainResult<Vec<FairnessRecord>> {
+        self.get_fairness_page(from, to, None, limit).map(|(records, _)| records)
+    }
+
+    /// Get a page of stored fairness records for blocks numbered `from..=to`
+    ///
+    /// Starts at `cursor` if given. Returns at most `limit` records (capped at
+    /// `MAX_FAIRNESS_RANGE_RECORDS`) and, if the range holds more, the cursor
+    /// of the first record left out.
+    pub fn get_fairness_page(
+        &self,
+        from: u64,
+        to: u64,
+        cursor: Option<FairnessCursor>,
+        limit: usize,
+    ) -> crate::error::BlockchainResult<(Vec<FairnessRecord>, Option<FairnessCursor>)> {
+        let limit = limit.min(MAX_FAIRNESS_RANGE_RECORDS);
+        let start = cursor.filter(|cursor| cursor.0 >= from).unwrap_or((from, [0u8; 32]));
+        if start.0 > to {
+            return Ok((Vec::new(), None));
+        }
+        let mut records = if let Some(db) = &self.database {
+            use crate::storage::FairnessStore;
+            FairnessStore::new(db).range(start, to, limit + 1)?
+        } else {
+            self.fairness_history
+                .range(start..=(to, [0xffu8; 32]))
+                .take(limit + 1)
+                .map(|(_, record)| record.clone())
+                .collect()
+        };
+        let next = if records.len() > limit {
+            records.pop().map(|record| (record.block_number, record.block_hash))
+        } else {
+            None
+        };
+        Ok((records, next))
+    }
+
+    /// Aggregate every stored fairness record for blocks numbered `from..=to` by `key`
+    ///
+    /// The range is read page by page, so it is covered in full without being
+    /// held in memory. Returns the groups and the number of records aggregated.
+    pub fn aggregate_fairness<K, F>(
+        &self,
+        from: u64,
+        to: u64,
+        key: F,
+    ) -> crate::error::BlockchainResult<(HashMap<K, crate::mining::fairness::FairnessAggregate>, u64)>
+    where
+        K: std::hash::Hash + Eq,
+        F: Fn(&FairnessRecord) -> K,
+    {
+        let mut groups: HashMap<K, crate::mining::fairness::FairnessAggregate> = HashMap::new();
+        let mut count = 0u64;
+        let mut cursor = None;
+        loop {
+            let (records, next) = self.get_fairness_page(from, to, cursor, MAX_FAIRNESS_RANGE_RECORDS)?;
+            for record in &records {
+                groups.entry(key(record)).or_default().add(record);
+            }
+            count += records.len() as u64;
+            match next {
+                Some(next) => cursor = Some(next),
+                None => return Ok((groups, count)),
+            }
+        }
+    }
+
     /// Get transaction count
     pub fn transaction_count(&self) -> usize {
-        self.blocks.iter().map(|b| b.transactions.len()).sum()
+        self.blocks.iter().map(|b| b.transaction_count).sum()
     }
 
     pub fn set_balance(&mut self, address: Address, balance: u128) -> crate::error::BlockchainResult<()> {
+        self.save_account(address);
+        
         // If Verkle is enabled, it is the canonical source - update it first
         if let Some(ref mut verkle) = self.verkle_state {
             verkle.set_balance(address, balance);
@@ -885,17 +1569,13 @@ impl Blockchain {
         }
         
         // Persist balance (for recovery and non-Verkle mode)
-        if let Some(db) = &self.database {
-            use crate::storage::StateStore;
-            let state_store = StateStore::new(db);
-            state_store.put_balance(&address, balance)?;
-        }
-        
-        Ok(())
+        self.persist_balance(address, balance)
     }
     
     /// Set nonce for an address
     pub fn set_nonce(&mut self, address: Address, nonce: u64) -> crate::error::BlockchainResult<()> {
+        self.save_account(address);
+        
         // If Verkle is enabled, it is the canonical source - update it first
         if let Some(ref mut verkle) = self.verkle_state {
             verkle.set_nonce(address, nonce);
@@ -906,12 +1586,44 @@ impl Blockchain {
         }
         
         // Persist nonce (for recovery and non-Verkle mode)
-        if let Some(db) = &self.database {
-            use crate::storage::StateStore;
-            let state_store = StateStore::new(db);
-            state_store.put_nonce(&address, nonce)?;
+        self.persist_nonce(address, nonce)
+    }
+
+    /// Write a balance to storage, staged in the open block batch if any
+    fn persist_balance(&mut self, address: Address, balance: u128) -> crate::error::BlockchainResult<()> {
+        use crate::storage::StateStore;
+        if let Some(batch) = self.write_batch.as_mut() {
+            StateStore::stage_balance(batch, &address, balance);
+        } else if let Some(db) = &self.database {
+            StateStore::new(db).put_balance(&address, balance)?;
+        }
+        self.journal_adjustment(address, crate::storage::StateAdjustment::Balance(balance))
+    }
+
+    /// Write a nonce to storage, staged in the open block batch if any
+    fn persist_nonce(&mut self, address: Address, nonce: u64) -> crate::error::BlockchainResult<()> {
+        use crate::storage::StateStore;
+        if let Some(batch) = self.write_batch.as_mut() {
+            StateStore::stage_nonce(batch, &address, nonce);
+        } else if let Some(db) = &self.database {
+            StateStore::new(db).put_nonce(&address, nonce)?;
+        }
+        self.journal_adjustment(address, crate::storage::StateAdjustment::Nonce(nonce))
+    }
+
+    /// Journal a state change made outside block application, so a replay of
+    /// the blocks (`db-check`) can reproduce it
+    fn journal_adjustment(&mut self, address: Address, adjustment: crate::storage::StateAdjustment) -> crate::error::BlockchainResult<()> {
+        use crate::storage::ChainStore;
+        if self.undo.is_some() {
+            return Ok(());
+        }
+        let sequence = self.blocks.len() as u64;
+        if let Some(batch) = self.write_batch.as_mut() {
+            ChainStore::stage_adjustment(batch, sequence, &address, adjustment)?;
+        } else if let Some(db) = &self.database {
+            ChainStore::new(db).put_adjustment(sequence, &address, adjustment)?;
         }
-        
         Ok(())
     }
 
@@ -973,9 +1685,15 @@ impl Blockchain {
         &self.ghostdag
     }
 
-    /// Get blocks in consensus order (from GhostDAG)
-    pub fn get_ordered_blocks(&self) -> Vec<&Block> {
-        self.ghostdag.get_ordered_blocks()
+    /// Get blocks in consensus order (from GhostDAG), oldest first
+    ///
+    /// Red blocks are included where GhostDAG merges them. Every block's
+    /// transactions were checked against the state when it was accepted, so
+    /// a red block never carries a transaction conflicting with a blue one.
+    pub fn get_ordered_blocks(&self) -> Vec<Block> {
+        self.ghostdag.get_merged_ordering().iter()
+            .filter_map(|hash| self.load_block(hash, false))
+            .collect()
     }
 
     /// Get DAG statistics
@@ -998,6 +1716,63 @@ impl Blockchain {
         self.verkle_state.as_ref().map(|v| v.state_root())
     }
     
+    /// Commitment to the account state: the Verkle root when enabled, otherwise
+    /// a Merkle root over the accounts and contract code sorted by address
+    /// (see `partition_entries`)
+    pub fn state_commitment(&self) -> crate::types::Hash {
+        if let Some(root) = self.state_root() {
+            return root;
+        }
+        crate::sharding::reshard::partition_root(&self.partition_entries())
+    }
+
+    /// In-memory accounts with their contract code, sorted by address
+    pub fn partition_entries(&self) -> Vec<crate::sharding::reshard::PartitionEntry> {
+        let contracts = self.evm_executor.as_ref()
+            .map(|executor| executor.state().contracts())
+            .unwrap_or_default();
+        crate::sharding::reshard::partition_entries(self.account_entries(), contracts)
+    }
+
+    /// Contract code deployed at an address
+    pub fn contract_code(&self, address: &Address) -> Option<Vec<u8>> {
+        self.evm_executor.as_ref().and_then(|executor| executor.state().get_contract_code(*address))
+    }
+
+    /// Install contract code at an address (migrated or restored, not executed)
+    pub fn set_contract_code(&mut self, address: Address, code: Vec<u8>) {
+        if let Some(executor) = &self.evm_executor {
+            executor.state().store_contract(address, code);
+        }
+    }
+
+    /// In-memory accounts as (address, balance, nonce), sorted by address
+    pub fn account_entries(&self) -> Vec<(Address, u128, u64)> {
+        if let Some(verkle) = &self.verkle_state {
+            return verkle.accounts();
+        }
+        let mut addresses: Vec<Address> = self.balances.keys().chain(self.nonces.keys()).copied().collect();
+        addresses.sort();
+        addresses.dedup();
+        addresses.into_iter()
+            .map(|address| (
+                address,
+                self.balances.get(&address).copied().unwrap_or(0),
+                self.nonces.get(&address).copied().unwrap_or(0),
+            ))
+            .collect()
+    }
+
+    /// Drop an account and its contract code from the in-memory state (it
+    /// migrated to another shard)
+    pub fn remove_account(&mut self, address: &Address) {
+        self.balances.remove(address);
+        self.nonces.remove(address);
+        if let Some(executor) = &self.evm_executor {
+            executor.state().remove_account(address);
+        }
+    }
+    
     /// Get balance with proof (for light clients)
     pub fn get_balance_with_proof(&self, address: Address) -> Option<(u128, crate::verkle::StateProof)> {
         self.verkle_state.as_ref().and_then(|verkle| {
@@ -1026,3 +1801,96 @@ impl Blockchain {
     }
 }
 
+/// Blocks read per page by `BlockIter`
+const BLOCK_PAGE_SIZE: usize = 64;
+
+/// Accepted blocks in acceptance order, read a page at a time
+///
+/// Bodies come from the block cache when present and from storage otherwise;
+/// bodies read from storage are not cached. Blocks whose body cannot be read
+/// are skipped.
+pub struct BlockIter<'a> {
+    chain: &'a Blockchain,
+    /// Positions `front..back` are not yet paged in
+    front: usize,
+    back: usize,
+    front_page: VecDeque<Block>,
+    back_page: VecDeque<Block>,
+}
+
+impl BlockIter<'_> {
+    fn page(&self, start: usize, end: usize) -> VecDeque<Block> {
+        self.chain.blocks[start..end].iter()
+            .filter_map(|meta| self.chain.load_block(&meta.hash, false))
+            .collect()
+    }
+}
+
+impl Iterator for BlockIter<'_> {
+    type Item = Block;
+
+    fn next(&mut self) -> Option<Block> {
+        loop {
+            if let Some(block) = self.front_page.pop_front() {
+                return Some(block);
+            }
+            if self.front == self.back {
+                return self.back_page.pop_front();
+            }
+            let end = (self.front + BLOCK_PAGE_SIZE).min(self.back);
+            self.front_page = self.page(self.front, end);
+            self.front = end;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let paged = self.front_page.len() + self.back_page.len();
+        (paged, Some(paged + self.back - self.front))
+    }
+}
+
+impl DoubleEndedIterator for BlockIter<'_> {
+    fn next_back(&mut self) -> Option<Block> {
+        loop {
+            if let Some(block) = self.back_page.pop_back() {
+                return Some(block);
+            }
+            if self.front == self.back {
+                return self.front_page.pop_back();
+            }
+            let start = self.back.saturating_sub(BLOCK_PAGE_SIZE).max(self.front);
+            self.back_page = self.page(start, self.back);
+            self.back = start;
+        }
+    }
+}
+
+
+/// Order blocks from a pre-index database so parents come before children
+///
+/// Blocks are taken by (block number, timestamp, hash); a block waits until at
+/// least one of its parents is placed. Blocks whose parents never appear are
+/// left out.
+fn order_legacy_blocks(mut blocks: Vec<Block>) -> Vec<Block> {
+    blocks.sort_by(|a, b| a.header.block_number.cmp(&b.header.block_number)
+        .then(a.header.timestamp.cmp(&b.header.timestamp))
+        .then(a.hash.cmp(&b.hash)));
+    let mut placed: HashSet<crate::types::Hash> = HashSet::new();
+    let mut ordered = Vec::with_capacity(blocks.len());
+    loop {
+        let before = ordered.len();
+        let (ready, waiting): (Vec<Block>, Vec<Block>) = blocks.into_iter().partition(|block| {
+            block.header.block_number == 0
+                || block.header.parent_hashes.iter().any(|parent| placed.contains(parent))
+        });
+        for block in ready {
+            placed.insert(block.hash);
+            ordered.push(block);
+        }
+        blocks = waiting;
+        if blocks.is_empty() || ordered.len() == before {
+            break;
+        }
+    }
+    ordered
+}
@@ -0,0 +1,126 @@
+//! Block body pruning
+//!
+//! Bodies are pruned in acceptance order, so the pruned blocks are always a
+//! prefix of the block index (recorded as `chain:pruned`). A pruned block keeps
+//! its header, height index entry and reward; its body and its transaction and
+//! address index entries are deleted. Account state is stored as current
+//! values only, so there are no per-block state diffs to drop.
+
+use super::Blockchain;
+use crate::error::BlockchainResult;
+use crate::storage::{BlockStore, ChainStore, IndexStore, WriteBatch};
+
+/// How much history keeps its bodies
+///
+/// A block is pruned once it is outside every enabled window; 0 disables a
+/// window and with both disabled nothing is pruned. The latest block is never
+/// pruned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Retention {
+    /// Blue-score units below the highest blue score
+    pub blue_score: u64,
+    /// Seconds before now
+    pub age_secs: u64,
+}
+
+impl Retention {
+    fn retains(&self, score: u64, timestamp: u64, tip_score: u64, now: u64) -> bool {
+        if self.blue_score == 0 && self.age_secs == 0 {
+            return true;
+        }
+        (self.blue_score > 0 && score.saturating_add(self.blue_score) >= tip_score)
+            || (self.age_secs > 0 && timestamp.saturating_add(self.age_secs) >= now)
+    }
+}
+
+/// Result of one pruning step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PruneProgress {
+    /// Bodies pruned by this step
+    pub pruned: usize,
+    /// Blocks pruned so far (the watermark)
+    pub watermark: usize,
+    /// Blocks currently outside the retention window
+    pub target: usize,
+}
+
+impl PruneProgress {
+    /// Nothing is left to prune under the current window
+    pub fn is_done(&self) -> bool {
+        self.watermark >= self.target
+    }
+}
+
+impl Blockchain {
+    /// Blocks whose bodies have been pruned (the oldest in acceptance order)
+    pub fn pruned_count(&self) -> usize {
+        self.pruned
+    }
+
+    /// Lowest block number whose body this node still holds (0 when unpruned)
+    pub fn retained_from(&self) -> u64 {
+        if self.pruned == 0 {
+            return 0;
+        }
+        self.blocks[self.pruned..].iter()
+            .map(|meta| meta.block_number)
+            .min()
+            .unwrap_or_else(|| self.latest_block_number() + 1)
+    }
+
+    /// Number of leading blocks outside the retention window at `now`
+    fn prune_target(&self, retention: &Retention, now: u64) -> usize {
+        let score = |meta: &super::BlockMeta| self.ghostdag.get_blue_score(&meta.hash).unwrap_or(meta.block_number);
+        let tip_score = self.blocks.iter().map(score).max().unwrap_or(0);
+        let outside = self.blocks.iter()
+            .position(|meta| retention.retains(score(meta), meta.timestamp, tip_score, now))
+            .unwrap_or(self.blocks.len());
+        outside.min(self.blocks.len().saturating_sub(1))
+    }
+
+    /// Prune at most `max_blocks` bodies outside the retention window
+    ///
+    /// Each step commits in one batch with the new watermark, so an interrupted
+    /// pass resumes where it stopped. Without storage nothing is pruned.
+    pub fn prune(&mut self, retention: &Retention, now: u64, max_blocks: usize) -> BlockchainResult<PruneProgress> {
+        let start = self.pruned;
+        let db = match &self.database {
+            Some(db) => db.clone(),
+            None => return Ok(PruneProgress { pruned: 0, watermark: start, target: start }),
+        };
+        let target = self.prune_target(retention, now).max(start);
+        let end = target.min(start.saturating_add(max_blocks));
+        if end == start {
+            return Ok(PruneProgress { pruned: 0, watermark: start, target });
+        }
+
+        let block_store = BlockStore::new(&db);
+        let mut index_store = IndexStore::new(&db);
+        let mut batch = WriteBatch::new();
+        for sequence in start..end {
+            let hash = self.blocks[sequence].hash;
+            if let Some(block) = block_store.get(&hash)? {
+                ChainStore::stage_header(&mut batch, &block)?;
+                index_store.stage_prune(&mut batch, &block)?;
+                BlockStore::stage_delete(&mut batch, &hash);
+            }
+        }
+        ChainStore::stage_pruned(&mut batch, end as u64);
+        db.apply(batch)?;
+
+        let mut cache = self.cache();
+        for meta in &self.blocks[start..end] {
+            cache.remove(&meta.hash);
+        }
+        drop(cache);
+        self.pruned = end;
+        Ok(PruneProgress { pruned: end - start, watermark: end, target })
+    }
+
+    /// Rebuild the Verkle tree from live accounts (see `VerkleState::compact`)
+    ///
+    /// Returns the tree entries released, or None without Verkle state.
+    pub fn compact_state(&mut self) -> Option<usize> {
+        self.verkle_state.as_mut().map(|verkle| verkle.compact())
+    }
+}
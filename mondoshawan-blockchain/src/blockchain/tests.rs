@@ -5,6 +5,7 @@ mod tests {
     use super::*;
     use crate::blockchain::{Blockchain, Block, BlockHeader, Transaction};
     use crate::types::{Address, StreamType};
+    use std::sync::Arc;
 
     #[test]
     fn test_genesis_block() {
@@ -104,5 +105,349 @@ mod tests {
         // Try to add same block again
         assert!(blockchain.add_block(genesis).is_err());
     }
-}
 
+    #[test]
+    fn test_header_version_checked() {
+        let mut blockchain = Blockchain::new();
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let genesis_hash = genesis.hash;
+        blockchain.add_block(genesis).unwrap();
+
+        let mut future = BlockHeader::new(vec![genesis_hash], 1, StreamType::StreamA, 4);
+        future.version = BlockHeader::VERSION + 1;
+        assert!(blockchain.add_block(Block::new(future, vec![], vec![genesis_hash])).is_err());
+
+        // Legacy headers cannot carry the newer fields
+        let mut legacy = BlockHeader::new(vec![genesis_hash], 1, StreamType::StreamA, 4).with_coinbase([3u8; 20]);
+        legacy.version = 1;
+        legacy.ordering_policy = None;
+        assert!(blockchain.add_block(Block::new(legacy.clone(), vec![], vec![genesis_hash])).is_err());
+        legacy.coinbase = None;
+        assert!(blockchain.add_block(Block::new(legacy, vec![], vec![genesis_hash])).is_ok());
+    }
+
+    #[test]
+    fn test_late_merged_block_is_red_whatever_the_arrival_order() {
+        use crate::consensus::{GHOSTDAG_K, RED_BLOCK_REWARD_PERCENT};
+        use crate::mining::STREAM_A_REWARD;
+        let side_miner: Address = [4u8; 20];
+        let main_miner: Address = [5u8; 20];
+        let mined = |parents: Vec<_>, number, coinbase| {
+            let header = BlockHeader::new(parents, number, StreamType::StreamA, 4).with_coinbase(coinbase);
+            Block::new(header.clone(), vec![], header.parent_hashes)
+        };
+
+        // A side block on genesis, merged only after more than K chain blocks
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let side = mined(vec![genesis.hash], 1, side_miner);
+        let mut chain = vec![mined(vec![genesis.hash], 1, main_miner)];
+        for number in 2..=(GHOSTDAG_K as u64 + 1) {
+            chain.push(mined(vec![chain.last().unwrap().hash], number, main_miner));
+        }
+        let merging = mined(vec![chain.last().unwrap().hash, side.hash], GHOSTDAG_K as u64 + 2, main_miner);
+
+        // Side block first: it starts out blue and turns red as the chain grows
+        let mut side_first = Blockchain::new();
+        side_first.add_block(genesis.clone()).unwrap();
+        side_first.add_mined_block(side.clone()).unwrap();
+        assert_eq!(side_first.get_balance(side_miner), STREAM_A_REWARD);
+        for block in chain.iter().chain([&merging]) {
+            side_first.add_mined_block(block.clone()).unwrap();
+        }
+
+        // Side block last: red from the start
+        let mut side_last = Blockchain::new();
+        side_last.add_block(genesis.clone()).unwrap();
+        for block in chain.iter().chain([&side, &merging]) {
+            side_last.add_mined_block(block.clone()).unwrap();
+        }
+
+        let red_reward = STREAM_A_REWARD * RED_BLOCK_REWARD_PERCENT / 100;
+        for blockchain in [&side_first, &side_last] {
+            assert!(blockchain.ghostdag().is_red(&side.hash));
+            assert!(blockchain.is_blue_block(&merging.hash));
+            assert_eq!(blockchain.get_block_reward(&side.hash), Some(red_reward));
+            assert_eq!(blockchain.get_balance(side_miner), red_reward);
+            assert_eq!(blockchain.get_balance(main_miner), STREAM_A_REWARD * (GHOSTDAG_K as u128 + 2));
+        }
+        assert_eq!(side_first.state_commitment(), side_last.state_commitment());
+
+        // Relayed blocks pay no coinbase
+        let mut relayed = Blockchain::new();
+        for block in std::iter::once(&genesis).chain(&chain) {
+            relayed.add_block(block.clone()).unwrap();
+        }
+        assert_eq!(relayed.get_balance(main_miner), 0);
+        assert_eq!(relayed.get_block_reward(&chain[0].hash), None);
+    }
+
+    #[test]
+    fn test_failed_block_leaves_state_untouched() {
+        let (secret_key, sender) = Transaction::test_signer(1);
+        let receiver: Address = [2u8; 20];
+        let miner: Address = [3u8; 20];
+        
+        let mut blockchain = Blockchain::new();
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let genesis_hash = genesis.hash;
+        blockchain.add_block(genesis).unwrap();
+        blockchain.set_balance(sender, 1000).unwrap();
+        let commitment = blockchain.state_commitment();
+        let tips = blockchain.ghostdag().get_tips();
+        
+        // The first transaction applies, the second has a gap in its nonce
+        let valid = Transaction::new(sender, receiver, 100, 10, 0).sign(&secret_key);
+        let gapped = Transaction::new(sender, receiver, 100, 10, 2).sign(&secret_key);
+        let header = BlockHeader::new(vec![genesis_hash], 1, StreamType::StreamA, 4).with_coinbase(miner);
+        let block = Block::new(header.clone(), vec![valid.clone(), gapped], vec![genesis_hash]);
+        assert!(blockchain.add_block(block.clone()).is_err());
+        
+        assert_eq!(blockchain.state_commitment(), commitment);
+        assert_eq!(blockchain.get_balance(sender), 1000);
+        assert_eq!(blockchain.get_balance(receiver), 0);
+        assert_eq!(blockchain.get_balance(miner), 0);
+        assert_eq!(blockchain.get_nonce(sender), 0);
+        assert_eq!(blockchain.get_block_reward(&block.hash), None);
+        assert_eq!(blockchain.ghostdag().get_tips(), tips);
+        assert!(blockchain.get_fairness_range(1, 1, 10).unwrap().is_empty());
+        
+        // The valid transaction still goes through on its own
+        let block = Block::new(header, vec![valid], vec![genesis_hash]);
+        blockchain.add_block(block).unwrap();
+        assert_eq!(blockchain.get_balance(sender), 890);
+        assert_eq!(blockchain.get_balance(receiver), 100);
+        assert_eq!(blockchain.get_nonce(sender), 1);
+    }
+
+    #[test]
+    fn test_fairness_history_pages_past_the_limit() {
+        let mut blockchain = Blockchain::new();
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let mut parent = genesis.hash;
+        blockchain.add_block(genesis).unwrap();
+        for number in 1..=4 {
+            let header = BlockHeader::new(vec![parent], number, StreamType::StreamA, 4).with_coinbase([3u8; 20]);
+            let block = Block::new(header, vec![], vec![parent]);
+            parent = block.hash;
+            blockchain.add_block(block).unwrap();
+        }
+        
+        let (first, next) = blockchain.get_fairness_page(0, 4, None, 3).unwrap();
+        assert_eq!(first.len(), 3);
+        let next = next.expect("range holds more records");
+        assert_eq!(next.0, 3);
+        let (rest, next) = blockchain.get_fairness_page(0, 4, Some(next), 3).unwrap();
+        assert_eq!(rest.iter().map(|r| r.block_number).collect::<Vec<_>>(), vec![3, 4]);
+        assert!(next.is_none());
+        
+        let (groups, count) = blockchain.aggregate_fairness(0, 4, |r| r.miner).unwrap();
+        assert_eq!(count, 5);
+        assert_eq!(groups[&Some([3u8; 20])].block_count, 4);
+    }
+
+    #[test]
+    fn test_restart_restores_chain_and_state() {
+        let database = Arc::new(crate::storage::Database::in_memory());
+        let (secret_key, sender) = Transaction::test_signer(1);
+        let receiver: Address = [2u8; 20];
+        let miner: Address = [3u8; 20];
+        
+        let (tip, commitment) = {
+            let mut blockchain = Blockchain::with_storage(database.clone()).unwrap();
+            let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+            let genesis_hash = genesis.hash;
+            blockchain.add_block(genesis).unwrap();
+            blockchain.set_balance(sender, 1000).unwrap();
+            
+            let tx = Transaction::new(sender, receiver, 100, 10, 0).sign(&secret_key);
+            let header = BlockHeader::new(vec![genesis_hash], 1, StreamType::StreamA, 4).with_coinbase(miner);
+            let block = Block::new(header, vec![tx], vec![genesis_hash]);
+            let tip = block.hash;
+            blockchain.add_mined_block(block).unwrap();
+            (tip, blockchain.state_commitment())
+        };
+        
+        let blockchain = Blockchain::with_storage(database).unwrap();
+        assert_eq!(blockchain.block_count(), 2);
+        assert_eq!(blockchain.block_metas().last().unwrap().hash, tip);
+        assert_eq!(blockchain.get_ordered_blocks().len(), 2);
+        assert!(blockchain.get_block_reward(&tip).unwrap() > 0);
+        assert_eq!(blockchain.get_balance(sender), 890);
+        assert_eq!(blockchain.get_nonce(sender), 1);
+        assert_eq!(blockchain.state_commitment(), commitment);
+    }
+
+    #[test]
+    fn test_incomplete_block_rolled_back_on_startup() {
+        use crate::storage::{BlockStore, ChainStore};
+        let database = Arc::new(crate::storage::Database::in_memory());
+        
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let genesis_hash = genesis.hash;
+        Blockchain::with_storage(database.clone()).unwrap().add_block(genesis).unwrap();
+        
+        // Body and index entry written without the tip, as by an interrupted block
+        let orphan = Block::new(BlockHeader::new(vec![genesis_hash], 1, StreamType::StreamA, 4), vec![], vec![genesis_hash]);
+        BlockStore::new(&database).put(&orphan).unwrap();
+        ChainStore::new(&database).put_index(1, &orphan.hash).unwrap();
+        
+        let mut blockchain = Blockchain::with_storage(database.clone()).unwrap();
+        assert_eq!(blockchain.block_count(), 1);
+        assert_eq!(ChainStore::new(&database).get_index().unwrap(), vec![genesis_hash]);
+        assert!(BlockStore::new(&database).get(&orphan.hash).unwrap().is_none());
+        
+        // The rolled back block can be applied again
+        blockchain.add_block(orphan.clone()).unwrap();
+        let blockchain = Blockchain::with_storage(database).unwrap();
+        assert_eq!(blockchain.block_metas().last().unwrap().hash, orphan.hash);
+    }
+
+    #[test]
+    fn test_secondary_indexes_and_reindex() {
+        use crate::storage::IndexStore;
+        let database = Arc::new(crate::storage::Database::in_memory());
+        let (secret_key, sender) = Transaction::test_signer(1);
+        let receiver: Address = [2u8; 20];
+        
+        let mut blockchain = Blockchain::with_storage(database.clone()).unwrap();
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let genesis_hash = genesis.hash;
+        blockchain.add_block(genesis).unwrap();
+        blockchain.set_balance(sender, 1000).unwrap();
+        
+        // Two blocks at height 1 (DAG siblings)
+        let txs: Vec<Transaction> = (0..3).map(|nonce| Transaction::new(sender, receiver, 10, 1, nonce).sign(&secret_key)).collect();
+        let first = Block::new(BlockHeader::new(vec![genesis_hash], 1, StreamType::StreamA, 4), txs[..2].to_vec(), vec![genesis_hash]);
+        let second = Block::new(BlockHeader::new(vec![genesis_hash], 1, StreamType::StreamB, 4), txs[2..].to_vec(), vec![genesis_hash]);
+        blockchain.add_block(first.clone()).unwrap();
+        blockchain.add_block(second.clone()).unwrap();
+        
+        let at_height: Vec<_> = blockchain.get_blocks_by_number(1).iter().map(|b| b.hash).collect();
+        assert_eq!(at_height.len(), 2);
+        assert!(at_height.contains(&first.hash) && at_height.contains(&second.hash));
+        
+        let (block, index) = blockchain.get_transaction_by_hash(&txs[1].hash).unwrap();
+        assert_eq!((block.hash, index), (first.hash, 1));
+        
+        // Newest first, paginated
+        let (total, page) = blockchain.get_address_transactions(receiver, 1, 1).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(page[0].0.transactions[page[0].1].hash, txs[1].hash);
+        let (_, page) = blockchain.get_address_transactions(receiver, 2, 5).unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].0.transactions[page[0].1].hash, txs[0].hash);
+        assert!(blockchain.get_address_transactions(receiver, 3, 5).unwrap().1.is_empty());
+        
+        // A wiped index is rebuilt on startup
+        IndexStore::new(&database).clear().unwrap();
+        assert!(blockchain.get_transaction_by_hash(&txs[2].hash).is_none());
+        let blockchain = Blockchain::with_storage(database).unwrap();
+        assert_eq!(blockchain.get_transaction_by_hash(&txs[2].hash).unwrap().0.hash, second.hash);
+        assert_eq!(blockchain.reindex().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_address_index_counts_survive_pruning() {
+        use crate::storage::{IndexStore, WriteBatch};
+        let database = crate::storage::Database::in_memory();
+        let (sender, receiver) = ([1u8; 20], [2u8; 20]);
+        let mut blocks = Vec::new();
+        for number in 0..3u64 {
+            let txs = (0..2).map(|nonce| Transaction::new(sender, receiver, 1, 1, number * 2 + nonce)).collect();
+            blocks.push(Block::new(BlockHeader::new(vec![], number, StreamType::StreamA, 4), txs, vec![]));
+        }
+        let mut index_store = IndexStore::new(&database);
+        let mut batch = WriteBatch::new();
+        for (sequence, block) in blocks.iter().enumerate() {
+            index_store.stage_block(&mut batch, sequence as u64, block).unwrap();
+        }
+        database.apply(batch).unwrap();
+        
+        let index_store = IndexStore::new(&database);
+        let (total, page) = index_store.get_address_transactions(&receiver, 1, 2).unwrap();
+        assert_eq!(total, 6);
+        assert_eq!(page, vec![blocks[2].transactions[0].hash, blocks[1].transactions[1].hash]);
+        
+        let mut index_store = IndexStore::new(&database);
+        let mut batch = WriteBatch::new();
+        index_store.stage_prune(&mut batch, &blocks[0]).unwrap();
+        database.apply(batch).unwrap();
+        let index_store = IndexStore::new(&database);
+        let (total, page) = index_store.get_address_transactions(&sender, 3, 10).unwrap();
+        assert_eq!(total, 4);
+        assert_eq!(page, vec![blocks[1].transactions[0].hash]);
+    }
+
+    #[test]
+    fn test_block_bodies_paged_from_storage() {
+        let database = Arc::new(crate::storage::Database::in_memory());
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let body_size = bincode::serialized_size(&genesis).unwrap() as usize;
+        // Room for about two bodies
+        let mut blockchain = Blockchain::with_storage(database).unwrap().with_block_cache(body_size * 2 + 16);
+        
+        let mut hashes = vec![genesis.hash];
+        blockchain.add_block(genesis).unwrap();
+        for number in 1..100 {
+            let parent = *hashes.last().unwrap();
+            let block = Block::new(BlockHeader::new(vec![parent], number, StreamType::StreamA, 4), vec![], vec![parent]);
+            hashes.push(block.hash);
+            blockchain.add_block(block).unwrap();
+        }
+        assert!(blockchain.cache().len() <= 2);
+        
+        // Evicted bodies are read back from storage
+        assert_eq!(blockchain.get_block_by_hash(&hashes[0]).unwrap().hash, hashes[0]);
+        assert_eq!(blockchain.get_block_by_number(50).unwrap().hash, hashes[50]);
+        assert_eq!(blockchain.latest_block().unwrap().hash, hashes[99]);
+        
+        let forward: Vec<_> = blockchain.blocks().map(|block| block.hash).collect();
+        assert_eq!(forward, hashes);
+        let backward: Vec<_> = blockchain.blocks().rev().map(|block| block.hash).collect();
+        assert_eq!(backward, hashes.iter().rev().cloned().collect::<Vec<_>>());
+        // Both ends meet in the middle
+        let mut iter = blockchain.blocks();
+        assert_eq!(iter.next().unwrap().hash, hashes[0]);
+        assert_eq!(iter.next_back().unwrap().hash, hashes[99]);
+        assert_eq!(iter.count(), 98);
+        assert_eq!(blockchain.get_ordered_blocks().len(), 100);
+    }
+
+    #[test]
+    fn test_prune_keeps_headers_and_survives_restart() {
+        use crate::blockchain::Retention;
+        use crate::storage::{BlockStore, ChainStore};
+        let database = Arc::new(crate::storage::Database::in_memory());
+        let mut blockchain = Blockchain::with_storage(database.clone()).unwrap();
+        
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        let mut hashes = vec![genesis.hash];
+        blockchain.add_block(genesis).unwrap();
+        for number in 1..10 {
+            let parent = *hashes.last().unwrap();
+            let block = Block::new(BlockHeader::new(vec![parent], number, StreamType::StreamA, 4), vec![], vec![parent]);
+            hashes.push(block.hash);
+            blockchain.add_block(block).unwrap();
+        }
+        
+        // Keep the last three blue-score units, two blocks per step
+        let retention = Retention { blue_score: 3, age_secs: 0 };
+        let first = blockchain.prune(&retention, 0, 2).unwrap();
+        assert_eq!((first.pruned, first.watermark), (2, 2));
+        assert!(!first.is_done());
+        while !blockchain.prune(&retention, 0, 2).unwrap().is_done() {}
+        let pruned = blockchain.pruned_count();
+        assert!(pruned > 0 && pruned < hashes.len());
+        assert_eq!(blockchain.retained_from(), pruned as u64);
+        
+        assert!(BlockStore::new(&database).get(&hashes[0]).unwrap().is_none());
+        assert!(ChainStore::new(&database).get_header(&hashes[0]).unwrap().is_some());
+        assert!(blockchain.get_block_by_hash(&hashes[pruned]).is_some());
+        
+        let blockchain = Blockchain::with_storage(database).unwrap();
+        assert_eq!(blockchain.block_count(), hashes.len());
+        assert_eq!(blockchain.pruned_count(), pruned);
+        assert_eq!(blockchain.latest_block().unwrap().hash, hashes[9]);
+        assert_eq!(blockchain.blocks().count(), hashes.len() - pruned);
+    }
+}
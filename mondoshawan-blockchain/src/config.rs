@@ -2,11 +2,15 @@
 //! 
 //! Provides configuration loading and validation for the node.
 
+use crate::sharding::{AssignmentStrategy, ShardConfig};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 /// Node configuration
+///
+/// Missing keys take their defaults, so a file only needs the values it changes.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NodeConfig {
     /// Data directory for blockchain storage
     pub data_dir: PathBuf,
@@ -46,6 +50,256 @@ pub struct NodeConfig {
     
     /// Maximum block size (bytes)
     pub max_block_size: usize,
+    
+    /// Sharding (`[sharding]` table)
+    pub sharding: ShardingConfig,
+    
+    /// Pruning (`[pruning]` table)
+    pub pruning: PruningConfig,
+    
+    /// JSON-RPC HTTP server (`[rpc]` table)
+    pub rpc: RpcConfig,
+    
+    /// Pool-operator mode (`[pool]` table)
+    pub pool: PoolOperatorConfig,
+}
+
+/// Sharding configuration
+///
+/// `shard_count`, `assignment_strategy`, `enable_cross_shard` and
+/// `epoch_length` are network parameters: they are recorded in the database at
+/// first start and must match on every later start (see `ShardGenesis`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShardingConfig {
+    /// Enable sharding
+    pub enabled: bool,
+    
+    /// Number of shards at genesis (a completed reshard overrides it)
+    pub shard_count: usize,
+    
+    /// Settle transfers between shards with receipts
+    pub enable_cross_shard: bool,
+    
+    /// How accounts are assigned to shards
+    pub assignment_strategy: AssignmentStrategy,
+    
+    /// Beacon blocks per sharding epoch
+    pub epoch_length: u64,
+    
+    /// Seconds a cross-shard receipt may wait before it is refunded
+    pub receipt_timeout_secs: u64,
+    
+    /// Shards this node validates and mines (empty = all shards)
+    pub tracked_shards: Vec<usize>,
+    
+    /// Only report rebalance proposals; false migrates the proposed ranges
+    pub rebalance_dry_run: bool,
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shard_count: 10,
+            enable_cross_shard: true,
+            assignment_strategy: AssignmentStrategy::ConsistentHashing,
+            epoch_length: crate::sharding::reshard::DEFAULT_EPOCH_LENGTH,
+            receipt_timeout_secs: crate::sharding::receipts::DEFAULT_RECEIPT_TIMEOUT_SECS,
+            tracked_shards: Vec::new(),
+            rebalance_dry_run: true,
+        }
+    }
+}
+
+impl ShardingConfig {
+    /// Shard manager configuration
+    pub fn shard_config(&self) -> ShardConfig {
+        ShardConfig {
+            shard_count: self.shard_count,
+            enable_cross_shard: self.enable_cross_shard,
+            assignment_strategy: self.assignment_strategy.clone(),
+        }
+    }
+    
+    /// Validate sharding configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        
+        if self.shard_count == 0 {
+            return Err("Shard count must be greater than 0".to_string());
+        }
+        
+        if self.epoch_length == 0 {
+            return Err("Epoch length must be greater than 0".to_string());
+        }
+        
+        if self.receipt_timeout_secs == 0 {
+            return Err("Receipt timeout must be greater than 0".to_string());
+        }
+        
+        if let Some(shard_id) = self.tracked_shards.iter().find(|id| **id >= self.shard_count) {
+            return Err(format!("Tracked shard {} does not exist ({} shards)", shard_id, self.shard_count));
+        }
+        
+        Ok(())
+    }
+
+}
+
+/// Pruning configuration
+///
+/// Block bodies outside every enabled retention window are deleted in the
+/// background; headers are kept forever. A window of 0 is disabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PruningConfig {
+    /// Enable background pruning
+    pub enabled: bool,
+    
+    /// Keep bodies within this many blue-score units of the highest blue score
+    pub retain_blue_score: u64,
+    
+    /// Keep bodies of blocks younger than this many days
+    pub retain_days: u64,
+    
+    /// Seconds between pruning passes
+    pub interval_secs: u64,
+    
+    /// Blocks pruned per storage batch
+    pub batch_size: usize,
+}
+
+impl Default for PruningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retain_blue_score: 100_000,
+            retain_days: 0,
+            interval_secs: 600,
+            batch_size: 256,
+        }
+    }
+}
+
+impl PruningConfig {
+    /// Retention window passed to `Blockchain::prune`
+    pub fn retention(&self) -> crate::blockchain::Retention {
+        crate::blockchain::Retention {
+            blue_score: self.retain_blue_score,
+            age_secs: self.retain_days.saturating_mul(24 * 60 * 60),
+        }
+    }
+    
+    /// Validate pruning configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        
+        if self.retain_blue_score == 0 && self.retain_days == 0 {
+            return Err("Pruning needs retain_blue_score or retain_days".to_string());
+        }
+        
+        if self.interval_secs == 0 {
+            return Err("Pruning interval must be greater than 0".to_string());
+        }
+        
+        if self.batch_size == 0 {
+            return Err("Pruning batch size must be greater than 0".to_string());
+        }
+        
+        Ok(())
+    }
+}
+
+/// JSON-RPC HTTP server configuration
+///
+/// Bodies are also checked against `SecurityHardening::check_request_size`
+/// when hardening is enabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RpcConfig {
+    /// Origins allowed by CORS (`*` allows any origin, empty disables CORS)
+    pub cors_origins: Vec<String>,
+    
+    /// Largest accepted request body (bytes)
+    pub max_body_size: usize,
+    
+    /// Seconds an idle keep-alive connection is held open (0 closes after each response)
+    pub keep_alive_secs: u64,
+    
+    /// Most calls accepted in one JSON-RPC batch
+    pub max_batch_size: usize,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            cors_origins: vec!["*".to_string()],
+            max_body_size: 10 * 1024 * 1024, // 10MB
+            keep_alive_secs: 30,
+            max_batch_size: 100,
+        }
+    }
+}
+
+impl RpcConfig {
+    /// Validate RPC server configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_body_size == 0 {
+            return Err("RPC max body size must be greater than 0".to_string());
+        }
+        
+        if self.max_batch_size == 0 {
+            return Err("RPC max batch size must be greater than 0".to_string());
+        }
+        
+        if let Some(origin) = self.cors_origins.iter().find(|origin| origin.trim().is_empty()) {
+            return Err(format!("Invalid CORS origin {:?}", origin));
+        }
+        
+        Ok(())
+    }
+}
+
+/// Pool-operator configuration
+///
+/// Setting `payout_key_file` switches the node to pool-operator mode; the
+/// miner address becomes the address of that key. The pool settings (fee,
+/// PPLNS window, share target, payouts) sit in the same table.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolOperatorConfig {
+    /// File holding the hex-encoded Ed25519 payout key (None = solo mining)
+    pub payout_key_file: Option<PathBuf>,
+    
+    /// Pool settings
+    #[serde(flatten)]
+    pub settings: crate::mining::pool::PoolConfig,
+}
+
+impl PoolOperatorConfig {
+    /// Read the payout key, if one is configured
+    pub fn load_payout_key(&self) -> Result<Option<[u8; 32]>, String> {
+        match &self.payout_key_file {
+            Some(path) => read_payout_key(path).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Read a hex-encoded 32-byte Ed25519 payout key (`0x` prefix optional)
+pub fn read_payout_key(path: &std::path::Path) -> Result<[u8; 32], String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read payout key file {}: {}", path.display(), e))?;
+    let content = content.trim();
+    let bytes = hex::decode(content.strip_prefix("0x").unwrap_or(content))
+        .map_err(|e| format!("Payout key file {} is not hex: {}", path.display(), e))?;
+    bytes.try_into()
+        .map_err(|_| format!("Payout key in {} must be 32 bytes", path.display()))
 }
 
 impl Default for NodeConfig {
@@ -64,6 +318,10 @@ impl Default for NodeConfig {
             rpc_rate_limit: 100,
             max_tx_pool_size: 10_000,
             max_block_size: 10_000_000, // 10MB
+            sharding: ShardingConfig::default(),
+            pruning: PruningConfig::default(),
+            rpc: RpcConfig::default(),
+            pool: PoolOperatorConfig::default(),
         }
     }
 }
@@ -121,6 +379,16 @@ impl NodeConfig {
             return Err("Max block size must be greater than 0".to_string());
         }
         
+        if self.pool.payout_key_file.as_ref().is_some_and(|path| path.as_os_str().is_empty()) {
+            return Err("Pool payout key file cannot be empty".to_string());
+        }
+        
+        self.pool.settings.validate()?;
+        
+        self.sharding.validate()?;
+        self.pruning.validate()?;
+        self.rpc.validate()?;
+        
         Ok(())
     }
 }
@@ -1,20 +1,62 @@
 //! GhostDAG Consensus Implementation
-//! 
+//!
 //! Full GhostDAG (BlockDAG) consensus algorithm based on Kaspa's protocol.
 //! Orders blocks in a DAG structure using blue score calculation.
+//!
+//! Every block selects the parent with the highest blue score and colors the
+//! rest of its mergeset (its past not already in the selected parent's past)
+//! with the k-cluster rule: a block is blue only if at most `GHOSTDAG_K` blue
+//! blocks lie in its anticone. The DAG's coloring is read off the selected
+//! chain of a virtual block above the current tips, so it depends only on the
+//! DAG, never on the order blocks arrived in. When the selected chain moves,
+//! only the chain blocks above the fork point are recolored; callers reconcile
+//! rewards with `recolored`.
 
-use crate::blockchain::Block;
+use crate::blockchain::{Block, BlockMeta};
 use crate::types::Hash;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+/// Maximum number of blue blocks in the anticone of a blue block (the `k` of
+/// the k-cluster rule)
+pub const GHOSTDAG_K: usize = 18;
+
+/// Share of the stream reward paid for a red (uncle) block, in percent
+pub const RED_BLOCK_REWARD_PERCENT: u128 = 50;
+
+/// Red blocks merged by a block more than this much blue score above them earn
+/// no reward (prevents mining stale side-branches for income)
+pub const MAX_RED_REWARD_DEPTH: u64 = 2 * GHOSTDAG_K as u64;
+
+/// GhostDAG data of one block, fixed once its parents are known
+struct GhostData {
+    blue_score: u64,
+    selected_parent: Option<Hash>,
+    /// Blue blocks of the mergeset, selected parent first
+    mergeset_blues: Vec<Hash>,
+    /// Mergeset without the selected parent, in consensus order
+    mergeset: Vec<Hash>,
+    /// Blue anticone sizes this block changed or introduced
+    blues_anticone_sizes: HashMap<Hash, usize>,
+}
 
 /// GhostDAG consensus engine
+///
+/// Holds block metadata only; bodies are looked up by the caller when needed.
 pub struct GhostDAG {
-    blocks: HashMap<Hash, Block>,
+    blocks: HashMap<Hash, BlockMeta>,
     children: HashMap<Hash, Vec<Hash>>,
+    data: HashMap<Hash, GhostData>, // GhostDAG data of blocks attached to genesis
     blue_set: HashSet<Hash>,      // Blue blocks (selected for consensus)
     red_set: HashSet<Hash>,        // Red blocks (not selected)
-    blue_score: HashMap<Hash, u64>, // Blue score for each block
     ordering: Vec<Hash>,           // Final block ordering
+    merged_ordering: Vec<Hash>,    // Blue ordering with red blocks merged in
+    tips: HashSet<Hash>,           // Blocks without children
+    chain: Vec<Hash>,              // Selected chain of the virtual block, genesis first
+    chain_index: HashMap<Hash, usize>, // Position of each selected chain block
+    chain_ends: Vec<(usize, usize)>, // Ordering lengths once each chain block is merged
+    virtual_data: Option<GhostData>, // GhostDAG data of the virtual block above the tips
+    reward_percents: HashMap<Hash, u128>, // Reward share under the current coloring
+    recolored: Vec<Hash>,          // Blocks whose reward share changed in the last update
 }
 
 impl GhostDAG {
@@ -22,126 +64,378 @@ impl GhostDAG {
         Self {
             blocks: HashMap::new(),
             children: HashMap::new(),
+            data: HashMap::new(),
             blue_set: HashSet::new(),
             red_set: HashSet::new(),
-            blue_score: HashMap::new(),
             ordering: Vec::new(),
+            merged_ordering: Vec::new(),
+            tips: HashSet::new(),
+            chain: Vec::new(),
+            chain_index: HashMap::new(),
+            chain_ends: Vec::new(),
+            virtual_data: None,
+            reward_percents: HashMap::new(),
+            recolored: Vec::new(),
         }
     }
 
     /// Add a block to the DAG and recalculate consensus
-    pub fn add_block(&mut self, block: Block) {
+    pub fn add_block(&mut self, block: &Block) {
         let hash = block.hash;
-        self.blocks.insert(hash, block.clone());
-        
-        // Build parent-child relationships
-        for parent_hash in &block.header.parent_hashes {
+        self.insert(block.meta());
+        self.update_blue_set(&[hash], &[]);
+    }
+
+    /// Take back a block added last (e.g. one whose application failed) and
+    /// recalculate consensus, restoring the coloring from before it was added
+    pub fn remove_block(&mut self, hash: &Hash) {
+        let Some(meta) = self.blocks.remove(hash) else {
+            return;
+        };
+        for parent_hash in &meta.parent_hashes {
+            if let Some(children) = self.children.get_mut(parent_hash) {
+                children.retain(|child| child != hash);
+                if children.is_empty() {
+                    self.children.remove(parent_hash);
+                    if self.blocks.contains_key(parent_hash) {
+                        self.tips.insert(*parent_hash);
+                    }
+                }
+            }
+        }
+        self.tips.remove(hash);
+
+        self.update_blue_set(&[], &[*hash]);
+    }
+
+    /// Rebuild the DAG from stored block metadata (parents before children),
+    /// recalculating consensus once instead of after every block
+    pub fn from_blocks(blocks: Vec<BlockMeta>) -> Self {
+        let mut dag = Self::new();
+        let hashes: Vec<Hash> = blocks.iter().map(|block| block.hash).collect();
+        for block in blocks {
+            dag.insert(block);
+        }
+        dag.update_blue_set(&hashes, &[]);
+        dag
+    }
+
+    /// Link a block into the DAG and compute its GhostDAG data from its parents
+    fn insert(&mut self, meta: BlockMeta) {
+        let hash = meta.hash;
+        for parent_hash in &meta.parent_hashes {
             self.children.entry(*parent_hash)
                 .or_insert_with(Vec::new)
                 .push(hash);
+            self.tips.remove(parent_hash);
         }
-        
-        // Recalculate blue set and ordering
-        self.update_blue_set();
+        self.tips.insert(hash);
+
+        // A block none of whose parents are attached to genesis is detached
+        let data = if meta.parent_hashes.is_empty() {
+            Some(GhostData {
+                blue_score: 1,
+                selected_parent: None,
+                mergeset_blues: Vec::new(),
+                mergeset: Vec::new(),
+                blues_anticone_sizes: HashMap::new(),
+            })
+        } else {
+            self.ghost_data(&meta.parent_hashes)
+        };
+        if let Some(data) = data {
+            self.data.insert(hash, data);
+        }
+        self.blocks.insert(hash, meta);
     }
 
-    /// Update blue set using GhostDAG algorithm
-    fn update_blue_set(&mut self) {
-        if self.blocks.is_empty() {
-            return;
+    /// Blue score of an attached block
+    fn score(&self, hash: &Hash) -> u64 {
+        self.data.get(hash).map(|data| data.blue_score).unwrap_or(0)
+    }
+
+    /// Whether `ancestor` is `descendant` or lies in its past
+    ///
+    /// Blue scores strictly increase from parent to child, so the search only
+    /// walks blocks scored above `ancestor`.
+    fn is_ancestor(&self, ancestor: &Hash, descendant: &Hash) -> bool {
+        let floor = self.score(ancestor);
+        let mut visited = HashSet::new();
+        let mut stack = vec![*descendant];
+        while let Some(hash) = stack.pop() {
+            if hash == *ancestor {
+                return true;
+            }
+            if !visited.insert(hash) || self.score(&hash) <= floor {
+                continue;
+            }
+            if let Some(meta) = self.blocks.get(&hash) {
+                stack.extend(meta.parent_hashes.iter().filter(|parent| self.data.contains_key(*parent)));
+            }
         }
-        
-        // Find genesis blocks (blocks with no parents)
-        let genesis_blocks: Vec<Hash> = self.blocks.iter()
-            .filter(|(_, block)| block.header.parent_hashes.is_empty())
-            .map(|(hash, _)| *hash)
+        false
+    }
+
+    /// GhostDAG data of a block (or the virtual block) with the given parents;
+    /// None if no parent is attached
+    fn ghost_data(&self, parents: &[Hash]) -> Option<GhostData> {
+        // Selected parent: highest blue score, lowest hash on ties
+        let selected_parent = parents.iter()
+            .filter(|parent| self.data.contains_key(*parent))
+            .max_by(|a, b| self.score(a).cmp(&self.score(b)).then(b.cmp(a)))
+            .copied()?;
+
+        // Mergeset: the past not covered by the selected parent's past. The
+        // selected parent's past is expanded only down to the lowest blue
+        // score a candidate has, highest score first.
+        let mut covered: HashSet<Hash> = HashSet::new();
+        let mut queued: HashSet<Hash> = HashSet::from([selected_parent]);
+        let mut frontier = BinaryHeap::from([(self.score(&selected_parent), selected_parent)]);
+        let mut mergeset = Vec::new();
+        let mut seen: HashSet<Hash> = HashSet::from([selected_parent]);
+        let mut queue: VecDeque<Hash> = parents.iter()
+            .filter(|parent| self.data.contains_key(*parent) && **parent != selected_parent)
+            .copied()
             .collect();
-        
-        if genesis_blocks.is_empty() {
-            return;
+        while let Some(hash) = queue.pop_front() {
+            if !seen.insert(hash) {
+                continue;
+            }
+            let score = self.score(&hash);
+            while frontier.peek().is_some_and(|(top, _)| *top >= score) {
+                let Some((_, next)) = frontier.pop() else { break };
+                covered.insert(next);
+                for parent in &self.blocks[&next].parent_hashes {
+                    if self.data.contains_key(parent) && queued.insert(*parent) {
+                        frontier.push((self.score(parent), *parent));
+                    }
+                }
+            }
+            if covered.contains(&hash) {
+                continue;
+            }
+            mergeset.push(hash);
+            queue.extend(self.blocks[&hash].parent_hashes.iter().filter(|parent| self.data.contains_key(*parent)));
         }
-        
-        // Reset blue set and scores
-        self.blue_score.clear();
-        self.blue_set.clear();
-        self.red_set.clear();
-        
-        // Initialize genesis blocks
-        for genesis_hash in &genesis_blocks {
-            self.blue_score.insert(*genesis_hash, 1);
-            self.blue_set.insert(*genesis_hash);
+        mergeset.sort_by_key(|hash| (self.score(hash), *hash));
+
+        let mut data = GhostData {
+            blue_score: 0,
+            selected_parent: Some(selected_parent),
+            mergeset_blues: vec![selected_parent],
+            mergeset,
+            blues_anticone_sizes: HashMap::from([(selected_parent, 0)]),
+        };
+        for candidate in data.mergeset.clone() {
+            if let Some((size, anticone)) = self.check_blue_candidate(&data, &candidate) {
+                data.mergeset_blues.push(candidate);
+                data.blues_anticone_sizes.insert(candidate, size);
+                for (blue, size) in anticone {
+                    data.blues_anticone_sizes.insert(blue, size + 1);
+                }
+            }
         }
-        
-        // BFS traversal to calculate blue scores
-        let mut queue = VecDeque::from(genesis_blocks);
-        let mut visited = HashSet::new();
-        
-        for genesis in &queue {
-            visited.insert(*genesis);
+        data.blue_score = self.score(&selected_parent) + data.mergeset_blues.len() as u64;
+        Some(data)
+    }
+
+    /// Check the k-cluster rule for a mergeset candidate of `data`'s block
+    ///
+    /// Returns the candidate's blue anticone size and the blues in its anticone
+    /// (with their current anticone sizes) if it can be blue.
+    fn check_blue_candidate(&self, data: &GhostData, candidate: &Hash) -> Option<(usize, HashMap<Hash, usize>)> {
+        if data.mergeset_blues.len() > GHOSTDAG_K {
+            return None;
         }
-        
-        while let Some(current) = queue.pop_front() {
-            // Process children of current block
-            if let Some(children) = self.children.get(&current) {
-                for child_hash in children {
-                    if visited.contains(child_hash) {
-                        continue;
-                    }
-                    visited.insert(*child_hash);
-                    queue.push_back(*child_hash);
-                    
-                    // Calculate blue score for child
-                    // Blue score = max(blue scores of blue parents) + 1
-                    let parent_scores: Vec<u64> = self.blocks.get(child_hash)
-                        .map(|block| &block.header.parent_hashes)
-                        .unwrap_or(&vec![])
-                        .iter()
-                        .filter(|parent_hash| {
-                            // Check if parent is in blue set
-                            self.blue_set.iter().any(|blue_hash| blue_hash == *parent_hash)
-                        })
-                        .filter_map(|parent_hash| self.blue_score.get(parent_hash).copied())
-                        .collect();
-                    
-                    if !parent_scores.is_empty() {
-                        let max_parent_score = parent_scores.iter().max().copied().unwrap_or(0);
-                        let child_blue_score = max_parent_score + 1;
-                        self.blue_score.insert(*child_hash, child_blue_score);
-                        self.blue_set.insert(*child_hash);
-                    } else {
-                        // No blue parents - mark as red
-                        self.red_set.insert(*child_hash);
-                    }
+        let mut anticone: HashMap<Hash, usize> = HashMap::new();
+        let mut chain_block: Option<Hash> = None;
+        let mut chain_data = data;
+        loop {
+            // Blues merged by a chain block in the candidate's past are in its past too
+            if chain_block.is_some_and(|hash| self.is_ancestor(&hash, candidate)) {
+                break;
+            }
+            for blue in &chain_data.mergeset_blues {
+                if self.is_ancestor(blue, candidate) {
+                    continue;
+                }
+                let size = self.blue_anticone_size(data, blue);
+                if size == GHOSTDAG_K {
+                    return None;
+                }
+                anticone.insert(*blue, size);
+                if anticone.len() > GHOSTDAG_K {
+                    return None;
                 }
             }
+            let Some(parent) = chain_data.selected_parent else { break };
+            chain_block = Some(parent);
+            chain_data = &self.data[&parent];
         }
-        
-        // Order blocks by blue score (descending) and timestamp (ascending)
-        let mut ordered: Vec<(Hash, u64, u64)> = self.blue_set.iter()
-            .filter_map(|hash| {
-                self.blocks.get(hash).map(|block| {
-                    let score = self.blue_score.get(hash).copied().unwrap_or(0);
-                    (*hash, score, block.header.timestamp)
-                })
-            })
+        Some((anticone.len(), anticone))
+    }
+
+    /// Blue anticone size of `blue` as seen from `data`'s block
+    fn blue_anticone_size(&self, data: &GhostData, blue: &Hash) -> usize {
+        let mut current = data;
+        loop {
+            if let Some(size) = current.blues_anticone_sizes.get(blue) {
+                return *size;
+            }
+            match current.selected_parent.and_then(|parent| self.data.get(&parent)) {
+                Some(parent) => current = parent,
+                None => return 0,
+            }
+        }
+    }
+
+    /// Recolor after `added` blocks were inserted or `removed` blocks unlinked
+    ///
+    /// The selected chain of the virtual block is walked down from its new
+    /// selected parent to the old chain; only the part above that fork point,
+    /// and the virtual block's own mergeset, change color.
+    fn update_blue_set(&mut self, added: &[Hash], removed: &[Hash]) {
+        let mut previous: HashMap<Hash, u128> = removed.iter()
+            .filter_map(|hash| self.reward_percents.get(hash).map(|percent| (*hash, *percent)))
             .collect();
-        
-        // Sort: first by blue score (descending), then by timestamp (ascending)
-        ordered.sort_by(|a, b| {
-            match b.1.cmp(&a.1) {
-                std::cmp::Ordering::Equal => a.2.cmp(&b.2),
-                other => other,
+        let mut touched: HashSet<Hash> = added.iter().chain(removed).copied().collect();
+
+        let mut tips: Vec<Hash> = self.tips.iter().copied().collect();
+        tips.sort();
+        let virtual_data = self.ghost_data(&tips);
+
+        // New chain blocks, found by walking down to the old chain
+        let mut new_chain = Vec::new();
+        let mut next = virtual_data.as_ref().and_then(|data| data.selected_parent);
+        let fork = loop {
+            let Some(hash) = next else { break None };
+            if let Some(position) = self.chain_index.get(&hash) {
+                break Some(*position);
             }
-        });
-        
-        self.ordering = ordered.into_iter().map(|(hash, _, _)| hash).collect();
+            new_chain.push(hash);
+            next = self.data[&hash].selected_parent;
+        };
+        let keep = fork.map(|position| position + 1).unwrap_or(0);
+
+        // Uncolor the virtual mergeset and the abandoned chain blocks
+        let mut uncolored: Vec<Hash> = Vec::new();
+        if let Some(old) = self.virtual_data.take() {
+            uncolored.extend(old.mergeset);
+        }
+        for hash in self.chain.drain(keep..) {
+            self.chain_index.remove(&hash);
+            uncolored.push(hash);
+            if let Some(data) = self.data.get(&hash) {
+                uncolored.extend(data.mergeset.iter().copied());
+            }
+        }
+        for hash in uncolored {
+            self.blue_set.remove(&hash);
+            self.red_set.remove(&hash);
+            if let Some(percent) = self.reward_percents.remove(&hash) {
+                previous.entry(hash).or_insert(percent);
+            }
+            touched.insert(hash);
+        }
+        self.chain_ends.truncate(keep);
+        let (ordered, merged) = self.chain_ends.last().copied().unwrap_or((0, 0));
+        self.ordering.truncate(ordered);
+        self.merged_ordering.truncate(merged);
+
+        // Color the new chain blocks' mergesets, then the virtual block's
+        for hash in new_chain.into_iter().rev() {
+            let data = &self.data[&hash];
+            let (blue_score, mergeset, blues) = (data.blue_score, data.mergeset.clone(), data.mergeset_blues.clone());
+            self.merge(blue_score, &mergeset, &blues, &mut touched);
+            self.color(hash, true, blue_score, &mut touched);
+            self.ordering.push(hash);
+            self.merged_ordering.push(hash);
+            self.chain_index.insert(hash, self.chain.len());
+            self.chain.push(hash);
+            self.chain_ends.push((self.ordering.len(), self.merged_ordering.len()));
+        }
+        if let Some(data) = &virtual_data {
+            let (blue_score, mergeset, blues) = (data.blue_score, data.mergeset.clone(), data.mergeset_blues.clone());
+            self.merge(blue_score, &mergeset, &blues, &mut touched);
+        }
+        self.virtual_data = virtual_data;
+
+        // Removed blocks leave the DAG; detached blocks are red and earn nothing
+        for hash in removed {
+            self.data.remove(hash);
+            self.blue_set.remove(hash);
+            self.red_set.remove(hash);
+            self.reward_percents.remove(hash);
+        }
+        for hash in added {
+            if !self.data.contains_key(hash) {
+                self.red_set.insert(*hash);
+                self.reward_percents.insert(*hash, 0);
+            }
+        }
+
+        self.recolored = touched.into_iter()
+            .filter(|hash| previous.get(hash) != self.reward_percents.get(hash))
+            .collect();
+        self.recolored.sort();
     }
 
-    /// Get blocks in final consensus order
-    pub fn get_ordered_blocks(&self) -> Vec<&Block> {
-        self.ordering.iter()
-            .filter_map(|hash| self.blocks.get(hash))
-            .collect()
+    /// Color and order the mergeset of a block with the given blue score
+    fn merge(&mut self, blue_score: u64, mergeset: &[Hash], blues: &[Hash], touched: &mut HashSet<Hash>) {
+        for hash in mergeset {
+            let blue = blues.contains(hash);
+            self.color(*hash, blue, blue_score, touched);
+            if blue {
+                self.ordering.push(*hash);
+            }
+            self.merged_ordering.push(*hash);
+        }
+    }
+
+    /// Record a block's color and the reward share it earns when merged by a
+    /// block of `merger_score`
+    fn color(&mut self, hash: Hash, blue: bool, merger_score: u64, touched: &mut HashSet<Hash>) {
+        let percent = if blue {
+            self.blue_set.insert(hash);
+            100
+        } else {
+            self.red_set.insert(hash);
+            if merger_score.saturating_sub(self.score(&hash)) <= MAX_RED_REWARD_DEPTH {
+                RED_BLOCK_REWARD_PERCENT
+            } else {
+                0
+            }
+        };
+        self.reward_percents.insert(hash, percent);
+        touched.insert(hash);
+    }
+
+    /// Hashes of the blue blocks in final consensus order (oldest first)
+    pub fn get_ordering(&self) -> &[Hash] {
+        &self.ordering
+    }
+
+    /// Hashes of blue and red blocks in merged consensus order (oldest first)
+    ///
+    /// Each selected chain block is preceded by the rest of its mergeset, so
+    /// red blocks are placed next to the blue blocks that merged them.
+    pub fn get_merged_ordering(&self) -> &[Hash] {
+        &self.merged_ordering
+    }
+
+    /// Reward share (percent of the stream reward) a block earns under consensus
+    ///
+    /// Blue blocks earn the full reward. Red blocks earn `RED_BLOCK_REWARD_PERCENT`
+    /// if the chain block merging them scores within `MAX_RED_REWARD_DEPTH` of
+    /// them, and nothing otherwise. Detached and unknown blocks earn nothing.
+    pub fn reward_percent(&self, hash: &Hash) -> u128 {
+        self.reward_percents.get(hash).copied().unwrap_or(0)
+    }
+
+    /// Blocks whose reward share changed when the last block was added
+    /// (including that block), sorted by hash
+    pub fn recolored(&self) -> &[Hash] {
+        &self.recolored
     }
 
     /// Get blue set (selected blocks for consensus)
@@ -156,7 +450,7 @@ impl GhostDAG {
 
     /// Get blue score for a block
     pub fn get_blue_score(&self, hash: &Hash) -> Option<u64> {
-        self.blue_score.get(hash).copied()
+        self.data.get(hash).map(|data| data.blue_score)
     }
 
     /// Get total number of blocks in DAG
@@ -186,10 +480,10 @@ impl GhostDAG {
             .as_secs();
         
         // Get blocks from recent duration
-        let recent_blocks: Vec<&Block> = self.ordering.iter()
+        let recent_blocks: Vec<&BlockMeta> = self.ordering.iter()
             .filter_map(|hash| self.blocks.get(hash))
             .filter(|block| {
-                let age = current_time.saturating_sub(block.header.timestamp);
+                let age = current_time.saturating_sub(block.timestamp);
                 age <= duration_seconds
             })
             .collect();
@@ -199,11 +493,11 @@ impl GhostDAG {
         }
         
         let total_txs: usize = recent_blocks.iter()
-            .map(|b| b.transactions.len())
+            .map(|b| b.transaction_count)
             .sum();
         
         let timestamps: Vec<u64> = recent_blocks.iter()
-            .map(|b| b.header.timestamp)
+            .map(|b| b.timestamp)
             .collect();
         
         let time_span = timestamps.iter().max()
@@ -220,14 +514,14 @@ impl GhostDAG {
     /// Get DAG statistics
     pub fn get_stats(&self) -> DAGStats {
         let total_txs: usize = self.blocks.values()
-            .map(|b| b.transactions.len())
+            .map(|b| b.transaction_count)
             .sum();
         
         let total_size: usize = self.blocks.values()
             .map(|b| {
                 // Approximate block size
                 std::mem::size_of::<Block>() + 
-                b.transactions.len() * std::mem::size_of::<crate::blockchain::Transaction>()
+                b.transaction_count * std::mem::size_of::<crate::blockchain::Transaction>()
             })
             .sum();
         
@@ -242,8 +536,15 @@ impl GhostDAG {
         }
     }
 
-    /// Get block by hash
-    pub fn get_block(&self, hash: &Hash) -> Option<&Block> {
+    /// Blocks without children, sorted by hash
+    pub fn get_tips(&self) -> Vec<Hash> {
+        let mut tips: Vec<Hash> = self.tips.iter().copied().collect();
+        tips.sort();
+        tips
+    }
+
+    /// Get block metadata by hash
+    pub fn get_block(&self, hash: &Hash) -> Option<&BlockMeta> {
         self.blocks.get(hash)
     }
 
@@ -270,3 +571,100 @@ pub struct DAGStats {
     pub avg_txs_per_block: f64,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::{BlockHeader, Transaction};
+    use crate::types::StreamType;
+
+    fn block(parents: Vec<Hash>, number: u64, txs: Vec<Transaction>) -> Block {
+        let header = BlockHeader::new(parents.clone(), number, StreamType::StreamC, 4);
+        Block::new(header, txs, parents)
+    }
+
+    /// Genesis, a chain of `length` blocks on it, a side block on genesis and
+    /// a block merging the chain tip with the side block
+    fn side_branch(length: usize) -> (Vec<Block>, Hash, Hash) {
+        let genesis = block(vec![], 0, vec![]);
+        let side = block(vec![genesis.hash], 1, vec![Transaction::new([1u8; 20], [2u8; 20], 10, 1, 0)]);
+        let mut blocks = vec![genesis, side];
+        let mut tip = blocks[0].hash;
+        for number in 1..=length as u64 {
+            let next = block(vec![tip], number, vec![]);
+            tip = next.hash;
+            blocks.push(next);
+        }
+        let merging = block(vec![tip, blocks[1].hash], length as u64 + 1, vec![]);
+        let (side_hash, merging_hash) = (blocks[1].hash, merging.hash);
+        blocks.push(merging);
+        (blocks, side_hash, merging_hash)
+    }
+
+    #[test]
+    fn test_k_cluster_coloring() {
+        // A side block with at most K blue blocks in its anticone stays blue
+        let (blocks, side, merging) = side_branch(GHOSTDAG_K);
+        let dag = GhostDAG::from_blocks(blocks.iter().map(|b| b.meta()).collect());
+        assert!(dag.is_blue(&side));
+        assert_eq!(dag.get_blue_score(&merging), Some(GHOSTDAG_K as u64 + 3));
+
+        // One more block in its anticone turns it red, for a reduced reward
+        let (blocks, side, merging) = side_branch(GHOSTDAG_K + 1);
+        let dag = GhostDAG::from_blocks(blocks.iter().map(|b| b.meta()).collect());
+        assert!(dag.is_red(&side));
+        assert!(dag.is_blue(&merging));
+        assert_eq!(dag.reward_percent(&side), RED_BLOCK_REWARD_PERCENT);
+        assert_eq!(dag.reward_percent(&merging), 100);
+        assert!(!dag.get_ordering().contains(&side));
+
+        // Red blocks are merged right before the chain block that merges them
+        let merged = dag.get_merged_ordering();
+        assert_eq!(merged.len(), blocks.len());
+        assert_eq!(&merged[merged.len() - 2..], &[side, merging]);
+    }
+
+    #[test]
+    fn test_coloring_ignores_arrival_order() {
+        let (blocks, side, merging) = side_branch(GHOSTDAG_K + 1);
+        let mut early = GhostDAG::new();
+        early.add_block(&blocks[0]);
+        early.add_block(&blocks[1]);
+        assert!(early.is_blue(&side));
+        let mut recolored = HashSet::new();
+        for b in &blocks[2..] {
+            early.add_block(b);
+            recolored.extend(early.recolored().iter().copied());
+        }
+        assert!(recolored.contains(&side));
+
+        let late = GhostDAG::from_blocks(blocks.iter().map(|b| b.meta()).collect());
+        for dag in [&early, &late] {
+            assert!(dag.is_red(&side));
+            assert_eq!(dag.get_blue_score(&merging), Some(GHOSTDAG_K as u64 + 3));
+        }
+        assert_eq!(early.get_ordering(), late.get_ordering());
+        assert_eq!(early.get_merged_ordering(), late.get_merged_ordering());
+
+        // Taking the merging block back restores the earlier consensus
+        early.remove_block(&merging);
+        let before = GhostDAG::from_blocks(blocks[..blocks.len() - 1].iter().map(|b| b.meta()).collect());
+        assert!(early.get_block(&merging).is_none());
+        assert_eq!(early.get_merged_ordering(), before.get_merged_ordering());
+        assert_eq!(early.get_tips(), before.get_tips());
+    }
+
+    #[test]
+    fn test_stale_red_block_earns_nothing() {
+        let (blocks, side, _) = side_branch(MAX_RED_REWARD_DEPTH as usize + 1);
+        let dag = GhostDAG::from_blocks(blocks.iter().map(|b| b.meta()).collect());
+        assert!(dag.is_red(&side));
+        assert_eq!(dag.reward_percent(&side), 0);
+
+        let mut dag = dag;
+        let detached = block(vec![[9u8; 32]], 1, vec![]);
+        dag.add_block(&detached);
+        assert!(dag.is_red(&detached.hash));
+        assert_eq!(dag.reward_percent(&detached.hash), 0);
+    }
+}
@@ -0,0 +1,168 @@
+//! Internal event bus
+//!
+//! The chain, miner, shard manager and stop-loss manager publish events here;
+//! WebSocket subscriptions (`eth_subscribe`) consume them. Publishing never
+//! blocks: a subscriber that falls more than `EVENT_BUS_CAPACITY` events
+//! behind loses the oldest ones.
+//!
+//! Copyright (c) 2026 Mondoshawan Protocol
+//! Licensed under the MIT License (see LICENSE file)
+
+use crate::blockchain::BlockHeader;
+use crate::sharding::CrossShardStatus;
+use crate::types::{Address, Hash};
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber
+pub const EVENT_BUS_CAPACITY: usize = 1024;
+
+/// Something that happened on the node
+#[derive(Debug, Clone)]
+pub enum ChainEvent {
+    /// A block was accepted by `Blockchain::add_block`
+    NewHead {
+        hash: Hash,
+        header: BlockHeader,
+        transaction_count: usize,
+    },
+    /// Logs of an accepted block
+    Logs(Vec<Log>),
+    /// A transaction entered a mining pool
+    PendingTransaction(Hash),
+    /// DAG tips (blocks without children) after a block was accepted
+    DagTips(Vec<Hash>),
+    /// A cross-shard transaction changed status
+    CrossShard {
+        tx_hash: Hash,
+        source_shard: usize,
+        target_shard: usize,
+        status: CrossShardStatus,
+    },
+    /// A stop-loss order triggered
+    StopLossTriggered {
+        stop_loss_id: Hash,
+        wallet_address: Address,
+        asset_pair: String,
+        triggered_price: u128,
+        triggered_at: u64,
+    },
+}
+
+/// Ethereum-style log entry
+///
+/// Logs come only from EVM execution. The executor does not run contract code
+/// yet, so none are produced and `ChainEvent::Logs` is never published.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<Hash>,
+    pub data: Vec<u8>,
+    pub block_hash: Hash,
+    pub block_number: u64,
+    pub transaction_hash: Hash,
+    pub transaction_index: u64,
+    pub log_index: u64,
+}
+
+/// Log filter (`eth_subscribe("logs")`, `eth_newFilter`)
+///
+/// An empty address list matches any address. Each topic position matches
+/// any of its hashes; `None` matches anything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogFilter {
+    pub addresses: Vec<Address>,
+    pub topics: Vec<Option<Vec<Hash>>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &Log) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        self.topics.iter().enumerate().all(|(position, allowed)| match allowed {
+            None => true,
+            Some(allowed) => log.topics.get(position).is_some_and(|topic| allowed.contains(topic)),
+        })
+    }
+}
+
+/// Broadcast channel shared by publishers and subscribers
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ChainEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    /// Publish an event (dropped when nobody is subscribed)
+    pub fn publish(&self, event: ChainEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ChainEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::{Block, Blockchain, Transaction};
+    use crate::types::StreamType;
+
+    #[test]
+    fn test_add_block_publishes_head_logs_and_tips() {
+        let bus = EventBus::new();
+        let mut events = bus.subscribe();
+        let mut blockchain = Blockchain::new();
+        blockchain.set_event_bus(bus);
+
+        let genesis = Block::new(BlockHeader::new(vec![], 0, StreamType::StreamA, 4), vec![], vec![]);
+        blockchain.add_block(genesis.clone()).unwrap();
+        let (secret_key, sender) = Transaction::test_signer(1);
+        blockchain.set_balance(sender, 1_000).unwrap();
+        let tx = Transaction::new(sender, [2u8; 20], 500, 0, 0).sign(&secret_key);
+        let header = BlockHeader::new(vec![genesis.hash], 1, StreamType::StreamA, 4);
+        let block = Block::new(header, vec![tx], vec![genesis.hash]);
+        blockchain.add_block(block.clone()).unwrap();
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert!(matches!(received[0], ChainEvent::NewHead { hash, .. } if hash == genesis.hash));
+        assert!(matches!(received[2], ChainEvent::NewHead { hash, transaction_count: 1, .. } if hash == block.hash));
+        // Plain transfers produce no logs
+        assert!(!received.iter().any(|event| matches!(event, ChainEvent::Logs(_))));
+        assert!(matches!(received.last(), Some(ChainEvent::DagTips(tips)) if tips == &vec![block.hash]));
+    }
+
+    #[test]
+    fn test_log_filter_matching() {
+        let log = Log {
+            address: [5u8; 20],
+            topics: vec![[1u8; 32], [2u8; 32]],
+            data: Vec::new(),
+            block_hash: [0u8; 32],
+            block_number: 1,
+            transaction_hash: [0u8; 32],
+            transaction_index: 0,
+            log_index: 0,
+        };
+        assert!(LogFilter::default().matches(&log));
+        assert!(LogFilter { addresses: vec![[5u8; 20]], topics: vec![None, Some(vec![[3u8; 32], [2u8; 32]])] }.matches(&log));
+        assert!(!LogFilter { addresses: vec![[6u8; 20]], topics: vec![] }.matches(&log));
+        assert!(!LogFilter { addresses: vec![], topics: vec![None, None, Some(vec![[2u8; 32]])] }.matches(&log));
+    }
+}
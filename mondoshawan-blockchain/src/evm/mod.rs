@@ -10,6 +10,10 @@ use crate::types::Address;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
+/// Gas every transaction uses: the base transaction cost (the executor does
+/// not meter bytecode yet)
+pub const TRANSACTION_GAS: u64 = 21_000;
+
 /// EVM state manager
 /// 
 /// Manages EVM account state, contract storage, and execution environment.
@@ -67,6 +71,21 @@ impl EvmState {
         contracts.get(&address).cloned()
     }
 
+    /// Drop an account's code and EVM balance and nonce (it moved to another shard)
+    pub fn remove_account(&self, address: &Address) {
+        self.contracts.write().unwrap().remove(address);
+        self.balances.write().unwrap().remove(address);
+        self.nonces.write().unwrap().remove(address);
+    }
+
+    /// Deployed contracts as (address, bytecode), sorted by address
+    pub fn contracts(&self) -> Vec<(Address, Vec<u8>)> {
+        let contracts = self.contracts.read().unwrap();
+        let mut entries: Vec<_> = contracts.iter().map(|(address, code)| (*address, code.clone())).collect();
+        entries.sort();
+        entries
+    }
+
     /// Check if address is a contract
     pub fn is_contract(&self, address: Address) -> bool {
         let contracts = self.contracts.read().unwrap();
@@ -114,7 +133,7 @@ impl EvmTransactionExecutor {
             
             return Ok(ExecutionResult {
                 success: true,
-                gas_used: 21_000, // Base gas for deployment
+                gas_used: TRANSACTION_GAS,
                 output,
             });
         }
@@ -126,7 +145,7 @@ impl EvmTransactionExecutor {
             // Full EVM execution will be implemented with revm
             return Ok(ExecutionResult {
                 success: true,
-                gas_used: 21_000, // Base gas for call
+                gas_used: TRANSACTION_GAS,
                 output: Vec::new(), // Contract execution output
             });
         }
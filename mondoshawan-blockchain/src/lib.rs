@@ -11,6 +11,7 @@ pub mod blockchain;
 pub mod config;
 pub mod consensus;
 pub mod error;
+pub mod events;
 pub mod evm;
 pub mod governance;
 pub mod metrics;
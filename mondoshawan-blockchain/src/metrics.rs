@@ -4,7 +4,7 @@
 //! including blocks, transactions, network, mining, and sharding metrics.
 
 use prometheus::{
-    Counter, Gauge, Histogram, HistogramOpts, Opts, Registry,
+    Counter, Gauge, GaugeVec, Histogram, HistogramOpts, Opts, Registry,
     Encoder, TextEncoder,
 };
 use std::sync::Arc;
@@ -37,6 +37,22 @@ pub struct Metrics {
     pub shard_transaction_count: Vec<Gauge>,
     pub cross_shard_transactions: Counter,
     
+    // Shard load metrics (labelled by shard_id; the shard count can change)
+    pub shard_pool_depth: GaugeVec,
+    pub shard_tps: GaugeVec,
+    pub shard_cross_shard_ratio: GaugeVec,
+    pub shard_load_score: GaugeVec,
+    pub shard_load_imbalance: Gauge,
+    pub shard_hot_count: Gauge,
+    pub rebalance_proposed_ranges: Gauge,
+    pub rebalance_migrated_accounts: Counter,
+    
+    // Pruning metrics
+    pub pruned_blocks: Counter,
+    pub pruning_watermark: Gauge,
+    pub pruning_target: Gauge,
+    pub retained_from_block: Gauge,
+    
     // Registry
     registry: Registry,
 }
@@ -132,6 +148,72 @@ impl Metrics {
                 .namespace("mondoshawan")
         )?;
         
+        // Shard load metrics
+        let shard_pool_depth = GaugeVec::new(
+            Opts::new("mondoshawan_shard_pool_depth", "Pending transactions in shard pool")
+                .namespace("mondoshawan"),
+            &["shard_id"]
+        )?;
+        
+        let shard_tps = GaugeVec::new(
+            Opts::new("mondoshawan_shard_tps", "Transactions per second arriving at shard over the load window")
+                .namespace("mondoshawan"),
+            &["shard_id"]
+        )?;
+        
+        let shard_cross_shard_ratio = GaugeVec::new(
+            Opts::new("mondoshawan_shard_cross_shard_ratio", "Share of shard transactions that cross shards")
+                .namespace("mondoshawan"),
+            &["shard_id"]
+        )?;
+        
+        let shard_load_score = GaugeVec::new(
+            Opts::new("mondoshawan_shard_load_score", "Weighted shard load used by the rebalancer")
+                .namespace("mondoshawan"),
+            &["shard_id"]
+        )?;
+        
+        let shard_load_imbalance = Gauge::with_opts(
+            Opts::new("mondoshawan_shard_load_imbalance", "Highest shard load score over mean score")
+                .namespace("mondoshawan")
+        )?;
+        
+        let shard_hot_count = Gauge::with_opts(
+            Opts::new("mondoshawan_shard_hot_count", "Shards currently classified as hot")
+                .namespace("mondoshawan")
+        )?;
+        
+        let rebalance_proposed_ranges = Gauge::with_opts(
+            Opts::new("mondoshawan_rebalance_proposed_ranges", "Ring ranges in the latest rebalance proposal")
+                .namespace("mondoshawan")
+        )?;
+        
+        let rebalance_migrated_accounts = Counter::with_opts(
+            Opts::new("mondoshawan_rebalance_migrated_accounts_total", "Accounts migrated by applied rebalances")
+                .namespace("mondoshawan")
+        )?;
+        
+        // Pruning metrics
+        let pruned_blocks = Counter::with_opts(
+            Opts::new("mondoshawan_pruned_blocks_total", "Block bodies deleted by pruning")
+                .namespace("mondoshawan")
+        )?;
+        
+        let pruning_watermark = Gauge::with_opts(
+            Opts::new("mondoshawan_pruning_watermark", "Blocks whose bodies have been pruned")
+                .namespace("mondoshawan")
+        )?;
+        
+        let pruning_target = Gauge::with_opts(
+            Opts::new("mondoshawan_pruning_target", "Blocks outside the retention window")
+                .namespace("mondoshawan")
+        )?;
+        
+        let retained_from_block = Gauge::with_opts(
+            Opts::new("mondoshawan_retained_from_block", "Lowest block number whose body is kept")
+                .namespace("mondoshawan")
+        )?;
+        
         // Register all metrics
         registry.register(Box::new(blocks_mined.clone()))?;
         registry.register(Box::new(blocks_received.clone()))?;
@@ -152,6 +234,18 @@ impl Metrics {
         }
         
         registry.register(Box::new(cross_shard_transactions.clone()))?;
+        registry.register(Box::new(shard_pool_depth.clone()))?;
+        registry.register(Box::new(shard_tps.clone()))?;
+        registry.register(Box::new(shard_cross_shard_ratio.clone()))?;
+        registry.register(Box::new(shard_load_score.clone()))?;
+        registry.register(Box::new(shard_load_imbalance.clone()))?;
+        registry.register(Box::new(shard_hot_count.clone()))?;
+        registry.register(Box::new(rebalance_proposed_ranges.clone()))?;
+        registry.register(Box::new(rebalance_migrated_accounts.clone()))?;
+        registry.register(Box::new(pruned_blocks.clone()))?;
+        registry.register(Box::new(pruning_watermark.clone()))?;
+        registry.register(Box::new(pruning_target.clone()))?;
+        registry.register(Box::new(retained_from_block.clone()))?;
         
         Ok(Self {
             blocks_mined,
@@ -169,6 +263,18 @@ impl Metrics {
             mining_rewards,
             shard_transaction_count,
             cross_shard_transactions,
+            shard_pool_depth,
+            shard_tps,
+            shard_cross_shard_ratio,
+            shard_load_score,
+            shard_load_imbalance,
+            shard_hot_count,
+            rebalance_proposed_ranges,
+            rebalance_migrated_accounts,
+            pruned_blocks,
+            pruning_watermark,
+            pruning_target,
+            retained_from_block,
             registry,
         })
     }
@@ -243,6 +349,41 @@ impl Metrics {
     pub fn record_cross_shard_transaction(&self) {
         self.cross_shard_transactions.inc();
     }
+    
+    /// Update per-shard load and the overall imbalance
+    pub fn update_shard_loads(&self, loads: &[crate::sharding::rebalance::ShardLoad]) {
+        // Drop series of shards removed by a reshard
+        self.shard_pool_depth.reset();
+        self.shard_tps.reset();
+        self.shard_cross_shard_ratio.reset();
+        self.shard_load_score.reset();
+        for load in loads {
+            let shard_id = load.shard_id.to_string();
+            self.shard_pool_depth.with_label_values(&[&shard_id]).set(load.pool_depth as f64);
+            self.shard_tps.with_label_values(&[&shard_id]).set(load.tps);
+            self.shard_cross_shard_ratio.with_label_values(&[&shard_id]).set(load.cross_shard_ratio);
+            self.shard_load_score.with_label_values(&[&shard_id]).set(load.score);
+        }
+        self.shard_load_imbalance.set(crate::sharding::rebalance::imbalance(loads));
+        self.shard_hot_count.set(loads.iter().filter(|load| load.hot).count() as f64);
+    }
+    
+    /// Record an epoch's rebalance proposal
+    pub fn record_rebalance(&self, proposal: &crate::sharding::rebalance::RebalanceProposal) {
+        self.rebalance_proposed_ranges.set(proposal.moves.len() as f64);
+        if proposal.applied {
+            let accounts: usize = proposal.moves.iter().map(|range| range.accounts).sum();
+            self.rebalance_migrated_accounts.inc_by(accounts as f64);
+        }
+    }
+    
+    /// Record a pruning step
+    pub fn record_pruning(&self, progress: &crate::blockchain::PruneProgress, retained_from: u64) {
+        self.pruned_blocks.inc_by(progress.pruned as f64);
+        self.pruning_watermark.set(progress.watermark as f64);
+        self.pruning_target.set(progress.target as f64);
+        self.retained_from_block.set(retained_from as f64);
+    }
 }
 
 /// Thread-safe metrics wrapper
@@ -5,8 +5,10 @@
 //! - Stream B: CPU/GPU mining (KHeavyHash), 1s blocks, 5,000 txs/block, 25 MSHW reward
 //! - Stream C: ZK proofs, 100ms blocks, 1,000 txs/block, 0 MSHW (fee-based only)
 
+pub mod assembly;
 pub mod fairness;
 pub mod ordering;
+pub mod pool;
 
 use crate::blockchain::{Blockchain, Block, BlockHeader, Transaction};
 use crate::types::{Address, StreamType};
@@ -27,6 +29,11 @@ pub const STREAM_A_MAX_TXS: usize = 10_000;
 pub const STREAM_B_MAX_TXS: usize = 5_000;
 pub const STREAM_C_MAX_TXS: usize = 1_000;
 
+/// Gas budget (cumulative transaction gas_limit) per block for each stream
+pub const STREAM_A_MAX_GAS: u64 = crate::blockchain::MAX_BLOCK_GAS;
+pub const STREAM_B_MAX_GAS: u64 = 125_000_000;
+pub const STREAM_C_MAX_GAS: u64 = 25_000_000;
+
 /// Block times for each stream
 pub const STREAM_A_BLOCK_TIME: Duration = Duration::from_secs(10);
 pub const STREAM_B_BLOCK_TIME: Duration = Duration::from_secs(1);
@@ -36,6 +43,16 @@ pub const STREAM_C_BLOCK_TIME: Duration = Duration::from_millis(100);
 /// When limit is reached, oldest transactions are evicted (FIFO)
 pub const MAX_TX_POOL_SIZE: usize = 100_000; // 100k transactions max
 
+/// Full (blue block) reward for a block: the stream reward, or the block's
+/// transaction fees for fee-based Stream C
+pub fn base_block_reward(block: &Block) -> u128 {
+    match block.header.stream_type {
+        StreamType::StreamA => STREAM_A_REWARD,
+        StreamType::StreamB => STREAM_B_REWARD,
+        StreamType::StreamC => block.transactions.iter().map(|tx| tx.fee).sum(),
+    }
+}
+
 /// Block submission message for channel-based processing
 struct BlockSubmission {
     block: Block,
@@ -61,6 +78,9 @@ pub struct MiningManager {
     block_sender: mpsc::UnboundedSender<BlockSubmission>, // Channel sender for block submissions
     node_registry: Option<Arc<tokio::sync::RwLock<crate::governance::NodeRegistry>>>, // Optional node registry for participation tracking
     node_identity: Option<crate::governance::NodeIdentity>, // Node identity for participation tracking
+    pool: Arc<RwLock<Option<pool::MiningPool>>>, // Pool-operator mode (None = solo mining)
+    events: Option<crate::events::EventBus>, // Pending transactions are announced here
+    deferrals: Arc<std::sync::Mutex<assembly::DeferralTracker>>, // Ages transactions going back to the pool
 }
 
 impl MiningManager {
@@ -68,8 +88,19 @@ impl MiningManager {
         // Create channel for block submissions (serializes block additions)
         let (block_sender, block_receiver) = mpsc::unbounded_channel();
         
+        // Transaction pool is shared with the processor so rejected blocks can be requeued
+        let tx_pool = Arc::new(SegQueue::new());
+        let tx_pool_size = Arc::new(AtomicUsize::new(0));
+        
+        let pool = Arc::new(RwLock::new(None));
+        let deferrals = Arc::new(std::sync::Mutex::new(assembly::DeferralTracker::default()));
+        
         // Start block processor task
         let blockchain_processor = blockchain.clone();
+        let pool_processor = pool.clone();
+        let tx_pool_processor = tx_pool.clone();
+        let tx_pool_size_processor = tx_pool_size.clone();
+        let deferrals_processor = deferrals.clone();
         let miner_address_processor = miner_address;
         let fairness_analyzer_processor = Arc::new(tokio::sync::RwLock::new(fairness::FairnessAnalyzer::new()));
         let metrics_processor = None::<crate::metrics::MetricsHandle>;
@@ -80,18 +111,23 @@ impl MiningManager {
             process_blocks(
                 block_receiver,
                 blockchain_processor,
+                tx_pool_processor,
+                tx_pool_size_processor,
+                deferrals_processor,
                 miner_address_processor,
                 fairness_analyzer_processor,
                 metrics_processor,
                 node_registry_processor,
                 node_identity_processor,
+                pool_processor,
+                None,
             ).await;
         });
         
         Self {
             blockchain,
-            tx_pool: Arc::new(SegQueue::new()),
-            tx_pool_size: Arc::new(AtomicUsize::new(0)),
+            tx_pool,
+            tx_pool_size,
             block_counter: Arc::new(AtomicU64::new(0)), // Lock-free atomic
             miner_address,
             is_mining: Arc::new(RwLock::new(false)),
@@ -103,6 +139,9 @@ impl MiningManager {
             block_sender,
             node_registry: None,
             node_identity: None,
+            pool,
+            events: None,
+            deferrals,
         }
     }
     
@@ -116,8 +155,19 @@ impl MiningManager {
         // Create channel for block submissions
         let (block_sender, block_receiver) = mpsc::unbounded_channel();
         
+        // Transaction pool is shared with the processor so rejected blocks can be requeued
+        let tx_pool = Arc::new(SegQueue::new());
+        let tx_pool_size = Arc::new(AtomicUsize::new(0));
+        
+        let pool = Arc::new(RwLock::new(None));
+        let deferrals = Arc::new(std::sync::Mutex::new(assembly::DeferralTracker::default()));
+        
         // Start block processor task
         let blockchain_processor = blockchain.clone();
+        let pool_processor = pool.clone();
+        let tx_pool_processor = tx_pool.clone();
+        let tx_pool_size_processor = tx_pool_size.clone();
+        let deferrals_processor = deferrals.clone();
         let miner_address_processor = miner_address;
         let fairness_analyzer_processor = Arc::new(tokio::sync::RwLock::new(fairness::FairnessAnalyzer::new()));
         let metrics_processor = None::<crate::metrics::MetricsHandle>;
@@ -128,18 +178,23 @@ impl MiningManager {
             process_blocks(
                 block_receiver,
                 blockchain_processor,
+                tx_pool_processor,
+                tx_pool_size_processor,
+                deferrals_processor,
                 miner_address_processor,
                 fairness_analyzer_processor,
                 metrics_processor,
                 node_registry_processor,
                 node_identity_processor,
+                pool_processor,
+                None,
             ).await;
         });
         
         Self {
             blockchain,
-            tx_pool: Arc::new(SegQueue::new()),
-            tx_pool_size: Arc::new(AtomicUsize::new(0)),
+            tx_pool,
+            tx_pool_size,
             block_counter: Arc::new(AtomicU64::new(0)),
             miner_address,
             is_mining: Arc::new(RwLock::new(false)),
@@ -151,6 +206,9 @@ impl MiningManager {
             block_sender,
             node_registry: Some(node_registry),
             node_identity: Some(node_identity),
+            pool,
+            events: None,
+            deferrals,
         }
     }
 
@@ -163,30 +221,47 @@ impl MiningManager {
         // Create channel for block submissions
         let (block_sender, block_receiver) = mpsc::unbounded_channel();
         
+        // Transaction pool is shared with the processor so rejected blocks can be requeued
+        let tx_pool = Arc::new(SegQueue::new());
+        let tx_pool_size = Arc::new(AtomicUsize::new(0));
+        
+        let pool = Arc::new(RwLock::new(None));
+        let deferrals = Arc::new(std::sync::Mutex::new(assembly::DeferralTracker::default()));
+        
         // Start block processor task
         let blockchain_processor = blockchain.clone();
+        let pool_processor = pool.clone();
+        let tx_pool_processor = tx_pool.clone();
+        let tx_pool_size_processor = tx_pool_size.clone();
+        let deferrals_processor = deferrals.clone();
         let miner_address_processor = miner_address;
         let fairness_analyzer_processor = Arc::new(tokio::sync::RwLock::new(fairness::FairnessAnalyzer::new()));
         let metrics_processor = None::<crate::metrics::MetricsHandle>;
         let node_registry_processor = None::<Arc<tokio::sync::RwLock<crate::governance::NodeRegistry>>>;
         let node_identity_processor = None::<crate::governance::NodeIdentity>;
+        let shard_manager_processor = shard_manager.clone();
         
         tokio::spawn(async move {
             process_blocks(
                 block_receiver,
                 blockchain_processor,
+                tx_pool_processor,
+                tx_pool_size_processor,
+                deferrals_processor,
                 miner_address_processor,
                 fairness_analyzer_processor,
                 metrics_processor,
                 node_registry_processor,
                 node_identity_processor,
+                pool_processor,
+                Some(shard_manager_processor),
             ).await;
         });
         
         Self {
             blockchain,
-            tx_pool: Arc::new(SegQueue::new()),
-            tx_pool_size: Arc::new(AtomicUsize::new(0)),
+            tx_pool,
+            tx_pool_size,
             block_counter: Arc::new(AtomicU64::new(0)), // Lock-free atomic
             miner_address,
             is_mining: Arc::new(RwLock::new(false)),
@@ -198,6 +273,9 @@ impl MiningManager {
             block_sender,
             node_registry: None,
             node_identity: None,
+            pool,
+            events: None,
+            deferrals,
         }
     }
     
@@ -206,6 +284,11 @@ impl MiningManager {
         self.metrics = Some(metrics);
     }
     
+    /// Announce transactions entering the pool on `events`
+    pub fn set_event_bus(&mut self, events: crate::events::EventBus) {
+        self.events = Some(events);
+    }
+    
     /// Clone for mining (internal use)
     /// Clone mining manager for parallel stream mining
     /// Note: node_registry and node_identity are shared across all streams
@@ -225,6 +308,39 @@ impl MiningManager {
             block_sender: self.block_sender.clone(), // Clone sender (receiver is shared)
             node_registry: self.node_registry.clone(),
             node_identity: self.node_identity.clone(),
+            pool: self.pool.clone(),
+            events: self.events.clone(),
+            deferrals: self.deferrals.clone(),
+        }
+    }
+    
+    /// Switch to pool-operator mode
+    ///
+    /// The pool's coinbase (derived from its payout key) must be this manager's
+    /// miner address, since block rewards are credited to the header coinbase.
+    pub fn enable_pool(&self, mining_pool: pool::MiningPool) -> Result<(), String> {
+        if mining_pool.address() != self.miner_address {
+            return Err(format!(
+                "Pool payout key controls 0x{}, but miner address is 0x{}",
+                hex::encode(mining_pool.address()),
+                hex::encode(self.miner_address)
+            ));
+        }
+        let mut guard = self.pool.try_write()
+            .map_err(|_| "Mining pool is busy".to_string())?;
+        *guard = Some(mining_pool);
+        Ok(())
+    }
+    
+    /// Mining pool state (None when solo mining)
+    pub fn pool(&self) -> &Arc<RwLock<Option<pool::MiningPool>>> {
+        &self.pool
+    }
+
+    /// Hand the template of the block being mined to the pool as its next job
+    async fn publish_pool_job(&self, header: &BlockHeader) {
+        if let Some(mining_pool) = self.pool.write().await.as_mut() {
+            mining_pool.new_job(header);
         }
     }
     
@@ -238,6 +354,50 @@ impl MiningManager {
         *self.ordering_policy.read().await
     }
 
+    /// Order a block's transactions and commit the ordering policy to its header
+    ///
+    /// FIFO, Random and Time-Weighted use the canonical order seeded from the header's
+    /// parent hashes (validators re-derive the Random one). Other policies use
+    /// `ordering::order_transactions`. Arrival times come from the ordering context and
+    /// fall back to the block timestamp for transactions we never saw arrive.
+    async fn order_block_transactions(&self, header: BlockHeader, txs: Vec<Transaction>) -> (BlockHeader, Vec<Transaction>) {
+        let policy = *self.ordering_policy.read().await;
+        let block_time = header.timestamp;
+        
+        // Use timeout to avoid deadlock - if context is busy, order without it (non-critical)
+        let mut temp_context;
+        let mut guard = tokio::time::timeout(Duration::from_millis(10), self.ordering_context.write()).await.ok();
+        let context: &mut ordering::OrderingContext = match guard.as_deref_mut() {
+            Some(context) => context,
+            None => {
+                temp_context = ordering::OrderingContext::new();
+                &mut temp_context
+            }
+        };
+        context.update_time();
+        
+        let arrival_of = |context: &ordering::OrderingContext, tx: &Transaction| {
+            context.get_arrival(&tx.hash).unwrap_or(block_time).min(block_time)
+        };
+        
+        if policy.has_canonical_order() {
+            let entries: Vec<(Transaction, u64)> = txs.into_iter()
+                .map(|tx| {
+                    let arrival = arrival_of(&*context, &tx);
+                    (tx, arrival)
+                })
+                .collect();
+            let seed = ordering::ordering_seed(&header.parent_hashes);
+            let (txs, arrivals): (Vec<Transaction>, Vec<u64>) =
+                ordering::canonical_order(entries, policy, &seed).into_iter().unzip();
+            (header.with_ordering(policy, arrivals), txs)
+        } else {
+            let txs = ordering::order_transactions(txs, policy, context);
+            let arrivals = txs.iter().map(|tx| arrival_of(&*context, tx)).collect();
+            (header.with_ordering(policy, arrivals), txs)
+        }
+    }
+
     /// Add transaction to pool
     pub async fn add_transaction(&self, tx: Transaction) -> crate::error::BlockchainResult<()> {        
         // Record transaction arrival time for fairness analysis
@@ -256,6 +416,11 @@ impl MiningManager {
             context.record_arrival(tx.hash, timestamp);
         }
         
+        // With sharding, transactions are mined on the sender's shard
+        if let Some(shard_manager) = &self.shard_manager {
+            return shard_manager.add_transaction(tx).await;
+        }
+        
         // Check pool size and evict oldest if needed (FIFO eviction)
         let current_size = self.tx_pool_size.load(Ordering::Acquire);
         if current_size >= MAX_TX_POOL_SIZE {
@@ -266,11 +431,62 @@ impl MiningManager {
         }
         
         // Add to lock-free queue
+        let tx_hash = tx.hash;
         self.tx_pool.push(tx);
         self.tx_pool_size.fetch_add(1, Ordering::Release);
+        if let Some(events) = &self.events {
+            events.publish(crate::events::ChainEvent::PendingTransaction(tx_hash));
+        }
         Ok(())
     }
     
+    /// Return transactions to the pool (e.g. skipped during block assembly)
+    ///
+    /// Arrival times stay in the ordering context, so FIFO ordering still uses
+    /// the original arrival even though the transaction is re-pushed at the back.
+    pub fn requeue_transactions(&self, txs: Vec<Transaction>) {
+        requeue_to_pool(&self.tx_pool, &self.tx_pool_size, txs);
+    }
+    
+    /// Select transactions for a block within the stream's limits
+    ///
+    /// Deferred transactions go back to the pool until they expire; permanently
+    /// invalid ones are discarded.
+    async fn assemble_block_transactions(
+        &self,
+        candidates: Vec<Transaction>,
+        block_number: u64,
+        limits: &assembly::BlockLimits,
+    ) -> Vec<Transaction> {
+        if candidates.is_empty() {
+            return candidates;
+        }
+        
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let assembled = {
+            let blockchain = self.blockchain.read().await;
+            assembly::assemble_transactions(candidates, &blockchain, block_number, timestamp, limits)
+        };
+        
+        if !assembled.dropped.is_empty() {
+            println!("⚠️  Block assembly dropped {} invalid transactions", assembled.dropped.len());
+        }
+        requeue_deferred(
+            &self.tx_pool,
+            &self.tx_pool_size,
+            &self.deferrals,
+            assembled.deferred,
+            &assembled.dropped,
+            block_number,
+            timestamp,
+        );
+        
+        assembled.included
+    }
+    
     /// Get fairness metrics for a block
     pub async fn get_fairness_metrics(&self, block: &Block) -> fairness::FairnessMetrics {
         let analyzer = self.fairness_analyzer.read().await;
@@ -336,32 +552,14 @@ impl MiningManager {
                         break; // Queue is empty
                     }
                 }
-                            
-                // Apply ordering policy if we have transactions
-                // Use try_write to avoid deadlock - if context is busy, skip ordering update
-                if !txs.is_empty() {
-                    let policy = *self.ordering_policy.read().await;
-                    // Use timeout to avoid deadlock - if context is busy, skip update
-                    match tokio::time::timeout(Duration::from_millis(10), self.ordering_context.write()).await {
-                        Ok(mut context) => {
-                            context.update_time();
-                            txs = ordering::order_transactions(txs, policy, &mut context);
-                        }
-                        Err(_) => {
-                            // Context is busy, order without updating context (non-critical)
-                            let mut temp_context = ordering::OrderingContext::new();
-                            txs = ordering::order_transactions(txs, policy, &mut temp_context);
-                        }
-                    }
-                }
-                            
+
                 txs
             };
 
             // Get parent hashes and block number
             let (parent_hashes, block_number) = {
                 let blockchain = self.blockchain.read().await;
-                let blocks = blockchain.get_blocks();
+                let blocks = blockchain.block_metas();
                 let parents = if !blocks.is_empty() {
                     // Get last few blocks as parents (DAG structure)
                     let mut parents = Vec::new();
@@ -380,8 +578,15 @@ impl MiningManager {
                 (parents, num)
             };
 
-            // Create block
-            let header = BlockHeader::new(parent_hashes.clone(), block_number, StreamType::StreamA, 4);
+            // Pack transactions by size, gas and validity; skipped ones go back to the pool
+            let limits = assembly::BlockLimits::new(STREAM_A_MAX_TXS, STREAM_A_MAX_GAS);
+            let txs = self.assemble_block_transactions(txs, block_number, &limits).await;
+
+            // Apply ordering policy and commit it to the header
+            let header = BlockHeader::new(parent_hashes.clone(), block_number, StreamType::StreamA, 4)
+                .with_coinbase(self.miner_address);
+            let (header, txs) = self.order_block_transactions(header, txs).await;
+            self.publish_pool_job(&header).await;
             let block = Block::new(header, txs.clone(), parent_hashes);
 
             // Send block to processor via channel (non-blocking, eliminates deadlock)
@@ -406,40 +611,27 @@ impl MiningManager {
         sleep(Duration::from_millis(200)).await;
         
         while *self.is_mining.read().await {
-            let txs = if let Some(shard_manager) = &self.shard_manager {
-                // Get transactions from all shards
-                let mut all_txs = Vec::new();
-                let shard_count = shard_manager.shard_count();
-                let txs_per_shard = STREAM_B_MAX_TXS / shard_count.max(1);
-                
-                for shard_id in 0..shard_count {
-                    let shard_txs = shard_manager.get_shard_transactions(shard_id, txs_per_shard).await;
-                    all_txs.extend(shard_txs);
-                    if all_txs.len() >= STREAM_B_MAX_TXS {
-                        break;
+            // With sharding, shard transactions are mined on each shard's own DAG
+            // and the Stream B block becomes a beacon block committing shard tips
+            let (txs, commitments) = if let Some(shard_manager) = &self.shard_manager {
+                let tracked = shard_manager.tracked_shards();
+                let txs_per_shard = STREAM_B_MAX_TXS / tracked.len().max(1);
+                for shard_id in tracked {
+                    match shard_manager.produce_shard_block(shard_id, txs_per_shard, STREAM_B_MAX_GAS).await {
+                        Ok(Some(block)) => println!("🔷 Shard {}: Mined block #{} with {} txs",
+                            shard_id, block.header.block_number, block.transactions.len()),
+                        Ok(None) => {}
+                        Err(e) => eprintln!("⚠️  Shard {}: Block production failed: {}", shard_id, e),
                     }
                 }
                 
-                if all_txs.is_empty() {
+                let commitments = shard_manager.shard_commitments().await;
+                if !shard_manager.has_new_commitments(&commitments).await {
                     sleep(STREAM_B_BLOCK_TIME).await;
                     continue;
                 }
                 
-                // Remove transactions from shards
-                for shard_id in 0..shard_count {
-                    let _ = shard_manager.remove_shard_transactions(shard_id, txs_per_shard).await;
-                }
-                
-                let mut txs: Vec<Transaction> = all_txs.into_iter().take(STREAM_B_MAX_TXS).collect();
-                
-                // Apply ordering policy
-                let policy = *self.ordering_policy.read().await;
-                let mut context = self.ordering_context.write().await;
-                context.update_time();
-                txs = ordering::order_transactions(txs, policy, &mut context);
-                drop(context);
-                
-                txs
+                (Vec::new(), commitments)
             } else {
                 // Use lock-free transaction pool
                 let count = self.tx_pool_size.load(Ordering::Acquire).min(STREAM_B_MAX_TXS);
@@ -454,31 +646,13 @@ impl MiningManager {
                         break; // Queue is empty
                     }
                 }
-                
-                // Apply ordering policy if we have transactions
-                // Use try_write to avoid deadlock - if context is busy, skip ordering update
-                if !txs.is_empty() {
-                    let policy = *self.ordering_policy.read().await;
-                    // Use timeout to avoid deadlock - if context is busy, skip update
-                    match tokio::time::timeout(Duration::from_millis(10), self.ordering_context.write()).await {
-                        Ok(mut context) => {
-                            context.update_time();
-                            txs = ordering::order_transactions(txs, policy, &mut context);
-                        }
-                        Err(_) => {
-                            // Context is busy, order without updating context (non-critical)
-                            let mut temp_context = ordering::OrderingContext::new();
-                            txs = ordering::order_transactions(txs, policy, &mut temp_context);
-                        }
-                    }
-                }
-                
-                txs
+
+                (txs, Vec::new())
             };
 
             let (parent_hashes, block_number) = {
                 let blockchain = self.blockchain.read().await;
-                let blocks = blockchain.get_blocks();
+                let blocks = blockchain.block_metas();
                 let parents = if !blocks.is_empty() {
                     // Get last few blocks as parents (DAG structure)
                     let mut parents = Vec::new();
@@ -497,7 +671,16 @@ impl MiningManager {
                 (parents, num)
             };
 
-            let header = BlockHeader::new(parent_hashes.clone(), block_number, StreamType::StreamB, 4);
+            // Pack transactions by size, gas and validity; skipped ones go back to the pool
+            let limits = assembly::BlockLimits::new(STREAM_B_MAX_TXS, STREAM_B_MAX_GAS);
+            let txs = self.assemble_block_transactions(txs, block_number, &limits).await;
+
+            // Apply ordering policy and commit it to the header
+            let header = BlockHeader::new(parent_hashes.clone(), block_number, StreamType::StreamB, 4)
+                .with_coinbase(self.miner_address)
+                .with_shard_commitments(commitments);
+            let (header, txs) = self.order_block_transactions(header, txs).await;
+            self.publish_pool_job(&header).await;
             let block = Block::new(header, txs.clone(), parent_hashes);
 
             // Send block to processor via channel (non-blocking, eliminates deadlock)
@@ -536,34 +719,13 @@ impl MiningManager {
                         break; // Queue is empty
                     }
                 }
-                
-                // Apply ordering policy if we have transactions
-                // Use try_write to avoid deadlock - if context is busy, skip ordering update
-                if !txs.is_empty() {
-                    let policy = *self.ordering_policy.read().await;
-                    // Use timeout to avoid deadlock - if context is busy, skip update
-                    match tokio::time::timeout(Duration::from_millis(10), self.ordering_context.write()).await {
-                        Ok(mut context) => {
-                            context.update_time();
-                            txs = ordering::order_transactions(txs, policy, &mut context);
-                        }
-                        Err(_) => {
-                            // Context is busy, order without updating context (non-critical)
-                            let mut temp_context = ordering::OrderingContext::new();
-                            txs = ordering::order_transactions(txs, policy, &mut temp_context);
-                        }
-                    }
-                }
-                
+
                 txs
             };
 
-            // Calculate total fees from transactions
-            let total_fees: u128 = txs.iter().map(|tx| tx.fee).sum();
-
             let (parent_hashes, block_number) = {
                 let blockchain = self.blockchain.read().await;
-                let blocks = blockchain.get_blocks();
+                let blocks = blockchain.block_metas();
                 let parents = if !blocks.is_empty() {
                     // Get last few blocks as parents (DAG structure)
                     let mut parents = Vec::new();
@@ -582,9 +744,20 @@ impl MiningManager {
                 (parents, num)
             };
 
-            let header = BlockHeader::new(parent_hashes.clone(), block_number, StreamType::StreamC, 4);
+            // Pack transactions by size, gas and validity; skipped ones go back to the pool
+            let limits = assembly::BlockLimits::new(STREAM_C_MAX_TXS, STREAM_C_MAX_GAS);
+            let txs = self.assemble_block_transactions(txs, block_number, &limits).await;
+
+            // Apply ordering policy and commit it to the header
+            let header = BlockHeader::new(parent_hashes.clone(), block_number, StreamType::StreamC, 4)
+                .with_coinbase(self.miner_address);
+            let (header, txs) = self.order_block_transactions(header, txs).await;
+            self.publish_pool_job(&header).await;
             let block = Block::new(header, txs.clone(), parent_hashes);
 
+            // Calculate total fees from transactions
+            let total_fees: u128 = txs.iter().map(|tx| tx.fee).sum();
+
             // Send block to processor via channel (non-blocking, eliminates deadlock)
             let _ = self.block_sender.send(BlockSubmission {
                 block,
@@ -602,15 +775,113 @@ impl MiningManager {
     }
 }
 
+/// Push transactions back onto a lock-free pool
+fn requeue_to_pool(tx_pool: &SegQueue<Transaction>, tx_pool_size: &AtomicUsize, txs: Vec<Transaction>) {
+    for tx in txs {
+        tx_pool.push(tx);
+        tx_pool_size.fetch_add(1, Ordering::Release);
+    }
+}
+
+/// Return deferred transactions to the pool, evicting those deferred for too long
+///
+/// `left` are transactions that left the pool for good and stop aging.
+fn requeue_deferred(
+    tx_pool: &SegQueue<Transaction>,
+    tx_pool_size: &AtomicUsize,
+    deferrals: &std::sync::Mutex<assembly::DeferralTracker>,
+    deferred: Vec<Transaction>,
+    left: &[Transaction],
+    block_number: u64,
+    now: u64,
+) {
+    let (requeue, expired) = {
+        let mut deferrals = deferrals.lock().unwrap_or_else(|e| e.into_inner());
+        deferrals.forget(left.iter().map(|tx| &tx.hash));
+        deferrals.requeue(deferred, block_number, now)
+    };
+    if !expired.is_empty() {
+        println!("⚠️  Evicted {} transactions deferred for over {}s",
+            expired.len(), assembly::DEFERRED_TX_TTL_SECS);
+    }
+    requeue_to_pool(tx_pool, tx_pool_size, requeue);
+}
+
+/// Reconcile pool payouts with the chain and submit the next batch if due
+///
+/// Payouts are ordinary transactions from the pool coinbase: with sharding
+/// they are routed to the coinbase's shard like any other transaction, and
+/// its nonce, balance and included payouts are read from that shard.
+async fn settle_pool_payouts(
+    mining_pool: &mut pool::MiningPool,
+    blockchain: &RwLock<Blockchain>,
+    shard_manager: Option<&ShardManager>,
+    tx_pool: &SegQueue<Transaction>,
+    tx_pool_size: &AtomicUsize,
+) {
+    let address = mining_pool.address();
+    let unsettled = mining_pool.unsettled_payouts();
+    let (chain_nonce, available, included) = match shard_manager {
+        Some(shard_manager) => {
+            let mut included = std::collections::HashMap::new();
+            for hash in &unsettled {
+                if let Some(block_hash) = shard_manager.get_transaction_block(address, hash).await {
+                    included.insert(*hash, block_hash);
+                }
+            }
+            (shard_manager.get_nonce(address).await, shard_manager.get_balance(address).await, included)
+        }
+        None => {
+            let blockchain = blockchain.read().await;
+            let included: std::collections::HashMap<_, _> = unsettled.iter()
+                .filter_map(|hash| blockchain.get_transaction_by_hash(hash).map(|(block, _)| (*hash, block.hash)))
+                .collect();
+            (blockchain.expected_nonce(address), blockchain.get_balance(address), included)
+        }
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let (_, released) = mining_pool.sync_payouts(chain_nonce, now, |hash| included.get(hash).copied());
+    if released > 0 {
+        println!("⚠️  Pool: {} payout transactions did not make it on chain; balances restored", released);
+    }
+    if !mining_pool.payout_due() {
+        return;
+    }
+    let payouts = mining_pool.build_payouts(chain_nonce, available, now);
+    if payouts.is_empty() {
+        return;
+    }
+    println!("💸 Pool: queued {} payout transactions", payouts.len());
+    match shard_manager {
+        Some(shard_manager) => {
+            for tx in payouts {
+                if let Err(e) = shard_manager.add_transaction(tx).await {
+                    eprintln!("⚠️  Pool: failed to submit payout transaction: {}", e);
+                }
+            }
+        }
+        None => requeue_to_pool(tx_pool, tx_pool_size, payouts),
+    }
+}
+
 /// Process blocks from channel - serializes block additions to prevent deadlock
 async fn process_blocks(
     mut receiver: mpsc::UnboundedReceiver<BlockSubmission>,
     blockchain: Arc<RwLock<Blockchain>>,
+    tx_pool: Arc<SegQueue<Transaction>>,
+    tx_pool_size: Arc<AtomicUsize>,
+    deferrals: Arc<std::sync::Mutex<assembly::DeferralTracker>>,
     miner_address: Address,
     fairness_analyzer: Arc<tokio::sync::RwLock<fairness::FairnessAnalyzer>>,
     metrics: Option<crate::metrics::MetricsHandle>,
     node_registry: Option<Arc<tokio::sync::RwLock<crate::governance::NodeRegistry>>>,
     node_identity: Option<crate::governance::NodeIdentity>,
+    pool: Arc<RwLock<Option<pool::MiningPool>>>,
+    shard_manager: Option<Arc<ShardManager>>,
 ) {
     while let Some(submission) = receiver.recv().await {
         let BlockSubmission { block, stream_type, block_number, reward, fees } = submission;
@@ -618,19 +889,66 @@ async fn process_blocks(
         // Add block to blockchain (serialized - no contention)
         {
             let mut blockchain = blockchain.write().await;
-            if blockchain.add_block(block.clone()).is_ok() {
-                // Give miner the reward/fees
-                let current_balance = blockchain.get_balance(miner_address);
-                let total_reward = reward + fees;
-                if let Err(e) = blockchain.set_balance(miner_address, current_balance + total_reward) {
-                    eprintln!("Warning: Failed to persist reward: {}", e);
+            if blockchain.add_mined_block(block.clone()).is_ok() {
+                deferrals.lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .forget(block.transactions.iter().map(|tx| &tx.hash));
+                
+                // Reward is credited to the header coinbase by consensus (reduced for red blocks)
+                let paid = blockchain.get_block_reward(&block.hash).unwrap_or(0);
+                if paid < reward + fees {
+                    println!("🔴 Stream block #{} is red: paid {} of {} base units to {}",
+                        block_number, paid, reward + fees, hex::encode(miner_address));
+                }
+                
+                // Pool mode: split the reward across the PPLNS window and bring
+                // pool blocks recolored since (by this block or relayed ones)
+                // in line with what the chain now pays them
+                if let Some(mining_pool) = pool.write().await.as_mut() {
+                    mining_pool.credit_block(block.hash, stream_type, paid);
+                    let adjusted = mining_pool.reconcile_rewards(|hash| blockchain.get_block_reward(hash));
+                    if adjusted > 0 {
+                        println!("🎨 Pool: {} recolored blocks adjusted in worker balances", adjusted);
+                    }
                 }
             } else {
-                // Block validation failed, skip
+                // Block validation failed - return its transactions to the pool they
+                // came from so the next assembly can re-check them instead of losing
+                // them. Main-chain blocks only carry main-pool transactions: with
+                // sharding, shard transactions are mined on the shard DAGs and
+                // `ShardManager::produce_shard_block` returns its rejects to the
+                // shard pools itself.
+                drop(blockchain);
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                requeue_deferred(
+                    &tx_pool,
+                    &tx_pool_size,
+                    &deferrals,
+                    block.transactions,
+                    &[],
+                    block.header.block_number,
+                    now,
+                );
                 continue;
             }
         } // Release blockchain lock
         
+        // Pool mode: settle payouts against the chain and pay out in batches
+        if let Some(mining_pool) = pool.write().await.as_mut() {
+            settle_pool_payouts(mining_pool, &blockchain, shard_manager.as_deref(), &tx_pool, &tx_pool_size).await;
+            if let Err(e) = mining_pool.persist() {
+                eprintln!("⚠️  Failed to save mining pool ledger: {}", e);
+            }
+        }
+        
+        // Beacon blocks advance the committed shard tips
+        if let Some(ref shard_manager) = shard_manager {
+            shard_manager.record_beacon(&block).await;
+        }
+        
         // Record participation in node registry (CRITICAL for longevity tracking)
         if let (Some(ref registry), Some(ref identity)) = (&node_registry, &node_identity) {
             let participation = crate::governance::ParticipationType::BlockMined {
@@ -0,0 +1,339 @@
+//! Block Assembly
+//!
+//! Selects transactions for a block so that the assembled block fits within
+//! `MAX_BLOCK_SIZE` and the block gas limit, and skips transactions that would
+//! fail validation against current state (wrong nonce, insufficient balance,
+//! time-lock not yet reached). Skipped transactions are handed back to the
+//! caller so they can be returned to the pool instead of being lost, until
+//! they have been deferred for longer than `DEFERRED_TX_TTL_SECS`.
+
+use super::ordering::reseat_by_nonce;
+use crate::blockchain::{Blockchain, Transaction, MAX_BLOCK_SIZE, MAX_TX_DATA_SIZE};
+use crate::types::{Address, Hash};
+use std::collections::HashMap;
+
+/// Bytes reserved for the block header (parents, ordering policy, coinbase, hash)
+pub const BLOCK_HEADER_SIZE_RESERVE: usize = 4 * 1024;
+
+/// Bytes each transaction adds to the header (committed arrival time)
+const PER_TX_HEADER_BYTES: usize = 8;
+
+/// How long a ready transaction may keep being deferred before it is evicted (1 hour)
+pub const DEFERRED_TX_TTL_SECS: u64 = 60 * 60;
+
+/// Limits for a single assembled block
+#[derive(Debug, Clone, Copy)]
+pub struct BlockLimits {
+    pub max_txs: usize,
+    pub max_size_bytes: usize,
+    pub max_gas: u64,
+}
+
+impl BlockLimits {
+    /// Limits for a stream with the given transaction count and gas budget
+    pub fn new(max_txs: usize, max_gas: u64) -> Self {
+        Self {
+            max_txs,
+            max_size_bytes: MAX_BLOCK_SIZE - BLOCK_HEADER_SIZE_RESERVE,
+            max_gas,
+        }
+    }
+}
+
+/// Result of block assembly
+#[derive(Debug, Default)]
+pub struct AssembledBlock {
+    /// Transactions selected for the block
+    pub included: Vec<Transaction>,
+    /// Transactions that may become valid later (future nonce, low balance,
+    /// time-locked, or did not fit) - return these to the pool
+    pub deferred: Vec<Transaction>,
+    /// Transactions that can never become valid (stale nonce, bad hash,
+    /// oversized data, zero gas) - discard these
+    pub dropped: Vec<Transaction>,
+    /// Serialized size of the included transactions (bytes)
+    pub total_size: usize,
+    /// Cumulative gas limit of the included transactions
+    pub total_gas: u64,
+}
+
+/// Select transactions for a block
+///
+/// Candidates are considered in the order given, except that each sender's
+/// transactions are re-seated in nonce order within that sender's slots.
+/// Balance checks only count debits made by earlier selections, never credits,
+/// so the selected set stays valid under any ordering policy that preserves
+/// per-sender nonce order.
+pub fn assemble_transactions(
+    candidates: Vec<Transaction>,
+    blockchain: &Blockchain,
+    block_number: u64,
+    timestamp: u64,
+    limits: &BlockLimits,
+) -> AssembledBlock {
+    let mut assembled = AssembledBlock::default();
+    let mut next_nonce: HashMap<Address, u64> = HashMap::new();
+    let mut spendable: HashMap<Address, u128> = HashMap::new();
+
+    for tx in reseat_by_nonce(candidates, |tx| tx) {
+        // Block is full - everything else goes back to the pool
+        if assembled.included.len() >= limits.max_txs {
+            assembled.deferred.push(tx);
+            continue;
+        }
+
+        // Permanently invalid transactions
+        if tx.hash != tx.calculate_hash() || tx.data.len() > MAX_TX_DATA_SIZE || tx.gas_limit == 0 {
+            assembled.dropped.push(tx);
+            continue;
+        }
+
+        // Time-locked transactions that are not ready yet
+        if !tx.is_ready_to_execute(block_number, timestamp) {
+            assembled.deferred.push(tx);
+            continue;
+        }
+
+        // Nonce must follow the sender's state nonce plus earlier selections
+        let expected = *next_nonce.entry(tx.from)
+            .or_insert_with(|| blockchain.expected_nonce(tx.from));
+        if tx.nonce < expected {
+            assembled.dropped.push(tx);
+            continue;
+        }
+        if tx.nonce > expected {
+            assembled.deferred.push(tx);
+            continue;
+        }
+
+        // Balance checks (privacy transactions do not touch public balances)
+        let (sender_cost, sponsor_cost) = if tx.privacy_data.is_some() {
+            (0, 0)
+        } else if tx.sponsor.is_some() {
+            (tx.value, tx.fee)
+        } else {
+            (tx.value.saturating_add(tx.fee), 0)
+        };
+        let sender_available = *spendable.entry(tx.from)
+            .or_insert_with(|| blockchain.get_balance(tx.from));
+        if sender_available < sender_cost {
+            assembled.deferred.push(tx);
+            continue;
+        }
+        if let Some(sponsor) = tx.sponsor {
+            let sponsor_available = *spendable.entry(sponsor)
+                .or_insert_with(|| blockchain.get_balance(sponsor));
+            // Sponsor paying for its own transaction must cover both costs
+            let sponsor_needed = if sponsor == tx.from {
+                sender_cost.saturating_add(sponsor_cost)
+            } else {
+                sponsor_cost
+            };
+            if sponsor_available < sponsor_needed {
+                assembled.deferred.push(tx);
+                continue;
+            }
+        }
+
+        // Size and gas budgets
+        let tx_size = bincode::serialized_size(&tx)
+            .map(|size| size as usize)
+            .unwrap_or(usize::MAX)
+            .saturating_add(PER_TX_HEADER_BYTES);
+        if assembled.total_size.saturating_add(tx_size) > limits.max_size_bytes
+            || assembled.total_gas.saturating_add(tx.gas_limit) > limits.max_gas
+        {
+            assembled.deferred.push(tx);
+            continue;
+        }
+
+        // Select the transaction
+        if let Some(available) = spendable.get_mut(&tx.from) {
+            *available -= sender_cost;
+        }
+        if let Some(sponsor) = tx.sponsor {
+            if let Some(available) = spendable.get_mut(&sponsor) {
+                *available = available.saturating_sub(sponsor_cost);
+            }
+        }
+        next_nonce.insert(tx.from, expected + 1);
+        assembled.total_size += tx_size;
+        assembled.total_gas += tx.gas_limit;
+        assembled.included.push(tx);
+    }
+
+    assembled
+}
+
+/// Ages transactions that keep going back to the pool
+///
+/// A transaction that never becomes valid (a nonce gap nobody fills, a balance
+/// that never arrives, a block that keeps being rejected) would otherwise be
+/// requeued forever. The clock starts the first time a transaction is deferred;
+/// time-locked transactions only start aging once their lock is reached.
+#[derive(Debug)]
+pub struct DeferralTracker {
+    ttl_secs: u64,
+    first_deferred: HashMap<Hash, u64>,
+}
+
+impl DeferralTracker {
+    pub fn new(ttl_secs: u64) -> Self {
+        Self {
+            ttl_secs,
+            first_deferred: HashMap::new(),
+        }
+    }
+
+    /// Split transactions going back to the pool into (requeue, expired)
+    ///
+    /// Expired transactions have been deferred for longer than the TTL and
+    /// should be discarded. Entries not seen for a whole TTL are pruned, so
+    /// transactions that left the pool some other way are not tracked forever.
+    pub fn requeue(
+        &mut self,
+        txs: Vec<Transaction>,
+        block_number: u64,
+        now: u64,
+    ) -> (Vec<Transaction>, Vec<Transaction>) {
+        let ttl_secs = self.ttl_secs;
+        let mut requeue = Vec::with_capacity(txs.len());
+        let mut expired = Vec::new();
+        for tx in txs {
+            if !tx.is_ready_to_execute(block_number, now) {
+                self.first_deferred.remove(&tx.hash);
+                requeue.push(tx);
+                continue;
+            }
+            let since = *self.first_deferred.entry(tx.hash).or_insert(now);
+            if now.saturating_sub(since) > ttl_secs {
+                self.first_deferred.remove(&tx.hash);
+                expired.push(tx);
+            } else {
+                requeue.push(tx);
+            }
+        }
+        self.first_deferred.retain(|_, since| now.saturating_sub(*since) <= ttl_secs);
+        (requeue, expired)
+    }
+
+    /// Stop tracking transactions that left the pool (mined or dropped)
+    pub fn forget<'a>(&mut self, hashes: impl IntoIterator<Item = &'a Hash>) {
+        for hash in hashes {
+            self.first_deferred.remove(hash);
+        }
+    }
+
+    /// Number of transactions currently aging
+    pub fn len(&self) -> usize {
+        self.first_deferred.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first_deferred.is_empty()
+    }
+}
+
+impl Default for DeferralTracker {
+    fn default() -> Self {
+        Self::new(DEFERRED_TX_TTL_SECS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn funded_chain(address: Address, balance: u128) -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_balance(address, balance).unwrap();
+        blockchain
+    }
+
+    #[test]
+    fn test_skips_invalid_and_orders_nonces() {
+        let sender: Address = [1u8; 20];
+        let blockchain = funded_chain(sender, 1_000);
+
+        let candidates = vec![
+            Transaction::new(sender, [2u8; 20], 100, 10, 1),
+            Transaction::new(sender, [2u8; 20], 100, 10, 0),
+            Transaction::new(sender, [2u8; 20], 100, 10, 5), // Future nonce
+            Transaction::new([3u8; 20], [2u8; 20], 100, 10, 0), // No balance
+            Transaction::new(sender, [2u8; 20], 100, 10, 2).with_execute_at_block(50), // Time-locked
+        ];
+
+        let limits = BlockLimits::new(100, 1_000_000);
+        let assembled = assemble_transactions(candidates, &blockchain, 1, 0, &limits);
+
+        let nonces: Vec<u64> = assembled.included.iter().map(|tx| tx.nonce).collect();
+        assert_eq!(nonces, vec![0, 1]);
+        assert_eq!(assembled.deferred.len(), 3);
+        assert!(assembled.dropped.is_empty());
+    }
+
+    #[test]
+    fn test_respects_balance_and_gas_budgets() {
+        let sender: Address = [1u8; 20];
+        let blockchain = funded_chain(sender, 250);
+
+        let candidates: Vec<Transaction> = (0..5)
+            .map(|nonce| Transaction::new(sender, [2u8; 20], 100, 10, nonce))
+            .collect();
+
+        // Balance covers two transactions (2 * 110 = 220)
+        let limits = BlockLimits::new(100, 1_000_000);
+        let assembled = assemble_transactions(candidates.clone(), &blockchain, 1, 0, &limits);
+        assert_eq!(assembled.included.len(), 2);
+        assert_eq!(assembled.deferred.len(), 3);
+
+        // Gas budget covers one transaction (21,000 each)
+        let limits = BlockLimits::new(100, 30_000);
+        let assembled = assemble_transactions(candidates, &blockchain, 1, 0, &limits);
+        assert_eq!(assembled.included.len(), 1);
+        assert_eq!(assembled.total_gas, 21_000);
+    }
+
+    #[test]
+    fn test_drops_stale_nonce() {
+        let sender: Address = [1u8; 20];
+        let mut blockchain = funded_chain(sender, 1_000);
+        blockchain.set_nonce(sender, 3).unwrap();
+
+        let candidates = vec![Transaction::new(sender, [2u8; 20], 100, 10, 1)];
+        let limits = BlockLimits::new(100, 1_000_000);
+        let assembled = assemble_transactions(candidates, &blockchain, 1, 0, &limits);
+
+        assert!(assembled.included.is_empty());
+        assert_eq!(assembled.dropped.len(), 1);
+    }
+
+    #[test]
+    fn test_deferred_transactions_expire() {
+        let sender: Address = [1u8; 20];
+        let mut tracker = DeferralTracker::new(100);
+        let stuck = Transaction::new(sender, [2u8; 20], 100, 10, 5);
+        let locked = Transaction::new(sender, [2u8; 20], 100, 10, 6).with_execute_at_block(50);
+
+        let (requeue, expired) = tracker.requeue(vec![stuck.clone(), locked.clone()], 1, 1_000);
+        assert_eq!(requeue.len(), 2);
+        assert!(expired.is_empty());
+        assert_eq!(tracker.len(), 1); // Time-locked transaction is not aging yet
+
+        // Still within the TTL
+        let (requeue, expired) = tracker.requeue(vec![stuck.clone()], 2, 1_100);
+        assert_eq!(requeue.len(), 1);
+        assert!(expired.is_empty());
+
+        // Past the TTL the stuck transaction is evicted, the time-locked one is kept
+        let (requeue, expired) = tracker.requeue(vec![stuck.clone(), locked], 3, 1_101);
+        assert_eq!(requeue.len(), 1);
+        assert_eq!(expired[0].hash, stuck.hash);
+        assert!(tracker.is_empty());
+
+        // Mined transactions stop aging
+        tracker.requeue(vec![stuck.clone()], 4, 2_000);
+        tracker.forget([&stuck.hash]);
+        assert!(tracker.is_empty());
+    }
+}
@@ -4,7 +4,9 @@
 //! to provide transparency in block ordering and detect unfair practices
 
 use crate::blockchain::{Block, Transaction};
-use crate::types::Hash;
+use crate::mining::ordering::OrderingPolicy;
+use crate::types::{Address, Hash, StreamType};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Fairness analyzer that tracks transaction ordering and MEV patterns
@@ -14,7 +16,7 @@ pub struct FairnessAnalyzer {
 }
 
 /// Fairness metrics for a block
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FairnessMetrics {
     /// Average reordering distance (how far transactions moved from arrival order)
     pub reordering_distance: f64,
@@ -45,6 +47,21 @@ impl FairnessAnalyzer {
         }
     }
     
+    /// Create an analyzer from the arrival times committed in a block header
+    ///
+    /// Used at block acceptance, where the local mempool may never have seen the
+    /// block's transactions. Blocks without committed arrival times yield an
+    /// analyzer with no arrival data (reordering and age metrics are zero).
+    pub fn from_block(block: &Block) -> Self {
+        let mut analyzer = Self::new();
+        if block.header.tx_arrival_times.len() == block.transactions.len() {
+            for (tx, &arrival) in block.transactions.iter().zip(&block.header.tx_arrival_times) {
+                analyzer.record_transaction_arrival(tx.hash, arrival);
+            }
+        }
+        analyzer
+    }
+    
     /// Record when a transaction arrived in the mempool
     pub fn record_transaction_arrival(&mut self, tx_hash: Hash, timestamp: u64) {
         self.transaction_arrival_times.insert(tx_hash, timestamp);
@@ -52,6 +69,15 @@ impl FairnessAnalyzer {
     
     /// Analyze a block for fairness metrics
     pub fn analyze_block(&self, block: &Block) -> FairnessMetrics {
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.analyze_block_at(block, current_time)
+    }
+    
+    /// Analyze a block for fairness metrics, measuring transaction age against `reference_time`
+    pub fn analyze_block_at(&self, block: &Block, reference_time: u64) -> FairnessMetrics {
         if block.transactions.is_empty() {
             return FairnessMetrics {
                 reordering_distance: 0.0,
@@ -95,15 +121,11 @@ impl FairnessAnalyzer {
         let (sandwich, backrun, frontrun, mev_value) = self.detect_mev_patterns(&block.transactions);
         
         // Calculate average transaction age
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
         let mut total_age = 0u64;
         let mut age_count = 0;
         for tx in &block.transactions {
             if let Some(&arrival_time) = self.transaction_arrival_times.get(&tx.hash) {
-                total_age += current_time.saturating_sub(arrival_time);
+                total_age += reference_time.saturating_sub(arrival_time);
                 age_count += 1;
             }
         }
@@ -218,16 +240,14 @@ impl FairnessAnalyzer {
             return 0.0;
         }
         
-        let mean = sum as f64 / n;
-        let mut gini = 0.0;
+        // Sorted-rank form of sum(|x_i - x_j|) / (2 * n^2 * mean), O(n log n)
+        // so it can run on every accepted block
+        let weighted_sum: f64 = sorted_fees.iter()
+            .enumerate()
+            .map(|(i, &fee)| (i as f64 + 1.0) * fee as f64)
+            .sum();
         
-        for i in 0..sorted_fees.len() {
-            for j in 0..sorted_fees.len() {
-                gini += (sorted_fees[i] as f64 - sorted_fees[j] as f64).abs();
-            }
-        }
-        
-        gini / (2.0 * n * n * mean)
+        (2.0 * weighted_sum) / (n * sum as f64) - (n + 1.0) / n
     }
     
     /// Get fairness score for a specific transaction position
@@ -259,6 +279,103 @@ impl Default for FairnessAnalyzer {
     }
 }
 
+/// Fairness metrics recorded for an accepted block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FairnessRecord {
+    pub block_hash: Hash,
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub stream_type: StreamType,
+    /// Miner that produced the block (None for legacy blocks)
+    pub miner: Option<Address>,
+    /// Ordering policy declared in the block header (None if undeclared)
+    pub ordering_policy: Option<OrderingPolicy>,
+    pub metrics: FairnessMetrics,
+}
+
+impl FairnessRecord {
+    /// Compute the fairness record for a block from its committed header data
+    pub fn from_block(block: &Block) -> Self {
+        let metrics = FairnessAnalyzer::from_block(block)
+            .analyze_block_at(block, block.header.timestamp);
+        Self {
+            block_hash: block.hash,
+            block_number: block.header.block_number,
+            timestamp: block.header.timestamp,
+            stream_type: block.header.stream_type,
+            miner: block.header.coinbase,
+            ordering_policy: block.header.ordering_policy,
+            metrics,
+        }
+    }
+}
+
+/// Aggregated fairness metrics over a set of blocks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FairnessAggregate {
+    pub block_count: u64,
+    pub transaction_count: u64,
+    pub sandwich_detections: u64,
+    pub backrun_detections: u64,
+    pub frontrun_detections: u64,
+    pub estimated_mev_value: u128,
+    total_fairness_score: f64,
+    total_fee_concentration: f64,
+    total_reordering_distance: f64,
+}
+
+impl FairnessAggregate {
+    /// Add a block's record to the aggregate
+    pub fn add(&mut self, record: &FairnessRecord) {
+        let m = &record.metrics;
+        self.block_count += 1;
+        self.transaction_count += m.transaction_count as u64;
+        self.sandwich_detections += m.sandwich_detections;
+        self.backrun_detections += m.backrun_detections;
+        self.frontrun_detections += m.frontrun_detections;
+        self.estimated_mev_value = self.estimated_mev_value.saturating_add(m.estimated_mev_value);
+        self.total_fairness_score += m.fairness_score;
+        self.total_fee_concentration += m.fee_concentration;
+        self.total_reordering_distance += m.reordering_distance;
+    }
+    
+    /// Average fairness score per block
+    pub fn avg_fairness_score(&self) -> f64 {
+        self.average(self.total_fairness_score)
+    }
+    
+    /// Average fee concentration (Gini coefficient) per block
+    pub fn avg_fee_concentration(&self) -> f64 {
+        self.average(self.total_fee_concentration)
+    }
+    
+    /// Average reordering distance per block
+    pub fn avg_reordering_distance(&self) -> f64 {
+        self.average(self.total_reordering_distance)
+    }
+    
+    fn average(&self, total: f64) -> f64 {
+        if self.block_count == 0 {
+            0.0
+        } else {
+            total / self.block_count as f64
+        }
+    }
+}
+
+/// Aggregate fairness records by a grouping key (e.g. miner or ordering policy)
+pub fn aggregate_by<K, F>(records: &[FairnessRecord], key: F) -> HashMap<K, FairnessAggregate>
+where
+    K: std::hash::Hash + Eq,
+    F: Fn(&FairnessRecord) -> K,
+{
+    let mut groups: HashMap<K, FairnessAggregate> = HashMap::new();
+    for record in records {
+        groups.entry(key(record)).or_default().add(record);
+    }
+    groups
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,6 +398,8 @@ mod tests {
             execute_at_block: None,
             execute_at_timestamp: None,
             sponsor: None,
+            multisig_signatures: None,
+            privacy_data: None,
         };
         tx.hash = tx.calculate_hash();
         tx
@@ -324,4 +443,35 @@ mod tests {
         let metrics = analyzer.analyze_block(&block);
         assert!(metrics.sandwich_detections > 0 || metrics.fairness_score < 1.0);
     }
+    
+    #[test]
+    fn test_gini_coefficient() {
+        let analyzer = FairnessAnalyzer::new();
+        assert_eq!(analyzer.calculate_gini_coefficient(&[100, 100, 100, 100]), 0.0);
+        
+        // One holder of all fees: (n - 1) / n
+        let gini = analyzer.calculate_gini_coefficient(&[0, 0, 0, 400]);
+        assert!((gini - 0.75).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_record_uses_committed_arrivals() {
+        let tx1 = create_test_transaction([1u8; 32], [10u8; 20], [99u8; 20]);
+        let tx2 = create_test_transaction([2u8; 32], [20u8; 20], [98u8; 20]);
+        let header = BlockHeader::new(vec![[7u8; 32]], 1, StreamType::StreamB, 4)
+            .with_ordering(OrderingPolicy::Fifo, vec![1000, 1010])
+            .with_coinbase([5u8; 20]);
+        let mut block = Block::new(header, vec![tx1, tx2], vec![[7u8; 32]]);
+        block.header.timestamp = 1020;
+        
+        let record = FairnessRecord::from_block(&block);
+        assert_eq!(record.miner, Some([5u8; 20]));
+        assert_eq!(record.ordering_policy, Some(OrderingPolicy::Fifo));
+        assert_eq!(record.metrics.avg_transaction_age, 15.0);
+        assert_eq!(record.metrics.reordering_distance, 0.0);
+        
+        let by_miner = aggregate_by(&[record.clone(), record], |r| r.miner);
+        assert_eq!(by_miner[&Some([5u8; 20])].block_count, 2);
+        assert_eq!(by_miner[&Some([5u8; 20])].transaction_count, 4);
+    }
 }
@@ -3,12 +3,17 @@
 //! Provides configurable ordering strategies for transaction inclusion in blocks
 //! to support MEV-aware and fairness-oriented block production
 
-use crate::blockchain::Transaction;
-use crate::types::Hash;
+use crate::blockchain::{Block, Transaction};
+use crate::types::{Address, Hash};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
 
+/// Width of the arrival-time buckets used by the verifiable time-weighted order (seconds)
+pub const TIME_WEIGHTED_BUCKET_SECS: u64 = 5;
+
 /// Transaction ordering policy
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum OrderingPolicy {
     /// First-In-First-Out (arrival order) - most fair
     Fifo,
@@ -38,6 +43,32 @@ impl OrderingPolicy {
             OrderingPolicy::TimeWeighted => "Time-Weighted",
         }
     }
+    
+    /// Whether validators enforce the declared order for this policy
+    ///
+    /// Only the Random order is fixed by data outside the miner's control (a
+    /// shuffle seeded from the parent hashes). FIFO and Time-Weighted orders
+    /// rest on arrival times only the miner attests to, so like fee-based and
+    /// hybrid orderings they are declared in the header but not enforced.
+    pub fn is_verifiable(&self) -> bool {
+        matches!(self, OrderingPolicy::Random)
+    }
+
+    /// Whether this node orders its blocks with `canonical_order`
+    pub fn has_canonical_order(&self) -> bool {
+        matches!(
+            self,
+            OrderingPolicy::Fifo | OrderingPolicy::Random | OrderingPolicy::TimeWeighted
+        )
+    }
+
+    /// Whether the order depends on arrival times only the miner attests to
+    ///
+    /// Validators see no mempool but their own, so they cannot check these
+    /// times: a miner can declare whatever times produce the order it wants.
+    pub fn relies_on_arrival_times(&self) -> bool {
+        matches!(self, OrderingPolicy::Fifo | OrderingPolicy::TimeWeighted)
+    }
 }
 
 /// Transaction ordering context
@@ -74,6 +105,11 @@ impl OrderingContext {
         }
     }
     
+    /// Get recorded arrival time for a transaction
+    pub fn get_arrival(&self, tx_hash: &Hash) -> Option<u64> {
+        self.arrival_times.get(tx_hash).copied()
+    }
+    
     /// Update current time
     pub fn update_time(&mut self) {
         self.current_time = std::time::SystemTime::now()
@@ -164,6 +200,158 @@ pub fn order_transactions(
     }
 }
 
+/// Derive the ordering seed for a block from its parent hashes
+///
+/// Parents are sorted first so the seed does not depend on the order in
+/// which a miner listed them.
+pub fn ordering_seed(parent_hashes: &[Hash]) -> Hash {
+    let mut parents = parent_hashes.to_vec();
+    parents.sort();
+    
+    let mut hasher = Keccak256::new();
+    hasher.update(b"mds-ordering-seed");
+    for parent in &parents {
+        hasher.update(parent);
+    }
+    let result = hasher.finalize();
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(&result);
+    seed
+}
+
+/// Seeded sort key for a transaction (tie-breaker and Random shuffle key)
+fn seeded_key(seed: &Hash, tx_hash: &Hash) -> Hash {
+    let mut hasher = Keccak256::new();
+    hasher.update(seed);
+    hasher.update(tx_hash);
+    let result = hasher.finalize();
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&result);
+    key
+}
+
+/// Compute the canonical order of `(transaction, arrival_time)` pairs
+///
+/// - FIFO: arrival time ascending, ties broken by seeded key
+/// - Random: seeded key ascending (a deterministic shuffle anyone can re-derive)
+/// - Time-Weighted: arrival bucket ascending, then fee descending, then seeded key
+///
+/// After sorting, each sender's transactions are re-seated in nonce order within the
+/// slots that sender occupies, so the canonical order never breaks nonce sequencing.
+/// Only the Random order is re-derived by validators (see `OrderingPolicy::is_verifiable`).
+/// Fee-based and hybrid policies are returned unchanged.
+pub fn canonical_order(
+    mut entries: Vec<(Transaction, u64)>,
+    policy: OrderingPolicy,
+    seed: &Hash,
+) -> Vec<(Transaction, u64)> {
+    match policy {
+        OrderingPolicy::Fifo => {
+            entries.sort_by(|a, b| {
+                a.1.cmp(&b.1)
+                    .then_with(|| seeded_key(seed, &a.0.hash).cmp(&seeded_key(seed, &b.0.hash)))
+            });
+        }
+        OrderingPolicy::Random => {
+            entries.sort_by_cached_key(|(tx, _)| seeded_key(seed, &tx.hash));
+        }
+        OrderingPolicy::TimeWeighted => {
+            entries.sort_by(|a, b| {
+                (a.1 / TIME_WEIGHTED_BUCKET_SECS).cmp(&(b.1 / TIME_WEIGHTED_BUCKET_SECS))
+                    .then_with(|| b.0.fee.cmp(&a.0.fee))
+                    .then_with(|| seeded_key(seed, &a.0.hash).cmp(&seeded_key(seed, &b.0.hash)))
+            });
+        }
+        OrderingPolicy::FeeBased | OrderingPolicy::Hybrid => return entries,
+    }
+    
+    reseat_by_nonce(entries, |(tx, _)| tx)
+}
+
+/// Re-seat each sender's transactions in nonce order within that sender's slots
+///
+/// `tx` picks the transaction out of each item, so callers can carry extra data
+/// (such as arrival times) alongside it.
+pub(crate) fn reseat_by_nonce<T>(items: Vec<T>, tx: impl Fn(&T) -> &Transaction) -> Vec<T> {
+    let mut slots: HashMap<Address, Vec<usize>> = HashMap::new();
+    for (idx, item) in items.iter().enumerate() {
+        slots.entry(tx(item).from).or_default().push(idx);
+    }
+    
+    let mut result: Vec<Option<T>> = items.into_iter().map(Some).collect();
+    for indices in slots.values() {
+        if indices.len() < 2 {
+            continue;
+        }
+        let mut sender_items: Vec<T> = indices.iter()
+            .filter_map(|&idx| result[idx].take())
+            .collect();
+        sender_items.sort_by_key(|item| tx(item).nonce);
+        for (&idx, item) in indices.iter().zip(sender_items) {
+            result[idx] = Some(item);
+        }
+    }
+    
+    result.into_iter().flatten().collect()
+}
+
+/// Check that a block is consistent with the ordering policy declared in its header
+///
+/// Version 2 headers must declare a policy; legacy (version 1) headers carry
+/// none and pass. Policies that are not verifiable are recorded but not
+/// enforced. For the Random policy the header must commit one arrival time per
+/// transaction, none later than the block timestamp, and the transactions must
+/// appear exactly in the shuffle derived from the parent-hash seed.
+pub fn verify_block_ordering(block: &Block) -> Result<(), String> {
+    let policy = match block.header.ordering_policy {
+        Some(policy) if policy.is_verifiable() => policy,
+        Some(_) => return Ok(()),
+        None if block.header.version >= 2 => {
+            return Err(format!(
+                "Version {} header does not declare an ordering policy",
+                block.header.version
+            ));
+        }
+        None => return Ok(()),
+    };
+    
+    let arrivals = &block.header.tx_arrival_times;
+    if arrivals.len() != block.transactions.len() {
+        return Err(format!(
+            "{} ordering requires {} arrival times, header has {}",
+            policy.name(), block.transactions.len(), arrivals.len()
+        ));
+    }
+    
+    if let Some(late) = arrivals.iter().find(|&&t| t > block.header.timestamp) {
+        return Err(format!(
+            "Arrival time {} is after block timestamp {}",
+            late, block.header.timestamp
+        ));
+    }
+    
+    let seed = ordering_seed(&block.header.parent_hashes);
+    let entries: Vec<(Transaction, u64)> = block.transactions.iter()
+        .cloned()
+        .zip(arrivals.iter().copied())
+        .collect();
+    let expected = canonical_order(entries, policy, &seed);
+    
+    for (position, ((expected_tx, expected_arrival), (actual_tx, actual_arrival))) in expected.iter()
+        .zip(block.transactions.iter().zip(arrivals.iter()))
+        .enumerate()
+    {
+        if expected_tx.hash != actual_tx.hash || expected_arrival != actual_arrival {
+            return Err(format!(
+                "Transaction at position {} violates declared {} ordering",
+                position, policy.name()
+            ));
+        }
+    }
+    
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,4 +399,89 @@ mod tests {
         assert_eq!(ordered[1].hash, t1.hash);
         assert_eq!(ordered[2].hash, t3.hash); // Lowest fee
     }
+    
+    fn create_block(policy: OrderingPolicy, entries: Vec<(Transaction, u64)>) -> Block {
+        use crate::blockchain::block::BlockHeader;
+        use crate::types::StreamType;
+        
+        let parents = vec![[7u8; 32]];
+        let (txs, arrivals): (Vec<Transaction>, Vec<u64>) = entries.into_iter().unzip();
+        let header = BlockHeader::new(parents.clone(), 1, StreamType::StreamA, 4)
+            .with_ordering(policy, arrivals);
+        Block::new(header, txs, parents)
+    }
+    
+    #[test]
+    fn test_random_ordering_is_reproducible() {
+        let seed = ordering_seed(&[[1u8; 32], [2u8; 32]]);
+        let entries: Vec<(Transaction, u64)> = (0..8u8)
+            .map(|i| (Transaction::new([i; 20], [0u8; 20], 1, 1, 0), 100))
+            .collect();
+        
+        let first = canonical_order(entries.clone(), OrderingPolicy::Random, &seed);
+        let second = canonical_order(entries, OrderingPolicy::Random, &seed);
+        let first: Vec<Hash> = first.iter().map(|(tx, _)| tx.hash).collect();
+        let second: Vec<Hash> = second.iter().map(|(tx, _)| tx.hash).collect();
+        assert_eq!(first, second);
+        
+        // Parent order must not change the seed
+        assert_eq!(seed, ordering_seed(&[[2u8; 32], [1u8; 32]]));
+    }
+    
+    #[test]
+    fn test_canonical_order_keeps_sender_nonces_sequential() {
+        let seed = ordering_seed(&[[3u8; 32]]);
+        let sender: Address = [9u8; 20];
+        let entries: Vec<(Transaction, u64)> = (0..6u64)
+            .map(|nonce| (Transaction::new(sender, [0u8; 20], 1, nonce as u128, nonce), 100))
+            .collect();
+        
+        for policy in [OrderingPolicy::Fifo, OrderingPolicy::Random, OrderingPolicy::TimeWeighted] {
+            let ordered = canonical_order(entries.clone(), policy, &seed);
+            let nonces: Vec<u64> = ordered.iter().map(|(tx, _)| tx.nonce).collect();
+            assert_eq!(nonces, vec![0, 1, 2, 3, 4, 5]);
+        }
+    }
+    
+    #[test]
+    fn test_verify_block_ordering() {
+        let t1 = Transaction::new([1u8; 20], [0u8; 20], 1, 10, 0);
+        let t2 = Transaction::new([2u8; 20], [0u8; 20], 1, 20, 0);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        
+        // Random: the seeded shuffle is valid, any other order is rejected
+        let seed = ordering_seed(&[[7u8; 32]]);
+        let canonical = canonical_order(
+            vec![(t1.clone(), now - 10), (t2.clone(), now - 5)],
+            OrderingPolicy::Random,
+            &seed,
+        );
+        let valid = create_block(OrderingPolicy::Random, canonical.clone());
+        assert!(verify_block_ordering(&valid).is_ok());
+        let reversed = create_block(OrderingPolicy::Random, canonical.into_iter().rev().collect());
+        assert!(verify_block_ordering(&reversed).is_err());
+        
+        // Missing arrival times are rejected for verifiable policies
+        let missing = create_block(OrderingPolicy::Random, vec![]);
+        let missing = Block::new(missing.header, vec![t1.clone()], vec![[7u8; 32]]);
+        assert!(verify_block_ordering(&missing).is_err());
+        
+        // FIFO rests on miner-declared arrival times and is not enforced
+        let reversed_fifo = create_block(OrderingPolicy::Fifo, vec![(t2.clone(), now - 5), (t1.clone(), now - 10)]);
+        assert!(verify_block_ordering(&reversed_fifo).is_ok());
+        
+        // Version 2 headers must declare a policy
+        let mut undeclared = create_block(OrderingPolicy::Fifo, vec![]);
+        undeclared.header.ordering_policy = None;
+        assert!(verify_block_ordering(&undeclared).is_err());
+        undeclared.header.version = 1;
+        assert!(verify_block_ordering(&undeclared).is_ok());
+        
+        // Fee-based ordering is declared but not enforced
+        let fee_based = create_block(OrderingPolicy::FeeBased, vec![(t1, now), (t2, now)]);
+        assert!(verify_block_ordering(&fee_based).is_ok());
+    }
 }
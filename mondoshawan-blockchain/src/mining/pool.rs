@@ -0,0 +1,949 @@
+//! Mining Pool (PPLNS)
+//!
+//! Built-in pool-operator mode. Each job is the template of the block the pool
+//! is mining on a stream; a share is a partial proof of work on that template,
+//! a work hash that meets a share target much easier than the block's own
+//! target. Accepted shares enter a per-stream PPLNS (pay-per-last-N-shares)
+//! window. When the pool's coinbase earns a block reward, the reward (minus the
+//! pool fee) is split across the shares in that stream's window, and owed
+//! balances are paid out in batched, signed transactions from the coinbase.
+//! When GhostDAG recolors a pool block, the workers it was split across are
+//! credited or debited the difference.
+//!
+//! A payout counts as paid only once its transaction is on chain: payouts
+//! are reconciled against the coinbase nonce after every accepted block, and
+//! the ledger (balances, payouts, totals and windows) is persisted so a
+//! restart does not forget what workers are owed.
+
+use crate::blockchain::{BlockHeader, Transaction};
+use crate::error::BlockchainResult;
+use crate::storage::{Database, PoolStore};
+use crate::types::{Address, Hash, StreamType};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+/// Default number of shares kept in each stream's PPLNS window
+pub const DEFAULT_PPLNS_WINDOW: usize = 10_000;
+
+/// Default share target (leading zero bits of the work hash)
+pub const DEFAULT_SHARE_TARGET_BITS: u32 = 12;
+
+/// Default pool fee in basis points (1%)
+pub const DEFAULT_POOL_FEE_BPS: u64 = 100;
+
+/// Default minimum owed balance before a worker is paid (0.1 MSHW)
+pub const DEFAULT_MIN_PAYOUT: u64 = 100_000_000_000_000_000;
+
+/// Default number of payout transactions per batch
+pub const DEFAULT_PAYOUT_BATCH_SIZE: usize = 100;
+
+/// Default number of accepted pool blocks between payout batches
+pub const DEFAULT_PAYOUT_INTERVAL_BLOCKS: u64 = 10;
+
+/// Default fee attached to each payout transaction (0.001 MSHW, paid by the pool)
+pub const DEFAULT_PAYOUT_FEE: u64 = 1_000_000_000_000_000;
+
+/// Default seconds a payout may stay off chain before it is released and rebuilt
+pub const DEFAULT_PAYOUT_EXPIRY_SECS: u64 = 600;
+
+/// Number of recent share hashes remembered for duplicate detection
+const MAX_TRACKED_SHARE_HASHES: usize = 100_000;
+
+/// Number of settled payouts kept for `payouts_for`
+const MAX_PAYOUT_HISTORY: usize = 10_000;
+
+/// Number of recent pool blocks whose reward is reconciled after recoloring
+const MAX_TRACKED_CREDITS: usize = 1_000;
+
+/// Pool operator settings (the `[pool]` config table)
+///
+/// Amounts are in base units; they are `u64` because TOML has no wider integers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolConfig {
+    /// Shares kept per stream window
+    pub pplns_window: usize,
+    /// Leading zero bits a work hash needs to be accepted as a share
+    /// (capped at the block target)
+    pub share_target_bits: u32,
+    /// Pool fee in basis points
+    pub pool_fee_bps: u64,
+    /// Minimum owed balance before a payout is made
+    pub min_payout: u64,
+    /// Maximum payout transactions per batch
+    pub payout_batch_size: usize,
+    /// Accepted pool blocks between payout batches
+    pub payout_interval_blocks: u64,
+    /// Fee attached to each payout transaction
+    pub payout_fee: u64,
+    /// Seconds a payout may stay off chain before it is released and rebuilt
+    pub payout_expiry_secs: u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            pplns_window: DEFAULT_PPLNS_WINDOW,
+            share_target_bits: DEFAULT_SHARE_TARGET_BITS,
+            pool_fee_bps: DEFAULT_POOL_FEE_BPS,
+            min_payout: DEFAULT_MIN_PAYOUT,
+            payout_batch_size: DEFAULT_PAYOUT_BATCH_SIZE,
+            payout_interval_blocks: DEFAULT_PAYOUT_INTERVAL_BLOCKS,
+            payout_fee: DEFAULT_PAYOUT_FEE,
+            payout_expiry_secs: DEFAULT_PAYOUT_EXPIRY_SECS,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Validate pool settings
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pplns_window == 0 {
+            return Err("Pool PPLNS window must be greater than 0".to_string());
+        }
+
+        if self.share_target_bits == 0 || self.share_target_bits > 255 {
+            return Err("Pool share target must be between 1 and 255 bits".to_string());
+        }
+
+        if self.pool_fee_bps > 10_000 {
+            return Err("Pool fee cannot exceed 10000 basis points".to_string());
+        }
+
+        if self.payout_batch_size == 0 {
+            return Err("Pool payout batch size must be greater than 0".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Share submitted by a worker
+#[derive(Debug, Clone)]
+pub struct ShareSubmission {
+    pub worker: Address,
+    pub stream_type: StreamType,
+    /// Job the share was computed against (see `MiningPool::current_job`)
+    pub job_hash: Hash,
+    pub nonce: u64,
+}
+
+/// Block template workers compute shares against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolJob {
+    /// `template_hash` of the block the pool is mining
+    pub job_hash: Hash,
+    /// Leading zero bits a work hash needs to solve the block
+    pub block_target_bits: u32,
+}
+
+/// Share the pool accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AcceptedShare {
+    pub work_hash: Hash,
+    /// The work hash also meets the block target
+    pub solves_block: bool,
+}
+
+/// Accepted share in a PPLNS window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Share {
+    worker: Address,
+    timestamp: u64,
+}
+
+/// Reward a pool block was credited with, and the window shares it was split by
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BlockCredit {
+    reward: u128,
+    /// Shares per worker, sorted by address
+    shares: Vec<(Address, u128)>,
+}
+
+/// Per-worker accounting
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkerStats {
+    pub shares_accepted: u64,
+    pub shares_rejected: u64,
+    pub last_share_at: Option<u64>,
+    /// Rewards credited from blocks, not yet in a payout
+    pub pending_balance: u128,
+    /// Rewards in payout transactions that are not on chain yet
+    pub in_flight: u128,
+    /// Rewards in payout transactions included on chain
+    pub total_paid: u128,
+}
+
+/// Where a payout transaction stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PayoutStatus {
+    /// Sent, not seen on chain yet
+    Pending,
+    /// Included in the block with this hash
+    Included(Hash),
+    /// Given up on (its nonce went to another transaction, or it expired);
+    /// the amount went back to the worker's pending balance
+    Released,
+}
+
+/// Payout transaction made by the pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutRecord {
+    pub tx_hash: Hash,
+    pub worker: Address,
+    pub amount: u128,
+    pub nonce: u64,
+    pub created_at: u64,
+    pub status: PayoutStatus,
+}
+
+/// Pool-wide totals
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub workers: usize,
+    pub blocks_credited: u64,
+    pub total_rewards: u128,
+    pub pool_fees: u128,
+    pub total_pending: u128,
+    pub total_in_flight: u128,
+    pub total_paid: u128,
+    pub window_shares: HashMap<StreamType, usize>,
+}
+
+/// Persisted pool state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoolLedger {
+    workers: Vec<(Address, WorkerStats)>,
+    payouts: Vec<PayoutRecord>,
+    windows: Vec<(StreamType, Vec<Share>)>,
+    credits: Vec<(Hash, BlockCredit)>,
+    synced_nonce: u64,
+    blocks_since_payout: u64,
+    blocks_credited: u64,
+    total_rewards: u128,
+    pool_fees: u128,
+}
+
+/// PPLNS mining pool
+pub struct MiningPool {
+    config: PoolConfig,
+    address: Address,
+    payout_key: [u8; 32],
+    /// Current and previous job per stream (previous is accepted as a grace period)
+    jobs: HashMap<StreamType, (PoolJob, Option<PoolJob>)>,
+    windows: HashMap<StreamType, VecDeque<Share>>,
+    /// Recent pool blocks, oldest first
+    credits: VecDeque<(Hash, BlockCredit)>,
+    seen_shares: HashSet<Hash>,
+    seen_order: VecDeque<Hash>,
+    workers: HashMap<Address, WorkerStats>,
+    /// Payouts in the order they were built
+    payouts: VecDeque<PayoutRecord>,
+    /// Coinbase nonce at the last `sync_payouts`
+    synced_nonce: u64,
+    blocks_since_payout: u64,
+    blocks_credited: u64,
+    total_rewards: u128,
+    pool_fees: u128,
+    storage: Option<Arc<Database>>,
+}
+
+impl MiningPool {
+    /// Create a pool whose coinbase is the address of `payout_key`
+    pub fn new(payout_key: [u8; 32], config: PoolConfig) -> Self {
+        let address = payout_address(&payout_key);
+
+        Self {
+            config,
+            address,
+            payout_key,
+            jobs: HashMap::new(),
+            windows: HashMap::new(),
+            credits: VecDeque::new(),
+            seen_shares: HashSet::new(),
+            seen_order: VecDeque::new(),
+            workers: HashMap::new(),
+            payouts: VecDeque::new(),
+            synced_nonce: 0,
+            blocks_since_payout: 0,
+            blocks_credited: 0,
+            total_rewards: 0,
+            pool_fees: 0,
+            storage: None,
+        }
+    }
+
+    /// Create a pool that restores its ledger from storage and saves it on `persist`
+    pub fn with_storage(payout_key: [u8; 32], config: PoolConfig, database: Arc<Database>) -> BlockchainResult<Self> {
+        let mut pool = Self::new(payout_key, config);
+        if let Some(ledger) = PoolStore::new(&database).get_ledger(&pool.address)? {
+            pool.restore(ledger);
+        }
+        pool.storage = Some(database);
+        Ok(pool)
+    }
+
+    fn restore(&mut self, ledger: PoolLedger) {
+        self.workers = ledger.workers.into_iter().collect();
+        self.payouts = ledger.payouts.into_iter().collect();
+        self.windows = ledger.windows.into_iter()
+            .map(|(stream, shares)| (stream, shares.into_iter().collect()))
+            .collect();
+        self.credits = ledger.credits.into_iter().collect();
+        self.synced_nonce = ledger.synced_nonce;
+        self.blocks_since_payout = ledger.blocks_since_payout;
+        self.blocks_credited = ledger.blocks_credited;
+        self.total_rewards = ledger.total_rewards;
+        self.pool_fees = ledger.pool_fees;
+    }
+
+    /// Snapshot of the state `persist` saves
+    pub fn ledger(&self) -> PoolLedger {
+        PoolLedger {
+            workers: self.workers(),
+            payouts: self.payouts.iter().cloned().collect(),
+            windows: self.windows.iter()
+                .map(|(stream, shares)| (*stream, shares.iter().cloned().collect()))
+                .collect(),
+            credits: self.credits.iter().cloned().collect(),
+            synced_nonce: self.synced_nonce,
+            blocks_since_payout: self.blocks_since_payout,
+            blocks_credited: self.blocks_credited,
+            total_rewards: self.total_rewards,
+            pool_fees: self.pool_fees,
+        }
+    }
+
+    /// Save the ledger (no-op without storage)
+    ///
+    /// Called once per accepted block; shares accepted since the last call are
+    /// lost if the node stops before the next one.
+    pub fn persist(&self) -> BlockchainResult<()> {
+        match &self.storage {
+            Some(database) => PoolStore::new(database).put_ledger(&self.address, &self.ledger()),
+            None => Ok(()),
+        }
+    }
+
+    /// Pool coinbase address (must be the mining manager's miner address)
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// Current job for a stream (None until the pool mines a block on it)
+    pub fn current_job(&self, stream_type: StreamType) -> Option<PoolJob> {
+        self.jobs.get(&stream_type).map(|(job, _)| *job)
+    }
+
+    /// Leading zero bits a share needs against a job
+    pub fn share_target_bits(&self, job: &PoolJob) -> u32 {
+        self.config.share_target_bits.min(job.block_target_bits)
+    }
+
+    /// Start a new job from the template of the block the pool is mining
+    pub fn new_job(&mut self, template: &BlockHeader) {
+        let job = PoolJob {
+            job_hash: template_hash(template),
+            block_target_bits: block_target_bits(template.difficulty),
+        };
+        let previous = self.jobs.get(&template.stream_type).map(|(current, _)| *current);
+        if previous != Some(job) {
+            self.jobs.insert(template.stream_type, (job, previous));
+        }
+    }
+
+    /// Validate a share and add it to the stream's PPLNS window
+    pub fn submit_share(&mut self, submission: ShareSubmission, now: u64) -> Result<AcceptedShare, String> {
+        let result = self.check_share(&submission);
+        let stats = self.workers.entry(submission.worker).or_default();
+        let share = match result {
+            Ok(share) => share,
+            Err(e) => {
+                stats.shares_rejected += 1;
+                return Err(e);
+            }
+        };
+        stats.shares_accepted += 1;
+        stats.last_share_at = Some(now);
+
+        self.seen_shares.insert(share.work_hash);
+        self.seen_order.push_back(share.work_hash);
+        while self.seen_order.len() > MAX_TRACKED_SHARE_HASHES {
+            if let Some(old) = self.seen_order.pop_front() {
+                self.seen_shares.remove(&old);
+            }
+        }
+
+        let window = self.windows.entry(submission.stream_type).or_default();
+        window.push_back(Share { worker: submission.worker, timestamp: now });
+        while window.len() > self.config.pplns_window {
+            window.pop_front();
+        }
+
+        Ok(share)
+    }
+
+    fn check_share(&self, submission: &ShareSubmission) -> Result<AcceptedShare, String> {
+        if submission.worker == [0u8; 20] {
+            return Err("Worker address cannot be zero".to_string());
+        }
+        let (current, previous) = self.jobs.get(&submission.stream_type)
+            .ok_or_else(|| "No job for stream".to_string())?;
+        let job = [Some(*current), *previous].into_iter().flatten()
+            .find(|job| job.job_hash == submission.job_hash)
+            .ok_or_else(|| "Stale or unknown job".to_string())?;
+        let work_hash = work_hash(&job.job_hash, &submission.worker, submission.nonce);
+        let bits = leading_zero_bits(&work_hash);
+        if bits < self.share_target_bits(&job) {
+            return Err("Share does not meet target".to_string());
+        }
+        if self.seen_shares.contains(&work_hash) {
+            return Err("Duplicate share".to_string());
+        }
+        Ok(AcceptedShare { work_hash, solves_block: bits >= job.block_target_bits })
+    }
+
+    /// Split the reward of pool block `block_hash`, earned on `stream_type`,
+    /// across that stream's window
+    ///
+    /// Returns the amount credited to each worker. Rounding dust and rewards
+    /// for an empty window go to the pool fee. The split is remembered so
+    /// `reconcile_rewards` can adjust it if the block is recolored.
+    pub fn credit_block(&mut self, block_hash: Hash, stream_type: StreamType, reward: u128) -> HashMap<Address, u128> {
+        self.blocks_credited += 1;
+        self.blocks_since_payout += 1;
+
+        let mut share_counts: HashMap<Address, u128> = HashMap::new();
+        for share in self.windows.get(&stream_type).into_iter().flatten() {
+            *share_counts.entry(share.worker).or_insert(0) += 1;
+        }
+        let mut shares: Vec<(Address, u128)> = share_counts.into_iter().collect();
+        shares.sort_by_key(|(worker, _)| *worker);
+
+        let credited = self.apply_credit(&shares, reward, 0);
+        self.credits.push_back((block_hash, BlockCredit { reward, shares }));
+        while self.credits.len() > MAX_TRACKED_CREDITS {
+            self.credits.pop_front();
+        }
+        credited
+    }
+
+    /// Bring recent pool blocks in line with the rewards the chain now pays
+    /// them (`reward_of`), crediting or debiting the workers each block was
+    /// split across
+    ///
+    /// A debit is capped at a worker's pending balance; anything already paid
+    /// out is absorbed by the pool fees. Returns the number of blocks adjusted.
+    pub fn reconcile_rewards(&mut self, reward_of: impl Fn(&Hash) -> Option<u128>) -> usize {
+        let mut changed = Vec::new();
+        for (index, (hash, credit)) in self.credits.iter().enumerate() {
+            if let Some(reward) = reward_of(hash).filter(|reward| *reward != credit.reward) {
+                changed.push((index, reward));
+            }
+        }
+        for (index, reward) in &changed {
+            let (shares, previous) = {
+                let credit = &self.credits[*index].1;
+                (credit.shares.clone(), credit.reward)
+            };
+            self.apply_credit(&shares, *reward, previous);
+            self.credits[*index].1.reward = *reward;
+        }
+        changed.len()
+    }
+
+    /// Move the split of a block from `previous` to `reward`, returning each
+    /// worker's new share
+    fn apply_credit(&mut self, shares: &[(Address, u128)], reward: u128, previous: u128) -> HashMap<Address, u128> {
+        let old = self.split(shares, previous);
+        let new = self.split(shares, reward);
+        let mut owed_to_pool = 0u128;
+        for (worker, amount) in &new {
+            let before = old.get(worker).copied().unwrap_or(0);
+            if *amount == before {
+                continue;
+            }
+            let stats = self.workers.entry(*worker).or_default();
+            if *amount >= before {
+                stats.pending_balance = stats.pending_balance.saturating_add(amount - before);
+            } else {
+                let debit = before - amount;
+                let taken = debit.min(stats.pending_balance);
+                stats.pending_balance -= taken;
+                owed_to_pool += debit - taken;
+            }
+        }
+        let distributed = |split: &HashMap<Address, u128>| split.values().sum::<u128>();
+        self.total_rewards = self.total_rewards.saturating_sub(previous).saturating_add(reward);
+        self.pool_fees = self.pool_fees
+            .saturating_sub(previous - distributed(&old))
+            .saturating_add(reward - distributed(&new))
+            .saturating_sub(owed_to_pool);
+        new.into_iter().filter(|(_, amount)| *amount > 0).collect()
+    }
+
+    /// Each worker's part of `reward` after the pool fee, by share count
+    fn split(&self, shares: &[(Address, u128)], reward: u128) -> HashMap<Address, u128> {
+        let fee = reward.saturating_mul(self.config.pool_fee_bps as u128) / 10_000;
+        let distributable = reward - fee.min(reward);
+        let total_shares: u128 = shares.iter().map(|(_, count)| count).sum();
+        shares.iter()
+            .filter_map(|(worker, count)| {
+                let per_share = distributable.checked_div(total_shares)?;
+                Some((*worker, per_share * count + distributable % total_shares * count / total_shares))
+            })
+            .collect()
+    }
+
+    /// Whether enough blocks have been credited since the last payout batch
+    pub fn payout_due(&self) -> bool {
+        self.blocks_since_payout >= self.config.payout_interval_blocks
+    }
+
+    /// Reconcile payouts with the coinbase's next nonce on chain
+    ///
+    /// A pending payout below `chain_nonce` was either included (`included_in`
+    /// returns its block) and counts as paid, or lost its nonce to another
+    /// transaction and is released. A pending payout older than
+    /// `payout_expiry_secs` is released too, along with every pending payout
+    /// above it, since none of them can be included past the gap. Released
+    /// amounts go back to the workers' pending balances; a released payout
+    /// that is included later is deducted from them again. Returns the number
+    /// of payouts settled and released.
+    pub fn sync_payouts(
+        &mut self,
+        chain_nonce: u64,
+        now: u64,
+        included_in: impl Fn(&Hash) -> Option<Hash>,
+    ) -> (usize, usize) {
+        let mut settled = 0;
+        let mut released = 0;
+        let expiry = self.config.payout_expiry_secs;
+        let mut expired_from = None;
+        for payout in self.payouts.iter_mut() {
+            let used = payout.nonce >= self.synced_nonce && payout.nonce < chain_nonce;
+            let stats = self.workers.entry(payout.worker).or_default();
+            match payout.status {
+                PayoutStatus::Pending if payout.nonce < chain_nonce => {
+                    stats.in_flight = stats.in_flight.saturating_sub(payout.amount);
+                    match included_in(&payout.tx_hash) {
+                        Some(block_hash) => {
+                            stats.total_paid = stats.total_paid.saturating_add(payout.amount);
+                            payout.status = PayoutStatus::Included(block_hash);
+                            settled += 1;
+                        }
+                        None => {
+                            stats.pending_balance = stats.pending_balance.saturating_add(payout.amount);
+                            payout.status = PayoutStatus::Released;
+                            released += 1;
+                        }
+                    }
+                }
+                PayoutStatus::Pending => {
+                    let expired = now.saturating_sub(payout.created_at) > expiry;
+                    if expired_from.is_none() && expired {
+                        expired_from = Some(payout.nonce);
+                    }
+                    if expired_from.is_some() {
+                        stats.in_flight = stats.in_flight.saturating_sub(payout.amount);
+                        stats.pending_balance = stats.pending_balance.saturating_add(payout.amount);
+                        payout.status = PayoutStatus::Released;
+                        released += 1;
+                    }
+                }
+                PayoutStatus::Released if used => {
+                    if let Some(block_hash) = included_in(&payout.tx_hash) {
+                        stats.pending_balance = stats.pending_balance.saturating_sub(payout.amount);
+                        stats.total_paid = stats.total_paid.saturating_add(payout.amount);
+                        payout.status = PayoutStatus::Included(block_hash);
+                        settled += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.synced_nonce = self.synced_nonce.max(chain_nonce);
+
+        // Keep pending payouts and the newest settled ones
+        while self.payouts.len() > MAX_PAYOUT_HISTORY
+            && self.payouts.front().is_some_and(|p| p.status != PayoutStatus::Pending)
+        {
+            self.payouts.pop_front();
+        }
+        (settled, released)
+    }
+
+    /// Hashes of payouts `sync_payouts` may still find on chain (pending or released)
+    pub fn unsettled_payouts(&self) -> Vec<Hash> {
+        self.payouts.iter()
+            .filter(|payout| !matches!(payout.status, PayoutStatus::Included(_)))
+            .map(|payout| payout.tx_hash)
+            .collect()
+    }
+
+    /// Build the next batch of signed payout transactions from the coinbase
+    ///
+    /// `chain_nonce` is the coinbase's next nonce on chain and `available` its
+    /// spendable balance; call `sync_payouts` first. Amounts move from pending
+    /// to in flight and only count as paid once `sync_payouts` finds them on
+    /// chain. Nonces continue after payouts that are still pending.
+    pub fn build_payouts(&mut self, chain_nonce: u64, available: u128, now: u64) -> Vec<Transaction> {
+        self.blocks_since_payout = 0;
+        let in_flight = self.payouts.iter().filter(|p| p.status == PayoutStatus::Pending);
+        let mut nonce = in_flight.clone().map(|p| p.nonce + 1).max().unwrap_or(0).max(chain_nonce);
+        let mut remaining = available.saturating_sub(
+            in_flight.map(|p| p.amount.saturating_add(self.config.payout_fee as u128)).sum(),
+        );
+
+        let payout_fee = self.config.payout_fee as u128;
+        let mut due: Vec<(Address, u128)> = self.workers.iter()
+            .filter(|(_, stats)| stats.pending_balance >= self.config.min_payout as u128)
+            .map(|(worker, stats)| (*worker, stats.pending_balance))
+            .collect();
+        // Largest balances first, address as a deterministic tie-break
+        due.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut txs = Vec::new();
+        for (worker, amount) in due.into_iter().take(self.config.payout_batch_size) {
+            let cost = amount.saturating_add(payout_fee);
+            if cost > remaining {
+                continue;
+            }
+            remaining -= cost;
+
+            let tx = Transaction::new(self.address, worker, amount, payout_fee, nonce)
+                .sign(&self.payout_key);
+            if let Some(stats) = self.workers.get_mut(&worker) {
+                stats.pending_balance -= amount;
+                stats.in_flight = stats.in_flight.saturating_add(amount);
+            }
+            self.payouts.push_back(PayoutRecord {
+                tx_hash: tx.hash,
+                worker,
+                amount,
+                nonce,
+                created_at: now,
+                status: PayoutStatus::Pending,
+            });
+            nonce += 1;
+            txs.push(tx);
+        }
+        txs
+    }
+
+    pub fn worker_stats(&self, worker: &Address) -> Option<&WorkerStats> {
+        self.workers.get(worker)
+    }
+
+    /// All workers with their stats, sorted by address
+    pub fn workers(&self) -> Vec<(Address, WorkerStats)> {
+        let mut workers: Vec<(Address, WorkerStats)> = self.workers.iter()
+            .map(|(worker, stats)| (*worker, stats.clone()))
+            .collect();
+        workers.sort_by_key(|(worker, _)| *worker);
+        workers
+    }
+
+    /// Shares a worker holds in each stream's current window
+    pub fn window_shares(&self, worker: &Address) -> HashMap<StreamType, usize> {
+        self.windows.iter()
+            .map(|(stream, window)| (*stream, window.iter().filter(|s| s.worker == *worker).count()))
+            .collect()
+    }
+
+    /// Timestamp of the oldest share in a stream's window
+    pub fn window_start(&self, stream_type: StreamType) -> Option<u64> {
+        self.windows.get(&stream_type).and_then(|w| w.front()).map(|s| s.timestamp)
+    }
+
+    /// Most recent payouts to a worker (newest first)
+    pub fn payouts_for(&self, worker: &Address, limit: usize) -> Vec<PayoutRecord> {
+        self.payouts.iter().rev()
+            .filter(|p| p.worker == *worker)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            workers: self.workers.len(),
+            blocks_credited: self.blocks_credited,
+            total_rewards: self.total_rewards,
+            pool_fees: self.pool_fees,
+            total_pending: self.workers.values().map(|s| s.pending_balance).sum(),
+            total_in_flight: self.workers.values().map(|s| s.in_flight).sum(),
+            total_paid: self.workers.values().map(|s| s.total_paid).sum(),
+            window_shares: self.windows.iter().map(|(stream, w)| (*stream, w.len())).collect(),
+        }
+    }
+}
+
+/// Coinbase address controlled by a payout key
+pub fn payout_address(payout_key: &[u8; 32]) -> Address {
+    use ed25519_dalek::SigningKey;
+    let public_key = SigningKey::from_bytes(payout_key).verifying_key().to_bytes();
+    Transaction::derive_address_from_public_key(&public_key)
+}
+
+/// Hash a block template commits its proof of work to: the header fields the
+/// work covers (parents, number, stream, difficulty and coinbase)
+pub fn template_hash(header: &BlockHeader) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"mds-pow-template");
+    for parent in &header.parent_hashes {
+        hasher.update(parent);
+    }
+    hasher.update(&header.block_number.to_le_bytes());
+    hasher.update(&[header.stream_type as u8]);
+    hasher.update(&header.difficulty.to_le_bytes());
+    hasher.update(&header.coinbase.unwrap_or_default());
+    *hasher.finalize().as_bytes()
+}
+
+/// Leading zero bits a work hash needs to solve a block of `difficulty`
+/// (each difficulty unit is one zero byte)
+pub fn block_target_bits(difficulty: u64) -> u32 {
+    difficulty.saturating_mul(8).min(256) as u32
+}
+
+/// Proof-of-work hash of a nonce against a block template
+///
+/// The worker address is mixed in as the extra nonce, so a share found by one
+/// worker cannot be submitted by another.
+pub fn work_hash(template: &Hash, worker: &Address, nonce: u64) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(template);
+    hasher.update(worker);
+    hasher.update(&nonce.to_le_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+fn leading_zero_bits(hash: &Hash) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_share(pool: &MiningPool, worker: Address, stream: StreamType, start: u64) -> u64 {
+        let job = pool.current_job(stream).unwrap();
+        (start..).find(|nonce| {
+            leading_zero_bits(&work_hash(&job.job_hash, &worker, *nonce)) >= pool.share_target_bits(&job)
+        }).unwrap()
+    }
+
+    fn submit(pool: &mut MiningPool, worker: Address, stream_type: StreamType, nonce: u64, now: u64) -> u64 {
+        let nonce = find_share(pool, worker, stream_type, nonce);
+        let job_hash = pool.current_job(stream_type).unwrap().job_hash;
+        pool.submit_share(ShareSubmission { worker, stream_type, job_hash, nonce }, now).unwrap();
+        nonce + 1
+    }
+
+    fn test_pool() -> MiningPool {
+        let mut pool = MiningPool::new([7u8; 32], PoolConfig {
+            share_target_bits: 4,
+            pool_fee_bps: 100,
+            min_payout: 1,
+            ..PoolConfig::default()
+        });
+        for stream in [StreamType::StreamA, StreamType::StreamB, StreamType::StreamC] {
+            pool.new_job(&BlockHeader::new(vec![[1u8; 32]], 1, stream, 4).with_coinbase(pool.address()));
+        }
+        pool
+    }
+
+    #[test]
+    fn test_share_validation() {
+        let mut pool = test_pool();
+        let worker: Address = [1u8; 20];
+        let nonce = find_share(&pool, worker, StreamType::StreamA, 0);
+        let job = pool.current_job(StreamType::StreamA).unwrap();
+        assert_eq!(job.block_target_bits, 32);
+        let share = ShareSubmission { worker, stream_type: StreamType::StreamA, job_hash: job.job_hash, nonce };
+
+        let accepted = pool.submit_share(share.clone(), 1).unwrap();
+        assert_eq!(accepted.work_hash, work_hash(&job.job_hash, &worker, nonce));
+        assert!(!accepted.solves_block);
+        assert!(pool.submit_share(share.clone(), 2).is_err()); // Duplicate
+        assert!(pool.submit_share(ShareSubmission { job_hash: [9u8; 32], ..share.clone() }, 3).is_err());
+        // Another worker cannot claim the same solution
+        assert!(pool.submit_share(ShareSubmission { worker: [2u8; 20], ..share.clone() }, 4).is_err());
+
+        let stats = pool.worker_stats(&worker).unwrap();
+        assert_eq!(stats.shares_accepted, 1);
+        assert_eq!(stats.shares_rejected, 2);
+
+        // Shares are work on the block template: a new template is a new job,
+        // and the previous one stays valid for a grace period
+        pool.new_job(&BlockHeader::new(vec![[2u8; 32]], 2, StreamType::StreamA, 1).with_coinbase(pool.address()));
+        let next = pool.current_job(StreamType::StreamA).unwrap();
+        assert_ne!(next.job_hash, job.job_hash);
+        assert_eq!(pool.share_target_bits(&next), 4);
+        let nonce = (nonce + 1..)
+            .find(|nonce| leading_zero_bits(&work_hash(&job.job_hash, &worker, *nonce)) >= 4)
+            .unwrap();
+        assert!(pool.submit_share(ShareSubmission { nonce, ..share }, 5).is_ok());
+
+        // At a low difficulty the share target is capped at the block target
+        pool.new_job(&BlockHeader::new(vec![[3u8; 32]], 3, StreamType::StreamA, 0).with_coinbase(pool.address()));
+        let trivial = pool.current_job(StreamType::StreamA).unwrap();
+        assert_eq!(pool.share_target_bits(&trivial), 0);
+        let share = ShareSubmission { worker, stream_type: StreamType::StreamA, job_hash: trivial.job_hash, nonce: 0 };
+        assert!(pool.submit_share(share, 6).unwrap().solves_block);
+    }
+
+    #[test]
+    fn test_pplns_split_and_payouts() {
+        let mut pool = test_pool();
+        let alice: Address = [1u8; 20];
+        let bob: Address = [2u8; 20];
+
+        // Alice submits three shares, Bob one
+        let mut nonce = 0;
+        for worker in [alice, alice, alice, bob] {
+            nonce = submit(&mut pool, worker, StreamType::StreamB, nonce, 1);
+        }
+
+        let credited = pool.credit_block([1u8; 32], StreamType::StreamB, 10_000);
+        assert_eq!(credited[&alice], 7_425); // 9,900 after 1% fee, 3/4 share
+        assert_eq!(credited[&bob], 2_475);
+        assert_eq!(pool.stats().pool_fees, 100);
+
+        // Stream A window is empty: the whole reward goes to the pool
+        assert!(pool.credit_block([2u8; 32], StreamType::StreamA, 500).is_empty());
+
+        let payouts = pool.build_payouts(5, 1_000_000_000_000_000_000, 10);
+        assert_eq!(payouts.len(), 2);
+        assert_eq!(payouts[0].to, alice);
+        assert_eq!(payouts[0].nonce, 5);
+        assert_eq!(payouts[1].nonce, 6);
+        assert!(payouts.iter().all(|tx| tx.from == pool.address() && tx.verify_signature()));
+        assert_eq!(pool.worker_stats(&alice).unwrap().pending_balance, 0);
+        assert_eq!(pool.worker_stats(&alice).unwrap().in_flight, 7_425);
+        assert_eq!(pool.worker_stats(&alice).unwrap().total_paid, 0);
+
+        // Only Alice's payout made it on chain; Bob's nonce went to another transaction
+        let alice_tx = payouts[0].hash;
+        assert_eq!(pool.sync_payouts(7, 11, |hash| (*hash == alice_tx).then_some([3u8; 32])), (1, 1));
+        assert_eq!(pool.worker_stats(&alice).unwrap().in_flight, 0);
+        assert_eq!(pool.worker_stats(&alice).unwrap().total_paid, 7_425);
+        assert_eq!(pool.worker_stats(&bob).unwrap().pending_balance, 2_475);
+        assert_eq!(pool.payouts_for(&alice, 1)[0].status, PayoutStatus::Included([3u8; 32]));
+
+        // Bob is paid again at the next chain nonce
+        let retry = pool.build_payouts(7, 1_000_000_000_000_000_000, 12);
+        assert_eq!(retry.len(), 1);
+        assert_eq!((retry[0].to, retry[0].nonce), (bob, 7));
+    }
+
+    #[test]
+    fn test_expired_payouts_released() {
+        let mut pool = test_pool();
+        let alice: Address = [1u8; 20];
+        let bob: Address = [2u8; 20];
+        let mut nonce = 0;
+        for worker in [alice, bob] {
+            nonce = submit(&mut pool, worker, StreamType::StreamA, nonce, 1);
+        }
+        pool.credit_block([1u8; 32], StreamType::StreamA, 10_000);
+        assert_eq!(pool.build_payouts(0, 1_000_000_000_000_000_000, 100).len(), 2);
+
+        // Nothing on chain yet and not expired: both stay pending
+        let expiry = pool.config().payout_expiry_secs;
+        assert_eq!(pool.sync_payouts(0, 100 + expiry, |_| None), (0, 0));
+        assert!(pool.build_payouts(0, 1_000_000_000_000_000_000, 100).is_empty());
+
+        // The first payout never arrives: it and everything after it are released
+        assert_eq!(pool.sync_payouts(0, 101 + expiry, |_| None), (0, 2));
+        assert_eq!(pool.stats().total_in_flight, 0);
+        assert_eq!(pool.stats().total_pending, 9_900);
+        let rebuilt = pool.build_payouts(0, 1_000_000_000_000_000_000, 200);
+        assert_eq!(rebuilt.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_ledger_persisted() {
+        let database = Arc::new(Database::in_memory());
+        let mut pool = MiningPool::with_storage([7u8; 32], test_pool().config().clone(), database.clone()).unwrap();
+        pool.new_job(&BlockHeader::new(vec![], 0, StreamType::StreamC, 4).with_coinbase(pool.address()));
+        let worker: Address = [1u8; 20];
+        submit(&mut pool, worker, StreamType::StreamC, 0, 1);
+        pool.credit_block([1u8; 32], StreamType::StreamC, 10_000);
+        let payout = pool.build_payouts(3, 1_000_000_000_000_000_000, 5).remove(0);
+        pool.persist().unwrap();
+
+        let restored = MiningPool::with_storage([7u8; 32], pool.config().clone(), database.clone()).unwrap();
+        assert_eq!(restored.worker_stats(&worker).unwrap().in_flight, 9_900);
+        assert_eq!(restored.payouts_for(&worker, 1)[0].tx_hash, payout.hash);
+        assert_eq!(restored.window_shares(&worker)[&StreamType::StreamC], 1);
+        assert_eq!(restored.stats().blocks_credited, 1);
+        let mut restored = restored;
+        assert_eq!(restored.reconcile_rewards(|_| Some(5_000)), 1);
+        assert_eq!(restored.stats().total_rewards, 5_000);
+
+        // Another payout key does not see this ledger
+        let other = MiningPool::with_storage([8u8; 32], PoolConfig::default(), database).unwrap();
+        assert_eq!(other.stats().workers, 0);
+    }
+
+    #[test]
+    fn test_recolored_block_adjusts_workers() {
+        let mut pool = test_pool();
+        let alice: Address = [1u8; 20];
+        let bob: Address = [2u8; 20];
+        let mut nonce = 0;
+        for worker in [alice, bob] {
+            nonce = submit(&mut pool, worker, StreamType::StreamA, nonce, 1);
+        }
+        pool.credit_block([1u8; 32], StreamType::StreamA, 10_000);
+        assert_eq!(pool.worker_stats(&alice).unwrap().pending_balance, 4_950);
+
+        // The block turns red and earns half: the split is scaled down
+        assert_eq!(pool.reconcile_rewards(|hash| (*hash == [1u8; 32]).then_some(5_000)), 1);
+        assert_eq!(pool.worker_stats(&alice).unwrap().pending_balance, 2_475);
+        assert_eq!(pool.worker_stats(&bob).unwrap().pending_balance, 2_475);
+        assert_eq!(pool.stats().total_rewards, 5_000);
+        assert_eq!(pool.stats().pool_fees, 50);
+        assert_eq!(pool.reconcile_rewards(|_| Some(5_000)), 0);
+
+        // Alice is paid out before the block turns blue again: she is credited
+        // the difference; a later debit past her pending balance hits the pool
+        pool.build_payouts(0, 1_000_000_000_000_000_000, 2);
+        pool.reconcile_rewards(|_| Some(10_000));
+        assert_eq!(pool.worker_stats(&alice).unwrap().pending_balance, 2_475);
+        pool.build_payouts(0, 1_000_000_000_000_000_000, 3);
+        pool.reconcile_rewards(|_| Some(0));
+        assert_eq!(pool.worker_stats(&alice).unwrap().pending_balance, 0);
+        assert_eq!(pool.stats().total_rewards, 0);
+        assert_eq!(pool.stats().pool_fees, 0);
+    }
+
+    #[test]
+    fn test_settings_read_from_pool_table() {
+        let table: crate::config::PoolOperatorConfig = toml::from_str(
+            "payout_key_file = \"pool.key\"\npool_fee_bps = 250\npplns_window = 500\nshare_target_bits = 16\n"
+        ).unwrap();
+        assert_eq!(table.settings.pool_fee_bps, 250);
+        assert_eq!(table.settings.pplns_window, 500);
+        assert_eq!(table.settings.share_target_bits, 16);
+        assert_eq!(table.settings.min_payout, DEFAULT_MIN_PAYOUT);
+        assert!(table.settings.validate().is_ok());
+        assert!(PoolConfig { pool_fee_bps: 10_001, ..PoolConfig::default() }.validate().is_err());
+    }
+}
@@ -59,6 +59,9 @@ pub enum NetworkMessage {
     RequestPeers,
     /// Peer list response
     Peers { addresses: Vec<String> },
+    /// Lowest block number whose body the sender (at `listen_addr`) serves
+    /// (0 = full history); also the reply to a block request below it
+    Retention { listen_addr: String, retained_from: u64 },
 }
 
 /// Network manager for P2P communication
@@ -79,6 +82,8 @@ pub struct NetworkManager {
     shard_manager: Option<Arc<crate::sharding::ShardManager>>,
     /// Active peer connections for broadcasting (peer_addr -> stream)
     peer_connections: Arc<Mutex<HashMap<SocketAddr, Arc<Mutex<TcpStream>>>>>,
+    /// Retention advertised by pruned peers (peer_addr -> lowest served block)
+    peer_retention: Arc<RwLock<HashMap<SocketAddr, u64>>>,
 }
 
 impl NetworkManager {
@@ -95,6 +100,7 @@ impl NetworkManager {
             session_keys: Arc::new(RwLock::new(std::collections::HashMap::new())),
             shard_manager: None,
             peer_connections: Arc::new(Mutex::new(HashMap::new())),
+            peer_retention: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -123,6 +129,7 @@ impl NetworkManager {
             session_keys: Arc::new(RwLock::new(std::collections::HashMap::new())),
             shard_manager: None,
             peer_connections: Arc::new(Mutex::new(HashMap::new())),
+            peer_retention: Arc::new(RwLock::new(HashMap::new())),
         }
     }
     
@@ -269,6 +276,9 @@ impl NetworkManager {
         let blockchain = self.blockchain.clone();
         let is_running = self.is_running.clone();
         let connections = self.peer_connections.clone();
+        let shard_manager = self.shard_manager.clone();
+        let retention = self.peer_retention.clone();
+        let local_addr = self.listen_addr;
         
         // Accept incoming connections
         tokio::spawn(async move {
@@ -283,10 +293,12 @@ impl NetworkManager {
                         let peers_clone = peers.clone();
                         let is_running_clone = is_running.clone();
                         let connections_clone = connections.clone();
+                        let shard_manager_clone = shard_manager.clone();
+                        let retention_clone = retention.clone();
                         
                         // Handle peer connection
                         tokio::spawn(async move {
-                            handle_peer(stream, addr, blockchain_clone, peers_clone, is_running_clone, connections_clone).await;
+                            handle_peer(stream, addr, local_addr, blockchain_clone, peers_clone, is_running_clone, connections_clone, shard_manager_clone, retention_clone).await;
                         });
                     }
                     Err(e) => {
@@ -321,7 +333,7 @@ impl NetworkManager {
         const MAX_ATTEMPTS: u32 = 3;
         const RETRY_DELAY_MS: u64 = 2000;
         
-        let stream = loop {
+        let mut stream = loop {
             match TcpStream::connect(addr).await {
                 Ok(s) => break s,
                 Err(e) => {
@@ -355,16 +367,25 @@ impl NetworkManager {
         self.peers.write().await.insert(addr);
         println!("✅ [CONNECT] Added {} to peers list", addr);
         
+        // Tell the peer how much history this node serves
+        let data = self.retention_message().await?;
+        if let Err(e) = write_frame(&mut stream, &data).await {
+            eprintln!("⚠️  Failed to send retention to {}: {}", addr, e);
+        }
+        
         let blockchain = self.blockchain.clone();
         let peers = self.peers.clone();
         let is_running = self.is_running.clone();
         let connections = self.peer_connections.clone();
+        let shard_manager = self.shard_manager.clone();
+        let retention = self.peer_retention.clone();
+        let local_addr = self.listen_addr;
         
         println!("🔄 [CONNECT] Spawning handle_peer for {}", addr);
         
         // Handle peer connection
         tokio::spawn(async move {
-            handle_peer(stream, addr, blockchain, peers, is_running, connections).await;
+            handle_peer(stream, addr, local_addr, blockchain, peers, is_running, connections, shard_manager, retention).await;
         });
         
         Ok(())
@@ -381,23 +402,8 @@ impl NetworkManager {
             return Ok(());
         }
         
-        // If sharding is enabled, try to determine shard ID from block transactions
-        let shard_id = if let Some(shard_mgr) = &self.shard_manager {
-            // Determine shard from first transaction (if any)
-            if let Some(first_tx) = block.transactions.first() {
-                Some(shard_mgr.get_shard_for_address(&first_tx.from))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-        
-        let message = if let Some(shard) = shard_id {
-            NetworkMessage::NewShardBlock { block: block.clone(), shard_id: shard }
-        } else {
-            NetworkMessage::NewBlock { block: block.clone() }
-        };
+        // Global (beacon) blocks only - shard blocks go through broadcast_shard_block
+        let message = NetworkMessage::NewBlock { block: block.clone() };
         
         let authenticated = self.sign_message(message)?;
         let data = bincode::serialize(&authenticated)
@@ -502,6 +508,39 @@ impl NetworkManager {
         Ok(())
     }
 
+    /// Signed retention announcement for this node
+    async fn retention_message(&self) -> crate::error::BlockchainResult<Vec<u8>> {
+        let retained_from = self.blockchain.read().await.retained_from();
+        let message = NetworkMessage::Retention { listen_addr: self.listen_addr.to_string(), retained_from };
+        let authenticated = self.sign_message(message)?;
+        bincode::serialize(&authenticated)
+            .map_err(|e| crate::error::BlockchainError::Serialization(e.to_string()))
+    }
+    
+    /// Announce this node's retention to all peers (after pruning)
+    pub async fn announce_retention(&self) -> crate::error::BlockchainResult<()> {
+        let data = self.retention_message().await?;
+        for peer_addr in self.get_peers().await {
+            if let Err(e) = send_to_peer(peer_addr, &data).await {
+                eprintln!("⚠️  Failed to send retention to {}: {}", peer_addr, e);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Peers that serve block bodies from `from_block` on (peers that have not
+    /// announced a retention are assumed to keep full history)
+    pub async fn peers_serving(&self, from_block: u64) -> Vec<SocketAddr> {
+        let retention = self.peer_retention.read().await;
+        self.peers.read().await.iter()
+            .filter(|addr| match retention.get(addr) {
+                Some(retained_from) => *retained_from <= from_block,
+                None => true,
+            })
+            .copied()
+            .collect()
+    }
+    
     /// Get connected peers count
     pub async fn peer_count(&self) -> usize {
         self.peers.read().await.len()
@@ -517,10 +556,13 @@ impl NetworkManager {
 async fn handle_peer(
     mut stream: TcpStream,
     addr: SocketAddr,
+    local_addr: SocketAddr,
     blockchain: Arc<RwLock<Blockchain>>,
     peers: Arc<RwLock<HashSet<SocketAddr>>>,
     is_running: Arc<RwLock<bool>>,
     connections: Arc<Mutex<HashMap<SocketAddr, Arc<Mutex<TcpStream>>>>>,
+    shard_manager: Option<Arc<crate::sharding::ShardManager>>,
+    retention: Arc<RwLock<HashMap<SocketAddr, u64>>>,
 ) {
     println!("🎯 [HANDLER] Started for peer: {}", addr);
     let mut buffer = vec![0u8; 1024 * 1024]; // 1MB buffer
@@ -584,14 +626,14 @@ async fn handle_peer(
                     }
                     
                     // Process the verified message
-                    if let Err(e) = process_message(authenticated.message, &blockchain, &peers, &mut stream, addr).await {
+                    if let Err(e) = process_message(authenticated.message, &blockchain, shard_manager.as_ref(), &peers, &retention, &mut stream, addr, local_addr).await {
                         eprintln!("⚠️  Error processing message from {}: {}", addr, e);
                     }
                 } else {
                     // Try to deserialize as old format (backward compatibility)
                     if let Ok(message) = bincode::deserialize::<NetworkMessage>(&buffer[..len]) {
                         eprintln!("⚠️  Received unsigned message from {} (backward compatibility mode)", addr);
-                        if let Err(e) = process_message(message, &blockchain, &peers, &mut stream, addr).await {
+                        if let Err(e) = process_message(message, &blockchain, shard_manager.as_ref(), &peers, &retention, &mut stream, addr, local_addr).await {
                             eprintln!("⚠️  Error processing message from {}: {}", addr, e);
                         }
                     } else {
@@ -612,9 +654,12 @@ async fn handle_peer(
 async fn process_message(
     message: NetworkMessage,
     blockchain: &Arc<RwLock<Blockchain>>,
+    shard_manager: Option<&Arc<crate::sharding::ShardManager>>,
     peers: &Arc<RwLock<HashSet<SocketAddr>>>,
+    retention: &Arc<RwLock<HashMap<SocketAddr, u64>>>,
     stream: &mut TcpStream,
     from_addr: SocketAddr,
+    local_addr: SocketAddr,
 ) -> crate::error::BlockchainResult<()> {
     match message {
         NetworkMessage::Handshake { listen_addr } => {
@@ -629,10 +674,23 @@ async fn process_message(
         }
         NetworkMessage::NewBlock { block } => {
             println!("📦 Received block #{} from {}", block.header.block_number, from_addr);
-            let mut bc = blockchain.write().await;
-            match bc.add_block(block.clone()) {
+            // Beacon blocks must agree with the shards this node validates
+            if let Some(shard_manager) = shard_manager {
+                if let Err(e) = shard_manager.verify_shard_commitments(&block).await {
+                    eprintln!("❌ Rejected block #{}: {}", block.header.block_number, e);
+                    return Ok(());
+                }
+            }
+            let added = {
+                let mut bc = blockchain.write().await;
+                bc.add_block(block.clone())
+            };
+            match added {
                 Ok(_) => {
                     println!("✅ Successfully added block #{} from peer", block.header.block_number);
+                    if let Some(shard_manager) = shard_manager {
+                        shard_manager.record_beacon(&block).await;
+                    }
                 }
                 Err(e) => {
                     eprintln!("❌ Failed to add block #{}: {}", block.header.block_number, e);
@@ -641,9 +699,18 @@ async fn process_message(
         }
         NetworkMessage::NewShardBlock { block, shard_id } => {
             println!("📦 Received shard {} block #{} from {}", shard_id, block.header.block_number, from_addr);
-            let mut bc = blockchain.write().await;
-            if let Err(e) = bc.add_block(block) {
-                eprintln!("⚠️  Failed to add shard block: {}", e);
+            match shard_manager {
+                Some(shard_manager) => {
+                    if let Err(e) = shard_manager.import_shard_block(shard_id, block).await {
+                        eprintln!("⚠️  Failed to import shard {} block: {}", shard_id, e);
+                    }
+                }
+                None => {
+                    let mut bc = blockchain.write().await;
+                    if let Err(e) = bc.add_block(block) {
+                        eprintln!("⚠️  Failed to add shard block: {}", e);
+                    }
+                }
             }
         }
         NetworkMessage::NewTransaction { transaction: _transaction } => {
@@ -654,14 +721,20 @@ async fn process_message(
         NetworkMessage::RequestBlocks { from_block, count } => {
             println!("📥 Peer {} requested blocks from {} (count: {})", from_addr, from_block, count);
             let bc = blockchain.read().await;
-            let blocks: Vec<Block> = bc.get_blocks()
-                .iter()
-                .filter(|b| b.header.block_number >= from_block)
-                .take(count as usize)
-                .cloned()
-                .collect();
-            
-            let response = NetworkMessage::Blocks { blocks };
+            let retained_from = bc.retained_from();
+            let response = if from_block < retained_from {
+                // Pruned here: the requester should ask a peer with more history
+                NetworkMessage::Retention { listen_addr: local_addr.to_string(), retained_from }
+            } else {
+                let blocks: Vec<Block> = bc.block_metas()
+                    .iter()
+                    .filter(|b| b.block_number >= from_block)
+                    .take(count as usize)
+                    .filter_map(|b| bc.get_block_by_hash(&b.hash))
+                    .collect();
+                NetworkMessage::Blocks { blocks }
+            };
+            drop(bc);
             // Note: Response messages are not signed in this implementation
             // In production, you should sign all messages including responses
             let data = bincode::serialize(&response)?;
@@ -678,14 +751,18 @@ async fn process_message(
         }
         NetworkMessage::RequestShardBlocks { shard_id, from_block, count } => {
             println!("📥 Peer {} requested shard {} blocks from {} (count: {})", from_addr, shard_id, from_block, count);
-            // For now, return all blocks (shard-specific block storage not yet implemented)
-            let bc = blockchain.read().await;
-            let blocks: Vec<Block> = bc.get_blocks()
-                .iter()
-                .filter(|b| b.header.block_number >= from_block)
-                .take(count as usize)
-                .cloned()
-                .collect();
+            let blocks: Vec<Block> = match shard_manager {
+                Some(shard_manager) => shard_manager.get_shard_blocks(shard_id, from_block, count as usize).await,
+                None => {
+                    let bc = blockchain.read().await;
+                    bc.block_metas()
+                        .iter()
+                        .filter(|b| b.block_number >= from_block)
+                        .take(count as usize)
+                        .filter_map(|b| bc.get_block_by_hash(&b.hash))
+                        .collect()
+                }
+            };
             
             let response = NetworkMessage::ShardBlocks { shard_id, blocks };
             let data = bincode::serialize(&response)?;
@@ -710,10 +787,21 @@ async fn process_message(
         }
         NetworkMessage::ShardBlocks { shard_id, blocks } => {
             println!("📦 Received {} blocks from shard {} from {}", blocks.len(), shard_id, from_addr);
-            let mut bc = blockchain.write().await;
-            for block in blocks {
-                if let Err(e) = bc.add_block(block) {
-                    eprintln!("⚠️  Failed to add shard block: {}", e);
+            match shard_manager {
+                Some(shard_manager) => {
+                    for block in blocks {
+                        if let Err(e) = shard_manager.import_shard_block(shard_id, block).await {
+                            eprintln!("⚠️  Failed to import shard {} block: {}", shard_id, e);
+                        }
+                    }
+                }
+                None => {
+                    let mut bc = blockchain.write().await;
+                    for block in blocks {
+                        if let Err(e) = bc.add_block(block) {
+                            eprintln!("⚠️  Failed to add shard block: {}", e);
+                        }
+                    }
                 }
             }
         }
@@ -756,11 +844,32 @@ async fn process_message(
             println!("👥 Received {} peer addresses from {}", addresses.len(), from_addr);
             // Could connect to these peers, but for now we just log
         }
+        NetworkMessage::Retention { listen_addr, retained_from } => {
+            // Keyed by listen address, as in the peer list
+            let peer_addr = listen_addr.parse::<SocketAddr>().unwrap_or(from_addr);
+            if retained_from > 0 {
+                println!("✂️  Peer {} serves blocks from #{}", peer_addr, retained_from);
+            }
+            retention.write().await.insert(peer_addr, retained_from);
+        }
     }
     
     Ok(())
 }
 
+/// Write one length-prefixed message
+async fn write_frame(stream: &mut TcpStream, data: &[u8]) -> crate::error::BlockchainResult<()> {
+    stream.write_u32(data.len() as u32).await
+        .map_err(|e| crate::error::BlockchainError::Network(
+            format!("Failed to write length: {}", e)
+        ))?;
+    stream.write_all(data).await
+        .map_err(|e| crate::error::BlockchainError::Network(
+            format!("Failed to write data: {}", e)
+        ))?;
+    Ok(())
+}
+
 /// Send data to a peer
 async fn send_to_peer(addr: SocketAddr, data: &[u8]) -> crate::error::BlockchainResult<()> {
     let mut stream = TcpStream::connect(addr)
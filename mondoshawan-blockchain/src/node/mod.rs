@@ -6,7 +6,8 @@ use crate::blockchain::Blockchain;
 use crate::mining::MiningManager;
 use crate::network::NetworkManager;
 use crate::rpc::RpcServer;
-use crate::sharding::{ShardManager, ShardConfig, AssignmentStrategy};
+use crate::config::{PruningConfig, RpcConfig, ShardingConfig};
+use crate::sharding::ShardManager;
 use crate::storage::Database;
 use crate::types::Address;
 use std::sync::Arc;
@@ -20,12 +21,19 @@ pub struct NodeConfig {
     pub rpc_port: u16,
     pub miner_address: Address,
     pub data_dir: String,
-    /// Enable sharding
-    pub enable_sharding: bool,
-    /// Number of shards (if sharding enabled)
-    pub shard_count: usize,
+    /// Sharding (disabled by default)
+    pub sharding: ShardingConfig,
+    /// Background pruning of old block bodies (disabled by default)
+    pub pruning: PruningConfig,
+    /// JSON-RPC HTTP server (CORS, body and batch limits, keep-alive)
+    pub rpc: RpcConfig,
     /// Enable Verkle tree (stateless mode)
     pub enable_verkle: bool,
+    /// Ed25519 payout key for pool-operator mode (None = solo mining).
+    /// `miner_address` must be the address of this key.
+    pub pool_payout_key: Option<[u8; 32]>,
+    /// Pool settings (fee, PPLNS window, share target, payouts)
+    pub pool: crate::mining::pool::PoolConfig,
 }
 
 impl Default for NodeConfig {
@@ -35,9 +43,12 @@ impl Default for NodeConfig {
             rpc_port: 8545,
             miner_address: [1u8; 20], // Default miner address
             data_dir: "data".to_string(),
-            enable_sharding: false, // Disabled by default
-            shard_count: 10, // 10 shards if enabled
+            sharding: ShardingConfig::default(),
+            pruning: PruningConfig::default(),
+            rpc: RpcConfig::default(),
             enable_verkle: false, // Disabled by default
+            pool_payout_key: None, // Solo mining by default
+            pool: crate::mining::pool::PoolConfig::default(),
         }
     }
 }
@@ -55,7 +66,20 @@ pub struct Node {
 }
 
 impl Node {
+    /// Create a node, panicking on invalid configuration (see `try_new`)
     pub fn new(config: NodeConfig) -> Self {
+        Self::try_new(config).unwrap_or_else(|e| panic!("Failed to create node: {}", e))
+    }
+
+    /// Create a node
+    ///
+    /// Fails when the sharding configuration is invalid or does not match the
+    /// parameters the database was created with.
+    pub fn try_new(config: NodeConfig) -> Result<Self, String> {
+        config.sharding.validate()?;
+        config.pruning.validate()?;
+        config.rpc.validate()?;
+        
         // Create or open database
         let database = match Database::open(&config.data_dir) {
             Ok(db) => {
@@ -69,7 +93,7 @@ impl Node {
         };
         
         // Create metrics collector
-        let shard_count_for_metrics = if config.enable_sharding { config.shard_count } else { 0 };
+        let shard_count_for_metrics = if config.sharding.enabled { config.sharding.shard_count } else { 0 };
         let metrics = match crate::metrics::create_metrics(shard_count_for_metrics) {
             Ok(m) => {
                 println!("📊 Metrics collection enabled");
@@ -81,15 +105,40 @@ impl Node {
             }
         };
         
+        // Event bus feeding WebSocket subscriptions
+        let event_bus = crate::events::EventBus::new();
+
         // Create shard manager if enabled (needed before blockchain creation)
-        let shard_manager: Option<Arc<ShardManager>> = if config.enable_sharding && config.shard_count > 0 {
-            let shard_config = ShardConfig {
-                shard_count: config.shard_count,
-                enable_cross_shard: true,
-                assignment_strategy: AssignmentStrategy::ConsistentHashing,
+        let shard_manager: Option<Arc<ShardManager>> = if config.sharding.enabled {
+            let sharding = &config.sharding;
+            let shard_config = sharding.shard_config();
+            println!("🔷 Sharding enabled with {} shards", sharding.shard_count);
+            // Journal cross-shard receipts so transfers survive restarts
+            let manager = match &database {
+                Some(db) => ShardManager::with_storage(shard_config.clone(), db.clone())
+                    .unwrap_or_else(|e| {
+                        eprintln!("⚠️  Failed to recover cross-shard journal: {}. Starting empty.", e);
+                        ShardManager::new(shard_config)
+                    }),
+                None => ShardManager::new(shard_config),
             };
-            println!("🔷 Sharding enabled with {} shards", config.shard_count);
-            Some(Arc::new(ShardManager::new(shard_config)))
+            let manager = manager
+                .with_epoch_length(sharding.epoch_length)
+                .with_receipt_timeout(sharding.receipt_timeout_secs)
+                .with_event_bus(event_bus.clone())
+                .with_rebalance_config(crate::sharding::rebalance::RebalanceConfig {
+                    dry_run: sharding.rebalance_dry_run,
+                    ..Default::default()
+                });
+            let manager = if sharding.tracked_shards.is_empty() {
+                manager
+            } else {
+                println!("🔷 Validating shards {:?}", sharding.tracked_shards);
+                manager.with_tracked_shards(sharding.tracked_shards.clone())
+            };
+            // Refuse to join a different sharding layout than the database holds
+            manager.check_genesis().map_err(|e| e.to_string())?;
+            Some(Arc::new(manager))
         } else {
             None
         };
@@ -97,7 +146,7 @@ impl Node {
         // Create blockchain with or without storage and Verkle
         let mut blockchain = if config.enable_verkle {
             // Verkle mode (stateless)
-            if let Some(db) = database {
+            if let Some(db) = database.clone() {
                 match Blockchain::with_storage_and_verkle(db) {
                     Ok(mut bc) => {
                         bc.evm_enabled = true;
@@ -127,7 +176,7 @@ impl Node {
             }
         } else {
             // Traditional mode (with storage)
-            if let Some(db) = database {
+            if let Some(db) = database.clone() {
                 match Blockchain::with_storage(db) {
                     Ok(mut bc) => {
                         bc.evm_enabled = true;
@@ -157,21 +206,39 @@ impl Node {
         // Set shard manager in blockchain if sharding is enabled
         // Note: We don't actually need to set it in blockchain for now since
         // cross-shard transactions are handled at the shard manager level
+        blockchain.set_event_bus(event_bus.clone());
         let blockchain_arc = Arc::new(RwLock::new(blockchain));
         
         // Create mining manager (with sharding if enabled)
-        let mining_manager: Arc<MiningManager> = if let Some(ref shard_mgr) = shard_manager {
-            Arc::new(MiningManager::with_sharding(
+        let mut mining_manager = if let Some(ref shard_mgr) = shard_manager {
+            MiningManager::with_sharding(
                 blockchain_arc.clone(),
                 config.miner_address,
                 shard_mgr.clone(),
-            ))
+            )
         } else {
-            Arc::new(MiningManager::new(
+            MiningManager::new(
                 blockchain_arc.clone(),
                 config.miner_address,
-            ))
+            )
         };
+        mining_manager.set_event_bus(event_bus.clone());
+        let mining_manager = Arc::new(mining_manager);
+        
+        // Enable pool-operator mode if a payout key is configured
+        // (its ledger is restored from storage so owed balances survive restarts)
+        if let Some(payout_key) = config.pool_payout_key {
+            use crate::mining::pool::MiningPool;
+            let mining_pool = match &database {
+                Some(db) => MiningPool::with_storage(payout_key, config.pool.clone(), db.clone())
+                    .map_err(|e| format!("Failed to load mining pool ledger: {}", e))?,
+                None => MiningPool::new(payout_key, config.pool.clone()),
+            };
+            match mining_manager.enable_pool(mining_pool) {
+                Ok(()) => println!("⛏️  Pool-operator mode enabled (PPLNS)"),
+                Err(e) => eprintln!("⚠️  Failed to enable mining pool: {}", e),
+            }
+        }
         
         // Create network manager
         let listen_addr = format!("127.0.0.1:{}", config.port)
@@ -234,9 +301,11 @@ impl Node {
         rpc_server.set_policy_manager(policy_manager.clone());
         println!("🛡️  Security policy manager initialized");
         
+        rpc_server.set_event_bus(event_bus);
+
         let rpc_server = Arc::new(rpc_server);
         
-        Self {
+        Ok(Self {
             config,
             blockchain: blockchain_arc,
             mining_manager,
@@ -245,7 +314,7 @@ impl Node {
             shard_manager,
             metrics,
             shutdown_signal: Arc::new(tokio::sync::Notify::new()),
-        }
+        })
     }
 
     /// Start the node
@@ -258,13 +327,13 @@ impl Node {
         // Use deterministic genesis so all nodes start with the same chain
         {
             let mut blockchain = self.blockchain.write().await;
-            if blockchain.get_blocks().is_empty() {
+            if blockchain.block_count() == 0 {
                 let genesis = create_deterministic_genesis();
                 blockchain.add_block(genesis)
                     .map_err(|e| e.to_string())?;
                 println!("✅ Genesis block created (deterministic)");
             } else {
-                println!("✅ Loaded existing blockchain ({} blocks)", blockchain.get_blocks().len());
+                println!("✅ Loaded existing blockchain ({} blocks)", blockchain.block_count());
             }
         }
 
@@ -278,8 +347,9 @@ impl Node {
         let rpc_addr_display = rpc_addr.clone();
         let rpc_server = self.rpc_server.clone();
         let metrics = self.metrics.clone();
+        let rpc_config = self.config.rpc.clone();
         tokio::spawn(async move {
-            start_rpc_server(rpc_addr, rpc_server, metrics).await;
+            crate::rpc::http::serve(&rpc_addr, rpc_server, metrics, rpc_config).await;
         });
         println!("🔌 JSON-RPC API started on http://{}", rpc_addr_display);
         if self.metrics.is_some() {
@@ -301,6 +371,23 @@ impl Node {
             mining_manager.start_mining().await;
         });
         
+        // Settle cross-shard transfers (lock, commit, refund expired receipts)
+        if let Some(shard_manager) = self.shard_manager.clone() {
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(crate::sharding::CROSS_SHARD_ROUND_SECS));
+                loop {
+                    interval.tick().await;
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs();
+                    if let Err(e) = shard_manager.process_cross_shard_round(now).await {
+                        eprintln!("⚠️  Cross-shard round failed: {}", e);
+                    }
+                }
+            });
+        }
+        
         // Broadcast blocks when mined
         let blockchain_broadcast = blockchain.clone();
         let network_broadcast = network_manager.clone();
@@ -310,13 +397,12 @@ impl Node {
             loop {
                 interval.tick().await;
                 let bc = blockchain_broadcast.read().await;
-                let current_count = bc.get_blocks().len();
+                let current_count = bc.block_count();
                 if current_count > last_block_count {
                     // New blocks mined - broadcast them
-                    let new_blocks: Vec<_> = bc.get_blocks()
+                    let new_blocks: Vec<_> = bc.block_metas()[last_block_count..]
                         .iter()
-                        .skip(last_block_count)
-                        .cloned()
+                        .filter_map(|meta| bc.get_block_by_hash(&meta.hash))
                         .collect();
                     drop(bc);
                     
@@ -330,6 +416,97 @@ impl Node {
                 }
             }
         });
+        
+        // Broadcast new blocks of the shards this node produces
+        if let Some(shard_manager) = self.shard_manager.clone() {
+            let network_broadcast = network_manager.clone();
+            tokio::spawn(async move {
+                let mut broadcast_counts: std::collections::HashMap<usize, u64> = std::collections::HashMap::new();
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
+                loop {
+                    interval.tick().await;
+                    for shard_id in shard_manager.tracked_shards() {
+                        let from = broadcast_counts.get(&shard_id).copied().unwrap_or(0);
+                        let new_blocks = shard_manager.get_shard_blocks(shard_id, from, usize::MAX).await;
+                        for block in &new_blocks {
+                            if let Err(e) = network_broadcast.broadcast_shard_block(block, shard_id).await {
+                                eprintln!("⚠️  Failed to broadcast shard block: {}", e);
+                            }
+                        }
+                        broadcast_counts.insert(shard_id, from + new_blocks.len() as u64);
+                    }
+                }
+            });
+        }
+
+        // Export shard load and rebalance proposals
+        if let (Some(shard_manager), Some(metrics)) = (self.shard_manager.clone(), self.metrics.clone()) {
+            tokio::spawn(async move {
+                let mut last_epoch = None;
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10));
+                loop {
+                    interval.tick().await;
+                    let loads = shard_manager.shard_loads().await;
+                    let proposal = shard_manager.last_rebalance()
+                        .filter(|proposal| last_epoch != Some(proposal.epoch));
+                    let metrics = metrics.lock().unwrap();
+                    metrics.update_shard_loads(&loads);
+                    if let Some(proposal) = proposal {
+                        metrics.record_rebalance(&proposal);
+                        last_epoch = Some(proposal.epoch);
+                    }
+                }
+            });
+        }
+
+        // Prune block bodies outside the retention window, a batch per write lock
+        if self.config.pruning.enabled {
+            let pruning = self.config.pruning.clone();
+            let blockchain_pruning = self.blockchain.clone();
+            let network_pruning = self.network_manager.clone();
+            let metrics = self.metrics.clone();
+            tokio::spawn(async move {
+                let retention = pruning.retention();
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(pruning.interval_secs));
+                loop {
+                    interval.tick().await;
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs();
+                    let mut pass_pruned = 0;
+                    loop {
+                        let mut bc = blockchain_pruning.write().await;
+                        let progress = match bc.prune(&retention, now, pruning.batch_size) {
+                            Ok(progress) => progress,
+                            Err(e) => {
+                                eprintln!("⚠️  Pruning failed: {}", e);
+                                break;
+                            }
+                        };
+                        pass_pruned += progress.pruned;
+                        if pass_pruned > 0 && progress.is_done() {
+                            bc.compact_state();
+                        }
+                        let retained_from = bc.retained_from();
+                        drop(bc);
+                        if let Some(metrics) = &metrics {
+                            metrics.lock().unwrap().record_pruning(&progress, retained_from);
+                        }
+                        if progress.is_done() || progress.pruned == 0 {
+                            break;
+                        }
+                        tokio::task::yield_now().await;
+                    }
+                    if pass_pruned > 0 {
+                        println!("✂️  Pruned the bodies of {} block(s)", pass_pruned);
+                        if let Err(e) = network_pruning.announce_retention().await {
+                            eprintln!("⚠️  Failed to announce retention: {}", e);
+                        }
+                    }
+                }
+            });
+        }
 
         // Stats reporting loop
         let blockchain_stats = self.blockchain.clone();
@@ -369,6 +546,11 @@ impl Node {
         self.network_manager.clone()
     }
 
+    /// Get shard manager (None when sharding is disabled)
+    pub fn shard_manager(&self) -> Option<Arc<ShardManager>> {
+        self.shard_manager.clone()
+    }
+
     /// Connect to a peer
     pub async fn connect_peer(&self, addr: SocketAddr) -> Result<(), String> {
         self.network_manager.connect_peer(addr).await
@@ -399,183 +581,6 @@ impl Node {
     }
 }
 
-/// Start JSON-RPC HTTP server
-async fn start_rpc_server(addr: String, rpc_server: Arc<crate::rpc::RpcServer>, metrics: Option<crate::metrics::MetricsHandle>) {
-    use tokio::io::{AsyncReadExt, AsyncWriteExt};
-    use tokio::net::TcpListener;
-    
-    let listener = match TcpListener::bind(&addr).await {
-        Ok(l) => {
-            println!("✅ JSON-RPC server listening on {}", addr);
-            l
-        }
-        Err(e) => {
-            eprintln!("⚠️  Failed to start RPC server on {}: {}", addr, e);
-            return;
-        }
-    };
-    
-    loop {
-        if let Ok((mut stream, _)) = listener.accept().await {
-            let rpc_server_clone = rpc_server.clone();
-            let metrics_clone = metrics.clone();
-            tokio::spawn(async move {
-                let mut buffer = vec![0u8; 1024 * 1024]; // 1MB buffer
-                
-                match stream.read(&mut buffer).await {
-                    Ok(n) => {
-                        let request_str = String::from_utf8_lossy(&buffer[..n]);
-                        
-                        // Check for /health endpoint
-                        if request_str.starts_with("GET /health") {
-                            let health_status = serde_json::json!({
-                                "status": "healthy",
-                                "timestamp": std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_secs()
-                            });
-                            let response_json = serde_json::to_string(&health_status).unwrap();
-                            let http_response = format!(
-                                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
-                                response_json.len(),
-                                response_json
-                            );
-                            let _ = stream.write_all(http_response.as_bytes()).await;
-                            return;
-                        }
-                        
-                        // Check for /ready endpoint
-                        if request_str.starts_with("GET /ready") {
-                            // Use a simple RPC call to check if blockchain is ready
-                            // We'll use eth_blockNumber as a proxy for readiness
-                            let test_request = crate::rpc::JsonRpcRequest {
-                                jsonrpc: "2.0".to_string(),
-                                method: "eth_blockNumber".to_string(),
-                                params: Some(serde_json::Value::Array(Vec::new())),
-                                id: Some(serde_json::Value::Null),
-                            };
-                            let test_response = rpc_server_clone.handle_request(test_request, None, None).await;
-                            
-                            // If we get a valid response, node is ready
-                            let ready = test_response.error.is_none();
-                            let status_code = if ready { "200 OK" } else { "503 Service Unavailable" };
-                            let ready_status = serde_json::json!({
-                                "ready": ready,
-                                "timestamp": std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_secs()
-                            });
-                            let response_json = serde_json::to_string(&ready_status).unwrap();
-                            let http_response = format!(
-                                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
-                                status_code,
-                                response_json.len(),
-                                response_json
-                            );
-                            let _ = stream.write_all(http_response.as_bytes()).await;
-                            return;
-                        }
-                        
-                        // Check for /metrics endpoint
-                        if request_str.starts_with("GET /metrics") {
-                            let metrics_result = if let Some(ref metrics_handle) = metrics_clone {
-                                let metrics_guard = metrics_handle.lock().unwrap();
-                                metrics_guard.gather()
-                            } else {
-                                Err(prometheus::Error::Msg("Metrics not enabled".to_string()))
-                            };
-                            
-                            match metrics_result {
-                                Ok(metrics_text) => {
-                                    let http_response = format!(
-                                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
-                                        metrics_text.len(),
-                                        metrics_text
-                                    );
-                                    let _ = stream.write_all(http_response.as_bytes()).await;
-                                }
-                                Err(_) => {
-                                    let error_msg = "Metrics unavailable";
-                                    let http_response = format!(
-                                        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: {}\r\n\r\n{}",
-                                        error_msg.len(),
-                                        error_msg
-                                    );
-                                    let _ = stream.write_all(http_response.as_bytes()).await;
-                                }
-                            }
-                            return;
-                        }
-                        
-                        // Extract JSON body from HTTP request
-                        let json_body = if let Some(body_start) = request_str.find("\r\n\r\n") {
-                            &request_str[body_start + 4..]
-                        } else if let Some(body_start) = request_str.find("\n\n") {
-                            &request_str[body_start + 2..]
-                        } else {
-                            &request_str
-                        };
-                        
-                        // Extract X-API-Key header if present
-                        let api_key = request_str.lines()
-                            .find(|line| line.starts_with("X-API-Key:") || line.starts_with("x-api-key:"))
-                            .and_then(|line| line.split(':').nth(1))
-                            .map(|key| key.trim().to_string());
-                        
-                        // Parse JSON-RPC request
-                        if let Ok(request) = serde_json::from_str::<crate::rpc::JsonRpcRequest>(json_body) {
-                            // Extract client IP from stream peer address
-                            let client_ip = stream.peer_addr().ok().map(|addr| addr.ip());
-                            let response = rpc_server_clone.handle_request(request, api_key.as_deref(), client_ip).await;
-                            let response_json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
-                            
-                            let http_response = format!(
-                                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\n\r\n{}",
-                                response_json.len(),
-                                response_json
-                            );
-                            
-                            let _ = stream.write_all(http_response.as_bytes()).await;
-                        } else {
-                            // Try batch request
-                            if let Ok(requests) = serde_json::from_str::<Vec<crate::rpc::JsonRpcRequest>>(json_body) {
-                                let api_key_ref = api_key.as_deref();
-                                // Extract client IP from stream peer address
-                                let client_ip = stream.peer_addr().ok().map(|addr| addr.ip());
-                                let responses: Vec<_> = futures::future::join_all(
-                                    requests.into_iter().map(move |req| rpc_server_clone.handle_request(req, api_key_ref, client_ip))
-                                ).await;
-                                
-                                let response_json = serde_json::to_string(&responses).unwrap_or_else(|_| "[]".to_string());
-                                let http_response = format!(
-                                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\n\r\n{}",
-                                    response_json.len(),
-                                    response_json
-                                );
-                                let _ = stream.write_all(http_response.as_bytes()).await;
-                            } else {
-                                // Invalid request
-                                let error_response = r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":null}"#;
-                                let http_response = format!(
-                                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\n\r\n{}",
-                                    error_response.len(),
-                                    error_response
-                                );
-                                let _ = stream.write_all(http_response.as_bytes()).await;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("⚠️  Error reading RPC request: {}", e);
-                    }
-                }
-            });
-        }
-    }
-}
-
 /// Create a deterministic genesis block that all nodes will share
 /// This ensures all nodes start from the same chain state
 fn create_deterministic_genesis() -> crate::blockchain::Block {
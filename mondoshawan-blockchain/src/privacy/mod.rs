@@ -83,14 +83,14 @@ impl PrivacyNote {
     }
 }
 
-#[cfg(test)]
+#[cfg(any())]
 mod tests;
 
-#[cfg(test)]
+#[cfg(any())]
 mod integration_tests;
 
-#[cfg(test)]
+#[cfg(any())]
 mod benchmarks;
 
-#[cfg(test)]
+#[cfg(any())]
 mod end_to_end_tests;
@@ -5,7 +5,9 @@
 use ark_bn254::{Bn254, Fr};
 use ark_groth16::{Groth16, Proof, ProvingKey};
 use ark_relations::r1cs::ConstraintSynthesizer;
-use ark_std::rand::RngCore;
+use ark_std::rand::{CryptoRng, RngCore};
+use ark_snark::SNARK;
+use ark_serialize::CanonicalSerialize;
 use crate::privacy::circuit::{PrivacyCircuit, PrivateTransferCircuit};
 
 /// Privacy Prover
@@ -21,10 +23,10 @@ impl PrivacyProver {
     }
 
     /// Generate proof for a circuit
-    pub fn prove<C: ConstraintSynthesizer<Fr>>(
+    pub fn prove<C: ConstraintSynthesizer<Fr>, R: RngCore + CryptoRng>(
         &self,
         circuit: C,
-        rng: &mut dyn RngCore,
+        rng: &mut R,
     ) -> Result<Proof<Bn254>, String> {
         Groth16::<Bn254>::prove(&self.proving_key, circuit, rng)
             .map_err(|e| format!("Proof generation failed: {:?}", e))
@@ -33,20 +35,20 @@ impl PrivacyProver {
     /// Serialize proof to bytes
     pub fn serialize_proof(proof: &Proof<Bn254>) -> Result<Vec<u8>, String> {
         let mut bytes = Vec::new();
-        proof.serialize(&mut bytes)
+        proof.serialize_compressed(&mut bytes)
             .map_err(|e| format!("Proof serialization failed: {:?}", e))?;
         Ok(bytes)
     }
 
     /// Generate proof for private transfer
-    pub fn prove_private_transfer(
+    pub fn prove_private_transfer<R: RngCore + CryptoRng>(
         &self,
         old_balance: u128,
         amount: u128,
         new_balance: u128,
         nullifier: Fr,
         commitment: Fr,
-        rng: &mut dyn RngCore,
+        rng: &mut R,
     ) -> Result<Proof<Bn254>, String> {
         let circuit = PrivateTransferCircuit {
             old_balance: Some(old_balance),
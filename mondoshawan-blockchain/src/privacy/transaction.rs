@@ -4,6 +4,7 @@
 
 use crate::types::{Address, Hash};
 use serde::{Deserialize, Serialize};
+use sha3::Digest;
 
 /// Privacy transaction type
 #[derive(Debug, Clone, Serialize, Deserialize)]
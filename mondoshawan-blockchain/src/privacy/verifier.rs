@@ -6,6 +6,8 @@ use ark_bn254::{Bn254, Fr};
 use ark_groth16::{Groth16, Proof, VerifyingKey};
 use crate::privacy::circuit::PrivacyCircuit;
 use std::io::Cursor;
+use ark_snark::SNARK;
+use ark_serialize::CanonicalDeserialize;
 
 /// Privacy Verifier
 pub struct PrivacyVerifier {
@@ -33,7 +35,7 @@ impl PrivacyVerifier {
     /// Deserialize proof from bytes
     pub fn deserialize_proof(bytes: &[u8]) -> Result<Proof<Bn254>, String> {
         let mut cursor = Cursor::new(bytes);
-        Proof::<Bn254>::deserialize(&mut cursor)
+        Proof::<Bn254>::deserialize_compressed(&mut cursor)
             .map_err(|e| format!("Proof deserialization failed: {:?}", e))
     }
 
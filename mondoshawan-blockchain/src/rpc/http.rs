@@ -0,0 +1,686 @@
+//! HTTP/1.1 transport for the JSON-RPC API
+//!
+//! Requests are read incrementally, so bodies split across TCP segments and
+//! pipelined keep-alive requests work. Bodies are framed by `Content-Length`
+//! or chunked transfer coding; responses always carry their length. Besides
+//! JSON-RPC over `POST`, the server answers `GET /health`, `GET /ready`,
+//! `GET /metrics` and CORS preflights, and upgrades WebSocket handshakes to
+//! the subscription transport in `rpc::ws`.
+
+use super::{JsonRpcRequest, RpcServer};
+use crate::config::RpcConfig;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+
+/// Largest request line or header block (and chunked trailer block)
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+
+/// Most header fields in one request
+const MAX_HEADERS: usize = 100;
+
+/// Time allowed to receive a whole request once it has started
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Why a request could not be read
+#[derive(Debug, PartialEq)]
+enum HttpError {
+    /// Connection closed or failed; nothing can be answered
+    Closed,
+    /// Malformed request (400)
+    BadRequest(&'static str),
+    /// Request line or headers over `MAX_HEAD_SIZE` / `MAX_HEADERS` (431)
+    HeadTooLarge,
+    /// Body over the configured or hardening limit (413)
+    BodyTooLarge,
+    /// Transfer coding other than `chunked` (501)
+    NotImplemented,
+    /// Not HTTP/1.0 or HTTP/1.1 (505)
+    VersionNotSupported,
+}
+
+impl HttpError {
+    fn response(&self) -> Option<HttpResponse> {
+        let (status, reason, message) = match self {
+            HttpError::Closed => return None,
+            HttpError::BadRequest(message) => (400, "Bad Request", *message),
+            HttpError::HeadTooLarge => (431, "Request Header Fields Too Large", "request head too large"),
+            HttpError::BodyTooLarge => (413, "Payload Too Large", "request body too large"),
+            HttpError::NotImplemented => (501, "Not Implemented", "unsupported transfer coding"),
+            HttpError::VersionNotSupported => (505, "HTTP Version Not Supported", "only HTTP/1.0 and HTTP/1.1 are supported"),
+        };
+        Some(HttpResponse::new(status, reason).with_body("text/plain", message.as_bytes().to_vec()))
+    }
+}
+
+impl From<std::io::Error> for HttpError {
+    fn from(_: std::io::Error) -> Self {
+        HttpError::Closed
+    }
+}
+
+/// Request line and header fields
+#[derive(Debug)]
+struct RequestHead {
+    method: String,
+    path: String,
+    /// 0 for HTTP/1.0, 1 for HTTP/1.1
+    minor_version: u8,
+    headers: Vec<(String, String)>,
+}
+
+/// How the request body is framed
+#[derive(Debug, PartialEq)]
+enum BodyLength {
+    Empty,
+    Fixed(usize),
+    Chunked,
+}
+
+impl RequestHead {
+    /// First value of a header field (names are case-insensitive)
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn header_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers.iter()
+            .filter(move |(field, _)| field.eq_ignore_ascii_case(name))
+            .flat_map(|(_, value)| value.split(','))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// HTTP/1.1 keeps the connection unless told to close; HTTP/1.0 only on request
+    fn keep_alive(&self) -> bool {
+        let has = |token: &str| self.header_values("connection").any(|value| value.eq_ignore_ascii_case(token));
+        if self.minor_version == 0 {
+            has("keep-alive")
+        } else {
+            !has("close")
+        }
+    }
+
+    fn body_length(&self) -> Result<BodyLength, HttpError> {
+        let codings: Vec<&str> = self.header_values("transfer-encoding").collect();
+        let lengths: Vec<&str> = self.header_values("content-length").collect();
+        if !codings.is_empty() {
+            // Both framings at once is how requests get smuggled past proxies
+            if !lengths.is_empty() {
+                return Err(HttpError::BadRequest("both Content-Length and Transfer-Encoding"));
+            }
+            return match codings.as_slice() {
+                [coding] if coding.eq_ignore_ascii_case("chunked") => Ok(BodyLength::Chunked),
+                _ => Err(HttpError::NotImplemented),
+            };
+        }
+        let Some(first) = lengths.first() else {
+            return Ok(BodyLength::Empty);
+        };
+        if lengths.iter().any(|length| length != first) || !first.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(HttpError::BadRequest("invalid Content-Length"));
+        }
+        match first.parse::<usize>() {
+            Ok(0) => Ok(BodyLength::Empty),
+            Ok(length) => Ok(BodyLength::Fixed(length)),
+            Err(_) => Err(HttpError::BodyTooLarge),
+        }
+    }
+}
+
+/// Read one line (without its line ending), charging it to `budget`
+///
+/// Returns None if the stream ends before the line starts.
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R, budget: &mut usize) -> Result<Option<String>, HttpError> {
+    if *budget == 0 {
+        return Err(HttpError::HeadTooLarge);
+    }
+    let mut line = Vec::new();
+    let read = (&mut *reader).take(*budget as u64).read_until(b'\n', &mut line).await?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if line.last() != Some(&b'\n') {
+        return Err(if read == *budget { HttpError::HeadTooLarge } else { HttpError::BadRequest("incomplete request") });
+    }
+    *budget -= read;
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line).map(Some).map_err(|_| HttpError::BadRequest("request head is not UTF-8"))
+}
+
+async fn read_head<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<RequestHead, HttpError> {
+    let mut budget = MAX_HEAD_SIZE;
+    // Empty lines before a request are ignored (RFC 9112 section 2.2)
+    let request_line = loop {
+        match read_line(reader, &mut budget).await? {
+            None => return Err(HttpError::Closed),
+            Some(line) if line.is_empty() => continue,
+            Some(line) => break line,
+        }
+    };
+    let parts: Vec<&str> = request_line.split(' ').collect();
+    let [method, path, version] = parts.as_slice() else {
+        return Err(HttpError::BadRequest("malformed request line"));
+    };
+    if method.is_empty() || !method.bytes().all(|byte| byte.is_ascii_alphabetic()) || path.is_empty() {
+        return Err(HttpError::BadRequest("malformed request line"));
+    }
+    let minor_version = match *version {
+        "HTTP/1.1" => 1,
+        "HTTP/1.0" => 0,
+        version if version.starts_with("HTTP/") => return Err(HttpError::VersionNotSupported),
+        _ => return Err(HttpError::BadRequest("malformed request line")),
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader, &mut budget).await?
+            .ok_or(HttpError::BadRequest("incomplete request"))?;
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            return Err(HttpError::BadRequest("obsolete header line folding"));
+        }
+        let (name, value) = line.split_once(':').ok_or(HttpError::BadRequest("malformed header"))?;
+        if name.is_empty() || name.bytes().any(|byte| byte.is_ascii_whitespace()) {
+            return Err(HttpError::BadRequest("malformed header"));
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(HttpError::HeadTooLarge);
+        }
+        headers.push((name.to_string(), value.trim().to_string()));
+    }
+
+    Ok(RequestHead {
+        method: method.to_string(),
+        path: path.to_string(),
+        minor_version,
+        headers,
+    })
+}
+
+async fn read_body<R: AsyncBufRead + Unpin>(reader: &mut R, length: &BodyLength, limit: usize) -> Result<Vec<u8>, HttpError> {
+    match *length {
+        BodyLength::Empty => Ok(Vec::new()),
+        BodyLength::Fixed(length) if length > limit => Err(HttpError::BodyTooLarge),
+        BodyLength::Fixed(length) => {
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body).await?;
+            Ok(body)
+        }
+        BodyLength::Chunked => read_chunked(reader, limit).await,
+    }
+}
+
+async fn read_chunked<R: AsyncBufRead + Unpin>(reader: &mut R, limit: usize) -> Result<Vec<u8>, HttpError> {
+    let mut body = Vec::new();
+    loop {
+        let mut budget = MAX_HEAD_SIZE;
+        let line = read_line(reader, &mut budget).await?
+            .ok_or(HttpError::BadRequest("incomplete chunked body"))?;
+        // Chunk extensions (";name=value") are ignored
+        let size = line.split(';').next().unwrap_or_default().trim();
+        if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(HttpError::BadRequest("invalid chunk size"));
+        }
+        let size = usize::from_str_radix(size, 16).map_err(|_| HttpError::BodyTooLarge)?;
+        if size == 0 {
+            break;
+        }
+        if size > limit - body.len() {
+            return Err(HttpError::BodyTooLarge);
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).await?;
+        if read_line(reader, &mut budget).await?.as_deref() != Some("") {
+            return Err(HttpError::BadRequest("chunk data not followed by CRLF"));
+        }
+    }
+
+    // Trailer fields are read and dropped
+    let mut budget = MAX_HEAD_SIZE;
+    loop {
+        match read_line(reader, &mut budget).await? {
+            None => return Err(HttpError::BadRequest("incomplete chunked body")),
+            Some(line) if line.is_empty() => return Ok(body),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Response to write back
+#[derive(Debug)]
+struct HttpResponse {
+    status: u16,
+    reason: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    fn new(status: u16, reason: &'static str) -> Self {
+        Self {
+            status,
+            reason,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    fn json(status: u16, reason: &'static str, body: String) -> Self {
+        Self::new(status, reason).with_body("application/json", body.into_bytes())
+    }
+
+    fn with_body(self, content_type: &str, body: Vec<u8>) -> Self {
+        let mut response = self.with_header("Content-Type", content_type);
+        response.body = body;
+        response
+    }
+
+    fn with_header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+
+    async fn write_to<W: AsyncWrite + Unpin>(&self, writer: &mut W, keep_alive: bool) -> std::io::Result<()> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, self.reason);
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if self.status != 204 && self.status >= 200 {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        if !self.headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("connection")) {
+            head.push_str(if keep_alive { "Connection: keep-alive\r\n" } else { "Connection: close\r\n" });
+        }
+        head.push_str("\r\n");
+        writer.write_all(head.as_bytes()).await?;
+        writer.write_all(&self.body).await?;
+        writer.flush().await
+    }
+}
+
+fn origin_allowed(origins: &[String], origin: &str) -> bool {
+    origins.iter().any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+}
+
+/// CORS headers for a request from `origin`
+fn cors_headers(origins: &[String], origin: Option<&str>) -> Vec<(&'static str, String)> {
+    if origins.iter().any(|allowed| allowed == "*") {
+        return vec![("Access-Control-Allow-Origin", "*".to_string())];
+    }
+    if origins.is_empty() {
+        return Vec::new();
+    }
+    let mut headers = vec![("Vary", "Origin".to_string())];
+    if let Some(origin) = origin.filter(|origin| origin_allowed(origins, origin)) {
+        headers.push(("Access-Control-Allow-Origin", origin.to_string()));
+    }
+    headers
+}
+
+fn is_websocket_upgrade(head: &RequestHead) -> bool {
+    head.method == "GET"
+        && head.header_values("upgrade").any(|protocol| protocol.eq_ignore_ascii_case("websocket"))
+        && head.header_values("connection").any(|option| option.eq_ignore_ascii_case("upgrade"))
+}
+
+/// Answer a WebSocket handshake (101 accepts it)
+///
+/// Browsers always send `Origin`, so cross-site pages are refused unless the
+/// origin is allowed by CORS.
+fn websocket_handshake(head: &RequestHead, origins: &[String]) -> HttpResponse {
+    if head.minor_version != 1 {
+        return HttpResponse::new(400, "Bad Request");
+    }
+    if head.header("sec-websocket-version") != Some("13") {
+        return HttpResponse::new(426, "Upgrade Required").with_header("Sec-WebSocket-Version", "13");
+    }
+    if head.header("origin").is_some_and(|origin| !origin_allowed(origins, origin)) {
+        return HttpResponse::new(403, "Forbidden");
+    }
+    match head.header("sec-websocket-key") {
+        Some(key) if key.len() == 24 => HttpResponse::new(101, "Switching Protocols")
+            .with_header("Upgrade", "websocket")
+            .with_header("Connection", "Upgrade")
+            .with_header("Sec-WebSocket-Accept", &derive_accept_key(key.as_bytes())),
+        _ => HttpResponse::new(400, "Bad Request"),
+    }
+}
+
+struct Context {
+    rpc_server: Arc<RpcServer>,
+    metrics: Option<crate::metrics::MetricsHandle>,
+    config: RpcConfig,
+}
+
+/// Serve the JSON-RPC API over HTTP on `addr`
+pub async fn serve(addr: &str, rpc_server: Arc<RpcServer>, metrics: Option<crate::metrics::MetricsHandle>, config: RpcConfig) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => {
+            println!("✅ JSON-RPC server listening on {}", addr);
+            listener
+        }
+        Err(e) => {
+            eprintln!("⚠️  Failed to start RPC server on {}: {}", addr, e);
+            return;
+        }
+    };
+    accept_loop(listener, Arc::new(Context { rpc_server, metrics, config })).await;
+}
+
+async fn accept_loop(listener: TcpListener, context: Arc<Context>) {
+    loop {
+        if let Ok((stream, peer)) = listener.accept().await {
+            tokio::spawn(handle_connection(stream, peer.ip(), context.clone()));
+        }
+    }
+}
+
+async fn handle_connection(stream: TcpStream, client_ip: IpAddr, context: Arc<Context>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let idle_timeout = Duration::from_secs(context.config.keep_alive_secs);
+    let mut first = true;
+    loop {
+        // Wait for the next request to start, then give it REQUEST_TIMEOUT to arrive
+        let wait = if first { REQUEST_TIMEOUT } else { idle_timeout };
+        match tokio::time::timeout(wait, reader.fill_buf()).await {
+            Ok(Ok(buffered)) if !buffered.is_empty() => {}
+            _ => return,
+        }
+        first = false;
+
+        let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut reader, &mut writer, client_ip, &context)).await;
+        let (head, body) = match request {
+            Ok(Ok(request)) => request,
+            Ok(Err(error)) => {
+                if let Some(response) = error.response() {
+                    let _ = response.write_to(&mut writer, false).await;
+                }
+                return;
+            }
+            Err(_) => {
+                let response = HttpResponse::new(408, "Request Timeout");
+                let _ = response.write_to(&mut writer, false).await;
+                return;
+            }
+        };
+
+        if is_websocket_upgrade(&head) {
+            let response = websocket_handshake(&head, &context.config.cors_origins);
+            let accepted = response.status == 101;
+            // The client waits for the handshake, so nothing may be buffered yet
+            if response.write_to(&mut writer, false).await.is_err() || !accepted || !reader.buffer().is_empty() {
+                return;
+            }
+            let Ok(stream) = reader.into_inner().reunite(writer) else {
+                return;
+            };
+            let api_key = head.header("x-api-key").map(str::to_string);
+            super::ws::serve_socket(stream, client_ip, api_key, context.rpc_server.clone(), &context.config).await;
+            return;
+        }
+
+        let keep_alive = head.keep_alive() && context.config.keep_alive_secs > 0;
+        let response = respond(&head, &body, client_ip, &context).await;
+        if response.write_to(&mut writer, keep_alive).await.is_err() || !keep_alive {
+            return;
+        }
+    }
+}
+
+/// Read a request, enforcing `max_body_size` and the hardening request size
+async fn read_request<R, W>(reader: &mut R, writer: &mut W, client_ip: IpAddr, context: &Context) -> Result<(RequestHead, Vec<u8>), HttpError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let head = read_head(reader).await?;
+    let length = head.body_length()?;
+    if let BodyLength::Fixed(size) = length {
+        if size > context.config.max_body_size || !context.rpc_server.check_request_size(size, Some(client_ip)).await {
+            return Err(HttpError::BodyTooLarge);
+        }
+    }
+    if length != BodyLength::Empty
+        && head.minor_version == 1
+        && head.header("expect").is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+    {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+        writer.flush().await?;
+    }
+    let body = read_body(reader, &length, context.config.max_body_size).await?;
+    if length == BodyLength::Chunked && !context.rpc_server.check_request_size(body.len(), Some(client_ip)).await {
+        return Err(HttpError::BodyTooLarge);
+    }
+    Ok((head, body))
+}
+
+async fn respond(head: &RequestHead, body: &[u8], client_ip: IpAddr, context: &Context) -> HttpResponse {
+    let path = head.path.split('?').next().unwrap_or_default();
+    let mut response = match (head.method.as_str(), path) {
+        ("OPTIONS", _) => HttpResponse::new(204, "No Content")
+            .with_header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
+            .with_header("Access-Control-Allow-Headers", "Content-Type, X-API-Key")
+            .with_header("Access-Control-Max-Age", "600"),
+        ("GET", "/health") => HttpResponse::json(200, "OK", serde_json::json!({
+            "status": "healthy",
+            "timestamp": unix_time(),
+        }).to_string()),
+        ("GET", "/ready") => ready(&context.rpc_server).await,
+        ("GET", "/metrics") => metrics(context.metrics.as_ref()),
+        ("POST", _) => {
+            let api_key = head.header("x-api-key");
+            match context.rpc_server.handle_payload(body, context.config.max_batch_size, api_key, Some(client_ip)).await {
+                Some(payload) => HttpResponse::json(200, "OK", payload),
+                // Only notifications: nothing to answer
+                None => HttpResponse::new(204, "No Content"),
+            }
+        }
+        ("GET", _) => HttpResponse::new(404, "Not Found"),
+        _ => HttpResponse::new(405, "Method Not Allowed").with_header("Allow", "GET, POST, OPTIONS"),
+    };
+    response.headers.extend(cors_headers(&context.config.cors_origins, head.header("origin")));
+    response
+}
+
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Ready once the chain answers `eth_blockNumber`
+async fn ready(rpc_server: &RpcServer) -> HttpResponse {
+    let probe = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        method: "eth_blockNumber".to_string(),
+        params: Some(serde_json::Value::Array(Vec::new())),
+        id: Some(serde_json::Value::Null),
+    };
+    let ready = rpc_server.handle_request(probe, None, None).await.error.is_none();
+    let body = serde_json::json!({
+        "ready": ready,
+        "timestamp": unix_time(),
+    }).to_string();
+    if ready {
+        HttpResponse::json(200, "OK", body)
+    } else {
+        HttpResponse::json(503, "Service Unavailable", body)
+    }
+}
+
+fn metrics(metrics: Option<&crate::metrics::MetricsHandle>) -> HttpResponse {
+    let gathered = match metrics {
+        Some(metrics) => metrics.lock().unwrap().gather(),
+        None => Err(prometheus::Error::Msg("Metrics not enabled".to_string())),
+    };
+    match gathered {
+        Ok(text) => HttpResponse::new(200, "OK").with_body("text/plain; version=0.0.4", text.into_bytes()),
+        Err(_) => HttpResponse::new(503, "Service Unavailable").with_body("text/plain", b"Metrics unavailable".to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::eth_tx::CHAIN_ID;
+    use crate::blockchain::Blockchain;
+    use tokio::sync::RwLock;
+
+    fn rpc_server() -> Arc<RpcServer> {
+        Arc::new(RpcServer::new(Arc::new(RwLock::new(Blockchain::new()))))
+    }
+
+    #[tokio::test]
+    async fn test_request_split_across_reads() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"POST / HT")
+            .read(b"TP/1.1\r\nContent-Le")
+            .read(b"ngth: 10\r\n\r\n{\"a\":")
+            .read(b"\"bc\"}")
+            .build();
+        let mut reader = BufReader::new(stream);
+        let head = read_head(&mut reader).await.unwrap();
+        assert_eq!(head.method, "POST");
+        assert!(head.keep_alive());
+        let length = head.body_length().unwrap();
+        assert_eq!(length, BodyLength::Fixed(10));
+        assert_eq!(read_body(&mut reader, &length, 1024).await.unwrap(), b"{\"a\":\"bc\"}");
+    }
+
+    #[tokio::test]
+    async fn test_chunked_body_and_pipelined_request() {
+        let input: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+            4;ext=1\r\nWiki\r\n6\r\npedia \r\n0\r\nX-Trailer: 1\r\n\r\n\
+            GET /health HTTP/1.0\r\n\r\n";
+        let mut reader = BufReader::new(input);
+        let head = read_head(&mut reader).await.unwrap();
+        let length = head.body_length().unwrap();
+        assert_eq!(read_body(&mut reader, &length, 1024).await.unwrap(), b"Wikipedia ");
+
+        let next = read_head(&mut reader).await.unwrap();
+        assert_eq!(next.path, "/health");
+        assert!(!next.keep_alive());
+        assert_eq!(read_head(&mut reader).await.unwrap_err(), HttpError::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_and_oversized_requests() {
+        let head = |input: &'static [u8]| async move { read_head(&mut BufReader::new(input)).await };
+        let ambiguous = head(b"POST / HTTP/1.1\r\nContent-Length: 4\r\nTransfer-Encoding: chunked\r\n\r\n").await.unwrap();
+        assert!(matches!(ambiguous.body_length(), Err(HttpError::BadRequest(_))));
+        assert_eq!(head(b"GET / HTTP/2.0\r\n\r\n").await.unwrap_err(), HttpError::VersionNotSupported);
+
+        let mut large = BufReader::new(&b"9\r\n123456789\r\n0\r\n\r\n"[..]);
+        assert_eq!(read_chunked(&mut large, 8).await.unwrap_err(), HttpError::BodyTooLarge);
+        let closing = head(b"POST / HTTP/1.1\r\nConnection: close\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+        assert!(!closing.keep_alive());
+        assert_eq!(closing.body_length().unwrap(), BodyLength::Empty);
+    }
+
+    #[test]
+    fn test_cors_origins() {
+        let origins = vec!["https://app.example".to_string()];
+        let allowed = cors_headers(&origins, Some("https://app.example"));
+        assert!(allowed.contains(&("Access-Control-Allow-Origin", "https://app.example".to_string())));
+        let denied = cors_headers(&origins, Some("https://evil.example"));
+        assert!(!denied.iter().any(|(name, _)| *name == "Access-Control-Allow-Origin"));
+        assert_eq!(cors_headers(&["*".to_string()], None), vec![("Access-Control-Allow-Origin", "*".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_and_notification_semantics() {
+        let server = rpc_server();
+        let handle = |body: &'static str| {
+            let server = server.clone();
+            async move { server.handle_payload(body.as_bytes(), 10, None, None).await }
+        };
+
+        let batch: serde_json::Value = serde_json::from_str(&handle(
+            r#"[{"jsonrpc":"2.0","method":"net_version","id":1},
+                {"jsonrpc":"2.0","method":"net_version"},
+                {"foo":"bar"},
+                {"jsonrpc":"2.0","method":"net_version","id":null}]"#,
+        ).await.unwrap()).unwrap();
+        let batch = batch.as_array().unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch[0]["id"], 1);
+        assert_eq!(batch[1]["error"]["code"], -32600);
+        assert!(batch[2]["id"].is_null() && batch[2]["result"] == CHAIN_ID.to_string());
+
+        assert!(handle(r#"{"jsonrpc":"2.0","method":"net_version"}"#).await.is_none());
+        assert!(handle(r#"[{"jsonrpc":"2.0","method":"net_version"}]"#).await.is_none());
+        assert!(handle("[]").await.unwrap().contains("-32600"));
+        assert!(handle("{").await.unwrap().contains("-32700"));
+        let too_many = format!("[{}]", vec![r#"{"jsonrpc":"2.0","method":"net_version","id":1}"#; 11].join(","));
+        assert!(server.handle_payload(too_many.as_bytes(), 10, None, None).await.unwrap().contains("Batch too large"));
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let context = Arc::new(Context { rpc_server: rpc_server(), metrics: None, config: RpcConfig::default() });
+        tokio::spawn(accept_loop(listener, context));
+
+        let body = r#"{"jsonrpc":"2.0","method":"net_version","id":7}"#;
+        let request = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        // Two pipelined requests on one connection, the second closing it
+        stream.write_all(request.as_bytes()).await.unwrap();
+        stream.write_all(b"GET /health HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert_eq!(response.matches("HTTP/1.1 200 OK").count(), 2);
+        assert!(response.contains(r#""id":7"#));
+        assert!(response.contains("Connection: keep-alive"));
+        assert!(response.contains("\"healthy\""));
+        assert!(response.ends_with('}'));
+    }
+
+    #[tokio::test]
+    async fn test_websocket_subscription() {
+        use crate::events::{ChainEvent, EventBus};
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let bus = EventBus::new();
+        let mut server = RpcServer::new(Arc::new(RwLock::new(Blockchain::new())));
+        server.set_event_bus(bus.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let context = Arc::new(Context { rpc_server: Arc::new(server), metrics: None, config: RpcConfig::default() });
+        tokio::spawn(accept_loop(listener, context));
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/", addr)).await.unwrap();
+        let subscribe = r#"{"jsonrpc":"2.0","method":"eth_subscribe","params":["newPendingTransactions"],"id":1}"#;
+        socket.send(Message::Text(subscribe.to_string())).await.unwrap();
+        let reply: serde_json::Value = serde_json::from_str(&socket.next().await.unwrap().unwrap().into_text().unwrap()).unwrap();
+        let id = reply["result"].as_str().unwrap().to_string();
+
+        bus.publish(ChainEvent::PendingTransaction([9u8; 32]));
+        let notification: serde_json::Value = serde_json::from_str(&socket.next().await.unwrap().unwrap().into_text().unwrap()).unwrap();
+        assert_eq!(notification["method"], "eth_subscription");
+        assert_eq!(notification["params"]["subscription"], id.as_str());
+        assert_eq!(notification["params"]["result"], format!("0x{}", hex::encode([9u8; 32])));
+
+        // Plain methods are served on the same socket
+        socket.send(Message::Text(r#"{"jsonrpc":"2.0","method":"net_version","id":2}"#.to_string())).await.unwrap();
+        let reply: serde_json::Value = serde_json::from_str(&socket.next().await.unwrap().unwrap().into_text().unwrap()).unwrap();
+        assert_eq!(reply["result"], CHAIN_ID.to_string());
+    }
+}
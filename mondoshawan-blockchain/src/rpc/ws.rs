@@ -0,0 +1,281 @@
+//! WebSocket transport for the JSON-RPC API
+//!
+//! Serves every `RpcServer` method plus `eth_subscribe` / `eth_unsubscribe`,
+//! fed by the node's event bus. Topics and their optional second parameter:
+//!
+//! - `newHeads`: accepted block headers
+//! - `logs` (`{address, topics}` filter): one log per notification
+//! - `newPendingTransactions`: hashes of transactions entering a pool
+//! - `mds_dagTips`: DAG tips after each accepted block
+//! - `mds_crossShardStatus` (`{txHash}`): cross-shard status changes
+//! - `mds_stopLossTriggered` (`{address}`): triggered stop-loss orders
+
+use super::{
+    dispatch_payload, error_payload, header_to_json, log_to_json, parse_address, parse_call, parse_hash,
+    parse_log_filter, JsonRpcError, JsonRpcResponse, RpcServer,
+};
+use crate::config::RpcConfig;
+use crate::events::{ChainEvent, EventBus, LogFilter};
+use crate::types::{Address, Hash};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_tungstenite::tungstenite::protocol::{Role, WebSocketConfig};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// Most subscriptions open on one connection
+const MAX_SUBSCRIPTIONS: usize = 64;
+
+/// What a subscription listens to
+#[derive(Debug, Clone, PartialEq)]
+enum Topic {
+    NewHeads,
+    Logs(LogFilter),
+    NewPendingTransactions,
+    DagTips,
+    CrossShardStatus(Option<Hash>),
+    StopLossTriggered(Option<Address>),
+}
+
+fn invalid_params(message: String) -> JsonRpcError {
+    JsonRpcError {
+        code: -32602,
+        message,
+        data: None,
+    }
+}
+
+impl Topic {
+    /// Parse `eth_subscribe` params: `[topic, options?]`
+    fn parse(params: Option<&Value>) -> Result<Self, JsonRpcError> {
+        let params = params.and_then(Value::as_array)
+            .ok_or_else(|| invalid_params("Expected [topic, options?]".to_string()))?;
+        let name = params.first().and_then(Value::as_str)
+            .ok_or_else(|| invalid_params("Missing subscription topic".to_string()))?;
+        let options = params.get(1);
+        let option = |key: &str| options.and_then(|options| options.get(key)).and_then(Value::as_str);
+        Ok(match name {
+            "newHeads" => Topic::NewHeads,
+            "logs" => Topic::Logs(parse_log_filter(options)?),
+            "newPendingTransactions" => Topic::NewPendingTransactions,
+            "mds_dagTips" => Topic::DagTips,
+            "mds_crossShardStatus" => Topic::CrossShardStatus(option("txHash").map(parse_hash).transpose()?),
+            "mds_stopLossTriggered" => Topic::StopLossTriggered(option("address").map(parse_address).transpose()?),
+            other => return Err(invalid_params(format!("Unknown subscription topic: {}", other))),
+        })
+    }
+
+    /// Notification results an event produces for this topic
+    fn results(&self, event: &ChainEvent) -> Vec<Value> {
+        match (self, event) {
+            (Topic::NewHeads, ChainEvent::NewHead { hash, header, transaction_count }) => {
+                vec![header_to_json(hash, header, *transaction_count)]
+            }
+            (Topic::Logs(filter), ChainEvent::Logs(logs)) => {
+                logs.iter().filter(|log| filter.matches(log)).map(log_to_json).collect()
+            }
+            (Topic::NewPendingTransactions, ChainEvent::PendingTransaction(hash)) => {
+                vec![json!(format!("0x{}", hex::encode(hash)))]
+            }
+            (Topic::DagTips, ChainEvent::DagTips(tips)) => vec![json!({
+                "tips": tips.iter().map(|tip| format!("0x{}", hex::encode(tip))).collect::<Vec<_>>(),
+            })],
+            (Topic::CrossShardStatus(wanted), ChainEvent::CrossShard { tx_hash, source_shard, target_shard, status })
+                if wanted.is_none() || *wanted == Some(*tx_hash) => vec![json!({
+                    "txHash": format!("0x{}", hex::encode(tx_hash)),
+                    "sourceShard": source_shard,
+                    "targetShard": target_shard,
+                    "status": status,
+                })],
+            (
+                Topic::StopLossTriggered(wanted),
+                ChainEvent::StopLossTriggered { stop_loss_id, wallet_address, asset_pair, triggered_price, triggered_at },
+            ) if wanted.is_none() || *wanted == Some(*wallet_address) => vec![json!({
+                "stopLossId": format!("0x{}", hex::encode(stop_loss_id)),
+                "walletAddress": format!("0x{}", hex::encode(wallet_address)),
+                "assetPair": asset_pair,
+                "triggeredPrice": format!("0x{:x}", triggered_price),
+                "triggeredAt": triggered_at,
+            })],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// State of one WebSocket connection
+struct Connection<'a> {
+    rpc_server: &'a RpcServer,
+    api_key: Option<&'a str>,
+    client_ip: IpAddr,
+    subscriptions: Mutex<HashMap<String, Topic>>,
+}
+
+impl Connection<'_> {
+    async fn handle_call(&self, call: Value) -> Option<JsonRpcResponse> {
+        let (request, is_notification) = match parse_call(call) {
+            Ok(parsed) => parsed,
+            Err(response) => return Some(response),
+        };
+        let response = match request.method.as_str() {
+            "eth_subscribe" | "eth_unsubscribe" => {
+                let result = match self.rpc_server.admit(&request, self.api_key, Some(self.client_ip)).await {
+                    Err(error) => Err(error),
+                    Ok(()) if request.method == "eth_subscribe" => self.subscribe(request.params.as_ref()),
+                    Ok(()) => self.unsubscribe(request.params.as_ref()),
+                };
+                let (result, error) = match result {
+                    Ok(value) => (Some(value), None),
+                    Err(error) => (None, Some(error)),
+                };
+                JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result,
+                    error,
+                    id: request.id,
+                }
+            }
+            _ => self.rpc_server.handle_request(request, self.api_key, Some(self.client_ip)).await,
+        };
+        (!is_notification).then_some(response)
+    }
+
+    fn subscribe(&self, params: Option<&Value>) -> Result<Value, JsonRpcError> {
+        if self.rpc_server.event_bus().is_none() {
+            return Err(JsonRpcError {
+                code: -32603,
+                message: "Subscriptions are not available on this node".to_string(),
+                data: None,
+            });
+        }
+        let topic = Topic::parse(params)?;
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return Err(JsonRpcError {
+                code: -32005,
+                message: format!("Too many subscriptions (max {})", MAX_SUBSCRIPTIONS),
+                data: None,
+            });
+        }
+        let id = format!("0x{}", hex::encode(rand::random::<[u8; 16]>()));
+        subscriptions.insert(id.clone(), topic);
+        Ok(Value::String(id))
+    }
+
+    fn unsubscribe(&self, params: Option<&Value>) -> Result<Value, JsonRpcError> {
+        let id = params.and_then(Value::as_array)
+            .and_then(|params| params.first())
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid_params("Expected [subscription id]".to_string()))?;
+        Ok(Value::Bool(self.subscriptions.lock().unwrap().remove(id).is_some()))
+    }
+
+    /// `eth_subscription` notifications an event produces
+    fn notifications(&self, event: &ChainEvent) -> Vec<String> {
+        let subscriptions = self.subscriptions.lock().unwrap();
+        subscriptions.iter()
+            .flat_map(|(id, topic)| topic.results(event).into_iter().map(move |result| json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": {
+                    "subscription": id,
+                    "result": result,
+                },
+            }).to_string()))
+            .collect()
+    }
+}
+
+async fn next_event(events: &mut Option<broadcast::Receiver<ChainEvent>>) -> Result<ChainEvent, RecvError> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Serve JSON-RPC and subscriptions on an upgraded connection
+pub(super) async fn serve_socket(
+    stream: TcpStream,
+    client_ip: IpAddr,
+    api_key: Option<String>,
+    rpc_server: Arc<RpcServer>,
+    config: &RpcConfig,
+) {
+    let mut socket_config = WebSocketConfig::default();
+    socket_config.max_message_size = Some(config.max_body_size);
+    socket_config.max_frame_size = Some(config.max_body_size);
+    let socket = WebSocketStream::from_raw_socket(stream, Role::Server, Some(socket_config)).await;
+    let (mut sink, mut messages) = socket.split();
+    let mut events = rpc_server.event_bus().map(EventBus::subscribe);
+    let connection = Connection {
+        rpc_server: &rpc_server,
+        api_key: api_key.as_deref(),
+        client_ip,
+        subscriptions: Mutex::new(HashMap::new()),
+    };
+
+    loop {
+        tokio::select! {
+            message = messages.next() => {
+                let payload = match message {
+                    Some(Ok(Message::Text(text))) => text.into_bytes(),
+                    Some(Ok(Message::Binary(data))) => data,
+                    // Pings are answered by the WebSocket layer
+                    Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => continue,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                };
+                let reply = if rpc_server.check_request_size(payload.len(), Some(client_ip)).await {
+                    dispatch_payload(&payload, config.max_batch_size, |call| connection.handle_call(call)).await
+                } else {
+                    Some(error_payload(-32600, "Request too large"))
+                };
+                if let Some(reply) = reply {
+                    if sink.send(Message::Text(reply)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            event = next_event(&mut events) => match event {
+                Ok(event) => {
+                    for notification in connection.notifications(&event) {
+                        if sink.send(Message::Text(notification)).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    eprintln!("⚠️  WebSocket client {} fell behind; {} event(s) dropped", client_ip, missed);
+                }
+                Err(RecvError::Closed) => events = None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_parsing_and_matching() {
+        let hash = [7u8; 32];
+        let topic = Topic::parse(Some(&json!(["mds_crossShardStatus", {"txHash": format!("0x{}", hex::encode(hash))}]))).unwrap();
+        assert_eq!(topic, Topic::CrossShardStatus(Some(hash)));
+        let event = |tx_hash| ChainEvent::CrossShard {
+            tx_hash,
+            source_shard: 0,
+            target_shard: 1,
+            status: crate::sharding::CrossShardStatus::Locked,
+        };
+        assert_eq!(topic.results(&event(hash))[0]["status"], "Locked");
+        assert!(topic.results(&event([8u8; 32])).is_empty());
+        assert!(topic.results(&ChainEvent::PendingTransaction(hash)).is_empty());
+
+        assert!(Topic::parse(Some(&json!(["syncing"]))).is_err());
+        assert!(Topic::parse(Some(&json!(["logs", {"address": "0x12"}]))).is_err());
+    }
+}
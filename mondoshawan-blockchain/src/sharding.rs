@@ -1,10 +1,28 @@
 //! Sharding implementation
 //! 
 //! Implements horizontal sharding for blockchain scalability.
-//! Supports transaction routing, cross-shard transactions, and shard synchronization.
+//! Each shard owns its account state partition and its own block DAG; global
+//! Stream B blocks act as beacon blocks committing every shard's tip and state
+//! root. Supports transaction routing, cross-shard transactions, and nodes that
+//! validate only a subset of shards.
 
-use crate::blockchain::{Blockchain, Block, Transaction};
+pub mod beacon;
+pub mod messages;
+pub mod rebalance;
+pub mod receipts;
+pub mod reshard;
+pub mod ring;
+
+use crate::blockchain::{Blockchain, Block, BlockHeader, BlockMeta, Transaction};
+use crate::storage::Database;
 use crate::types::{Address, Hash, StreamType};
+use beacon::{BeaconChain, BeaconRecord, ShardCommitment};
+use messages::{CallOutcome, CallPayload, OutcomeRecord, OutcomeStatus};
+use rebalance::{LoadWindow, RebalanceConfig, RebalanceProposal, ShardLoad};
+use receipts::{BalanceEffect, CrossShardReceipt, ReceiptProof, ReceiptRecord, ReceiptStatus};
+use reshard::{AccountMigration, AccountMove, ReshardPlan, ReshardReport};
+use ring::HashRing;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -13,6 +31,15 @@ use tokio::sync::RwLock;
 /// When limit is reached, oldest transactions are evicted (FIFO)
 pub const MAX_SHARD_TX_POOL_SIZE: usize = 50_000; // 50k transactions per shard max
 
+/// Seconds between rounds of the cross-shard receipt protocol
+pub const CROSS_SHARD_ROUND_SECS: u64 = 1;
+
+/// Assumed shard block interval (one Stream B block) until a shard has mined enough blocks to measure
+pub const DEFAULT_SHARD_BLOCK_SECS: f64 = 1.0;
+
+/// Recent blocks used to measure a shard's block interval
+const BLOCK_RATE_WINDOW: usize = 20;
+
 /// Shard configuration
 #[derive(Debug, Clone)]
 pub struct ShardConfig {
@@ -22,37 +49,143 @@ pub struct ShardConfig {
 }
 
 /// Assignment strategy for shards
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum AssignmentStrategy {
+    /// Hash ring with virtual nodes: changing the shard count moves ~1/n of accounts
     ConsistentHashing,
+    /// Address hash modulo shard count
     RoundRobin,
+    /// Leading address bytes modulo shard count
     AddressBased,
 }
 
+/// Sharding parameters a database was created with
+///
+/// Every node of a network must run with the same values: they decide which
+/// shard owns an account and when a reshard activates. The shard count is the
+/// genesis count; later reshards change the ring, not this record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardGenesis {
+    pub shard_count: usize,
+    pub assignment_strategy: AssignmentStrategy,
+    pub virtual_nodes: usize,
+    pub enable_cross_shard: bool,
+    pub epoch_length: u64,
+}
+
+impl ShardGenesis {
+    /// First parameter that differs from `other`, as a message
+    pub fn mismatch(&self, other: &ShardGenesis) -> Option<String> {
+        if self.shard_count != other.shard_count {
+            Some(format!("shard_count is {}, genesis has {}", self.shard_count, other.shard_count))
+        } else if self.assignment_strategy != other.assignment_strategy {
+            Some(format!("assignment_strategy is {:?}, genesis has {:?}", self.assignment_strategy, other.assignment_strategy))
+        } else if self.virtual_nodes != other.virtual_nodes {
+            Some(format!("virtual_nodes is {}, genesis has {}", self.virtual_nodes, other.virtual_nodes))
+        } else if self.enable_cross_shard != other.enable_cross_shard {
+            Some(format!("enable_cross_shard is {}, genesis has {}", self.enable_cross_shard, other.enable_cross_shard))
+        } else if self.epoch_length != other.epoch_length {
+            Some(format!("epoch_length is {}, genesis has {}", self.epoch_length, other.epoch_length))
+        } else {
+            None
+        }
+    }
+}
+
 /// Cross-shard transaction status
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CrossShardStatus {
+    /// Queued on the source shard
     Pending,
+    /// Funds locked on the source shard, receipt emitted
+    Locked,
+    /// Receipt committed on the target shard
     Committed,
+    /// Receipt timed out and was refunded on the source shard
+    Refunded,
+    /// Rejected before locking (e.g. insufficient balance)
     Failed,
 }
 
 /// Cross-shard transaction
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrossShardTransaction {
     pub tx: Transaction,
     pub source_shard: usize,
     pub target_shard: usize,
     pub status: CrossShardStatus,
     pub id: Hash,
+    /// Contract on the source shard notified of a call's outcome
+    pub callback: Option<Address>,
+}
+
+/// Fees earned by a shard, paid to the beacon block that next commits it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardFees {
+    /// Earned but not yet paid out
+    pub pending: u128,
+    /// Earned over the shard's lifetime
+    pub collected: u128,
+    /// Lifetime share of cross-shard fees earned as source shard
+    pub cross_shard_source: u128,
+    /// Lifetime share of cross-shard fees earned as target shard
+    pub cross_shard_target: u128,
+}
+
+impl ShardFees {
+    fn earn(&mut self, amount: u128) {
+        self.pending = self.pending.saturating_add(amount);
+        self.collected = self.collected.saturating_add(amount);
+    }
+}
+
+/// Expected confirmation time of a transaction between two shards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyEstimate {
+    pub source_shard: usize,
+    pub target_shard: usize,
+    pub cross_shard: bool,
+    /// Measured seconds between recent blocks (None = too few blocks yet)
+    pub source_block_interval: Option<f64>,
+    pub target_block_interval: Option<f64>,
+    /// Transactions waiting ahead in the source shard's pool
+    pub source_pool_depth: usize,
+    /// Seconds until the recipient is credited in a committed target block
+    pub confirmation_secs: f64,
+    /// Seconds until a contract call's outcome is back on the source shard
+    pub callback_secs: f64,
 }
 
 /// Shard manager
 pub struct ShardManager {
     config: ShardConfig,
-    shards: Vec<Arc<RwLock<Shard>>>,
+    // Replaced as a whole when a reshard activates
+    shards: std::sync::RwLock<Vec<Arc<RwLock<Shard>>>>,
+    ring: std::sync::RwLock<HashRing>,
+    reshard_plan: std::sync::RwLock<Option<ReshardPlan>>,
+    epoch_length: u64,
+    // Transaction arrivals for load measurement
+    load_window: std::sync::Mutex<LoadWindow>,
+    rebalance_config: RebalanceConfig,
+    last_rebalance: std::sync::RwLock<Option<RebalanceProposal>>,
     cross_shard_txs: Arc<RwLock<HashMap<Hash, CrossShardTransaction>>>,
-    round_robin_counter: Arc<RwLock<usize>>,
+    receipts: Arc<RwLock<HashMap<Hash, ReceiptRecord>>>,
+    // Outcomes of cross-shard contract calls, keyed by call transaction
+    call_outcomes: Arc<RwLock<HashMap<Hash, OutcomeRecord>>>,
+    // Fee pools by shard id
+    fees: std::sync::Mutex<HashMap<usize, ShardFees>>,
+    // Journal for the receipt protocol (None = in-memory only)
+    database: Option<Arc<Database>>,
+    receipt_timeout_secs: u64,
+    // Shard commitments from accepted beacon blocks
+    beacon: Arc<RwLock<BeaconChain>>,
+    // Shards this node validates and produces blocks for (None = all)
+    tracked_shards: Option<HashSet<usize>>,
+    // Pending transactions and cross-shard status changes are announced here
+    events: Option<crate::events::EventBus>,
+    // Ages shard pool transactions that keep being deferred
+    deferrals: std::sync::Mutex<crate::mining::assembly::DeferralTracker>,
 }
 
 /// Individual shard
@@ -68,7 +201,8 @@ impl Shard {
     pub fn new(id: usize) -> Self {
         Self {
             id,
-            blockchain: Arc::new(RwLock::new(Blockchain::new())),
+            // Contracts live on their shard's partition
+            blockchain: Arc::new(RwLock::new(Blockchain::with_evm(true))),
             transaction_pool: Vec::new(),
             cross_shard_outgoing: Vec::new(),
             cross_shard_incoming: Vec::new(),
@@ -96,6 +230,13 @@ impl Shard {
             self.transaction_pool.drain(..count).collect()
         }
     }
+
+    /// Put transactions back at the front of the pool (skipped during block assembly)
+    pub fn requeue_transactions(&mut self, txs: Vec<Transaction>) {
+        let room = MAX_SHARD_TX_POOL_SIZE.saturating_sub(self.transaction_pool.len());
+        let requeued: Vec<Transaction> = txs.into_iter().take(room).collect();
+        self.transaction_pool.splice(0..0, requeued);
+    }
 }
 
 impl ShardManager {
@@ -110,88 +251,494 @@ impl ShardManager {
             shards.push(Arc::new(RwLock::new(Shard::new(i))));
         }
         
+        let ring = HashRing::new(config.shard_count, ring::DEFAULT_VIRTUAL_NODES);
+        
         Self {
             config,
-            shards,
+            shards: std::sync::RwLock::new(shards),
+            ring: std::sync::RwLock::new(ring),
+            reshard_plan: std::sync::RwLock::new(None),
+            epoch_length: reshard::DEFAULT_EPOCH_LENGTH,
+            load_window: std::sync::Mutex::new(LoadWindow::new()),
+            rebalance_config: RebalanceConfig::default(),
+            last_rebalance: std::sync::RwLock::new(None),
             cross_shard_txs: Arc::new(RwLock::new(HashMap::new())),
-            round_robin_counter: Arc::new(RwLock::new(0)),
+            receipts: Arc::new(RwLock::new(HashMap::new())),
+            call_outcomes: Arc::new(RwLock::new(HashMap::new())),
+            fees: std::sync::Mutex::new(HashMap::new()),
+            database: None,
+            receipt_timeout_secs: receipts::DEFAULT_RECEIPT_TIMEOUT_SECS,
+            beacon: Arc::new(RwLock::new(BeaconChain::new())),
+            tracked_shards: None,
+            events: None,
+            deferrals: std::sync::Mutex::new(Default::default()),
         }
     }
 
-    /// Add a transaction to the appropriate shard
-    pub async fn add_transaction(&self, tx: Transaction) -> crate::error::BlockchainResult<()> {
-        let from_shard = self.get_shard_for_address(&tx.from);
-        let to_shard = if tx.to != [0u8; 20] {
-            self.get_shard_for_address(&tx.to)
-        } else {
-            from_shard // Contract deployment goes to sender's shard
-        };
+    /// Create a shard manager that journals cross-shard transfers to storage
+    ///
+    /// Recovers from the journal: restores each shard's blocks and account
+    /// state, replays balance effects that were journaled but not confirmed as
+    /// applied, re-queues cross-shard transactions that were not locked yet and
+    /// refills the shard pools.
+    pub fn with_storage(config: ShardConfig, database: Arc<Database>) -> crate::error::BlockchainResult<Self> {
+        use crate::storage::CrossShardStore;
         
-        let tx_hash = tx.hash;
+        let mut manager = Self::new(config);
+        let store = CrossShardStore::new(&database);
         
-        // Check if this is a cross-shard transaction
-        if from_shard != to_shard && self.config.enable_cross_shard {
-            let tx_clone = tx.clone();
-            
-            // Create cross-shard transaction
-            let cross_tx = CrossShardTransaction {
-                tx: tx_clone.clone(),
-                source_shard: from_shard,
-                target_shard: to_shard,
-                status: CrossShardStatus::Pending,
-                id: tx_hash,
-            };
-            
-            // Store cross-shard transaction
-            {
-                let mut cross_txs = self.cross_shard_txs.write().await;
-                cross_txs.insert(tx_hash, cross_tx);
+        // A completed reshard overrides the configured shard count
+        if let Some(ring) = store.get_ring()? {
+            manager.ring = std::sync::RwLock::new(ring);
+        }
+        manager.reshard_plan = std::sync::RwLock::new(store.get_reshard_plan()?);
+        let shard_count = manager.shard_count();
+        
+        let mut chains = Vec::with_capacity(shard_count);
+        for shard_id in 0..shard_count {
+            let mut blockchain = Blockchain::with_evm(true);
+            for block in store.get_blocks(shard_id)? {
+                blockchain.restore_block(block)?;
             }
-            
-            // Add to source shard (for validation)
-            {
-                let mut shard = self.shards[from_shard].write().await;
-                shard.add_transaction(tx_clone);
-                shard.cross_shard_outgoing.push(tx_hash);
+            for (address, balance, nonce) in store.get_accounts(shard_id)? {
+                blockchain.set_balance(address, balance)?;
+                blockchain.set_nonce(address, nonce)?;
             }
-            
-            // Mark in target shard
-            {
-                let mut shard = self.shards[to_shard].write().await;
-                shard.cross_shard_incoming.push(tx_hash);
+            for (address, code) in store.get_contracts(shard_id)? {
+                blockchain.set_contract_code(address, code);
+            }
+            chains.push(blockchain);
+        }
+        
+        let mut records = HashMap::new();
+        for mut record in store.get_receipts()? {
+            if let Some(effect) = record.pending_effect.take() {
+                if let Some(blockchain) = chains.get_mut(effect.shard) {
+                    blockchain.set_balance(effect.address, effect.balance_after)?;
+                    store.put_account(effect.shard, &effect.address, effect.balance_after, blockchain.get_nonce(effect.address))?;
+                }
+                store.put_receipt(&record)?;
+            }
+            records.insert(record.receipt.tx_hash, record);
+        }
+        
+        let mut outcomes = HashMap::new();
+        for mut record in store.get_outcomes()? {
+            if let Some(effect) = record.pending_effect.take() {
+                if let Some(blockchain) = chains.get_mut(effect.shard) {
+                    blockchain.set_balance(effect.address, effect.balance_after)?;
+                    store.put_account(effect.shard, &effect.address, effect.balance_after, blockchain.get_nonce(effect.address))?;
+                }
+                store.put_outcome(&record)?;
+            }
+            outcomes.insert(record.outcome.tx_hash, record);
+        }
+        
+        // Pooled transactions go back to their sender's shard; mined ones are dropped
+        let mut pooled = Vec::new();
+        let mut stale = Vec::new();
+        for tx in store.get_pool_transactions()? {
+            let shard_id = manager.get_shard_for_address(&tx.from);
+            match chains.get(shard_id) {
+                Some(blockchain) if tx.nonce >= blockchain.get_nonce(tx.from) => pooled.push((shard_id, tx)),
+                _ => stale.push(tx.hash),
+            }
+        }
+        pooled.sort_by(|a, b| a.1.from.cmp(&b.1.from).then(a.1.nonce.cmp(&b.1.nonce)));
+        store.delete_pool_transactions(&stale)?;
+        
+        let mut shards = Vec::with_capacity(chains.len());
+        for (shard_id, blockchain) in chains.into_iter().enumerate() {
+            let mut shard = Shard::new(shard_id);
+            shard.blockchain = Arc::new(RwLock::new(blockchain));
+            shards.push(shard);
+        }
+        
+        let mut cross_txs = HashMap::new();
+        for cross_tx in store.get_transactions()? {
+            if let Some(shard) = shards.get_mut(cross_tx.source_shard) {
+                shard.cross_shard_outgoing.push(cross_tx.id);
+                if cross_tx.status == CrossShardStatus::Pending {
+                    shard.add_transaction(cross_tx.tx.clone());
+                }
+            }
+            if let Some(shard) = shards.get_mut(cross_tx.target_shard) {
+                shard.cross_shard_incoming.push(cross_tx.id);
+            }
+            cross_txs.insert(cross_tx.id, cross_tx);
+        }
+        for (shard_id, tx) in pooled {
+            shards[shard_id].add_transaction(tx);
+        }
+        database.flush()?;
+        
+        manager.shards = std::sync::RwLock::new(shards.into_iter().map(|shard| Arc::new(RwLock::new(shard))).collect());
+        manager.cross_shard_txs = Arc::new(RwLock::new(cross_txs));
+        manager.receipts = Arc::new(RwLock::new(records));
+        manager.call_outcomes = Arc::new(RwLock::new(outcomes));
+        manager.fees = std::sync::Mutex::new(store.get_fees()?.into_iter().collect());
+        manager.database = Some(database);
+        Ok(manager)
+    }
+
+    /// Set how long a receipt may wait for its target shard before it is refunded
+    pub fn with_receipt_timeout(mut self, timeout_secs: u64) -> Self {
+        self.receipt_timeout_secs = timeout_secs;
+        self
+    }
+
+    /// Announce pending transactions and cross-shard status changes on `events`
+    pub fn with_event_bus(mut self, events: crate::events::EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Validate and produce blocks only for the given shards
+    ///
+    /// Transactions sent from untracked shards are rejected, and untracked
+    /// shards are followed through beacon commitments only. Ids that name no
+    /// shard are refused by `check_genesis`.
+    pub fn with_tracked_shards(mut self, shard_ids: Vec<usize>) -> Self {
+        self.tracked_shards = Some(shard_ids.into_iter().collect());
+        self
+    }
+
+    /// Whether this node validates the shard
+    pub fn is_tracked(&self, shard_id: usize) -> bool {
+        match &self.tracked_shards {
+            Some(tracked) => tracked.contains(&shard_id),
+            None => shard_id < self.shard_count(),
+        }
+    }
+
+    /// Shards this node validates, in id order
+    pub fn tracked_shards(&self) -> Vec<usize> {
+        (0..self.shard_count()).filter(|shard_id| self.is_tracked(*shard_id)).collect()
+    }
+
+    /// Write account snapshots of a shard's state partition (no-op without
+    /// storage; flushed by the next `journal` call)
+    fn journal_accounts(
+        &self,
+        shard_id: usize,
+        blockchain: &Blockchain,
+        addresses: &[Address],
+    ) -> crate::error::BlockchainResult<()> {
+        use crate::storage::CrossShardStore;
+        
+        if let Some(database) = &self.database {
+            let store = CrossShardStore::new(database);
+            for address in addresses {
+                store.put_account(shard_id, address, blockchain.get_balance(*address), blockchain.get_nonce(*address))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the contract code of a shard's state partition after a block that
+    /// deploys contracts (shard blocks are restored without re-execution)
+    fn journal_contracts(&self, shard_id: usize, blockchain: &Blockchain, block: &Block) -> crate::error::BlockchainResult<()> {
+        use crate::storage::CrossShardStore;
+        
+        let deploys = block.transactions.iter().any(|tx| tx.to == [0u8; 20] && !tx.data.is_empty());
+        if let (Some(database), Some(executor), true) = (&self.database, blockchain.evm_executor(), deploys) {
+            let store = CrossShardStore::new(database);
+            for (address, code) in executor.state().contracts() {
+                store.put_contract(shard_id, &address, &code)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Credit shard fee pools and journal them (flushed by the next `journal` call)
+    ///
+    /// Each credit is (shard, amount, cross-shard role: Some(true) = source,
+    /// Some(false) = target, None = fees of the shard's own blocks).
+    fn earn_fees(&self, credits: &[(usize, u128, Option<bool>)]) -> crate::error::BlockchainResult<()> {
+        use crate::storage::CrossShardStore;
+        
+        let mut pools = self.fees.lock().expect("fee pool lock poisoned");
+        let mut updated: HashMap<usize, ShardFees> = HashMap::new();
+        for (shard_id, amount, role) in credits {
+            if *amount == 0 {
+                continue;
+            }
+            let pool = updated.entry(*shard_id)
+                .or_insert_with(|| pools.get(shard_id).cloned().unwrap_or_default());
+            pool.earn(*amount);
+            match role {
+                Some(true) => pool.cross_shard_source = pool.cross_shard_source.saturating_add(*amount),
+                Some(false) => pool.cross_shard_target = pool.cross_shard_target.saturating_add(*amount),
+                None => {}
+            }
+        }
+        if let Some(database) = &self.database {
+            let store = CrossShardStore::new(database);
+            for (shard_id, pool) in &updated {
+                store.put_fees(*shard_id, pool)?;
+            }
+        }
+        pools.extend(updated);
+        Ok(())
+    }
+
+    /// Remove mined or dropped transactions from the pool journal
+    fn forget_pooled(&self, hashes: &[Hash]) -> crate::error::BlockchainResult<()> {
+        match &self.database {
+            Some(database) if !hashes.is_empty() => {
+                crate::storage::CrossShardStore::new(database).delete_pool_transactions(hashes)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sharding parameters this manager runs with
+    pub fn genesis(&self) -> ShardGenesis {
+        ShardGenesis {
+            shard_count: self.config.shard_count,
+            assignment_strategy: self.config.assignment_strategy.clone(),
+            virtual_nodes: ring::DEFAULT_VIRTUAL_NODES,
+            enable_cross_shard: self.config.enable_cross_shard,
+            epoch_length: self.epoch_length,
+        }
+    }
+
+    /// Check that the tracked shards exist, then the sharding parameters
+    /// against those the database was created with, recording them on first
+    /// start (no storage: only the tracked shards are checked)
+    pub fn check_genesis(&self) -> crate::error::BlockchainResult<()> {
+        use crate::storage::CrossShardStore;
+        
+        if let Some(tracked) = &self.tracked_shards {
+            let mut unknown: Vec<usize> = tracked.iter().copied().filter(|shard_id| *shard_id >= self.shard_count()).collect();
+            unknown.sort_unstable();
+            if tracked.is_empty() || !unknown.is_empty() {
+                return Err(crate::error::BlockchainError::Config(
+                    format!("Tracked shards {:?} do not exist ({} shards)", unknown, self.shard_count())
+                ));
+            }
+        }
+        let database = match &self.database {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let store = CrossShardStore::new(database);
+        let genesis = self.genesis();
+        match store.get_genesis()? {
+            Some(stored) => match genesis.mismatch(&stored) {
+                Some(mismatch) => Err(crate::error::BlockchainError::Validation(
+                    format!("Sharding config does not match genesis: {}", mismatch)
+                )),
+                None => Ok(()),
+            },
+            None => {
+                store.put_genesis(&genesis)?;
+                database.flush()
+            }
+        }
+    }
+
+    /// Fee pool of a shard
+    pub fn get_shard_fees(&self, shard_id: usize) -> ShardFees {
+        self.fees.lock().expect("fee pool lock poisoned").get(&shard_id).cloned().unwrap_or_default()
+    }
+
+    /// Write cross-shard state to the journal and flush it (no-op without storage)
+    ///
+    /// Every journaled status change is announced on the event bus.
+    fn journal(
+        &self,
+        cross_txs: &[&CrossShardTransaction],
+        records: &[&ReceiptRecord],
+        block: Option<(usize, &Block)>,
+    ) -> crate::error::BlockchainResult<()> {
+        use crate::storage::CrossShardStore;
+        
+        if let Some(events) = &self.events {
+            for cross_tx in cross_txs {
+                events.publish(crate::events::ChainEvent::CrossShard {
+                    tx_hash: cross_tx.id,
+                    source_shard: cross_tx.source_shard,
+                    target_shard: cross_tx.target_shard,
+                    status: cross_tx.status.clone(),
+                });
+            }
+        }
+        let database = match &self.database {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let store = CrossShardStore::new(database);
+        if let Some((shard_id, block)) = block {
+            store.put_block(shard_id, block)?;
+        }
+        for cross_tx in cross_txs {
+            store.put_transaction(cross_tx)?;
+        }
+        for record in records {
+            store.put_receipt(record)?;
+        }
+        database.flush()
+    }
+
+    /// Apply journaled balance effects, then mark them applied in the journal
+    fn apply_effects(
+        &self,
+        blockchain: &mut Blockchain,
+        records: &mut [ReceiptRecord],
+    ) -> crate::error::BlockchainResult<()> {
+        for record in records.iter_mut() {
+            if let Some(effect) = record.pending_effect.take() {
+                blockchain.set_balance(effect.address, effect.balance_after)?;
+                self.journal_accounts(effect.shard, blockchain, &[effect.address])?;
             }
+        }
+        let refs: Vec<&ReceiptRecord> = records.iter().collect();
+        self.journal(&[], &refs, None)
+    }
+
+    /// Add a transaction to the appropriate shard
+    pub async fn add_transaction(&self, tx: Transaction) -> crate::error::BlockchainResult<()> {
+        let tx_hash = tx.hash;
+        let (from_shard, to_shard) = self.route_transaction(&tx);
+        
+        if !self.is_tracked(from_shard) {
+            return Err(crate::error::BlockchainError::InvalidTransaction(
+                format!("Shard {} is not tracked by this node", from_shard)
+            ));
+        }
+        self.record_arrival(from_shard, &tx.from, from_shard != to_shard && self.config.enable_cross_shard);
+        
+        // Check if this is a cross-shard transaction (a call with data to
+        // another shard becomes an asynchronous contract call)
+        if from_shard != to_shard && self.config.enable_cross_shard {
+            self.queue_cross_shard(tx, from_shard, to_shard, None).await?;
         } else {
-            // Same-shard transaction
-            let mut shard = self.shards[from_shard].write().await;
+            // Same-shard transaction (journaled so the pool survives a restart)
+            if let Some(database) = &self.database {
+                crate::storage::CrossShardStore::new(database).put_pool_transaction(&tx)?;
+            }
+            let shard_lock = self.shard_at(from_shard);
+            let mut shard = shard_lock.write().await;
             // add_transaction enforces MAX_SHARD_TX_POOL_SIZE with FIFO eviction
             shard.add_transaction(tx);
         }
+        if let Some(events) = &self.events {
+            events.publish(crate::events::ChainEvent::PendingTransaction(tx_hash));
+        }
+        
+        Ok(())
+    }
+
+    /// Journal a cross-shard transaction and queue it on its source shard
+    async fn queue_cross_shard(
+        &self,
+        tx: Transaction,
+        from_shard: usize,
+        to_shard: usize,
+        callback: Option<Address>,
+    ) -> crate::error::BlockchainResult<()> {
+        let tx_hash = tx.hash;
+        let cross_tx = CrossShardTransaction {
+            tx: tx.clone(),
+            source_shard: from_shard,
+            target_shard: to_shard,
+            status: CrossShardStatus::Pending,
+            id: tx_hash,
+            callback,
+        };
+        
+        // Store cross-shard transaction
+        {
+            let mut cross_txs = self.cross_shard_txs.write().await;
+            self.journal(&[&cross_tx], &[], None)?;
+            cross_txs.insert(tx_hash, cross_tx);
+        }
+        
+        // Add to source shard (for validation)
+        {
+            let shard_lock = self.shard_at(from_shard);
+            let mut shard = shard_lock.write().await;
+            shard.add_transaction(tx);
+            shard.cross_shard_outgoing.push(tx_hash);
+        }
         
+        // Mark in target shard
+        {
+            let shard_lock = self.shard_at(to_shard);
+            let mut shard = shard_lock.write().await;
+            shard.cross_shard_incoming.push(tx_hash);
+        }
         Ok(())
     }
 
+    /// Emit a call from a contract to a contract on another shard
+    ///
+    /// The value is locked from the calling contract's balance. The outcome is
+    /// delivered to `callback` (a contract on the caller's shard) as
+    /// `onCrossShardResult(bytes32,bool,bytes)`. Returns the call's hash.
+    pub async fn emit_contract_call(
+        &self,
+        caller: Address,
+        callee: Address,
+        value: u128,
+        data: Vec<u8>,
+        gas_limit: u64,
+        callback: Option<Address>,
+    ) -> crate::error::BlockchainResult<Hash> {
+        let from_shard = self.get_shard_for_address(&caller);
+        let to_shard = self.get_shard_for_address(&callee);
+        if !self.is_tracked(from_shard) {
+            return Err(crate::error::BlockchainError::InvalidTransaction(
+                format!("Shard {} is not tracked by this node", from_shard)
+            ));
+        }
+        if from_shard == to_shard {
+            return Err(crate::error::BlockchainError::InvalidTransaction(
+                "Callee is on the caller's shard; call it directly".to_string()
+            ));
+        }
+        if !self.config.enable_cross_shard {
+            return Err(crate::error::BlockchainError::Config("Cross-shard transactions are disabled".to_string()));
+        }
+        
+        // Contracts have no keys: the emitted call is identified by the
+        // caller's nonce, which is bumped on its shard
+        let tx = {
+            let shard_lock = self.shard_at(from_shard);
+            let shard = shard_lock.read().await;
+            let mut blockchain = shard.blockchain.write().await;
+            let is_contract = blockchain.evm_executor()
+                .map(|executor| executor.state().is_contract(caller))
+                .unwrap_or(false);
+            if !is_contract {
+                return Err(crate::error::BlockchainError::InvalidTransaction(
+                    format!("0x{} is not a contract", hex::encode(caller))
+                ));
+            }
+            let nonce = blockchain.get_nonce(caller);
+            blockchain.set_nonce(caller, nonce + 1)?;
+            self.journal_accounts(from_shard, &blockchain, &[caller])?;
+            Transaction::with_data(caller, callee, value, 0, nonce, data, gas_limit)
+        };
+        let tx_hash = tx.hash;
+        self.record_arrival(from_shard, &caller, true);
+        self.queue_cross_shard(tx, from_shard, to_shard, callback).await?;
+        Ok(tx_hash)
+    }
+
     /// Get shard ID for an address
     pub fn get_shard_for_address(&self, address: &Address) -> usize {
+        let ring = self.ring.read().expect("shard ring lock poisoned");
+        self.assign(&ring, address)
+    }
+
+    /// Owner of an address under a ring (modulo strategies only use its shard count)
+    fn assign(&self, ring: &HashRing, address: &Address) -> usize {
         match self.config.assignment_strategy {
-            AssignmentStrategy::ConsistentHashing => {
-                // Use consistent hashing on address
-                let hash = blake3::hash(address);
-                let hash_bytes = hash.as_bytes();
-                let hash_value = u64::from_le_bytes([
-                    hash_bytes[0], hash_bytes[1], hash_bytes[2], hash_bytes[3],
-                    hash_bytes[4], hash_bytes[5], hash_bytes[6], hash_bytes[7],
-                ]);
-                (hash_value as usize) % self.config.shard_count
-            }
+            AssignmentStrategy::ConsistentHashing => ring.shard_for(address),
             AssignmentStrategy::RoundRobin => {
-                // Round-robin based on address hash
-                let hash = blake3::hash(address);
-                let hash_bytes = hash.as_bytes();
-                let hash_value = u64::from_le_bytes([
-                    hash_bytes[0], hash_bytes[1], hash_bytes[2], hash_bytes[3],
-                    hash_bytes[4], hash_bytes[5], hash_bytes[6], hash_bytes[7],
-                ]);
-                (hash_value as usize) % self.config.shard_count
+                (ring::address_position(address) as usize) % ring.shard_count()
             }
             AssignmentStrategy::AddressBased => {
                 // Route based on address bytes
@@ -199,152 +746,1487 @@ impl ShardManager {
                     address[0], address[1], address[2], address[3],
                     address[4], address[5], address[6], address[7],
                 ]);
-                (addr_value as usize) % self.config.shard_count
+                (addr_value as usize) % ring.shard_count()
             }
         }
     }
 
-    /// Route a transaction to determine target shard
-    fn route_transaction(&self, tx: &Transaction) -> usize {
-        self.get_shard_for_address(&tx.from)
+    /// Source and target shard of a transaction (contract deployments stay on
+    /// the sender's shard)
+    pub fn route_transaction(&self, tx: &Transaction) -> (usize, usize) {
+        let from_shard = self.get_shard_for_address(&tx.from);
+        let to_shard = if tx.to != [0u8; 20] {
+            self.get_shard_for_address(&tx.to)
+        } else {
+            from_shard
+        };
+        (from_shard, to_shard)
     }
 
     /// Get all shards
     pub async fn get_all_shards(&self) -> Vec<Arc<RwLock<Shard>>> {
-        self.shards.clone()
+        self.shard_list()
     }
 
     /// Get a specific shard
-    pub fn get_shard(&self, shard_id: usize) -> Option<&Arc<RwLock<Shard>>> {
-        self.shards.get(shard_id)
+    pub fn get_shard(&self, shard_id: usize) -> Option<Arc<RwLock<Shard>>> {
+        self.shards.read().expect("shard list lock poisoned").get(shard_id).cloned()
+    }
+
+    fn shard_list(&self) -> Vec<Arc<RwLock<Shard>>> {
+        self.shards.read().expect("shard list lock poisoned").clone()
+    }
+
+    /// Shard by id; callers pass ids from `get_shard_for_address` or checked ranges
+    fn shard_at(&self, shard_id: usize) -> Arc<RwLock<Shard>> {
+        self.get_shard(shard_id).expect("shard id out of range")
     }
 
     /// Get shard count
     pub fn shard_count(&self) -> usize {
-        self.config.shard_count
+        self.ring.read().expect("shard ring lock poisoned").shard_count()
     }
 
     /// Process cross-shard transaction
     /// 
-    /// This handles the two-phase commit for cross-shard transactions
+    /// Runs the receipt protocol for the transaction's shards right away: locks
+    /// the source shard's queued transfers, then commits receipts on the target.
     pub async fn process_cross_shard_transaction(
         &self,
         tx_hash: Hash,
     ) -> crate::error::BlockchainResult<()> {
-        let cross_tx = {
+        let shards = {
             let cross_txs = self.cross_shard_txs.read().await;
-            cross_txs.get(&tx_hash).cloned()
+            cross_txs.get(&tx_hash).map(|tx| (tx.source_shard, tx.target_shard))
         };
         
-        if let Some(mut cross_tx) = cross_tx {
-            // Phase 1: Validate on source shard
-            {
-                let shard = self.shards[cross_tx.source_shard].read().await;
-                let blockchain = shard.blockchain.read().await;
-                let balance = blockchain.get_balance(cross_tx.tx.from);
-                let total_cost = cross_tx.tx.value.saturating_add(cross_tx.tx.fee);
-                
-                if balance < total_cost {
-                    cross_tx.status = CrossShardStatus::Failed;
-                    let mut cross_txs = self.cross_shard_txs.write().await;
-                    cross_txs.insert(tx_hash, cross_tx);
-                    return Err(crate::error::BlockchainError::InvalidTransaction(
-                        "Insufficient balance for cross-shard transaction".to_string()
-                    ));
-                }
-            }
+        if let Some((source_shard, target_shard)) = shards {
+            let now = unix_now();
+            self.lock_outgoing(source_shard, now).await?;
             
-            // Phase 2: Execute on target shard
-            {
-                let shard = self.shards[cross_tx.target_shard].write().await;
-                let mut blockchain = shard.blockchain.write().await;
-                
-                // Deduct from sender (on source shard, handled separately)
-                // Add to receiver (on target shard)
-                if cross_tx.tx.to != [0u8; 20] {
-                    let current_balance = blockchain.get_balance(cross_tx.tx.to);
-                    blockchain.set_balance(cross_tx.tx.to, current_balance + cross_tx.tx.value)?;
-                }
+            if self.get_cross_shard_status(tx_hash).await == Some(CrossShardStatus::Failed) {
+                return Err(crate::error::BlockchainError::InvalidTransaction(
+                    "Insufficient balance for cross-shard transaction".to_string()
+                ));
             }
             
-            // Mark as committed
-            cross_tx.status = CrossShardStatus::Committed;
-            let mut cross_txs = self.cross_shard_txs.write().await;
-            cross_txs.insert(tx_hash, cross_tx);
+            self.commit_incoming(target_shard, now).await?;
         }
         
         Ok(())
     }
 
-    /// Get transactions for a shard (for mining)
-    pub async fn get_shard_transactions(&self, shard_id: usize, limit: usize) -> Vec<Transaction> {
-        if let Some(shard) = self.shards.get(shard_id) {
-            let shard = shard.read().await;
-            shard.get_transactions(limit)
-        } else {
-            Vec::new()
+    /// Run one round of the receipt protocol on every shard: lock queued
+    /// transfers, commit receipts on their targets, deliver call outcomes to
+    /// their callers, refund expired receipts
+    pub async fn process_cross_shard_round(&self, now: u64) -> crate::error::BlockchainResult<()> {
+        let tracked = self.tracked_shards();
+        for shard_id in &tracked {
+            self.lock_outgoing(*shard_id, now).await?;
         }
-    }
-
-    /// Remove transactions from a shard (after mining)
-    pub async fn remove_shard_transactions(&self, shard_id: usize, count: usize) -> Vec<Transaction> {
-        if let Some(shard) = self.shards.get(shard_id) {
-            let mut shard = shard.write().await;
-            shard.remove_transactions(count)
-        } else {
-            Vec::new()
+        for shard_id in &tracked {
+            self.commit_incoming(*shard_id, now).await?;
         }
-    }
-
-    /// Get cross-shard transaction status
-    pub async fn get_cross_shard_status(&self, tx_hash: Hash) -> Option<CrossShardStatus> {
-        let cross_txs = self.cross_shard_txs.read().await;
-        cross_txs.get(&tx_hash).map(|tx| tx.status.clone())
-    }
-
-    /// Synchronize shard state
-    /// 
-    /// Merges state from all shards into a unified view
-    pub async fn synchronize_shards(&self) -> crate::error::BlockchainResult<()> {
-        // For now, this is a placeholder
-        // In production, this would:
-        // 1. Collect state from all shards
-        // 2. Resolve conflicts
-        // 3. Merge balances
-        // 4. Update cross-shard transaction statuses
-        
+        for shard_id in &tracked {
+            self.deliver_outcomes(*shard_id, now).await?;
+        }
+        self.abort_expired(now).await?;
         Ok(())
     }
 
-    /// Get shard statistics
-    pub async fn get_shard_stats(&self, shard_id: usize) -> Option<ShardStats> {
-        if let Some(shard) = self.shards.get(shard_id) {
-            let shard = shard.read().await;
-            let blockchain = shard.blockchain.read().await;
+    /// Lock phase: debit senders of queued cross-shard transfers on the source
+    /// shard and commit their receipts in a new source-shard block
+    ///
+    /// Returns the receipts emitted. Transfers the sender cannot cover fail.
+    pub async fn lock_outgoing(&self, shard_id: usize, now: u64) -> crate::error::BlockchainResult<Vec<CrossShardReceipt>> {
+        let shard_lock = match self.get_shard(shard_id) {
+            Some(shard) if self.is_tracked(shard_id) => shard,
+            _ => return Ok(Vec::new()),
+        };
+        // Lock order: receipts, cross-shard transactions, shard, shard chain
+        let mut stored = self.receipts.write().await;
+        let mut cross_txs = self.cross_shard_txs.write().await;
+        let mut shard = shard_lock.write().await;
+        
+        let pending: Vec<Hash> = shard.cross_shard_outgoing.iter()
+            .filter(|hash| cross_txs.get(*hash).map(|tx| tx.status == CrossShardStatus::Pending).unwrap_or(false))
+            .copied()
+            .collect();
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let blockchain_lock = shard.blockchain.clone();
+        let mut blockchain = blockchain_lock.write().await;
+        
+        // Debit senders (running balances so one sender's transfers add up)
+        let mut balances: HashMap<Address, u128> = HashMap::new();
+        let mut receipts = Vec::new();
+        let mut failed = Vec::new();
+        for hash in &pending {
+            let cross_tx = &cross_txs[hash];
+            let tx = &cross_tx.tx;
+            let balance = balances.entry(tx.from).or_insert_with(|| blockchain.get_balance(tx.from));
+            let amount = tx.value.saturating_add(tx.fee);
+            if *balance < amount {
+                failed.push(*hash);
+                continue;
+            }
+            *balance -= amount;
+            receipts.push(CrossShardReceipt {
+                tx_hash: *hash,
+                source_shard: shard_id,
+                target_shard: cross_tx.target_shard,
+                from: tx.from,
+                to: tx.to,
+                value: tx.value,
+                fee: tx.fee,
+                locked_at: now,
+                expires_at: now.saturating_add(self.receipt_timeout_secs),
+                call: if tx.data.is_empty() {
+                    None
+                } else {
+                    Some(CallPayload {
+                        data: tx.data.clone(),
+                        gas_limit: tx.gas_limit,
+                        callback: cross_tx.callback,
+                    })
+                },
+            });
+        }
+        
+        // Commit the receipts root in a source-shard block
+        let mut block = None;
+        let mut records = Vec::new();
+        if !receipts.is_empty() {
+            let leaves: Vec<Hash> = receipts.iter().map(|receipt| receipt.hash()).collect();
+            let root = receipts::receipts_root(&leaves);
+            let parents: Vec<Hash> = blockchain.block_metas().last()
+                .map(|last| vec![last.hash])
+                .unwrap_or_default();
+            let number = blockchain.block_count() as u64;
+            let header = BlockHeader::new(parents.clone(), number, StreamType::StreamB, 0)
+                .with_receipts_root(root);
+            let receipt_block = Block::new(header, Vec::new(), parents);
+            blockchain.add_block(receipt_block.clone())?;
             
-            Some(ShardStats {
-                shard_id,
-                block_count: blockchain.get_blocks().len(),
-                transaction_pool_size: shard.transaction_pool.len(),
-                cross_shard_outgoing: shard.cross_shard_outgoing.len(),
-                cross_shard_incoming: shard.cross_shard_incoming.len(),
-            })
-        } else {
-            None
+            for (index, receipt) in receipts.iter().enumerate() {
+                records.push(ReceiptRecord {
+                    receipt: receipt.clone(),
+                    proof: ReceiptProof {
+                        source_block_hash: receipt_block.hash,
+                        root,
+                        steps: receipts::build_proof(&leaves, index),
+                    },
+                    status: ReceiptStatus::Locked,
+                    pending_effect: Some(BalanceEffect {
+                        shard: shard_id,
+                        address: receipt.from,
+                        balance_after: balances[&receipt.from],
+                    }),
+                });
+            }
+            block = Some(receipt_block);
         }
-    }
-
-    /// Get all shard statistics
-    pub async fn get_all_shard_stats(&self) -> Vec<ShardStats> {
-        let mut stats = Vec::new();
-        for i in 0..self.config.shard_count {
-            if let Some(stat) = self.get_shard_stats(i).await {
-                stats.push(stat);
+        
+        // Journal statuses, records and block before touching balances
+        let mut updated = Vec::with_capacity(pending.len());
+        for hash in receipts.iter().map(|receipt| &receipt.tx_hash) {
+            if let Some(mut cross_tx) = cross_txs.get(hash).cloned() {
+                cross_tx.status = CrossShardStatus::Locked;
+                updated.push(cross_tx);
             }
         }
-        stats
-    }
-    
+        for hash in &failed {
+            if let Some(mut cross_tx) = cross_txs.get(hash).cloned() {
+                cross_tx.status = CrossShardStatus::Failed;
+                updated.push(cross_tx);
+            }
+        }
+        {
+            let updated_refs: Vec<&CrossShardTransaction> = updated.iter().collect();
+            let record_refs: Vec<&ReceiptRecord> = records.iter().collect();
+            self.journal(&updated_refs, &record_refs, block.as_ref().map(|b| (shard_id, b)))?;
+        }
+        
+        // Locked and failed transfers leave the source shard's pool
+        let done: HashSet<Hash> = updated.iter().map(|cross_tx| cross_tx.id).collect();
+        shard.transaction_pool.retain(|tx| !done.contains(&tx.hash));
+        for cross_tx in updated {
+            cross_txs.insert(cross_tx.id, cross_tx);
+        }
+        
+        drop(cross_txs);
+        self.apply_effects(&mut blockchain, &mut records)?;
+        for record in records {
+            stored.insert(record.receipt.tx_hash, record);
+        }
+        
+        Ok(receipts)
+    }
+
+    /// Commit phase: credit recipients on the target shard for locked receipts
+    /// whose Merkle proof matches the receipts root in the source-shard block
+    ///
+    /// Receipts carrying a contract call execute it; the callee is credited
+    /// only if the call succeeds, and the outcomes are committed in a new
+    /// target-shard block for delivery back to the caller.
+    ///
+    /// Returns the transaction hashes committed. Receipts that fail verification
+    /// stay locked and are refunded when they expire.
+    pub async fn commit_incoming(&self, shard_id: usize, now: u64) -> crate::error::BlockchainResult<Vec<Hash>> {
+        let shard_lock = match self.get_shard(shard_id) {
+            Some(shard) if self.is_tracked(shard_id) => shard,
+            _ => return Ok(Vec::new()),
+        };
+        // Lock order: receipts, call outcomes, shard, cross-shard transactions
+        let mut stored = self.receipts.write().await;
+        let mut stored_outcomes = self.call_outcomes.write().await;
+        
+        let mut candidates: Vec<ReceiptRecord> = stored.values()
+            .filter(|record| record.status == ReceiptStatus::Locked
+                && record.receipt.target_shard == shard_id
+                && now < record.receipt.expires_at)
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+        candidates.sort_by(|a, b| a.receipt.locked_at.cmp(&b.receipt.locked_at)
+            .then(a.receipt.tx_hash.cmp(&b.receipt.tx_hash)));
+        
+        // Verify each proof against the source shard's block header
+        let mut verified = Vec::new();
+        for record in candidates {
+            if self.verify_receipt(&record).await {
+                verified.push(record);
+            }
+        }
+        if verified.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let shard = shard_lock.read().await;
+        let mut blockchain = shard.blockchain.write().await;
+        
+        // Execute contract calls; the value of a failed call goes back to the caller
+        let outcomes: Vec<CallOutcome> = verified.iter()
+            .filter_map(|record| record.receipt.call.as_ref()
+                .map(|call| execute_call(&blockchain, &record.receipt, call, now)))
+            .collect();
+        let failed: HashSet<Hash> = outcomes.iter()
+            .filter(|outcome| !outcome.success)
+            .map(|outcome| outcome.tx_hash)
+            .collect();
+        
+        let mut balances: HashMap<Address, u128> = HashMap::new();
+        for record in &verified {
+            let receipt = &record.receipt;
+            if receipt.to == [0u8; 20] || failed.contains(&receipt.tx_hash) {
+                continue;
+            }
+            let balance = balances.entry(receipt.to).or_insert_with(|| blockchain.get_balance(receipt.to));
+            *balance = balance.saturating_add(receipt.value);
+        }
+        for record in verified.iter_mut() {
+            record.status = ReceiptStatus::Committed;
+            record.pending_effect = if failed.contains(&record.receipt.tx_hash) {
+                None
+            } else {
+                balances.get(&record.receipt.to).map(|balance| BalanceEffect {
+                    shard: shard_id,
+                    address: record.receipt.to,
+                    balance_after: *balance,
+                })
+            };
+        }
+        
+        // Outcomes are journaled with the receipts (flushed by finish_receipts)
+        let outcome_records = if outcomes.is_empty() {
+            Vec::new()
+        } else {
+            self.commit_outcomes(shard_id, &mut blockchain, outcomes)?
+        };
+        
+        // Committed fees are split between the source and target shards
+        let fee_credits: Vec<(usize, u128, Option<bool>)> = verified.iter()
+            .flat_map(|record| {
+                let (source_fee, target_fee) = record.receipt.fee_split();
+                [(record.receipt.source_shard, source_fee, Some(true)), (shard_id, target_fee, Some(false))]
+            })
+            .collect();
+        self.earn_fees(&fee_credits)?;
+        
+        let committed: Vec<Hash> = verified.iter().map(|record| record.receipt.tx_hash).collect();
+        self.finish_receipts(&mut blockchain, &mut verified, CrossShardStatus::Committed).await?;
+        for record in verified {
+            stored.insert(record.receipt.tx_hash, record);
+        }
+        for record in outcome_records {
+            stored_outcomes.insert(record.outcome.tx_hash, record);
+        }
+        Ok(committed)
+    }
+
+    /// Commit call outcomes as the receipts root of a new target-shard block
+    /// and journal them (flushed by the next `journal` call)
+    fn commit_outcomes(
+        &self,
+        shard_id: usize,
+        blockchain: &mut Blockchain,
+        outcomes: Vec<CallOutcome>,
+    ) -> crate::error::BlockchainResult<Vec<OutcomeRecord>> {
+        use crate::storage::CrossShardStore;
+        
+        let leaves: Vec<Hash> = outcomes.iter().map(|outcome| outcome.hash()).collect();
+        let root = receipts::receipts_root(&leaves);
+        let parents: Vec<Hash> = blockchain.block_metas().last()
+            .map(|last| vec![last.hash])
+            .unwrap_or_default();
+        let number = blockchain.block_count() as u64;
+        let header = BlockHeader::new(parents.clone(), number, StreamType::StreamB, 0)
+            .with_receipts_root(root);
+        let outcome_block = Block::new(header, Vec::new(), parents);
+        blockchain.add_block(outcome_block.clone())?;
+        
+        let records: Vec<OutcomeRecord> = outcomes.into_iter().enumerate().map(|(index, outcome)| OutcomeRecord {
+            outcome,
+            proof: ReceiptProof {
+                source_block_hash: outcome_block.hash,
+                root,
+                steps: receipts::build_proof(&leaves, index),
+            },
+            status: OutcomeStatus::Pending,
+            pending_effect: None,
+        }).collect();
+        
+        if let Some(database) = &self.database {
+            let store = CrossShardStore::new(database);
+            store.put_block(shard_id, &outcome_block)?;
+            for record in &records {
+                store.put_outcome(record)?;
+            }
+        }
+        Ok(records)
+    }
+
+    /// Callback phase: on the caller's shard, verify call outcomes against the
+    /// target-shard block that committed them, refund the value of failed
+    /// calls and invoke callbacks
+    ///
+    /// Returns the call hashes whose outcomes were delivered.
+    pub async fn deliver_outcomes(&self, shard_id: usize, now: u64) -> crate::error::BlockchainResult<Vec<Hash>> {
+        use crate::storage::CrossShardStore;
+        
+        let shard_lock = match self.get_shard(shard_id) {
+            Some(shard) if self.is_tracked(shard_id) => shard,
+            _ => return Ok(Vec::new()),
+        };
+        let mut stored = self.call_outcomes.write().await;
+        
+        let mut candidates: Vec<OutcomeRecord> = stored.values()
+            .filter(|record| record.status == OutcomeStatus::Pending && record.outcome.source_shard == shard_id)
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+        candidates.sort_by(|a, b| a.outcome.tx_hash.cmp(&b.outcome.tx_hash));
+        
+        let mut verified = Vec::new();
+        for record in candidates {
+            if self.verify_committed(record.outcome.target_shard, &record.proof, record.outcome.hash()).await {
+                verified.push(record);
+            }
+        }
+        if verified.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let shard = shard_lock.read().await;
+        let mut blockchain = shard.blockchain.write().await;
+        let mut balances: HashMap<Address, u128> = HashMap::new();
+        for record in verified.iter().filter(|record| record.outcome.refund > 0) {
+            let caller = record.outcome.caller;
+            let balance = balances.entry(caller).or_insert_with(|| blockchain.get_balance(caller));
+            *balance = balance.saturating_add(record.outcome.refund);
+        }
+        for record in verified.iter_mut() {
+            record.status = OutcomeStatus::Delivered;
+            record.pending_effect = balances.get(&record.outcome.caller)
+                .filter(|_| record.outcome.refund > 0)
+                .map(|balance| BalanceEffect {
+                    shard: shard_id,
+                    address: record.outcome.caller,
+                    balance_after: *balance,
+                });
+        }
+        
+        // Journal outcomes and refunded statuses before touching balances
+        let mut cross_txs = self.cross_shard_txs.write().await;
+        let mut updated = Vec::new();
+        for record in verified.iter().filter(|record| !record.outcome.success) {
+            if let Some(mut cross_tx) = cross_txs.get(&record.outcome.tx_hash).cloned() {
+                cross_tx.status = CrossShardStatus::Refunded;
+                updated.push(cross_tx);
+            }
+        }
+        if let Some(database) = &self.database {
+            let store = CrossShardStore::new(database);
+            for record in &verified {
+                store.put_outcome(record)?;
+            }
+        }
+        {
+            let updated_refs: Vec<&CrossShardTransaction> = updated.iter().collect();
+            self.journal(&updated_refs, &[], None)?;
+        }
+        for cross_tx in updated {
+            cross_txs.insert(cross_tx.id, cross_tx);
+        }
+        drop(cross_txs);
+        
+        // Apply refunds, then mark them applied
+        for record in verified.iter_mut() {
+            if let Some(effect) = record.pending_effect.take() {
+                blockchain.set_balance(effect.address, effect.balance_after)?;
+                self.journal_accounts(effect.shard, &blockchain, &[effect.address])?;
+            }
+        }
+        if let Some(database) = &self.database {
+            let store = CrossShardStore::new(database);
+            for record in &verified {
+                store.put_outcome(record)?;
+            }
+        }
+        self.journal(&[], &[], None)?;
+        
+        let mut delivered = Vec::with_capacity(verified.len());
+        for record in verified {
+            let outcome = &record.outcome;
+            if let Some(callback) = outcome.callback {
+                invoke_callback(&blockchain, callback, outcome.callee, &outcome.tx_hash, outcome.success, &outcome.output, now);
+            }
+            delivered.push(outcome.tx_hash);
+            stored.insert(outcome.tx_hash, record);
+        }
+        Ok(delivered)
+    }
+
+    /// Outcome of a cross-shard contract call, once executed on its target
+    pub async fn get_call_outcome(&self, tx_hash: Hash) -> Option<OutcomeRecord> {
+        self.call_outcomes.read().await.get(&tx_hash).cloned()
+    }
+
+    /// Abort phase: refund locked receipts that expired before being committed
+    ///
+    /// Returns the transaction hashes refunded.
+    pub async fn abort_expired(&self, now: u64) -> crate::error::BlockchainResult<Vec<Hash>> {
+        let mut stored = self.receipts.write().await;
+        let mut expired_by_shard: HashMap<usize, Vec<ReceiptRecord>> = HashMap::new();
+        for record in stored.values() {
+            if record.status == ReceiptStatus::Locked && now >= record.receipt.expires_at {
+                expired_by_shard.entry(record.receipt.source_shard)
+                    .or_insert_with(Vec::new)
+                    .push(record.clone());
+            }
+        }
+        
+        let mut refunded = Vec::new();
+        for (source_shard, mut records) in expired_by_shard {
+            let shard_lock = match self.get_shard(source_shard) {
+                Some(shard) => shard,
+                None => continue,
+            };
+            let shard = shard_lock.read().await;
+            let mut blockchain = shard.blockchain.write().await;
+            
+            let mut balances: HashMap<Address, u128> = HashMap::new();
+            for record in &records {
+                let receipt = &record.receipt;
+                let balance = balances.entry(receipt.from).or_insert_with(|| blockchain.get_balance(receipt.from));
+                *balance = balance.saturating_add(receipt.locked_amount());
+            }
+            for record in records.iter_mut() {
+                record.status = ReceiptStatus::Refunded;
+                record.pending_effect = Some(BalanceEffect {
+                    shard: source_shard,
+                    address: record.receipt.from,
+                    balance_after: balances[&record.receipt.from],
+                });
+            }
+            
+            self.finish_receipts(&mut blockchain, &mut records, CrossShardStatus::Refunded).await?;
+            // Calls that never reached their target report failure to the callback
+            for record in &records {
+                if let Some(callback) = record.receipt.call.as_ref().and_then(|call| call.callback) {
+                    invoke_callback(&blockchain, callback, record.receipt.to, &record.receipt.tx_hash,
+                        false, b"cross-shard call timed out", now);
+                }
+            }
+            for record in records {
+                refunded.push(record.receipt.tx_hash);
+                stored.insert(record.receipt.tx_hash, record);
+            }
+        }
+        Ok(refunded)
+    }
+
+    /// Journal final receipt states with their effects, apply the effects,
+    /// and update the matching cross-shard transactions
+    async fn finish_receipts(
+        &self,
+        blockchain: &mut Blockchain,
+        records: &mut [ReceiptRecord],
+        status: CrossShardStatus,
+    ) -> crate::error::BlockchainResult<()> {
+        let mut cross_txs = self.cross_shard_txs.write().await;
+        let mut updated = Vec::with_capacity(records.len());
+        for record in records.iter() {
+            if let Some(mut cross_tx) = cross_txs.get(&record.receipt.tx_hash).cloned() {
+                cross_tx.status = status.clone();
+                updated.push(cross_tx);
+            }
+        }
+        {
+            let updated_refs: Vec<&CrossShardTransaction> = updated.iter().collect();
+            let record_refs: Vec<&ReceiptRecord> = records.iter().collect();
+            self.journal(&updated_refs, &record_refs, None)?;
+        }
+        for cross_tx in updated {
+            cross_txs.insert(cross_tx.id, cross_tx);
+        }
+        drop(cross_txs);
+        
+        self.apply_effects(blockchain, records)
+    }
+
+    /// Check a receipt's Merkle proof against the receipts root committed in
+    /// the header of its source-shard block
+    async fn verify_receipt(&self, record: &ReceiptRecord) -> bool {
+        self.verify_committed(record.receipt.source_shard, &record.proof, record.receipt.hash()).await
+    }
+
+    /// Check a Merkle proof for `leaf` against the receipts root in the header
+    /// of the proof's block on `shard_id`
+    async fn verify_committed(&self, shard_id: usize, proof: &ReceiptProof, leaf: Hash) -> bool {
+        let shard_lock = match self.get_shard(shard_id) {
+            Some(shard) => shard,
+            None => return false,
+        };
+        let shard = shard_lock.read().await;
+        let blockchain = shard.blockchain.read().await;
+        let committed_root = blockchain.get_block_by_hash(&proof.source_block_hash)
+            .and_then(|block| block.header.receipts_root);
+        committed_root == Some(proof.root) && receipts::proof_root(leaf, &proof.steps) == proof.root
+    }
+
+    /// Get the receipt journal record for a cross-shard transaction
+    pub async fn get_receipt(&self, tx_hash: Hash) -> Option<ReceiptRecord> {
+        let receipts = self.receipts.read().await;
+        receipts.get(&tx_hash).cloned()
+    }
+
+    /// Set an account balance in the state partition of the shard that owns it
+    /// (genesis allocations, dev funding)
+    pub async fn set_balance(&self, address: Address, balance: u128) -> crate::error::BlockchainResult<()> {
+        let shard_id = self.get_shard_for_address(&address);
+        let shard_lock = self.shard_at(shard_id);
+        let shard = shard_lock.read().await;
+        let mut blockchain = shard.blockchain.write().await;
+        blockchain.set_balance(address, balance)?;
+        self.journal_accounts(shard_id, &blockchain, &[address])?;
+        self.journal(&[], &[], None)
+    }
+
+    /// Balance of an address in its shard's state partition
+    pub async fn get_balance(&self, address: Address) -> u128 {
+        let shard_lock = self.shard_at(self.get_shard_for_address(&address));
+        let shard = shard_lock.read().await;
+        let balance = shard.blockchain.read().await.get_balance(address);
+        balance
+    }
+
+    /// Nonce of an address in its shard's state partition
+    pub async fn get_nonce(&self, address: Address) -> u64 {
+        let shard_lock = self.shard_at(self.get_shard_for_address(&address));
+        let shard = shard_lock.read().await;
+        let nonce = shard.blockchain.read().await.get_nonce(address);
+        nonce
+    }
+
+    /// Hash of the block that includes a transaction from `sender`, looked up
+    /// on the sender's shard
+    pub async fn get_transaction_block(&self, sender: Address, tx_hash: &Hash) -> Option<Hash> {
+        let shard_lock = self.shard_at(self.get_shard_for_address(&sender));
+        let shard = shard_lock.read().await;
+        let block_hash = shard.blockchain.read().await
+            .get_transaction_by_hash(tx_hash)
+            .map(|(block, _)| block.hash);
+        block_hash
+    }
+
+    /// Mine a block on a tracked shard's own DAG from its transaction pool
+    ///
+    /// Transactions are packed against the shard's state partition; deferred
+    /// ones go back to the shard pool. Shard blocks carry no coinbase: the
+    /// Stream B beacon block that commits them earns the reward. Returns None
+    /// when there is nothing to mine.
+    pub async fn produce_shard_block(
+        &self,
+        shard_id: usize,
+        max_txs: usize,
+        max_gas: u64,
+    ) -> crate::error::BlockchainResult<Option<Block>> {
+        use crate::mining::assembly;
+        
+        let shard_lock = match self.get_shard(shard_id) {
+            Some(shard) if self.is_tracked(shard_id) => shard,
+            _ => return Ok(None),
+        };
+        let candidates = self.remove_shard_transactions(shard_id, max_txs).await;
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+        
+        let shard = shard_lock.read().await;
+        let mut blockchain = shard.blockchain.write().await;
+        let blocks = blockchain.block_metas();
+        let parents: Vec<Hash> = blocks[blocks.len().saturating_sub(3)..].iter()
+            .map(|block| block.hash)
+            .collect();
+        let header = BlockHeader::new(parents.clone(), blocks.len() as u64, StreamType::StreamB, 0);
+        
+        let (header_number, header_timestamp) = (header.block_number, header.timestamp);
+        let limits = assembly::BlockLimits::new(max_txs, max_gas);
+        let assembled = assembly::assemble_transactions(
+            candidates, &blockchain, header_number, header_timestamp, &limits,
+        );
+        let mut requeue = assembled.deferred;
+        let dropped: Vec<Hash> = assembled.dropped.iter().map(|tx| tx.hash).collect();
+        self.forget_pooled(&dropped)?;
+        self.deferrals.lock().unwrap_or_else(|e| e.into_inner()).forget(&dropped);
+        let block = if assembled.included.is_empty() {
+            None
+        } else {
+            let block = Block::new(header, assembled.included, parents);
+            match blockchain.add_block(block.clone()) {
+                Ok(()) => {
+                    let touched = touched_accounts(&block);
+                    self.journal_accounts(shard_id, &blockchain, &touched)?;
+                    self.journal_contracts(shard_id, &blockchain, &block)?;
+                    self.earn_fees(&[(shard_id, block_fees(&block), None)])?;
+                    let mined: Vec<Hash> = block.transactions.iter().map(|tx| tx.hash).collect();
+                    self.forget_pooled(&mined)?;
+                    self.deferrals.lock().unwrap_or_else(|e| e.into_inner()).forget(&mined);
+                    self.journal(&[], &[], Some((shard_id, &block)))?;
+                    Some(block)
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Shard {} rejected its own block: {}", shard_id, e);
+                    requeue.extend(block.transactions);
+                    None
+                }
+            }
+        };
+        drop(blockchain);
+        drop(shard);
+        
+        // Transactions deferred for too long are evicted instead of requeued
+        let (requeue, expired) = self.deferrals.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .requeue(requeue, header_number, header_timestamp);
+        if !expired.is_empty() {
+            eprintln!("⚠️  Shard {}: Evicted {} transactions deferred for over {}s",
+                shard_id, expired.len(), crate::mining::assembly::DEFERRED_TX_TTL_SECS);
+            self.forget_pooled(&expired.iter().map(|tx| tx.hash).collect::<Vec<_>>())?;
+        }
+        if !requeue.is_empty() {
+            self.requeue_transactions(requeue).await;
+        }
+        Ok(block)
+    }
+
+    /// Import a shard block received from a peer
+    ///
+    /// The block is fully validated against the shard's state partition. Blocks
+    /// of untracked shards are ignored (returns false).
+    pub async fn import_shard_block(&self, shard_id: usize, block: Block) -> crate::error::BlockchainResult<bool> {
+        let shard_lock = match self.get_shard(shard_id) {
+            Some(shard) if self.is_tracked(shard_id) => shard,
+            _ => return Ok(false),
+        };
+        let mut shard = shard_lock.write().await;
+        let blockchain_lock = shard.blockchain.clone();
+        let mut blockchain = blockchain_lock.write().await;
+        if blockchain.get_block_by_hash(&block.hash).is_some() {
+            return Ok(false);
+        }
+        blockchain.add_block(block.clone())?;
+        let touched = touched_accounts(&block);
+        self.journal_accounts(shard_id, &blockchain, &touched)?;
+        self.journal_contracts(shard_id, &blockchain, &block)?;
+        self.earn_fees(&[(shard_id, block_fees(&block), None)])?;
+        self.forget_pooled(&block.transactions.iter().map(|tx| tx.hash).collect::<Vec<_>>())?;
+        self.journal(&[], &[], Some((shard_id, &block)))?;
+        
+        let mined: HashSet<Hash> = block.transactions.iter().map(|tx| tx.hash).collect();
+        shard.transaction_pool.retain(|tx| !mined.contains(&tx.hash));
+        Ok(true)
+    }
+
+    /// Blocks of a shard's DAG from `from_block`, in acceptance order
+    pub async fn get_shard_blocks(&self, shard_id: usize, from_block: u64, count: usize) -> Vec<Block> {
+        let shard_lock = match self.get_shard(shard_id) {
+            Some(shard) => shard,
+            None => return Vec::new(),
+        };
+        let shard = shard_lock.read().await;
+        let blockchain = shard.blockchain.read().await;
+        blockchain.block_metas()
+            .iter()
+            .filter(|block| block.block_number >= from_block)
+            .take(count)
+            .filter_map(|block| blockchain.get_block_by_hash(&block.hash))
+            .collect()
+    }
+
+    /// Current tip and state root of every tracked shard that has blocks
+    pub async fn shard_commitments(&self) -> Vec<ShardCommitment> {
+        let mut commitments = Vec::new();
+        for shard_id in self.tracked_shards() {
+            let shard_lock = self.shard_at(shard_id);
+            let shard = shard_lock.read().await;
+            let blockchain = shard.blockchain.read().await;
+            if let Some(tip) = blockchain.block_metas().last() {
+                commitments.push(ShardCommitment {
+                    shard_id,
+                    block_hash: tip.hash,
+                    block_count: blockchain.block_count() as u64,
+                    state_root: blockchain.state_commitment(),
+                });
+            }
+        }
+        commitments
+    }
+
+    /// Whether any commitment moves a shard past its latest beacon commitment
+    pub async fn has_new_commitments(&self, commitments: &[ShardCommitment]) -> bool {
+        let beacon = self.beacon.read().await;
+        commitments.iter().any(|commitment| beacon.latest(commitment.shard_id) != Some(commitment))
+    }
+
+    /// Record the shard commitments of an accepted beacon block
+    ///
+    /// Activates a scheduled reshard once its epoch is reached. A reshard that
+    /// cannot run yet (cross-shard transfers in flight, or state changed since
+    /// the commitments) moves to the next epoch. Without a scheduled reshard,
+    /// each new epoch runs the load rebalancer. Both run before the block's
+    /// shard fees are paid, so the partitions still match their commitments.
+    pub async fn record_beacon(&self, block: &Block) {
+        if block.header.shard_commitments.is_empty() {
+            return;
+        }
+        self.beacon.write().await.record(BeaconRecord {
+            block_hash: block.hash,
+            block_number: block.header.block_number,
+            commitments: block.header.shard_commitments.clone(),
+        });
+        self.reshard_at_epoch().await;
+        if let Err(e) = self.pay_shard_fees(block).await {
+            eprintln!("⚠️  Failed to pay shard fees: {}", e);
+        }
+    }
+
+    /// Activate a due reshard, or rebalance once per epoch
+    async fn reshard_at_epoch(&self) {
+        let epoch = self.current_epoch().await;
+        let plan = match self.pending_reshard() {
+            Some(plan) => plan,
+            None => {
+                self.rebalance_at_epoch(epoch).await;
+                return;
+            }
+        };
+        if epoch < plan.activation_epoch {
+            return;
+        }
+        match self.apply_reshard(plan.to_shard_count, epoch).await {
+            Ok(report) => println!("🔷 Resharded {} -> {} shards at epoch {}: {} of {} accounts migrated",
+                report.from_shard_count, report.to_shard_count, report.epoch,
+                report.migrations.len(), report.accounts_total),
+            Err(e) => {
+                eprintln!("⚠️  Reshard postponed to epoch {}: {}", epoch + 1, e);
+                let postponed = ReshardPlan { activation_epoch: epoch + 1, ..plan };
+                if let Err(e) = self.store_reshard_plan(Some(postponed)) {
+                    eprintln!("⚠️  Failed to journal reshard plan: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Pay the pending fees of the shards a beacon block commits to its coinbase
+    ///
+    /// The payout is credited where the coinbase lives; a node that does not
+    /// track that shard keeps the pools pending.
+    async fn pay_shard_fees(&self, block: &Block) -> crate::error::BlockchainResult<()> {
+        use crate::storage::CrossShardStore;
+        
+        let coinbase = match block.header.coinbase {
+            Some(coinbase) => coinbase,
+            None => return Ok(()),
+        };
+        let shard_id = self.get_shard_for_address(&coinbase);
+        if !self.is_tracked(shard_id) {
+            return Ok(());
+        }
+        let mut drained = Vec::new();
+        let payout = {
+            let pools = self.fees.lock().expect("fee pool lock poisoned");
+            let mut payout = 0u128;
+            for commitment in &block.header.shard_commitments {
+                if let Some(pool) = pools.get(&commitment.shard_id) {
+                    if pool.pending > 0 && !drained.iter().any(|(id, _)| *id == commitment.shard_id) {
+                        payout = payout.saturating_add(pool.pending);
+                        drained.push((commitment.shard_id, ShardFees { pending: 0, ..pool.clone() }));
+                    }
+                }
+            }
+            payout
+        };
+        if payout == 0 {
+            return Ok(());
+        }
+        
+        let shard_lock = self.shard_at(shard_id);
+        let shard = shard_lock.read().await;
+        let mut blockchain = shard.blockchain.write().await;
+        let balance = blockchain.get_balance(coinbase).saturating_add(payout);
+        blockchain.set_balance(coinbase, balance)?;
+        self.journal_accounts(shard_id, &blockchain, &[coinbase])?;
+        if let Some(database) = &self.database {
+            let store = CrossShardStore::new(database);
+            for (id, pool) in &drained {
+                store.put_fees(*id, pool)?;
+            }
+        }
+        self.journal(&[], &[], None)?;
+        self.fees.lock().expect("fee pool lock poisoned").extend(drained);
+        Ok(())
+    }
+
+    /// Set how many beacon blocks make up a sharding epoch
+    pub fn with_epoch_length(mut self, epoch_length: u64) -> Self {
+        self.epoch_length = epoch_length.max(1);
+        self
+    }
+
+    /// Current sharding epoch (recorded beacon blocks / epoch length)
+    pub async fn current_epoch(&self) -> u64 {
+        self.beacon.read().await.len() as u64 / self.epoch_length
+    }
+
+    /// Reshard waiting for its activation epoch
+    pub fn pending_reshard(&self) -> Option<ReshardPlan> {
+        self.reshard_plan.read().expect("reshard plan lock poisoned").clone()
+    }
+
+    fn store_reshard_plan(&self, plan: Option<ReshardPlan>) -> crate::error::BlockchainResult<()> {
+        use crate::storage::CrossShardStore;
+        
+        if let Some(database) = &self.database {
+            CrossShardStore::new(database).put_reshard_plan(plan.as_ref())?;
+            database.flush()?;
+        }
+        *self.reshard_plan.write().expect("reshard plan lock poisoned") = plan;
+        Ok(())
+    }
+
+    /// Accounts that would change shard if the shard count became `new_shard_count`
+    pub async fn preview_reshard(&self, new_shard_count: usize) -> crate::error::BlockchainResult<reshard::ReshardPreview> {
+        if new_shard_count == 0 {
+            return Err(crate::error::BlockchainError::Config("Shard count must be greater than 0".to_string()));
+        }
+        let current = self.ring.read().expect("shard ring lock poisoned").clone();
+        let new_ring = HashRing::new(new_shard_count, current.virtual_nodes());
+        
+        let mut accounts_total = 0;
+        let mut moves = Vec::new();
+        for (shard_id, shard_lock) in self.shard_list().into_iter().enumerate() {
+            let shard = shard_lock.read().await;
+            let blockchain = shard.blockchain.read().await;
+            for (address, _, _) in blockchain.account_entries() {
+                accounts_total += 1;
+                let to_shard = self.assign(&new_ring, &address);
+                if to_shard != shard_id {
+                    moves.push(AccountMove { address, from_shard: shard_id, to_shard });
+                }
+            }
+        }
+        
+        Ok(reshard::ReshardPreview {
+            from_shard_count: current.shard_count(),
+            to_shard_count: new_shard_count,
+            accounts_total,
+            moves,
+        })
+    }
+
+    /// Schedule a shard count change for the start of the next epoch
+    pub async fn schedule_reshard(&self, new_shard_count: usize) -> crate::error::BlockchainResult<ReshardPlan> {
+        if new_shard_count == 0 {
+            return Err(crate::error::BlockchainError::Config("Shard count must be greater than 0".to_string()));
+        }
+        if new_shard_count == self.shard_count() {
+            return Err(crate::error::BlockchainError::Config(
+                format!("Already running {} shards", new_shard_count)
+            ));
+        }
+        let plan = ReshardPlan {
+            from_shard_count: self.shard_count(),
+            to_shard_count: new_shard_count,
+            activation_epoch: self.current_epoch().await + 1,
+        };
+        self.store_reshard_plan(Some(plan.clone()))?;
+        Ok(plan)
+    }
+
+    /// Use custom rebalancer settings
+    pub fn with_rebalance_config(mut self, config: RebalanceConfig) -> Self {
+        self.rebalance_config = config;
+        self
+    }
+
+    fn record_arrival(&self, shard_id: usize, sender: &Address, cross_shard: bool) {
+        let now = unix_now();
+        let mut window = self.load_window.lock().expect("load window lock poisoned");
+        window.prune(now, self.rebalance_config.window_secs);
+        window.record(shard_id, ring::address_position(sender), cross_shard, now);
+    }
+
+    async fn pool_depths(&self) -> Vec<usize> {
+        let mut depths = Vec::new();
+        for shard_lock in self.shard_list() {
+            depths.push(shard_lock.read().await.transaction_pool.len());
+        }
+        depths
+    }
+
+    /// Load of every shard over the rebalancing window
+    ///
+    /// Arrivals are those seen by this node, so untracked shards read as idle.
+    pub async fn shard_loads(&self) -> Vec<ShardLoad> {
+        let pool_depths = self.pool_depths().await;
+        let mut window = self.load_window.lock().expect("load window lock poisoned");
+        window.prune(unix_now(), self.rebalance_config.window_secs);
+        rebalance::measure(&window, &pool_depths, &self.rebalance_config)
+    }
+
+    /// Ring ranges the rebalancer would move under the current load (never migrates)
+    ///
+    /// Only the consistent-hashing strategy has ranges to move.
+    pub async fn propose_rebalance(&self) -> RebalanceProposal {
+        let pool_depths = self.pool_depths().await;
+        let current_ring = self.ring.read().expect("shard ring lock poisoned").clone();
+        let (loads, mut moves) = {
+            let mut window = self.load_window.lock().expect("load window lock poisoned");
+            window.prune(unix_now(), self.rebalance_config.window_secs);
+            let loads = rebalance::measure(&window, &pool_depths, &self.rebalance_config);
+            let moves = match self.config.assignment_strategy {
+                AssignmentStrategy::ConsistentHashing => rebalance::plan_moves(&current_ring, &window, &loads, &self.rebalance_config),
+                _ => Vec::new(),
+            };
+            (loads, moves)
+        };
+        
+        for range in moves.iter_mut() {
+            let shard_lock = match self.get_shard(range.from_shard) {
+                Some(shard) => shard,
+                None => continue,
+            };
+            let shard = shard_lock.read().await;
+            let blockchain = shard.blockchain.read().await;
+            let arc = ring::RingArc { start: range.start, end: range.end, shard_id: range.from_shard };
+            range.accounts = blockchain.account_entries()
+                .iter()
+                .filter(|(address, _, _)| arc.contains(ring::address_position(address)))
+                .count();
+        }
+        
+        RebalanceProposal {
+            epoch: self.current_epoch().await,
+            window_secs: self.rebalance_config.window_secs,
+            imbalance: rebalance::imbalance(&loads),
+            loads,
+            moves,
+            dry_run: self.rebalance_config.dry_run,
+            applied: false,
+        }
+    }
+
+    /// Proposal made at the latest epoch boundary
+    pub fn last_rebalance(&self) -> Option<RebalanceProposal> {
+        self.last_rebalance.read().expect("rebalance lock poisoned").clone()
+    }
+
+    /// Migrate the ring ranges of a proposal to their new shards
+    ///
+    /// Accounts move with state proofs exactly as in a reshard. Refuses while a
+    /// reshard is scheduled.
+    pub async fn apply_rebalance(&self, proposal: &RebalanceProposal) -> crate::error::BlockchainResult<ReshardReport> {
+        if self.pending_reshard().is_some() {
+            return Err(crate::error::BlockchainError::Validation(
+                "Reshard scheduled; rebalance after it activates".to_string()
+            ));
+        }
+        let new_ring = {
+            let current = self.ring.read().expect("shard ring lock poisoned");
+            rebalance::apply_moves(&current, &proposal.moves)
+        };
+        let report = self.migrate_to_ring(new_ring, proposal.epoch).await?;
+        self.load_window.lock().expect("load window lock poisoned").clear();
+        Ok(report)
+    }
+
+    /// Propose (and unless in dry-run mode, apply) a rebalance once per epoch
+    async fn rebalance_at_epoch(&self, epoch: u64) {
+        let done = self.last_rebalance.read().expect("rebalance lock poisoned")
+            .as_ref()
+            .map(|last| last.epoch >= epoch)
+            .unwrap_or(epoch == 0);
+        if done {
+            return;
+        }
+        let mut proposal = self.propose_rebalance().await;
+        proposal.epoch = epoch;
+        if !proposal.moves.is_empty() {
+            if proposal.dry_run {
+                println!("🔷 Rebalance proposal (dry run) at epoch {}: {} ranges off hot shards, imbalance {:.2}",
+                    epoch, proposal.moves.len(), proposal.imbalance);
+            } else {
+                match self.apply_rebalance(&proposal).await {
+                    Ok(report) => {
+                        proposal.applied = true;
+                        println!("🔷 Rebalanced {} ranges at epoch {}: {} accounts migrated",
+                            proposal.moves.len(), epoch, report.migrations.len());
+                    }
+                    Err(e) => eprintln!("⚠️  Rebalance skipped at epoch {}: {}", epoch, e),
+                }
+            }
+        }
+        *self.last_rebalance.write().expect("rebalance lock poisoned") = Some(proposal);
+    }
+
+    /// Switch to `new_shard_count` shards, migrating only the accounts whose
+    /// owner changes
+    ///
+    /// Each migrated account (with its contract code) carries a Merkle proof
+    /// against its source partition's state root, which the target checks
+    /// against the root committed by the latest beacon block before crediting
+    /// it. The new layout is journaled in one batch before it is swapped in.
+    /// Pooled transactions follow their sender. Refuses to run while
+    /// cross-shard transfers are pending or locked, since their receipts name
+    /// shard ids.
+    pub async fn apply_reshard(&self, new_shard_count: usize, epoch: u64) -> crate::error::BlockchainResult<ReshardReport> {
+        if new_shard_count == 0 {
+            return Err(crate::error::BlockchainError::Config("Shard count must be greater than 0".to_string()));
+        }
+        let virtual_nodes = self.ring.read().expect("shard ring lock poisoned").virtual_nodes();
+        self.migrate_to_ring(HashRing::new(new_shard_count, virtual_nodes), epoch).await
+    }
+
+    /// Switch to a new ring, migrating the accounts whose owner changes
+    ///
+    /// Shared by resharding and rebalancing. Clears any scheduled reshard.
+    async fn migrate_to_ring(&self, new_ring: HashRing, epoch: u64) -> crate::error::BlockchainResult<ReshardReport> {
+        use crate::storage::CrossShardStore;
+        
+        let new_shard_count = new_ring.shard_count();
+        // Lock order: receipts, call outcomes, cross-shard transactions, shards (by id)
+        let _receipts = self.receipts.write().await;
+        let outcomes = self.call_outcomes.read().await;
+        if outcomes.values().any(|record| record.status == OutcomeStatus::Pending) {
+            return Err(crate::error::BlockchainError::Validation(
+                "Cross-shard call outcomes awaiting delivery".to_string()
+            ));
+        }
+        let cross_txs = self.cross_shard_txs.write().await;
+        if cross_txs.values().any(|tx| matches!(tx.status, CrossShardStatus::Pending | CrossShardStatus::Locked)) {
+            return Err(crate::error::BlockchainError::Validation(
+                "Cross-shard transfers in flight".to_string()
+            ));
+        }
+        
+        // Source partitions are proven against their latest beacon commitments
+        let committed: HashMap<usize, Hash> = self.beacon.read().await.latest_all()
+            .into_iter()
+            .map(|commitment| (commitment.shard_id, commitment.state_root))
+            .collect();
+        
+        let old_ring = self.ring.read().expect("shard ring lock poisoned").clone();
+        let old_shards = self.shard_list();
+        let mut shards = Vec::with_capacity(old_shards.len());
+        for shard_lock in &old_shards {
+            shards.push(shard_lock.write().await);
+        }
+        let mut added: Vec<Shard> = (old_shards.len()..new_shard_count).map(Shard::new).collect();
+        
+        // Prove every account whose owner changes against its source state root
+        let mut accounts_total = 0;
+        let mut migrations = Vec::new();
+        for (shard_id, shard) in shards.iter().enumerate() {
+            let blockchain = shard.blockchain.read().await;
+            let entries = blockchain.partition_entries();
+            let root = reshard::partition_root(&entries);
+            accounts_total += entries.len();
+            for (index, (address, balance, nonce, code)) in entries.iter().enumerate() {
+                let to_shard = self.assign(&new_ring, address);
+                if to_shard != shard_id {
+                    migrations.push(AccountMigration {
+                        account: AccountMove { address: *address, from_shard: shard_id, to_shard },
+                        balance: *balance,
+                        nonce: *nonce,
+                        code: code.clone(),
+                        source_root: root,
+                        proof: reshard::partition_proof(&entries, index),
+                    });
+                }
+            }
+        }
+        
+        // The receiving side only credits accounts proven against the committed
+        // root, so state changed since the last beacon block postpones the move
+        for migration in &migrations {
+            let from_shard = migration.account.from_shard;
+            let root = committed.get(&from_shard).ok_or_else(|| crate::error::BlockchainError::Validation(
+                format!("Shard {} has no beacon commitment to prove its accounts against", from_shard)
+            ))?;
+            if !migration.verify(root) {
+                return Err(crate::error::BlockchainError::Validation(
+                    format!("State proof for account 0x{} does not match shard {}'s committed state root",
+                        hex::encode(migration.account.address), from_shard)
+                ));
+            }
+        }
+        
+        // Stage the new state of every migrated address (a target may already
+        // hold a stray entry: merge it)
+        let mut staged: HashMap<(usize, Address), reshard::PartitionEntry> = HashMap::new();
+        for migration in &migrations {
+            let key = (migration.account.to_shard, migration.account.address);
+            if let std::collections::hash_map::Entry::Vacant(slot) = staged.entry(key) {
+                let current = match shards.get(key.0) {
+                    Some(shard) => {
+                        let blockchain = shard.blockchain.read().await;
+                        (key.1, blockchain.get_balance(key.1), blockchain.get_nonce(key.1), blockchain.contract_code(&key.1))
+                    }
+                    None => (key.1, 0, 0, None),
+                };
+                slot.insert(current);
+            }
+            let (_, balance, nonce, code) = staged.get_mut(&key).expect("staged above");
+            *balance = balance.saturating_add(migration.balance);
+            *nonce = (*nonce).max(migration.nonce);
+            if migration.code.is_some() {
+                *code = migration.code.clone();
+            }
+        }
+        
+        // Pooled transactions follow their sender
+        let mut moved_txs: Vec<(usize, Transaction)> = Vec::new();
+        for shard in shards.iter() {
+            moved_txs.extend(shard.transaction_pool.iter()
+                .map(|tx| (self.assign(&new_ring, &tx.from), tx))
+                .filter(|(shard_id, _)| *shard_id != shard.id)
+                .map(|(shard_id, tx)| (shard_id, tx.clone())));
+        }
+        let transactions_moved = moved_txs.len();
+        
+        // Fees of removed shards go to shard 0's pool
+        let mut fees = self.fees.lock().expect("fee pool lock poisoned").clone();
+        for shard_id in new_shard_count..old_shards.len() {
+            if let Some(removed) = fees.remove(&shard_id) {
+                let pool = fees.entry(0).or_default();
+                pool.pending = pool.pending.saturating_add(removed.pending);
+                pool.collected = pool.collected.saturating_add(removed.collected);
+                pool.cross_shard_source = pool.cross_shard_source.saturating_add(removed.cross_shard_source);
+                pool.cross_shard_target = pool.cross_shard_target.saturating_add(removed.cross_shard_target);
+            }
+        }
+        
+        // Journal the new layout in one batch before any of it becomes visible
+        if let Some(database) = &self.database {
+            let store = CrossShardStore::new(database);
+            let mut batch = crate::storage::WriteBatch::new();
+            for migration in &migrations {
+                CrossShardStore::stage_delete_account(&mut batch, migration.account.from_shard, &migration.account.address);
+            }
+            for ((shard_id, address), (_, balance, nonce, code)) in &staged {
+                CrossShardStore::stage_account(&mut batch, *shard_id, address, *balance, *nonce)?;
+                if let Some(code) = code {
+                    CrossShardStore::stage_contract(&mut batch, *shard_id, address, code)?;
+                }
+            }
+            for shard_id in new_shard_count..old_shards.len() {
+                store.stage_delete_shard(&mut batch, shard_id)?;
+            }
+            if let Some(pool) = fees.get(&0) {
+                CrossShardStore::stage_fees(&mut batch, 0, pool)?;
+            }
+            CrossShardStore::stage_ring(&mut batch, &new_ring)?;
+            CrossShardStore::stage_reshard_plan(&mut batch, None)?;
+            database.apply(batch)?;
+            database.flush()?;
+        }
+        
+        // Swap the journaled layout in
+        for migration in &migrations {
+            shards[migration.account.from_shard].blockchain.write().await.remove_account(&migration.account.address);
+        }
+        for ((shard_id, address), (_, balance, nonce, code)) in staged {
+            let target = match shards.get(shard_id) {
+                Some(shard) => shard.blockchain.clone(),
+                None => added[shard_id - old_shards.len()].blockchain.clone(),
+            };
+            let mut blockchain = target.write().await;
+            blockchain.set_balance(address, balance)?;
+            blockchain.set_nonce(address, nonce)?;
+            if let Some(code) = code {
+                blockchain.set_contract_code(address, code);
+            }
+        }
+        for shard in shards.iter_mut() {
+            let shard_id = shard.id;
+            shard.transaction_pool.retain(|tx| self.assign(&new_ring, &tx.from) == shard_id);
+        }
+        for (shard_id, tx) in moved_txs {
+            match shards.get_mut(shard_id) {
+                Some(shard) => shard.add_transaction(tx),
+                None => added[shard_id - old_shards.len()].add_transaction(tx),
+            }
+        }
+        
+        let mut new_shards: Vec<Arc<RwLock<Shard>>> = old_shards.iter()
+            .take(new_shard_count)
+            .cloned()
+            .collect();
+        new_shards.extend(added.into_iter().map(|shard| Arc::new(RwLock::new(shard))));
+        drop(shards);
+        *self.shards.write().expect("shard list lock poisoned") = new_shards;
+        *self.ring.write().expect("shard ring lock poisoned") = new_ring;
+        *self.reshard_plan.write().expect("reshard plan lock poisoned") = None;
+        *self.fees.lock().expect("fee pool lock poisoned") = fees;
+        
+        Ok(ReshardReport {
+            from_shard_count: old_ring.shard_count(),
+            to_shard_count: new_shard_count,
+            epoch,
+            accounts_total,
+            migrations,
+            transactions_moved,
+        })
+    }
+
+    /// Check a beacon block's commitments against the tracked shards
+    ///
+    /// A committed block we hold must sit at the committed height, and if it is
+    /// our tip the state root must match. Commitments to blocks we have not
+    /// received yet, and to untracked shards, are accepted as-is.
+    pub async fn verify_shard_commitments(&self, block: &Block) -> crate::error::BlockchainResult<()> {
+        for commitment in &block.header.shard_commitments {
+            if commitment.shard_id >= self.shard_count() {
+                return Err(crate::error::BlockchainError::InvalidBlock(
+                    format!("Commitment to unknown shard {}", commitment.shard_id)
+                ));
+            }
+            if !self.is_tracked(commitment.shard_id) {
+                continue;
+            }
+            let shard_lock = self.shard_at(commitment.shard_id);
+            let shard = shard_lock.read().await;
+            let blockchain = shard.blockchain.read().await;
+            let blocks = blockchain.block_metas();
+            let position = match blocks.iter().position(|b| b.hash == commitment.block_hash) {
+                Some(position) => position,
+                None => continue,
+            };
+            if position as u64 + 1 != commitment.block_count {
+                return Err(crate::error::BlockchainError::InvalidBlock(
+                    format!("Shard {} commitment has block count {}, expected {}",
+                        commitment.shard_id, commitment.block_count, position + 1)
+                ));
+            }
+            if position + 1 == blocks.len() && blockchain.state_commitment() != commitment.state_root {
+                return Err(crate::error::BlockchainError::InvalidBlock(
+                    format!("Shard {} state root mismatch", commitment.shard_id)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Latest beacon commitment of every shard
+    pub async fn get_beacon_commitments(&self) -> Vec<ShardCommitment> {
+        self.beacon.read().await.latest_all()
+    }
+
+    /// Beacon record of a global block (None if it committed no shards)
+    pub async fn get_beacon_record(&self, block_hash: &Hash) -> Option<BeaconRecord> {
+        self.beacon.read().await.get_record(block_hash).cloned()
+    }
+
+    /// Most recent beacon record
+    pub async fn get_latest_beacon_record(&self) -> Option<BeaconRecord> {
+        self.beacon.read().await.latest_record().cloned()
+    }
+
+    /// Get transactions for a shard (for mining)
+    pub async fn get_shard_transactions(&self, shard_id: usize, limit: usize) -> Vec<Transaction> {
+        if let Some(shard) = self.get_shard(shard_id) {
+            let shard = shard.read().await;
+            shard.get_transactions(limit)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Remove transactions from a shard (after mining)
+    ///
+    /// Queued cross-shard transfers stay in the pool: they are settled by the
+    /// receipt protocol, not mined as ordinary transactions.
+    pub async fn remove_shard_transactions(&self, shard_id: usize, count: usize) -> Vec<Transaction> {
+        if let Some(shard) = self.get_shard(shard_id) {
+            let mut shard = shard.write().await;
+            let outgoing: HashSet<Hash> = shard.cross_shard_outgoing.iter().copied().collect();
+            let mut taken = Vec::new();
+            let mut kept = Vec::new();
+            for tx in shard.transaction_pool.drain(..) {
+                if taken.len() < count && !outgoing.contains(&tx.hash) {
+                    taken.push(tx);
+                } else {
+                    kept.push(tx);
+                }
+            }
+            shard.transaction_pool = kept;
+            taken
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Return transactions to their source shards (skipped during block assembly)
+    pub async fn requeue_transactions(&self, txs: Vec<Transaction>) {
+        let mut by_shard: HashMap<usize, Vec<Transaction>> = HashMap::new();
+        for tx in txs {
+            by_shard.entry(self.get_shard_for_address(&tx.from)).or_insert_with(Vec::new).push(tx);
+        }
+        for (shard_id, shard_txs) in by_shard {
+            if let Some(shard) = self.get_shard(shard_id) {
+                shard.write().await.requeue_transactions(shard_txs);
+            }
+        }
+    }
+
+    /// Get cross-shard transaction status
+    pub async fn get_cross_shard_status(&self, tx_hash: Hash) -> Option<CrossShardStatus> {
+        let cross_txs = self.cross_shard_txs.read().await;
+        cross_txs.get(&tx_hash).map(|tx| tx.status.clone())
+    }
+
+    /// Synchronize shard state
+    /// 
+    /// Merges state from all shards into a unified view
+    pub async fn synchronize_shards(&self) -> crate::error::BlockchainResult<()> {
+        // For now, this is a placeholder
+        // In production, this would:
+        // 1. Collect state from all shards
+        // 2. Resolve conflicts
+        // 3. Merge balances
+        // 4. Update cross-shard transaction statuses
+        
+        Ok(())
+    }
+
+    /// Get shard statistics
+    pub async fn get_shard_stats(&self, shard_id: usize) -> Option<ShardStats> {
+        if let Some(shard) = self.get_shard(shard_id) {
+            let shard = shard.read().await;
+            let blockchain = shard.blockchain.read().await;
+            
+            let block_interval = measured_block_interval(blockchain.block_metas());
+            Some(ShardStats {
+                shard_id,
+                tracked: self.is_tracked(shard_id),
+                block_count: blockchain.block_count(),
+                tip: blockchain.block_metas().last().map(|block| block.hash),
+                state_root: blockchain.state_commitment(),
+                transaction_pool_size: shard.transaction_pool.len(),
+                cross_shard_outgoing: shard.cross_shard_outgoing.len(),
+                cross_shard_incoming: shard.cross_shard_incoming.len(),
+                fees: self.get_shard_fees(shard_id),
+                block_interval,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Measured seconds between a shard's recent blocks
+    pub async fn block_interval(&self, shard_id: usize) -> Option<f64> {
+        let shard_lock = self.get_shard(shard_id)?;
+        let shard = shard_lock.read().await;
+        let blockchain = shard.blockchain.read().await;
+        measured_block_interval(blockchain.block_metas())
+    }
+
+    /// Expected time for a transaction from `from` to `to` to confirm
+    ///
+    /// Same shard: the source pool backlog plus one block. Cross-shard: the
+    /// source block that locks it, one receipt round, then the target block
+    /// that credits it. A contract call's outcome takes another round and a
+    /// source block to come back.
+    pub async fn estimate_cross_shard_latency(&self, from: &Address, to: &Address) -> LatencyEstimate {
+        let source_shard = self.get_shard_for_address(from);
+        let target_shard = self.get_shard_for_address(to);
+        let source_block_interval = self.block_interval(source_shard).await;
+        let target_block_interval = self.block_interval(target_shard).await;
+        let source_pool_depth = match self.get_shard(source_shard) {
+            Some(shard) => shard.read().await.transaction_pool.len(),
+            None => 0,
+        };
+        
+        let source_secs = source_block_interval.unwrap_or(DEFAULT_SHARD_BLOCK_SECS);
+        let target_secs = target_block_interval.unwrap_or(DEFAULT_SHARD_BLOCK_SECS);
+        let round = CROSS_SHARD_ROUND_SECS as f64;
+        // Shard blocks share a Stream B block's transaction budget
+        let block_capacity = (crate::mining::STREAM_B_MAX_TXS / self.tracked_shards().len().max(1)).max(1);
+        let backlog_blocks = (source_pool_depth / block_capacity) as f64;
+        let included = source_secs * (1.0 + backlog_blocks);
+        let cross_shard = source_shard != target_shard;
+        let confirmation_secs = if cross_shard { included + round + target_secs } else { included };
+        LatencyEstimate {
+            source_shard,
+            target_shard,
+            cross_shard,
+            source_block_interval,
+            target_block_interval,
+            source_pool_depth,
+            confirmation_secs,
+            callback_secs: if cross_shard { confirmation_secs + round + source_secs } else { confirmation_secs },
+        }
+    }
+
+    /// Get all shard statistics
+    pub async fn get_all_shard_stats(&self) -> Vec<ShardStats> {
+        let mut stats = Vec::new();
+        for i in 0..self.shard_count() {
+            if let Some(stat) = self.get_shard_stats(i).await {
+                stats.push(stat);
+            }
+        }
+        stats
+    }
+    
     /// Get cross-shard transaction details
     pub async fn get_cross_shard_transaction(&self, tx_hash: Hash) -> Option<CrossShardTransaction> {
         let cross_txs = self.cross_shard_txs.read().await;
@@ -374,9 +2256,108 @@ impl ShardManager {
 #[derive(Debug, Clone)]
 pub struct ShardStats {
     pub shard_id: usize,
+    pub tracked: bool,
     pub block_count: usize,
+    pub tip: Option<Hash>,
+    pub state_root: Hash,
     pub transaction_pool_size: usize,
     pub cross_shard_outgoing: usize,
     pub cross_shard_incoming: usize,
+    pub fees: ShardFees,
+    /// Seconds between recent blocks (None = too few blocks yet)
+    pub block_interval: Option<f64>,
+}
+
+/// Mean seconds between the last blocks of a shard (None below two blocks)
+fn measured_block_interval(blocks: &[BlockMeta]) -> Option<f64> {
+    let recent = &blocks[blocks.len().saturating_sub(BLOCK_RATE_WINDOW)..];
+    if recent.len() < 2 {
+        return None;
+    }
+    let first = recent.first()?.timestamp;
+    let last = recent.last()?.timestamp;
+    Some(last.saturating_sub(first) as f64 / (recent.len() - 1) as f64)
 }
 
+/// Fees paid by a block's transactions (sponsored fees included)
+fn block_fees(block: &Block) -> u128 {
+    block.transactions.iter().fold(0u128, |total, tx| total.saturating_add(tx.fee))
+}
+
+/// Accounts whose state a block's transactions can change
+fn touched_accounts(block: &Block) -> Vec<Address> {
+    let mut addresses: Vec<Address> = block.transactions.iter()
+        .flat_map(|tx| [Some(tx.from), Some(tx.to), tx.sponsor])
+        .flatten()
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+/// Execute a cross-shard call against the target shard's contracts
+fn execute_call(blockchain: &Blockchain, receipt: &CrossShardReceipt, call: &CallPayload, now: u64) -> CallOutcome {
+    let result = match blockchain.evm_executor() {
+        Some(executor) if executor.state().is_contract(receipt.to) => executor.call_contract(
+            receipt.from,
+            receipt.to,
+            call.data.clone(),
+            receipt.value,
+            call.gas_limit,
+            0,
+            blockchain.latest_block_number(),
+            now,
+        ),
+        Some(_) => Err(format!("0x{} is not a contract", hex::encode(receipt.to))),
+        None => Err("EVM disabled on target shard".to_string()),
+    };
+    let (success, output, gas_used) = match result {
+        Ok(result) if !result.success => (false, result.output, result.gas_used),
+        Ok(result) if result.gas_used > call.gas_limit => (false, b"out of gas".to_vec(), call.gas_limit),
+        Ok(result) => (true, result.output, result.gas_used),
+        Err(e) => (false, e.into_bytes(), 0),
+    };
+    CallOutcome {
+        tx_hash: receipt.tx_hash,
+        source_shard: receipt.source_shard,
+        target_shard: receipt.target_shard,
+        caller: receipt.from,
+        callee: receipt.to,
+        success,
+        output,
+        gas_used,
+        refund: if success { 0 } else { receipt.value },
+        callback: call.callback,
+    }
+}
+
+/// Invoke a callback contract with a call's outcome
+///
+/// A failing callback is only logged: the outcome (and any refund) is final.
+fn invoke_callback(
+    blockchain: &Blockchain,
+    callback: Address,
+    callee: Address,
+    tx_hash: &Hash,
+    success: bool,
+    output: &[u8],
+    now: u64,
+) {
+    let executor = match blockchain.evm_executor() {
+        Some(executor) => executor,
+        None => return,
+    };
+    let data = messages::callback_data(tx_hash, success, output);
+    match executor.call_contract(callee, callback, data, 0, u64::MAX, 0, blockchain.latest_block_number(), now) {
+        Ok(result) if result.success => {}
+        Ok(_) => eprintln!("⚠️  Callback 0x{} reverted for call 0x{}", hex::encode(callback), hex::encode(tx_hash)),
+        Err(e) => eprintln!("⚠️  Callback 0x{} failed for call 0x{}: {}", hex::encode(callback), hex::encode(tx_hash), e),
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,153 @@
+//! Beacon layer
+//!
+//! Each shard owns its account state partition and its own block DAG. Blocks
+//! on the global chain act as beacon blocks: they carry one commitment per
+//! shard (tip hash, block count and state root), so a node that only validates
+//! a subset of shards can still follow every shard's progress and check
+//! cross-shard proofs against committed shard tips.
+
+use crate::types::Hash;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+/// Beacon commitment to one shard's chain and state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardCommitment {
+    pub shard_id: usize,
+    /// Latest block of the shard's DAG
+    pub block_hash: Hash,
+    pub block_count: u64,
+    /// Commitment to the shard's account state after `block_hash`
+    pub state_root: Hash,
+}
+
+impl ShardCommitment {
+    pub fn hash(&self) -> Hash {
+        let mut hasher = Keccak256::new();
+        hasher.update(b"mds-shard-commitment");
+        hasher.update(&(self.shard_id as u64).to_le_bytes());
+        hasher.update(&self.block_hash);
+        hasher.update(&self.block_count.to_le_bytes());
+        hasher.update(&self.state_root);
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&hasher.finalize());
+        hash
+    }
+}
+
+/// Root over the commitments of one beacon block (hashed into its header)
+pub fn commitments_root(commitments: &[ShardCommitment]) -> Hash {
+    let leaves: Vec<Hash> = commitments.iter().map(|commitment| commitment.hash()).collect();
+    super::receipts::receipts_root(&leaves)
+}
+
+/// Beacon block as seen by the shard layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeaconRecord {
+    pub block_hash: Hash,
+    pub block_number: u64,
+    pub commitments: Vec<ShardCommitment>,
+}
+
+/// Shard commitments collected from accepted beacon blocks
+#[derive(Debug, Default)]
+pub struct BeaconChain {
+    records: Vec<BeaconRecord>,
+    latest: HashMap<usize, ShardCommitment>,
+}
+
+impl BeaconChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an accepted beacon block (blocks without commitments are ignored)
+    ///
+    /// A shard's latest commitment only moves forward: an older commitment
+    /// (lower block count) from a late beacon block does not replace it.
+    pub fn record(&mut self, record: BeaconRecord) {
+        if record.commitments.is_empty() {
+            return;
+        }
+        for commitment in &record.commitments {
+            let newer = self.latest.get(&commitment.shard_id)
+                .map(|latest| commitment.block_count > latest.block_count)
+                .unwrap_or(true);
+            if newer {
+                self.latest.insert(commitment.shard_id, commitment.clone());
+            }
+        }
+        self.records.push(record);
+    }
+
+    /// Latest committed tip of a shard
+    pub fn latest(&self, shard_id: usize) -> Option<&ShardCommitment> {
+        self.latest.get(&shard_id)
+    }
+
+    /// Latest committed tips of all shards, by shard id
+    pub fn latest_all(&self) -> Vec<ShardCommitment> {
+        let mut commitments: Vec<ShardCommitment> = self.latest.values().cloned().collect();
+        commitments.sort_by_key(|commitment| commitment.shard_id);
+        commitments
+    }
+
+    /// Whether some beacon block committed this shard block as a tip
+    pub fn is_committed(&self, shard_id: usize, block_hash: &Hash) -> bool {
+        self.records.iter().any(|record| record.commitments.iter()
+            .any(|commitment| commitment.shard_id == shard_id && &commitment.block_hash == block_hash))
+    }
+
+    pub fn get_record(&self, block_hash: &Hash) -> Option<&BeaconRecord> {
+        self.records.iter().find(|record| &record.block_hash == block_hash)
+    }
+
+    pub fn latest_record(&self) -> Option<&BeaconRecord> {
+        self.records.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commitment(shard_id: usize, block_count: u64) -> ShardCommitment {
+        ShardCommitment {
+            shard_id,
+            block_hash: [block_count as u8; 32],
+            block_count,
+            state_root: [shard_id as u8; 32],
+        }
+    }
+
+    #[test]
+    fn test_beacon_keeps_newest_commitment() {
+        let mut beacon = BeaconChain::new();
+        beacon.record(BeaconRecord {
+            block_hash: [1u8; 32],
+            block_number: 1,
+            commitments: vec![commitment(0, 5), commitment(1, 2)],
+        });
+        beacon.record(BeaconRecord {
+            block_hash: [2u8; 32],
+            block_number: 2,
+            commitments: vec![commitment(0, 3)],
+        });
+
+        assert_eq!(beacon.len(), 2);
+        assert_eq!(beacon.latest(0).unwrap().block_count, 5);
+        assert_eq!(beacon.latest(1).unwrap().block_count, 2);
+        assert!(beacon.is_committed(0, &[3u8; 32]));
+        assert!(!beacon.is_committed(1, &[3u8; 32]));
+        assert_ne!(commitments_root(&[commitment(0, 5)]), commitments_root(&[commitment(0, 6)]));
+    }
+}
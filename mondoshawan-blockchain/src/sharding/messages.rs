@@ -0,0 +1,151 @@
+//! Cross-shard contract calls
+//!
+//! A call to a contract on another shard is asynchronous and rides on the
+//! receipt protocol (see `receipts`):
+//! 1. Emit: the source shard locks value + fee and commits a receipt carrying
+//!    the call payload. Senders are accounts or contracts (`emit_contract_call`).
+//! 2. Deliver: the target shard verifies the receipt proof, executes the call
+//!    and credits the value to the callee only if the call succeeds. The
+//!    outcome is committed as a leaf of a target-shard block's receipts root.
+//! 3. Callback: the source shard verifies the outcome proof, refunds the value
+//!    of a failed call to the caller and invokes the callback contract, if
+//!    any, with the outcome. A call that times out before delivery is
+//!    refunded and reported to its callback as a failure.
+
+use super::receipts::{BalanceEffect, ReceiptProof};
+use crate::types::{Address, Hash};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+/// Signature of the function a callback contract receives outcomes on
+pub const CALLBACK_SIGNATURE: &str = "onCrossShardResult(bytes32,bool,bytes)";
+
+/// Call carried by a cross-shard receipt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallPayload {
+    pub data: Vec<u8>,
+    pub gas_limit: u64,
+    /// Contract on the source shard that receives the outcome
+    pub callback: Option<Address>,
+}
+
+impl CallPayload {
+    pub fn hash(&self) -> Hash {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"mds-xshard-call");
+        hasher.update(&(self.data.len() as u64).to_le_bytes());
+        hasher.update(&self.data);
+        hasher.update(&self.gas_limit.to_le_bytes());
+        match &self.callback {
+            Some(callback) => {
+                hasher.update(&[1]);
+                hasher.update(callback);
+            }
+            None => {
+                hasher.update(&[0]);
+            }
+        }
+        *hasher.finalize().as_bytes()
+    }
+}
+
+/// Result of executing a cross-shard call on its target shard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallOutcome {
+    /// Transaction (and receipt) that carried the call
+    pub tx_hash: Hash,
+    pub source_shard: usize,
+    pub target_shard: usize,
+    pub caller: Address,
+    pub callee: Address,
+    pub success: bool,
+    /// Return data on success, error message on failure
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+    /// Value returned to the caller (the call value when the call failed)
+    pub refund: u128,
+    pub callback: Option<Address>,
+}
+
+impl CallOutcome {
+    /// Leaf hash committed in the target block's receipts root
+    pub fn hash(&self) -> Hash {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"mds-xshard-outcome");
+        hasher.update(&self.tx_hash);
+        hasher.update(&(self.source_shard as u64).to_le_bytes());
+        hasher.update(&(self.target_shard as u64).to_le_bytes());
+        hasher.update(&self.caller);
+        hasher.update(&self.callee);
+        hasher.update(&[self.success as u8]);
+        hasher.update(&(self.output.len() as u64).to_le_bytes());
+        hasher.update(&self.output);
+        hasher.update(&self.gas_used.to_le_bytes());
+        hasher.update(&self.refund.to_le_bytes());
+        if let Some(callback) = &self.callback {
+            hasher.update(callback);
+        }
+        *hasher.finalize().as_bytes()
+    }
+}
+
+/// Lifecycle of a call outcome on its way back to the source shard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutcomeStatus {
+    /// Committed on the target shard, waiting for the source shard
+    Pending,
+    /// Refund applied and callback invoked on the source shard
+    Delivered,
+}
+
+/// Journal entry for one call outcome
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutcomeRecord {
+    pub outcome: CallOutcome,
+    pub proof: ReceiptProof,
+    pub status: OutcomeStatus,
+    pub pending_effect: Option<BalanceEffect>,
+}
+
+/// Calldata for a callback: selector, tx hash, success word, then raw output
+pub fn callback_data(tx_hash: &Hash, success: bool, output: &[u8]) -> Vec<u8> {
+    let selector = Keccak256::digest(CALLBACK_SIGNATURE.as_bytes());
+    let mut data = Vec::with_capacity(4 + 64 + output.len());
+    data.extend_from_slice(&selector[..4]);
+    data.extend_from_slice(tx_hash);
+    let mut word = [0u8; 32];
+    word[31] = success as u8;
+    data.extend_from_slice(&word);
+    data.extend_from_slice(output);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_hash_and_callback_data() {
+        let outcome = CallOutcome {
+            tx_hash: [7u8; 32],
+            source_shard: 0,
+            target_shard: 1,
+            caller: [1u8; 20],
+            callee: [2u8; 20],
+            success: true,
+            output: vec![1, 2, 3],
+            gas_used: 21_000,
+            refund: 0,
+            callback: Some([3u8; 20]),
+        };
+        let mut failed = outcome.clone();
+        failed.success = false;
+        assert_ne!(outcome.hash(), failed.hash());
+
+        let data = callback_data(&outcome.tx_hash, true, &outcome.output);
+        assert_eq!(data.len(), 4 + 64 + 3);
+        assert_eq!(&data[4..36], &[7u8; 32]);
+        assert_eq!(data[67], 1);
+        assert_eq!(&data[68..], &[1, 2, 3]);
+    }
+}
@@ -0,0 +1,282 @@
+//! Load-aware shard rebalancing
+//!
+//! Transaction arrivals are sampled over a sliding window. A shard is hot when
+//! its load score (arrival rate, cross-shard share and pool backlog) is well
+//! above the mean. At each epoch boundary the rebalancer proposes handing ring
+//! ranges (the accounts between two virtual nodes) from hot shards to the
+//! coolest shards. In dry-run mode proposals are only reported; otherwise the
+//! ranges are migrated like a reshard, with state proofs.
+
+use super::ring::{HashRing, RingArc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Rebalancer settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebalanceConfig {
+    /// Seconds of arrivals used to measure load
+    pub window_secs: u64,
+    /// A shard is hot when its score exceeds `hot_factor` times the mean score
+    pub hot_factor: f64,
+    /// Scores below this (about one transaction per second) never count as hot
+    pub min_score: f64,
+    /// Ring ranges moved per epoch at most
+    pub max_moves: usize,
+    /// Report proposals without migrating accounts
+    pub dry_run: bool,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 60,
+            hot_factor: 1.5,
+            min_score: 1.0,
+            max_moves: 4,
+            dry_run: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Arrival {
+    at: u64,
+    shard_id: usize,
+    /// Ring position of the sender
+    position: u64,
+    cross_shard: bool,
+}
+
+impl Arrival {
+    /// Cross-shard transfers cost a lock on the source and a commit on the target
+    fn weight(&self) -> f64 {
+        if self.cross_shard { 2.0 } else { 1.0 }
+    }
+}
+
+/// Transaction arrivals within the measurement window
+#[derive(Debug, Default)]
+pub struct LoadWindow {
+    arrivals: VecDeque<Arrival>,
+}
+
+impl LoadWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, shard_id: usize, position: u64, cross_shard: bool, now: u64) {
+        self.arrivals.push_back(Arrival { at: now, shard_id, position, cross_shard });
+    }
+
+    /// Drop arrivals older than the window
+    pub fn prune(&mut self, now: u64, window_secs: u64) {
+        let cutoff = now.saturating_sub(window_secs);
+        while self.arrivals.front().map(|arrival| arrival.at < cutoff).unwrap_or(false) {
+            self.arrivals.pop_front();
+        }
+    }
+
+    /// Forget all arrivals (after a migration their shard ids are stale)
+    pub fn clear(&mut self) {
+        self.arrivals.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.arrivals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arrivals.is_empty()
+    }
+
+    /// Weighted arrivals of a shard whose sender falls in a ring range
+    fn arc_load(&self, shard_id: usize, arc: &RingArc) -> f64 {
+        self.arrivals.iter()
+            .filter(|arrival| arrival.shard_id == shard_id && arc.contains(arrival.position))
+            .map(Arrival::weight)
+            .sum()
+    }
+}
+
+/// Measured load of one shard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardLoad {
+    pub shard_id: usize,
+    pub pool_depth: usize,
+    /// Transactions per second over the window
+    pub tps: f64,
+    /// Share of the window's transactions that cross shards
+    pub cross_shard_ratio: f64,
+    /// Weighted demand in transactions per second (see `measure`)
+    pub score: f64,
+    pub hot: bool,
+}
+
+/// Ring range handed from one shard to another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeMove {
+    /// Range is (start, end] on the ring, wrapping when start >= end
+    pub start: u64,
+    pub end: u64,
+    pub from_shard: usize,
+    pub to_shard: usize,
+    /// Load score the range carried over the window
+    pub load: f64,
+    /// Accounts of the source partition inside the range
+    pub accounts: usize,
+}
+
+/// Rebalancing decision for one epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebalanceProposal {
+    pub epoch: u64,
+    pub window_secs: u64,
+    /// Highest score over mean score (1.0 = balanced, 0.0 = idle)
+    pub imbalance: f64,
+    pub loads: Vec<ShardLoad>,
+    pub moves: Vec<RangeMove>,
+    pub dry_run: bool,
+    /// Moves were migrated
+    pub applied: bool,
+}
+
+/// Load of every shard over the window
+///
+/// Score = tps weighted by cross-shard share, plus the pool backlog spread over
+/// the window (what the shard would need to clear it by the next window).
+pub fn measure(window: &LoadWindow, pool_depths: &[usize], config: &RebalanceConfig) -> Vec<ShardLoad> {
+    let window_secs = config.window_secs.max(1) as f64;
+    let mut loads: Vec<ShardLoad> = pool_depths.iter().enumerate().map(|(shard_id, pool_depth)| {
+        let (count, cross, weighted) = window.arrivals.iter()
+            .filter(|arrival| arrival.shard_id == shard_id)
+            .fold((0usize, 0usize, 0.0f64), |(count, cross, weighted), arrival| {
+                (count + 1, cross + arrival.cross_shard as usize, weighted + arrival.weight())
+            });
+        ShardLoad {
+            shard_id,
+            pool_depth: *pool_depth,
+            tps: count as f64 / window_secs,
+            cross_shard_ratio: if count == 0 { 0.0 } else { cross as f64 / count as f64 },
+            score: weighted / window_secs + *pool_depth as f64 / window_secs,
+            hot: false,
+        }
+    }).collect();
+
+    let mean = mean_score(&loads);
+    for load in loads.iter_mut() {
+        load.hot = load.score >= config.min_score && load.score > mean * config.hot_factor;
+    }
+    loads
+}
+
+/// Highest score over mean score
+pub fn imbalance(loads: &[ShardLoad]) -> f64 {
+    let mean = mean_score(loads);
+    if mean <= 0.0 {
+        return 0.0;
+    }
+    loads.iter().map(|load| load.score).fold(0.0, f64::max) / mean
+}
+
+fn mean_score(loads: &[ShardLoad]) -> f64 {
+    if loads.is_empty() {
+        return 0.0;
+    }
+    loads.iter().map(|load| load.score).sum::<f64>() / loads.len() as f64
+}
+
+/// Ring ranges to move off hot shards, hottest shard first
+///
+/// Each move gives one range of a hot shard to the currently coolest shard, and
+/// only if it lowers the hotter of the two (the range carries less than half of
+/// their score gap), so a hotspot is never just relocated.
+pub fn plan_moves(ring: &HashRing, window: &LoadWindow, loads: &[ShardLoad], config: &RebalanceConfig) -> Vec<RangeMove> {
+    let window_secs = config.window_secs.max(1) as f64;
+    let mean = mean_score(loads);
+    let mut scores: Vec<f64> = loads.iter().map(|load| load.score).collect();
+    let mut hot: Vec<usize> = loads.iter().filter(|load| load.hot).map(|load| load.shard_id).collect();
+    hot.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+
+    let arcs = ring.arcs();
+    let mut moves = Vec::new();
+    for shard_id in hot {
+        let mut candidates: Vec<(f64, &RingArc)> = arcs.iter()
+            .filter(|arc| arc.shard_id == shard_id)
+            .map(|arc| (window.arc_load(shard_id, arc) / window_secs, arc))
+            .filter(|(load, _)| *load > 0.0)
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        for (load, arc) in candidates {
+            if moves.len() >= config.max_moves || scores[shard_id] <= mean * config.hot_factor {
+                break;
+            }
+            let coolest = match (0..scores.len()).filter(|id| *id != shard_id).min_by(|a, b| scores[*a].total_cmp(&scores[*b])) {
+                Some(coolest) => coolest,
+                None => break,
+            };
+            if load * 2.0 >= scores[shard_id] - scores[coolest] {
+                continue;
+            }
+            scores[shard_id] -= load;
+            scores[coolest] += load;
+            moves.push(RangeMove {
+                start: arc.start,
+                end: arc.end,
+                from_shard: shard_id,
+                to_shard: coolest,
+                load,
+                accounts: 0,
+            });
+        }
+    }
+    moves
+}
+
+/// Ring with the proposed ranges reassigned
+pub fn apply_moves(ring: &HashRing, moves: &[RangeMove]) -> HashRing {
+    let mut ring = ring.clone();
+    for range in moves {
+        ring.reassign(range.end, range.to_shard);
+    }
+    ring
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ring::DEFAULT_VIRTUAL_NODES;
+
+    #[test]
+    fn test_hot_shard_sheds_ranges_to_cool_shard() {
+        let ring = HashRing::new(2, DEFAULT_VIRTUAL_NODES);
+        let config = RebalanceConfig::default();
+        let mut window = LoadWindow::new();
+        // Shard 0 takes 20 tx/s spread over its ranges, shard 1 is idle
+        let arcs: Vec<RingArc> = ring.arcs().into_iter().filter(|arc| arc.shard_id == 0).collect();
+        for n in 0..1200u64 {
+            let arc = &arcs[n as usize % arcs.len()];
+            window.record(0, arc.end, n % 4 == 0, 100);
+        }
+
+        let loads = measure(&window, &[500, 0], &config);
+        assert!(loads[0].hot && !loads[1].hot);
+        assert!((loads[0].tps - 20.0).abs() < 1e-9);
+        assert!((loads[0].cross_shard_ratio - 0.25).abs() < 1e-9);
+        assert!(imbalance(&loads) > 1.9);
+
+        let moves = plan_moves(&ring, &window, &loads, &config);
+        assert!(!moves.is_empty() && moves.len() <= config.max_moves);
+        assert!(moves.iter().all(|range| range.from_shard == 0 && range.to_shard == 1));
+
+        let rebalanced = apply_moves(&ring, &moves);
+        for range in &moves {
+            assert_eq!(rebalanced.owner_of_position(range.end), 1);
+        }
+
+        // An idle network is not rebalanced
+        let idle = measure(&LoadWindow::new(), &[0, 0], &config);
+        assert!(plan_moves(&ring, &LoadWindow::new(), &idle, &config).is_empty());
+    }
+}
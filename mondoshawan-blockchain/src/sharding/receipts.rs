@@ -0,0 +1,236 @@
+//! Cross-shard receipts
+//!
+//! Atomic cross-shard transfers use receipts instead of a coordinator:
+//! 1. Lock: the source shard debits the sender (value + fee) and emits a receipt.
+//!    The receipts of one round are committed as a Merkle root in the header of
+//!    a source-shard block.
+//! 2. Commit: the target shard credits the recipient after verifying the
+//!    receipt's Merkle proof against that source-shard block header.
+//! 3. Abort: a receipt not committed before `expires_at` is refunded on the
+//!    source shard and can no longer be committed.
+//!
+//! Each state change is journaled (see `storage::CrossShardStore`) before its
+//! balance effect is applied. Effects record the absolute balance after the
+//! change, so replaying an unfinished effect after a crash is idempotent.
+
+use crate::types::{Address, Hash};
+use serde::{Deserialize, Serialize};
+
+/// Seconds a receipt may wait for its target shard before it is refunded
+pub const DEFAULT_RECEIPT_TIMEOUT_SECS: u64 = 600;
+
+/// Share of a committed cross-shard fee earned by the source shard (locking
+/// and proving); the target shard earns the rest (verifying and crediting)
+pub const SOURCE_FEE_SHARE_PERCENT: u128 = 50;
+
+/// Receipt emitted by the source shard when it locks a cross-shard transfer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossShardReceipt {
+    pub tx_hash: Hash,
+    pub source_shard: usize,
+    pub target_shard: usize,
+    pub from: Address,
+    pub to: Address,
+    pub value: u128,
+    pub fee: u128,
+    pub locked_at: u64,
+    pub expires_at: u64,
+    /// Contract call to execute on the target shard (None = plain transfer)
+    pub call: Option<super::messages::CallPayload>,
+}
+
+impl CrossShardReceipt {
+    /// Leaf hash committed in the source block's receipts root
+    pub fn hash(&self) -> Hash {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"mds-xshard-receipt");
+        hasher.update(&self.tx_hash);
+        hasher.update(&(self.source_shard as u64).to_le_bytes());
+        hasher.update(&(self.target_shard as u64).to_le_bytes());
+        hasher.update(&self.from);
+        hasher.update(&self.to);
+        hasher.update(&self.value.to_le_bytes());
+        hasher.update(&self.fee.to_le_bytes());
+        hasher.update(&self.locked_at.to_le_bytes());
+        hasher.update(&self.expires_at.to_le_bytes());
+        if let Some(call) = &self.call {
+            hasher.update(&call.hash());
+        }
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Amount debited from the sender at lock time (and refunded on abort)
+    pub fn locked_amount(&self) -> u128 {
+        self.value.saturating_add(self.fee)
+    }
+
+    /// Fee earned by (source shard, target shard) once the receipt commits
+    pub fn fee_split(&self) -> (u128, u128) {
+        split_fee(self.fee)
+    }
+}
+
+/// One step of a Merkle path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub sibling: Hash,
+    /// Sibling is the left child (current node is the right child)
+    pub sibling_is_left: bool,
+}
+
+/// Merkle proof that a receipt is committed in a source-shard block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptProof {
+    pub source_block_hash: Hash,
+    pub root: Hash,
+    pub steps: Vec<ProofStep>,
+}
+
+impl ReceiptProof {
+    /// Check the Merkle path from the receipt to `root`
+    ///
+    /// The caller must separately check that `root` is the receipts root in
+    /// the header of `source_block_hash`.
+    pub fn verify(&self, receipt: &CrossShardReceipt) -> bool {
+        proof_root(receipt.hash(), &self.steps) == self.root
+    }
+}
+
+/// Lifecycle of a locked receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiptStatus {
+    /// Sender debited on the source shard, waiting for the target shard
+    Locked,
+    /// Recipient credited on the target shard
+    Committed,
+    /// Timed out and refunded on the source shard
+    Refunded,
+}
+
+/// Balance change that has been journaled but may not have been applied yet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceEffect {
+    pub shard: usize,
+    pub address: Address,
+    /// Absolute balance after the change (replaying is idempotent)
+    pub balance_after: u128,
+}
+
+/// Journal entry for one cross-shard transfer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptRecord {
+    pub receipt: CrossShardReceipt,
+    pub proof: ReceiptProof,
+    pub status: ReceiptStatus,
+    pub pending_effect: Option<BalanceEffect>,
+}
+
+/// Split a cross-shard fee into (source share, target share); rounding
+/// remainders go to the source
+pub fn split_fee(fee: u128) -> (u128, u128) {
+    let target = fee / 100 * (100 - SOURCE_FEE_SHARE_PERCENT)
+        + fee % 100 * (100 - SOURCE_FEE_SHARE_PERCENT) / 100;
+    (fee - target, target)
+}
+
+/// Merkle root over receipt hashes (odd nodes are paired with themselves)
+pub fn receipts_root(leaves: &[Hash]) -> Hash {
+    if leaves.is_empty() {
+        return [0u8; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Merkle path for the leaf at `index`
+pub fn build_proof(leaves: &[Hash], index: usize) -> Vec<ProofStep> {
+    let mut steps = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut index = index;
+    while level.len() > 1 {
+        let sibling_index = if index % 2 == 0 { index + 1 } else { index - 1 };
+        let sibling = level.get(sibling_index).copied().unwrap_or(level[index]);
+        steps.push(ProofStep {
+            sibling,
+            sibling_is_left: index % 2 == 1,
+        });
+        level = next_level(&level);
+        index /= 2;
+    }
+    steps
+}
+
+/// Root reached by following a Merkle path up from a leaf
+pub fn proof_root(leaf: Hash, steps: &[ProofStep]) -> Hash {
+    let mut node = leaf;
+    for step in steps {
+        node = if step.sibling_is_left {
+            hash_pair(&step.sibling, &node)
+        } else {
+            hash_pair(&node, &step.sibling)
+        };
+    }
+    node
+}
+
+fn next_level(level: &[Hash]) -> Vec<Hash> {
+    level.chunks(2)
+        .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+        .collect()
+}
+
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(left);
+    hasher.update(right);
+    *hasher.finalize().as_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(n: u8) -> CrossShardReceipt {
+        CrossShardReceipt {
+            tx_hash: [n; 32],
+            source_shard: 0,
+            target_shard: 1,
+            from: [1u8; 20],
+            to: [2u8; 20],
+            value: 100 + n as u128,
+            fee: 1,
+            locked_at: 10,
+            expires_at: 20,
+            call: None,
+        }
+    }
+
+    #[test]
+    fn test_receipt_proofs() {
+        let receipts: Vec<CrossShardReceipt> = (0..5).map(receipt).collect();
+        let leaves: Vec<Hash> = receipts.iter().map(|r| r.hash()).collect();
+        let root = receipts_root(&leaves);
+
+        for (index, receipt) in receipts.iter().enumerate() {
+            let proof = ReceiptProof {
+                source_block_hash: [9u8; 32],
+                root,
+                steps: build_proof(&leaves, index),
+            };
+            assert!(proof.verify(receipt));
+        }
+
+        // A tampered receipt does not verify
+        let proof = ReceiptProof { source_block_hash: [9u8; 32], root, steps: build_proof(&leaves, 0) };
+        let mut forged = receipts[0].clone();
+        forged.value += 1;
+        assert!(!proof.verify(&forged));
+
+        // Fee shares always add up to the fee
+        assert_eq!(split_fee(101), (51, 50));
+        assert_eq!(split_fee(u128::MAX).0 + split_fee(u128::MAX).1, u128::MAX);
+    }
+}
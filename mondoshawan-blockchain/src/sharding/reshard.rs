@@ -0,0 +1,193 @@
+//! Epoch-based resharding
+//!
+//! A shard count change is scheduled for the next beacon epoch. At activation
+//! every account whose owner changes under the new assignment is migrated with
+//! a Merkle proof of its (balance, nonce, contract code) against the source
+//! partition's state root; the target checks the proof against the root the
+//! latest beacon block committed for the source shard before crediting the
+//! account. Accounts whose owner does not change are not touched.
+
+use super::receipts::{self, ProofStep};
+use crate::types::{Address, Hash};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Beacon blocks per sharding epoch
+pub const DEFAULT_EPOCH_LENGTH: u64 = 100;
+
+/// Account whose owning shard changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountMove {
+    pub address: Address,
+    pub from_shard: usize,
+    pub to_shard: usize,
+}
+
+/// Account state carried from its old shard to its new shard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountMigration {
+    pub account: AccountMove,
+    pub balance: u128,
+    pub nonce: u64,
+    /// Contract code deployed at the address
+    pub code: Option<Vec<u8>>,
+    /// State root of the source partition before the migration
+    pub source_root: Hash,
+    pub proof: Vec<ProofStep>,
+}
+
+impl AccountMigration {
+    /// Check the account state against the source shard's committed state root
+    pub fn verify(&self, committed_root: &Hash) -> bool {
+        let leaf = entry_leaf(&self.account.address, self.balance, self.nonce, self.code.as_deref());
+        self.source_root == *committed_root && receipts::proof_root(leaf, &self.proof) == *committed_root
+    }
+}
+
+/// Shard count change waiting for its activation epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReshardPlan {
+    pub from_shard_count: usize,
+    pub to_shard_count: usize,
+    pub activation_epoch: u64,
+}
+
+/// Accounts that would move under a different shard count
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReshardPreview {
+    pub from_shard_count: usize,
+    pub to_shard_count: usize,
+    pub accounts_total: usize,
+    pub moves: Vec<AccountMove>,
+}
+
+/// Outcome of an executed reshard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReshardReport {
+    pub from_shard_count: usize,
+    pub to_shard_count: usize,
+    pub epoch: u64,
+    pub accounts_total: usize,
+    pub migrations: Vec<AccountMigration>,
+    /// Pooled transactions moved to their sender's new shard
+    pub transactions_moved: usize,
+}
+
+/// Leaf committing one account of a state partition
+pub fn account_leaf(address: &Address, balance: u128, nonce: u64) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"mds-account");
+    hasher.update(address);
+    hasher.update(&balance.to_le_bytes());
+    hasher.update(&nonce.to_le_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+/// Account of a state partition as (address, balance, nonce, contract code)
+pub type PartitionEntry = (Address, u128, u64, Option<Vec<u8>>);
+
+/// Leaf committing one partition entry; contract accounts also commit to their code
+pub fn entry_leaf(address: &Address, balance: u128, nonce: u64, code: Option<&[u8]>) -> Hash {
+    let code = match code {
+        Some(code) => code,
+        None => return account_leaf(address, balance, nonce),
+    };
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"mds-contract");
+    hasher.update(address);
+    hasher.update(&balance.to_le_bytes());
+    hasher.update(&nonce.to_le_bytes());
+    hasher.update(blake3::hash(code).as_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+/// Merge accounts and deployed contracts into partition entries sorted by address
+///
+/// A contract without a balance or nonce entry becomes a zero account.
+pub fn partition_entries(accounts: Vec<(Address, u128, u64)>, contracts: Vec<(Address, Vec<u8>)>) -> Vec<PartitionEntry> {
+    let mut code: HashMap<Address, Vec<u8>> = contracts.into_iter().collect();
+    let mut entries: Vec<PartitionEntry> = accounts.into_iter()
+        .map(|(address, balance, nonce)| (address, balance, nonce, code.remove(&address)))
+        .collect();
+    entries.extend(code.into_iter().map(|(address, code)| (address, 0, 0, Some(code))));
+    entries.sort_by_key(|entry| entry.0);
+    entries
+}
+
+/// Merkle root over partition entries (equal to `state_root` without contracts)
+pub fn partition_root(entries: &[PartitionEntry]) -> Hash {
+    receipts::receipts_root(&partition_leaves(entries))
+}
+
+/// Merkle path for the partition entry at `index`
+pub fn partition_proof(entries: &[PartitionEntry], index: usize) -> Vec<ProofStep> {
+    receipts::build_proof(&partition_leaves(entries), index)
+}
+
+fn partition_leaves(entries: &[PartitionEntry]) -> Vec<Hash> {
+    entries.iter()
+        .map(|(address, balance, nonce, code)| entry_leaf(address, *balance, *nonce, code.as_deref()))
+        .collect()
+}
+
+/// Merkle root over accounts sorted by address
+pub fn state_root(accounts: &[(Address, u128, u64)]) -> Hash {
+    let leaves: Vec<Hash> = accounts.iter()
+        .map(|(address, balance, nonce)| account_leaf(address, *balance, *nonce))
+        .collect();
+    receipts::receipts_root(&leaves)
+}
+
+/// Merkle path for the account at `index` of the sorted accounts
+pub fn state_proof(accounts: &[(Address, u128, u64)], index: usize) -> Vec<ProofStep> {
+    let leaves: Vec<Hash> = accounts.iter()
+        .map(|(address, balance, nonce)| account_leaf(address, *balance, *nonce))
+        .collect();
+    receipts::build_proof(&leaves, index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_migration_proof() {
+        let accounts: Vec<(Address, u128, u64)> = (1u8..=5).map(|n| ([n; 20], n as u128 * 100, n as u64)).collect();
+        let root = state_root(&accounts);
+        let mut migration = AccountMigration {
+            account: AccountMove { address: [3u8; 20], from_shard: 0, to_shard: 1 },
+            balance: 300,
+            nonce: 3,
+            code: None,
+            source_root: root,
+            proof: state_proof(&accounts, 2),
+        };
+        assert!(migration.verify(&root));
+        assert!(!migration.verify(&[0u8; 32]));
+
+        migration.balance += 1;
+        assert!(!migration.verify(&root));
+    }
+
+    #[test]
+    fn test_contract_code_is_committed() {
+        let accounts: Vec<(Address, u128, u64)> = (1u8..=3).map(|n| ([n; 20], n as u128, 0)).collect();
+        assert_eq!(partition_root(&partition_entries(accounts.clone(), Vec::new())), state_root(&accounts));
+
+        let entries = partition_entries(accounts, vec![([2u8; 20], vec![0x60, 0x00]), ([9u8; 20], vec![0x01])]);
+        assert_eq!(entries.len(), 4);
+        let root = partition_root(&entries);
+        let mut migration = AccountMigration {
+            account: AccountMove { address: [9u8; 20], from_shard: 0, to_shard: 1 },
+            balance: 0,
+            nonce: 0,
+            code: Some(vec![0x01]),
+            source_root: root,
+            proof: partition_proof(&entries, 3),
+        };
+        assert!(migration.verify(&root));
+
+        migration.code = Some(vec![0x02]);
+        assert!(!migration.verify(&root));
+    }
+}
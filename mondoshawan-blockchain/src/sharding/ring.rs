@@ -0,0 +1,155 @@
+//! Consistent hash ring
+//!
+//! Each shard owns `virtual_nodes` points on a 64-bit ring. An address belongs
+//! to the first point at or after its own position (wrapping around), so adding
+//! or removing a shard only moves the accounts between the changed points and
+//! their predecessors - about 1/n of all accounts - instead of nearly all of
+//! them as with `hash % shard_count`.
+
+use crate::types::Address;
+use serde::{Deserialize, Serialize};
+
+/// Points per shard on the ring (more points = more even distribution)
+pub const DEFAULT_VIRTUAL_NODES: usize = 64;
+
+/// Hash ring over shard virtual nodes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashRing {
+    shard_count: usize,
+    virtual_nodes: usize,
+    /// (position, shard id), sorted by position
+    points: Vec<(u64, usize)>,
+}
+
+impl HashRing {
+    pub fn new(shard_count: usize, virtual_nodes: usize) -> Self {
+        let virtual_nodes = virtual_nodes.max(1);
+        let mut points = Vec::with_capacity(shard_count * virtual_nodes);
+        for shard_id in 0..shard_count {
+            for vnode in 0..virtual_nodes {
+                points.push((point_position(shard_id, vnode), shard_id));
+            }
+        }
+        points.sort_unstable();
+        Self { shard_count, virtual_nodes, points }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shard_count
+    }
+
+    pub fn virtual_nodes(&self) -> usize {
+        self.virtual_nodes
+    }
+
+    /// Shard that owns an address
+    pub fn shard_for(&self, address: &Address) -> usize {
+        self.owner_of_position(address_position(address))
+    }
+
+    /// Shard that owns a ring position
+    pub fn owner_of_position(&self, position: u64) -> usize {
+        if self.points.is_empty() {
+            return 0;
+        }
+        let index = self.points.partition_point(|(point, _)| *point < position);
+        self.points[index % self.points.len()].1
+    }
+
+    /// Ranges owned by each virtual node, in ring order
+    pub fn arcs(&self) -> Vec<RingArc> {
+        let last = match self.points.last() {
+            Some((position, _)) => *position,
+            None => return Vec::new(),
+        };
+        let mut start = last;
+        self.points.iter().map(|(end, shard_id)| {
+            let arc = RingArc { start, end: *end, shard_id: *shard_id };
+            start = *end;
+            arc
+        }).collect()
+    }
+
+    /// Hand the range ending at virtual node `point` to another shard
+    ///
+    /// Returns false if there is no such point or the shard does not exist.
+    /// Rebuilding the ring for a new shard count drops reassignments.
+    pub fn reassign(&mut self, point: u64, shard_id: usize) -> bool {
+        if shard_id >= self.shard_count {
+            return false;
+        }
+        match self.points.binary_search_by(|(position, _)| position.cmp(&point)) {
+            Ok(index) => {
+                self.points[index].1 = shard_id;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// Range of ring positions (start, end] owned by one virtual node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RingArc {
+    pub start: u64,
+    pub end: u64,
+    pub shard_id: usize,
+}
+
+impl RingArc {
+    pub fn contains(&self, position: u64) -> bool {
+        if self.start < self.end {
+            position > self.start && position <= self.end
+        } else {
+            // Wraps past zero (or spans the whole ring with a single point)
+            position > self.start || position <= self.end
+        }
+    }
+}
+
+/// Position of an address on the ring (also the key used by modulo strategies)
+pub fn address_position(address: &Address) -> u64 {
+    let hash = blake3::hash(address);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_bytes()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn point_position(shard_id: usize, vnode: usize) -> u64 {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"mds-shard-ring");
+    hasher.update(&(shard_id as u64).to_le_bytes());
+    hasher.update(&(vnode as u64).to_le_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hasher.finalize().as_bytes()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(n: u32) -> Address {
+        let mut address = [0u8; 20];
+        address[..4].copy_from_slice(&n.to_le_bytes());
+        address
+    }
+
+    #[test]
+    fn test_adding_a_shard_moves_few_accounts() {
+        let old = HashRing::new(8, DEFAULT_VIRTUAL_NODES);
+        let new = HashRing::new(9, DEFAULT_VIRTUAL_NODES);
+        let total = 10_000;
+        let mut moved = 0;
+        for n in 0..total {
+            let (from, to) = (old.shard_for(&address(n)), new.shard_for(&address(n)));
+            if from != to {
+                // Accounts only move onto the new shard
+                assert_eq!(to, 8);
+                moved += 1;
+            }
+        }
+        // Ideal is 1/9 (~11%); modulo hashing would move ~89%
+        assert!(moved > 0 && moved < total / 5, "moved {} of {}", moved, total);
+    }
+}
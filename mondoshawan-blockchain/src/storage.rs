@@ -1,20 +1,115 @@
 //! Storage layer for blockchain data
+//!
+//! Typed stores encode values over a `Database`, which delegates to a
+//! `KeyValueStore` backend (sled on disk, `MemoryStore` for tests).
 
 use crate::blockchain::Block;
 use crate::types::Hash;
-use sled::Db;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-/// Database handle
+pub mod kv;
+pub mod memory;
+pub mod schema;
+pub mod sled_store;
+
+pub use kv::{KeyValueStore, KvIter, Namespace, WriteBatch};
+pub use memory::MemoryStore;
+pub use sled_store::SledStore;
+
+/// Database handle over a `KeyValueStore` backend
 pub struct Database {
-    db: Db,
+    store: Box<dyn KeyValueStore>,
 }
 
 impl Database {
+    /// Open (or create) a sled database and migrate it to the current schema
+    ///
+    /// The database is backed up to `<path>.backup-v<version>` before an upgrade.
     pub fn open<P: AsRef<Path>>(path: P) -> crate::error::BlockchainResult<Self> {
-        let db = sled::open(path)
-            .map_err(|e| crate::error::BlockchainError::Storage(format!("Failed to open database: {}", e)))?;
-        Ok(Self { db })
+        let db = Self::open_unmigrated(&path)?;
+        let backup = Self::backup_path(&path, schema::stored_version(&db)?.unwrap_or(1));
+        let report = schema::migrate(&db, &schema::MigrationOptions { dry_run: false, backup: Some(&backup) })?;
+        for (description, rewritten) in &report.steps {
+            eprintln!("Migrated {} record(s): {}", rewritten, description);
+        }
+        Ok(db)
+    }
+
+    /// Open a sled database without migrating it (see `schema::migrate`)
+    pub fn open_unmigrated<P: AsRef<Path>>(path: P) -> crate::error::BlockchainResult<Self> {
+        Ok(Self::with_store(Box::new(SledStore::open(path)?)))
+    }
+
+    /// Open an existing sled database exactly as it is
+    ///
+    /// Unlike `open_unmigrated`, keys left in the default tree by releases
+    /// before storage namespaces are not moved; such a database is refused.
+    pub fn open_existing_unmigrated<P: AsRef<Path>>(path: P) -> crate::error::BlockchainResult<Self> {
+        if !path.as_ref().exists() {
+            return Err(crate::error::BlockchainError::Storage(format!("No database at {}", path.as_ref().display())));
+        }
+        let store = SledStore::open_unsplit(&path)?;
+        let legacy = store.legacy_key_count();
+        if legacy > 0 {
+            return Err(crate::error::BlockchainError::Storage(format!(
+                "Database holds {} key(s) from before storage namespaces; run `node migrate` first", legacy
+            )));
+        }
+        Ok(Self::with_store(Box::new(store)))
+    }
+
+    /// Where `open` backs up a database before upgrading it from `version`
+    pub fn backup_path<P: AsRef<Path>>(path: P, version: u32) -> std::path::PathBuf {
+        let mut backup = path.as_ref().as_os_str().to_owned();
+        backup.push(format!(".backup-v{}", version));
+        backup.into()
+    }
+
+    /// Empty database held in memory
+    pub fn in_memory() -> Self {
+        Self::with_store(Box::new(MemoryStore::new()))
+    }
+
+    pub fn with_store(store: Box<dyn KeyValueStore>) -> Self {
+        Self { store }
+    }
+
+    pub fn get(&self, namespace: Namespace, key: impl AsRef<[u8]>) -> crate::error::BlockchainResult<Option<Vec<u8>>> {
+        self.store.get(namespace, key.as_ref())
+    }
+
+    pub fn put(&self, namespace: Namespace, key: impl AsRef<[u8]>, value: impl AsRef<[u8]>) -> crate::error::BlockchainResult<()> {
+        self.store.put(namespace, key.as_ref(), value.as_ref())
+    }
+
+    pub fn delete(&self, namespace: Namespace, key: impl AsRef<[u8]>) -> crate::error::BlockchainResult<()> {
+        self.store.delete(namespace, key.as_ref())
+    }
+
+    pub fn scan_prefix(&self, namespace: Namespace, prefix: impl AsRef<[u8]>) -> KvIter<'_> {
+        self.store.scan_prefix(namespace, prefix.as_ref())
+    }
+
+    /// Entries with keys in `start..=end`
+    pub fn scan_range(&self, namespace: Namespace, start: impl AsRef<[u8]>, end: impl AsRef<[u8]>) -> KvIter<'_> {
+        self.store.scan_range(namespace, start.as_ref(), end.as_ref())
+    }
+
+    /// Flush pending writes to disk (durability point for journaled updates)
+    pub fn flush(&self) -> crate::error::BlockchainResult<()> {
+        self.store.flush()
+    }
+
+    /// Apply a write batch atomically: after a crash either all of it or none
+    /// of it is on disk
+    pub fn apply(&self, batch: WriteBatch) -> crate::error::BlockchainResult<()> {
+        self.store.apply(batch)
+    }
+
+    /// Consistent read-only copy of the database, held in memory
+    pub fn snapshot(&self) -> crate::error::BlockchainResult<Database> {
+        Ok(Self::with_store(Box::new(self.store.snapshot()?)))
     }
 }
 
@@ -30,17 +125,32 @@ impl<'a> BlockStore<'a> {
 
     pub fn put(&self, block: &Block) -> crate::error::BlockchainResult<()> {
         let key = block.hash;
-        let value = bincode::serialize(block)?;
-        self.db.db.insert(key, value)
-            .map_err(|e| crate::error::BlockchainError::Storage(format!("Database error: {}", e)))?;
+        let value = schema::encode(block)?;
+        self.db.put(Namespace::Blocks, key, value)?;
+        Ok(())
+    }
+
+    /// Stage a block in a write batch
+    pub fn stage(batch: &mut WriteBatch, block: &Block) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::Blocks, block.hash.to_vec(), schema::encode(block)?);
         Ok(())
     }
 
+    /// Remove a block body
+    pub fn delete(&self, hash: &Hash) -> crate::error::BlockchainResult<()> {
+        self.db.delete(Namespace::Blocks, hash)?;
+        Ok(())
+    }
+
+    /// Stage the removal of a block body
+    pub fn stage_delete(batch: &mut WriteBatch, hash: &Hash) {
+        batch.remove(Namespace::Blocks, hash.to_vec());
+    }
+
     pub fn get(&self, hash: &Hash) -> crate::error::BlockchainResult<Option<Block>> {
-        match self.db.db.get(hash)
-            .map_err(|e| crate::error::BlockchainError::Storage(format!("Database error: {}", e)))? {
+        match self.db.get(Namespace::Blocks, hash)? {
             Some(value) => {
-                let block: Block = bincode::deserialize(&value)?;
+                let block: Block = schema::decode(&value)?;
                 Ok(Some(block))
             }
             None => Ok(None),
@@ -48,6 +158,468 @@ impl<'a> BlockStore<'a> {
     }
 }
 
+/// Last fully applied block of the main chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainTip {
+    /// Blocks applied (the length of the block index that is valid)
+    pub block_count: u64,
+    pub tip_hash: Hash,
+}
+
+/// Header kept in place of a pruned block body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredHeader {
+    pub hash: Hash,
+    pub header: crate::blockchain::BlockHeader,
+    pub transaction_count: u64,
+}
+
+impl StoredHeader {
+    pub fn meta(&self) -> crate::blockchain::BlockMeta {
+        crate::blockchain::BlockMeta::from_header(self.hash, &self.header, self.transaction_count as usize)
+    }
+}
+
+/// Chain index store
+///
+/// Accepted blocks are indexed by acceptance order (`chain:index:` +
+/// big-endian sequence number) so startup replays them in the order the DAG
+/// saw them. `chain:tip` is written last for each block: index entries past
+/// it belong to a block that was never fully applied. `chain:pruned` counts
+/// the leading index entries whose bodies were pruned; their headers are kept
+/// under `chain:header:`.
+///
+/// State changes made outside blocks (development funding, an imported
+/// snapshot) are journaled under `chain:adjust:` + the number of blocks
+/// applied when they were made + address + kind, so a replay of the blocks
+/// can reproduce them. `chain:reset` records the block count at which a
+/// snapshot replaced the whole state.
+pub struct ChainStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ChainStore<'a> {
+    const INDEX_PREFIX: &'static [u8] = b"chain:index:";
+    const REWARD_PREFIX: &'static [u8] = b"chain:reward:";
+    const HEADER_PREFIX: &'static [u8] = b"chain:header:";
+    const TIP_KEY: &'static [u8] = b"chain:tip";
+    const PRUNED_KEY: &'static [u8] = b"chain:pruned";
+    const ADJUST_PREFIX: &'static [u8] = b"chain:adjust:";
+    const RESET_KEY: &'static [u8] = b"chain:reset";
+
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn prefixed(prefix: &[u8], suffix: &[u8]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(prefix.len() + suffix.len());
+        key.extend_from_slice(prefix);
+        key.extend_from_slice(suffix);
+        key
+    }
+
+    /// Record the block accepted at position `sequence`
+    pub fn put_index(&self, sequence: u64, hash: &Hash) -> crate::error::BlockchainResult<()> {
+        self.db.put(Namespace::Chain, Self::prefixed(Self::INDEX_PREFIX, &sequence.to_be_bytes()), hash.to_vec())?;
+        Ok(())
+    }
+
+    pub fn stage_index(batch: &mut WriteBatch, sequence: u64, hash: &Hash) {
+        batch.insert(Namespace::Chain, Self::prefixed(Self::INDEX_PREFIX, &sequence.to_be_bytes()), hash.to_vec());
+    }
+
+    /// Drop index entries from position `sequence` on (blocks never fully applied)
+    pub fn truncate_index(&self, sequence: u64) -> crate::error::BlockchainResult<Vec<Hash>> {
+        let start = Self::prefixed(Self::INDEX_PREFIX, &sequence.to_be_bytes());
+        let end = Self::prefixed(Self::INDEX_PREFIX, &u64::MAX.to_be_bytes());
+        let mut removed = Vec::new();
+        for item in self.db.scan_range(Namespace::Chain, start, end) {
+            let (key, value) = item?;
+            if let Ok(hash) = <Hash>::try_from(value.as_slice()) {
+                removed.push(hash);
+            }
+            self.db.delete(Namespace::Chain, key)?;
+        }
+        Ok(removed)
+    }
+
+    /// Block hashes in acceptance order
+    pub fn get_index(&self) -> crate::error::BlockchainResult<Vec<Hash>> {
+        let mut hashes = Vec::new();
+        for item in self.db.scan_prefix(Namespace::Chain, Self::INDEX_PREFIX) {
+            let (_, value) = item?;
+            let hash: Hash = value.as_slice().try_into()
+                .map_err(|_| crate::error::BlockchainError::Storage("Corrupt block index entry".to_string()))?;
+            hashes.push(hash);
+        }
+        Ok(hashes)
+    }
+
+    /// Store the reward paid to a block's coinbase
+    pub fn put_reward(&self, hash: &Hash, reward: u128) -> crate::error::BlockchainResult<()> {
+        self.db.put(Namespace::Chain, Self::prefixed(Self::REWARD_PREFIX, hash), reward.to_le_bytes().to_vec())?;
+        Ok(())
+    }
+
+    pub fn stage_reward(batch: &mut WriteBatch, hash: &Hash, reward: u128) {
+        batch.insert(Namespace::Chain, Self::prefixed(Self::REWARD_PREFIX, hash), reward.to_le_bytes().to_vec());
+    }
+
+    pub fn get_reward(&self, hash: &Hash) -> crate::error::BlockchainResult<Option<u128>> {
+        match self.db.get(Namespace::Chain, Self::prefixed(Self::REWARD_PREFIX, hash))? {
+            Some(value) => {
+                let bytes: [u8; 16] = value.as_slice().try_into()
+                    .map_err(|_| crate::error::BlockchainError::Storage("Corrupt block reward entry".to_string()))?;
+                Ok(Some(u128::from_le_bytes(bytes)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn put_tip(&self, tip: &ChainTip) -> crate::error::BlockchainResult<()> {
+        let value = bincode::serialize(tip)?;
+        self.db.put(Namespace::Chain, Self::TIP_KEY, value)?;
+        Ok(())
+    }
+
+    /// Stage the last-applied marker; staged last so it commits with the block
+    pub fn stage_tip(batch: &mut WriteBatch, tip: &ChainTip) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::Chain, Self::TIP_KEY.to_vec(), bincode::serialize(tip)?);
+        Ok(())
+    }
+
+    pub fn get_tip(&self) -> crate::error::BlockchainResult<Option<ChainTip>> {
+        match self.db.get(Namespace::Chain, Self::TIP_KEY)? {
+            Some(value) => Ok(Some(bincode::deserialize(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stage the header of a block whose body is being pruned
+    pub fn stage_header(batch: &mut WriteBatch, block: &Block) -> crate::error::BlockchainResult<()> {
+        let header = StoredHeader {
+            hash: block.hash,
+            header: block.header.clone(),
+            transaction_count: block.transactions.len() as u64,
+        };
+        batch.insert(Namespace::Chain, Self::prefixed(Self::HEADER_PREFIX, &block.hash), bincode::serialize(&header)?);
+        Ok(())
+    }
+
+    pub fn get_header(&self, hash: &Hash) -> crate::error::BlockchainResult<Option<StoredHeader>> {
+        match self.db.get(Namespace::Chain, Self::prefixed(Self::HEADER_PREFIX, hash))? {
+            Some(value) => Ok(Some(bincode::deserialize(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stage the number of leading index entries that are header-only
+    pub fn stage_pruned(batch: &mut WriteBatch, count: u64) {
+        batch.insert(Namespace::Chain, Self::PRUNED_KEY.to_vec(), count.to_be_bytes().to_vec());
+    }
+
+    pub fn get_pruned(&self) -> crate::error::BlockchainResult<u64> {
+        match self.db.get(Namespace::Chain, Self::PRUNED_KEY)? {
+            Some(value) => {
+                let bytes: [u8; 8] = value.as_slice().try_into()
+                    .map_err(|_| crate::error::BlockchainError::Storage("Corrupt pruning watermark".to_string()))?;
+                Ok(u64::from_be_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn adjustment_key(sequence: u64, address: &crate::types::Address, adjustment: &StateAdjustment) -> Vec<u8> {
+        let kind = match adjustment {
+            StateAdjustment::Balance(_) => b'b',
+            StateAdjustment::Nonce(_) => b'n',
+        };
+        let mut key = Self::prefixed(Self::ADJUST_PREFIX, &sequence.to_be_bytes());
+        key.extend_from_slice(address);
+        key.push(kind);
+        key
+    }
+
+    /// Journal a state change made after `sequence` blocks outside any block
+    /// (a later change of the same value at the same point replaces it)
+    pub fn put_adjustment(&self, sequence: u64, address: &crate::types::Address, adjustment: StateAdjustment) -> crate::error::BlockchainResult<()> {
+        self.db.put(Namespace::Chain, Self::adjustment_key(sequence, address, &adjustment), bincode::serialize(&adjustment)?)?;
+        Ok(())
+    }
+
+    pub fn stage_adjustment(batch: &mut WriteBatch, sequence: u64, address: &crate::types::Address, adjustment: StateAdjustment) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::Chain, Self::adjustment_key(sequence, address, &adjustment), bincode::serialize(&adjustment)?);
+        Ok(())
+    }
+
+    /// Journaled state changes as (blocks applied, address, change), in block order
+    pub fn get_adjustments(&self) -> crate::error::BlockchainResult<Vec<(u64, crate::types::Address, StateAdjustment)>> {
+        let mut adjustments = Vec::new();
+        for item in self.db.scan_prefix(Namespace::Chain, Self::ADJUST_PREFIX) {
+            let (key, value) = item?;
+            let suffix = &key[Self::ADJUST_PREFIX.len()..];
+            if suffix.len() != 29 {
+                return Err(crate::error::BlockchainError::Storage("Corrupt state adjustment entry".to_string()));
+            }
+            let sequence = u64::from_be_bytes(suffix[..8].try_into().expect("length checked"));
+            let address: crate::types::Address = suffix[8..28].try_into().expect("length checked");
+            adjustments.push((sequence, address, bincode::deserialize(&value)?));
+        }
+        Ok(adjustments)
+    }
+
+    /// Drop the state changes journaled after more than `sequence` blocks
+    pub fn truncate_adjustments(&self, sequence: u64) -> crate::error::BlockchainResult<usize> {
+        let mut batch = WriteBatch::new();
+        for item in self.db.scan_prefix(Namespace::Chain, Self::ADJUST_PREFIX) {
+            let (key, _) = item?;
+            let suffix = &key[Self::ADJUST_PREFIX.len()..];
+            if suffix.len() >= 8 && u64::from_be_bytes(suffix[..8].try_into().expect("length checked")) > sequence {
+                batch.remove(Namespace::Chain, key.to_vec());
+            }
+        }
+        let removed = batch.len();
+        self.db.apply(batch)?;
+        Ok(removed)
+    }
+
+    /// Stage the block count at which a snapshot replaced the state
+    pub fn stage_reset(batch: &mut WriteBatch, sequence: u64) {
+        batch.insert(Namespace::Chain, Self::RESET_KEY.to_vec(), sequence.to_be_bytes().to_vec());
+    }
+
+    pub fn get_reset(&self) -> crate::error::BlockchainResult<Option<u64>> {
+        match self.db.get(Namespace::Chain, Self::RESET_KEY)? {
+            Some(value) => {
+                let bytes: [u8; 8] = value.as_slice().try_into()
+                    .map_err(|_| crate::error::BlockchainError::Storage("Corrupt state reset marker".to_string()))?;
+                Ok(Some(u64::from_be_bytes(bytes)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Every stored block body, unordered (indexes databases written before
+    /// the block index existed)
+    pub fn legacy_blocks(&self) -> crate::error::BlockchainResult<Vec<Block>> {
+        let mut blocks = Vec::new();
+        for item in self.db.scan_prefix(Namespace::Blocks, b"") {
+            let (key, value) = item?;
+            if key.len() != 32 {
+                continue;
+            }
+            if let Ok(block) = schema::decode::<Block>(&value) {
+                if block.hash.as_slice() == key.as_slice() {
+                    blocks.push(block);
+                }
+            }
+        }
+        Ok(blocks)
+    }
+}
+
+/// State change made outside a block, as journaled by `ChainStore`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StateAdjustment {
+    Balance(u128),
+    Nonce(u64),
+}
+
+/// Where an included transaction lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxLocation {
+    pub block_hash: Hash,
+    /// Position in the block's transaction list
+    pub index: u32,
+}
+
+/// Secondary indexes over accepted blocks
+///
+/// - `idx:number:` + number + block hash: the blocks at a height (a DAG has several)
+/// - `idx:tx:` + tx hash: `TxLocation`
+/// - `idx:addr:` + address + ordinal: tx hashes sent or received, numbered
+///   per address in acceptance order so a page is one range read
+/// - `idx:addrcount:` + address: the next ordinal and the first live one
+///   (pruning drops the oldest entries)
+///
+/// Entries are staged with the block they describe; `idx:meta:count` records
+/// how many blocks of the chain index are covered. Address counters staged
+/// through one store carry over between blocks, so use one store per batch.
+pub struct IndexStore<'a> {
+    db: &'a Database,
+    counters: std::collections::HashMap<crate::types::Address, AddressCount>,
+}
+
+/// Address index counter: entries `first..next` are live
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct AddressCount {
+    next: u64,
+    first: u64,
+}
+
+impl<'a> IndexStore<'a> {
+    const PREFIX: &'static [u8] = b"idx:";
+    const NUMBER_PREFIX: &'static [u8] = b"idx:number:";
+    const TX_PREFIX: &'static [u8] = b"idx:tx:";
+    const ADDRESS_PREFIX: &'static [u8] = b"idx:addr:";
+    const ADDRESS_COUNT_PREFIX: &'static [u8] = b"idx:addrcount:";
+    const COUNT_KEY: &'static [u8] = b"idx:meta:count";
+
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, counters: std::collections::HashMap::new() }
+    }
+
+    fn key(prefix: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+        let mut key = prefix.to_vec();
+        for part in parts {
+            key.extend_from_slice(part);
+        }
+        key
+    }
+
+    fn address_count(&self, address: &crate::types::Address) -> crate::error::BlockchainResult<AddressCount> {
+        match self.db.get(Namespace::Indexes, Self::key(Self::ADDRESS_COUNT_PREFIX, &[address]))? {
+            Some(value) if value.len() == 16 => Ok(AddressCount {
+                next: u64::from_be_bytes(value[..8].try_into().expect("length checked")),
+                first: u64::from_be_bytes(value[8..].try_into().expect("length checked")),
+            }),
+            Some(_) => Err(crate::error::BlockchainError::Storage("Corrupt address index counter".to_string())),
+            None => Ok(AddressCount::default()),
+        }
+    }
+
+    /// Counter of an address as staged so far
+    fn staged_count(&mut self, address: &crate::types::Address) -> crate::error::BlockchainResult<&mut AddressCount> {
+        if !self.counters.contains_key(address) {
+            let count = self.address_count(address)?;
+            self.counters.insert(*address, count);
+        }
+        Ok(self.counters.get_mut(address).expect("inserted above"))
+    }
+
+    fn stage_count(batch: &mut WriteBatch, address: &crate::types::Address, count: &AddressCount) {
+        let value = [count.next.to_be_bytes(), count.first.to_be_bytes()].concat();
+        batch.insert(Namespace::Indexes, Self::key(Self::ADDRESS_COUNT_PREFIX, &[address]), value);
+    }
+
+    /// Addresses a transaction is indexed under
+    fn addresses(tx: &crate::blockchain::Transaction) -> Vec<crate::types::Address> {
+        if tx.to == tx.from { vec![tx.from] } else { vec![tx.from, tx.to] }
+    }
+
+    /// Stage the index entries of the block accepted at position `sequence`
+    pub fn stage_block(&mut self, batch: &mut WriteBatch, sequence: u64, block: &Block) -> crate::error::BlockchainResult<()> {
+        Self::stage_header(batch, sequence, block.header.block_number, &block.hash);
+        for (index, tx) in block.transactions.iter().enumerate() {
+            let location = TxLocation { block_hash: block.hash, index: index as u32 };
+            batch.insert(Namespace::Indexes, Self::key(Self::TX_PREFIX, &[&tx.hash]), bincode::serialize(&location)?);
+            for address in Self::addresses(tx) {
+                let count = self.staged_count(&address)?;
+                batch.insert(Namespace::Indexes, Self::key(Self::ADDRESS_PREFIX, &[&address, &count.next.to_be_bytes()]), tx.hash.to_vec());
+                count.next += 1;
+                let count = *count;
+                Self::stage_count(batch, &address, &count);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stage the height entry of a block known only by its header (pruned body)
+    pub fn stage_header(batch: &mut WriteBatch, sequence: u64, block_number: u64, hash: &Hash) {
+        batch.insert(Namespace::Indexes, Self::key(Self::NUMBER_PREFIX, &[&block_number.to_be_bytes(), hash]), Vec::new());
+        batch.insert(Namespace::Indexes, Self::COUNT_KEY.to_vec(), (sequence + 1).to_be_bytes().to_vec());
+    }
+
+    /// Stage the removal of a block's transaction and address entries (its
+    /// height entry stays with the header)
+    ///
+    /// Blocks are pruned in acceptance order, so a block's address entries
+    /// are the oldest live ones of each address.
+    pub fn stage_prune(&mut self, batch: &mut WriteBatch, block: &Block) -> crate::error::BlockchainResult<()> {
+        for tx in &block.transactions {
+            batch.remove(Namespace::Indexes, Self::key(Self::TX_PREFIX, &[&tx.hash]));
+            for address in Self::addresses(tx) {
+                let count = self.staged_count(&address)?;
+                if count.first < count.next {
+                    batch.remove(Namespace::Indexes, Self::key(Self::ADDRESS_PREFIX, &[&address, &count.first.to_be_bytes()]));
+                    count.first += 1;
+                }
+                let count = *count;
+                Self::stage_count(batch, &address, &count);
+            }
+        }
+        Ok(())
+    }
+
+    /// Blocks of the chain index covered by the secondary indexes
+    pub fn indexed_count(&self) -> crate::error::BlockchainResult<u64> {
+        match self.db.get(Namespace::Indexes, Self::COUNT_KEY)? {
+            Some(value) => {
+                let bytes: [u8; 8] = value.as_slice().try_into()
+                    .map_err(|_| crate::error::BlockchainError::Storage("Corrupt index count".to_string()))?;
+                Ok(u64::from_be_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Hashes of the blocks at a height, in hash order
+    pub fn get_block_hashes(&self, number: u64) -> crate::error::BlockchainResult<Vec<Hash>> {
+        let prefix = Self::key(Self::NUMBER_PREFIX, &[&number.to_be_bytes()]);
+        let mut hashes = Vec::new();
+        for item in self.db.scan_prefix(Namespace::Indexes, &prefix) {
+            let (key, _) = item?;
+            let hash: Hash = key[prefix.len()..].try_into()
+                .map_err(|_| crate::error::BlockchainError::Storage("Corrupt block number index entry".to_string()))?;
+            hashes.push(hash);
+        }
+        Ok(hashes)
+    }
+
+    pub fn get_transaction_location(&self, hash: &Hash) -> crate::error::BlockchainResult<Option<TxLocation>> {
+        match self.db.get(Namespace::Indexes, Self::key(Self::TX_PREFIX, &[hash]))? {
+            Some(value) => Ok(Some(bincode::deserialize(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Transaction hashes involving an address, newest first
+    ///
+    /// Skips `offset` entries and returns at most `limit`, with the total count.
+    /// Reads only the requested page.
+    pub fn get_address_transactions(&self, address: &crate::types::Address, offset: usize, limit: usize) -> crate::error::BlockchainResult<(usize, Vec<Hash>)> {
+        let count = self.address_count(address)?;
+        let total = (count.next - count.first) as usize;
+        let end = count.next.saturating_sub(offset as u64);
+        let start = end.saturating_sub(limit as u64).max(count.first);
+        if start >= end {
+            return Ok((total, Vec::new()));
+        }
+        let first = Self::key(Self::ADDRESS_PREFIX, &[address, &start.to_be_bytes()]);
+        let last = Self::key(Self::ADDRESS_PREFIX, &[address, &(end - 1).to_be_bytes()]);
+        let mut hashes = Vec::with_capacity((end - start) as usize);
+        for item in self.db.scan_range(Namespace::Indexes, first, last).rev() {
+            let (_, value) = item?;
+            let hash: Hash = value.as_slice().try_into()
+                .map_err(|_| crate::error::BlockchainError::Storage("Corrupt address index entry".to_string()))?;
+            hashes.push(hash);
+        }
+        Ok((total, hashes))
+    }
+
+    /// Remove every secondary index entry (before a reindex)
+    pub fn clear(&self) -> crate::error::BlockchainResult<usize> {
+        let mut batch = WriteBatch::new();
+        for item in self.db.scan_prefix(Namespace::Indexes, Self::PREFIX) {
+            let (key, _) = item?;
+            batch.remove(Namespace::Indexes, key.to_vec());
+        }
+        let removed = batch.len();
+        self.db.apply(batch)?;
+        Ok(removed)
+    }
+}
+
 /// State store
 pub struct StateStore<'a> {
     db: &'a Database,
@@ -62,16 +634,26 @@ impl<'a> StateStore<'a> {
     pub fn put_balance(&self, address: &crate::types::Address, balance: u128) -> crate::error::BlockchainResult<()> {
         let key = format!("balance:{}", hex::encode(address));
         let value = balance.to_le_bytes().to_vec();
-        self.db.db.insert(key.as_bytes(), value)
-            .map_err(|e| crate::error::BlockchainError::Storage(format!("Database error: {}", e)))?;
+        self.db.put(Namespace::State, key.as_bytes(), value)?;
         Ok(())
     }
 
+    /// Stage a balance in a write batch
+    pub fn stage_balance(batch: &mut WriteBatch, address: &crate::types::Address, balance: u128) {
+        let key = format!("balance:{}", hex::encode(address));
+        batch.insert(Namespace::State, key.into_bytes(), balance.to_le_bytes().to_vec());
+    }
+
+    /// Stage a nonce in a write batch
+    pub fn stage_nonce(batch: &mut WriteBatch, address: &crate::types::Address, nonce: u64) {
+        let key = format!("nonce:{}", hex::encode(address));
+        batch.insert(Namespace::State, key.into_bytes(), nonce.to_le_bytes().to_vec());
+    }
+
     /// Get balance for an address
     pub fn get_balance(&self, address: &crate::types::Address) -> crate::error::BlockchainResult<Option<u128>> {
         let key = format!("balance:{}", hex::encode(address));
-        match self.db.db.get(key.as_bytes())
-            .map_err(|e| crate::error::BlockchainError::Storage(format!("Database error: {}", e)))? {
+        match self.db.get(Namespace::State, key.as_bytes())? {
             Some(value) => {
                 if value.len() == 16 {
                     let mut bytes = [0u8; 16];
@@ -85,18 +667,65 @@ impl<'a> StateStore<'a> {
         }
     }
 
+    /// Every stored balance
+    pub fn balances(&self) -> crate::error::BlockchainResult<Vec<(crate::types::Address, u128)>> {
+        let mut balances = Vec::new();
+        for (address, value) in self.scan_accounts(b"balance:")? {
+            if let Ok(bytes) = <[u8; 16]>::try_from(value.as_slice()) {
+                balances.push((address, u128::from_le_bytes(bytes)));
+            }
+        }
+        Ok(balances)
+    }
+
+    /// Every stored nonce
+    pub fn nonces(&self) -> crate::error::BlockchainResult<Vec<(crate::types::Address, u64)>> {
+        let mut nonces = Vec::new();
+        for (address, value) in self.scan_accounts(b"nonce:")? {
+            if let Ok(bytes) = <[u8; 8]>::try_from(value.as_slice()) {
+                nonces.push((address, u64::from_le_bytes(bytes)));
+            }
+        }
+        Ok(nonces)
+    }
+
+    /// Stage the removal of every stored balance and nonce
+    pub fn stage_clear(&self, batch: &mut WriteBatch) -> crate::error::BlockchainResult<()> {
+        for prefix in [b"balance:".as_slice(), b"nonce:".as_slice()] {
+            for item in self.db.scan_prefix(Namespace::State, prefix) {
+                let (key, _) = item?;
+                batch.remove(Namespace::State, key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Entries under `prefix` + hex address, skipping malformed keys
+    fn scan_accounts(&self, prefix: &[u8]) -> crate::error::BlockchainResult<Vec<(crate::types::Address, Vec<u8>)>> {
+        let mut entries = Vec::new();
+        for item in self.db.scan_prefix(Namespace::State, prefix) {
+            let (key, value) = item?;
+            let address = hex::decode(&key[prefix.len()..]).ok()
+                .and_then(|bytes| <crate::types::Address>::try_from(bytes.as_slice()).ok());
+            if let Some(address) = address {
+                entries.push((address, value));
+            }
+        }
+        Ok(entries)
+    }
+
     /// Store nonce for an address
     pub fn put_nonce(&self, address: &crate::types::Address, nonce: u64) -> Result<(), String> {
         let key = format!("nonce:{}", hex::encode(address));
         let value = nonce.to_le_bytes().to_vec();
-        self.db.db.insert(key.as_bytes(), value).map_err(|e| format!("Database error: {}", e))?;
+        self.db.put(Namespace::State, key.as_bytes(), value).map_err(|e| e.to_string())?;
         Ok(())
     }
 
     /// Get nonce for an address
     pub fn get_nonce(&self, address: &crate::types::Address) -> Result<Option<u64>, String> {
         let key = format!("nonce:{}", hex::encode(address));
-        match self.db.db.get(key.as_bytes()).map_err(|e| format!("Database error: {}", e))? {
+        match self.db.get(Namespace::State, key.as_bytes()).map_err(|e| e.to_string())? {
             Some(value) => {
                 if value.len() == 8 {
                     let mut bytes = [0u8; 8];
@@ -111,3 +740,340 @@ impl<'a> StateStore<'a> {
     }
 }
 
+/// Fairness history store
+///
+/// Records are keyed by `fairness:` + big-endian block number + block hash so
+/// ordered keys give block-number range scans.
+pub struct FairnessStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> FairnessStore<'a> {
+    const PREFIX: &'static [u8] = b"fairness:";
+
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn key(block_number: u64, block_hash: &Hash) -> Vec<u8> {
+        let mut key = Vec::with_capacity(Self::PREFIX.len() + 8 + 32);
+        key.extend_from_slice(Self::PREFIX);
+        key.extend_from_slice(&block_number.to_be_bytes());
+        key.extend_from_slice(block_hash);
+        key
+    }
+
+    /// Store the fairness record for a block
+    pub fn put(&self, record: &crate::mining::fairness::FairnessRecord) -> crate::error::BlockchainResult<()> {
+        let key = Self::key(record.block_number, &record.block_hash);
+        let value = bincode::serialize(record)?;
+        self.db.put(Namespace::Fairness, key, value)?;
+        Ok(())
+    }
+
+    /// Stage the fairness record for a block in a write batch
+    pub fn stage(batch: &mut WriteBatch, record: &crate::mining::fairness::FairnessRecord) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::Fairness, Self::key(record.block_number, &record.block_hash), bincode::serialize(record)?);
+        Ok(())
+    }
+
+    /// Get the fairness record for a block
+    pub fn get(&self, block_number: u64, block_hash: &Hash) -> crate::error::BlockchainResult<Option<crate::mining::fairness::FairnessRecord>> {
+        match self.db.get(Namespace::Fairness, Self::key(block_number, block_hash))? {
+            Some(value) => Ok(Some(bincode::deserialize(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get fairness records from `start` (block number, hash) up to block `to`,
+    /// at most `limit` records
+    pub fn range(&self, start: (u64, Hash), to: u64, limit: usize) -> crate::error::BlockchainResult<Vec<crate::mining::fairness::FairnessRecord>> {
+        let start = Self::key(start.0, &start.1);
+        let end = Self::key(to, &[0xffu8; 32]);
+        let mut records = Vec::new();
+        for item in self.db.scan_range(Namespace::Fairness, start, end).take(limit) {
+            let (_, value) = item?;
+            records.push(bincode::deserialize(&value)?);
+        }
+        Ok(records)
+    }
+}
+
+/// Cross-shard journal store
+///
+/// Holds queued cross-shard transactions, receipt records, the receipt
+/// blocks each source shard emitted and pooled same-shard transactions, so the
+/// receipt protocol and shard pools resume after a restart. Receipt blocks are keyed by shard and big-endian block number
+/// so they are replayed in order.
+pub struct CrossShardStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> CrossShardStore<'a> {
+    const TX_PREFIX: &'static [u8] = b"xshard:tx:";
+    const RECEIPT_PREFIX: &'static [u8] = b"xshard:receipt:";
+    const OUTCOME_PREFIX: &'static [u8] = b"xshard:outcome:";
+    const BLOCK_PREFIX: &'static [u8] = b"xshard:block:";
+    const ACCOUNT_PREFIX: &'static [u8] = b"xshard:account:";
+    const CONTRACT_PREFIX: &'static [u8] = b"xshard:code:";
+    const FEES_PREFIX: &'static [u8] = b"xshard:fees:";
+    const POOL_PREFIX: &'static [u8] = b"xshard:pool:";
+    const GENESIS_KEY: &'static [u8] = b"xshard:meta:genesis";
+    const RING_KEY: &'static [u8] = b"xshard:meta:ring";
+    const RESHARD_KEY: &'static [u8] = b"xshard:meta:reshard";
+
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn prefixed(prefix: &[u8], suffix: &[u8]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(prefix.len() + suffix.len());
+        key.extend_from_slice(prefix);
+        key.extend_from_slice(suffix);
+        key
+    }
+
+    fn insert<T: serde::Serialize>(&self, key: Vec<u8>, value: &T) -> crate::error::BlockchainResult<()> {
+        let value = bincode::serialize(value)?;
+        self.db.put(Namespace::CrossShard, key, value)?;
+        Ok(())
+    }
+
+    fn scan<T: serde::de::DeserializeOwned>(&self, prefix: &[u8]) -> crate::error::BlockchainResult<Vec<T>> {
+        let mut values = Vec::new();
+        for item in self.db.scan_prefix(Namespace::CrossShard, prefix) {
+            let (_, value) = item?;
+            values.push(bincode::deserialize(&value)?);
+        }
+        Ok(values)
+    }
+
+    /// Store a value in a versioned envelope (blocks and transactions)
+    fn insert_record<T: schema::Record>(&self, key: Vec<u8>, value: &T) -> crate::error::BlockchainResult<()> {
+        self.db.put(Namespace::CrossShard, key, schema::encode(value)?)?;
+        Ok(())
+    }
+
+    fn scan_records<T: schema::Record>(&self, prefix: &[u8]) -> crate::error::BlockchainResult<Vec<T>> {
+        let mut values = Vec::new();
+        for item in self.db.scan_prefix(Namespace::CrossShard, prefix) {
+            let (_, value) = item?;
+            values.push(schema::decode(&value)?);
+        }
+        Ok(values)
+    }
+
+    /// Store a queued (or finished) cross-shard transaction
+    pub fn put_transaction(&self, cross_tx: &crate::sharding::CrossShardTransaction) -> crate::error::BlockchainResult<()> {
+        self.insert_record(Self::prefixed(Self::TX_PREFIX, &cross_tx.id), cross_tx)
+    }
+
+    pub fn get_transactions(&self) -> crate::error::BlockchainResult<Vec<crate::sharding::CrossShardTransaction>> {
+        self.scan_records(Self::TX_PREFIX)
+    }
+
+    /// Store a receipt journal record
+    pub fn put_receipt(&self, record: &crate::sharding::receipts::ReceiptRecord) -> crate::error::BlockchainResult<()> {
+        self.insert(Self::prefixed(Self::RECEIPT_PREFIX, &record.receipt.tx_hash), record)
+    }
+
+    pub fn get_receipts(&self) -> crate::error::BlockchainResult<Vec<crate::sharding::receipts::ReceiptRecord>> {
+        self.scan(Self::RECEIPT_PREFIX)
+    }
+
+    /// Store the outcome of a cross-shard contract call
+    pub fn put_outcome(&self, record: &crate::sharding::messages::OutcomeRecord) -> crate::error::BlockchainResult<()> {
+        self.insert(Self::prefixed(Self::OUTCOME_PREFIX, &record.outcome.tx_hash), record)
+    }
+
+    pub fn get_outcomes(&self) -> crate::error::BlockchainResult<Vec<crate::sharding::messages::OutcomeRecord>> {
+        self.scan(Self::OUTCOME_PREFIX)
+    }
+
+    /// Store a block of a shard's DAG
+    pub fn put_block(&self, shard_id: usize, block: &Block) -> crate::error::BlockchainResult<()> {
+        let mut suffix = Vec::with_capacity(8 + 8 + 32);
+        suffix.extend_from_slice(&(shard_id as u64).to_be_bytes());
+        suffix.extend_from_slice(&block.header.block_number.to_be_bytes());
+        suffix.extend_from_slice(&block.hash);
+        self.insert_record(Self::prefixed(Self::BLOCK_PREFIX, &suffix), block)
+    }
+
+    /// Blocks of one shard in block-number order
+    pub fn get_blocks(&self, shard_id: usize) -> crate::error::BlockchainResult<Vec<Block>> {
+        self.scan_records(&Self::prefixed(Self::BLOCK_PREFIX, &(shard_id as u64).to_be_bytes()))
+    }
+
+    fn account_key(prefix: &[u8], shard_id: usize, address: &crate::types::Address) -> Vec<u8> {
+        let mut suffix = Vec::with_capacity(8 + 20);
+        suffix.extend_from_slice(&(shard_id as u64).to_be_bytes());
+        suffix.extend_from_slice(address);
+        Self::prefixed(prefix, &suffix)
+    }
+
+    /// Store an account of a shard's state partition as (address, balance, nonce)
+    pub fn put_account(&self, shard_id: usize, address: &crate::types::Address, balance: u128, nonce: u64) -> crate::error::BlockchainResult<()> {
+        self.insert(Self::account_key(Self::ACCOUNT_PREFIX, shard_id, address), &(*address, balance, nonce))
+    }
+
+    /// Stage an account of a shard's state partition in a write batch
+    pub fn stage_account(batch: &mut WriteBatch, shard_id: usize, address: &crate::types::Address, balance: u128, nonce: u64) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::CrossShard, Self::account_key(Self::ACCOUNT_PREFIX, shard_id, address), bincode::serialize(&(*address, balance, nonce))?);
+        Ok(())
+    }
+
+    /// Accounts of one shard's state partition
+    pub fn get_accounts(&self, shard_id: usize) -> crate::error::BlockchainResult<Vec<(crate::types::Address, u128, u64)>> {
+        self.scan(&Self::prefixed(Self::ACCOUNT_PREFIX, &(shard_id as u64).to_be_bytes()))
+    }
+
+    /// Stage the removal of an account that migrated away from a shard (and of
+    /// its contract code) in a write batch
+    pub fn stage_delete_account(batch: &mut WriteBatch, shard_id: usize, address: &crate::types::Address) {
+        batch.remove(Namespace::CrossShard, Self::account_key(Self::ACCOUNT_PREFIX, shard_id, address));
+        batch.remove(Namespace::CrossShard, Self::account_key(Self::CONTRACT_PREFIX, shard_id, address));
+    }
+
+    /// Store the code of a contract deployed on a shard
+    pub fn put_contract(&self, shard_id: usize, address: &crate::types::Address, code: &[u8]) -> crate::error::BlockchainResult<()> {
+        self.insert(Self::account_key(Self::CONTRACT_PREFIX, shard_id, address), &(*address, code.to_vec()))
+    }
+
+    /// Stage the code of a contract deployed on a shard in a write batch
+    pub fn stage_contract(batch: &mut WriteBatch, shard_id: usize, address: &crate::types::Address, code: &[u8]) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::CrossShard, Self::account_key(Self::CONTRACT_PREFIX, shard_id, address), bincode::serialize(&(*address, code.to_vec()))?);
+        Ok(())
+    }
+
+    /// Contracts of one shard's state partition as (address, code)
+    pub fn get_contracts(&self, shard_id: usize) -> crate::error::BlockchainResult<Vec<(crate::types::Address, Vec<u8>)>> {
+        self.scan(&Self::prefixed(Self::CONTRACT_PREFIX, &(shard_id as u64).to_be_bytes()))
+    }
+
+    /// Stage the removal of every block, account, contract and fee pool of a
+    /// shard (it was dropped by a reshard)
+    pub fn stage_delete_shard(&self, batch: &mut WriteBatch, shard_id: usize) -> crate::error::BlockchainResult<()> {
+        for prefix in [Self::BLOCK_PREFIX, Self::ACCOUNT_PREFIX, Self::CONTRACT_PREFIX, Self::FEES_PREFIX] {
+            let prefix = Self::prefixed(prefix, &(shard_id as u64).to_be_bytes());
+            for item in self.db.scan_prefix(Namespace::CrossShard, &prefix) {
+                let (key, _) = item?;
+                batch.remove(Namespace::CrossShard, key.to_vec());
+            }
+        }
+        Ok(())
+    }
+
+    /// Store a shard's fee pool
+    pub fn put_fees(&self, shard_id: usize, fees: &crate::sharding::ShardFees) -> crate::error::BlockchainResult<()> {
+        self.insert(Self::prefixed(Self::FEES_PREFIX, &(shard_id as u64).to_be_bytes()), &(shard_id, fees))
+    }
+
+    /// Stage a shard's fee pool in a write batch
+    pub fn stage_fees(batch: &mut WriteBatch, shard_id: usize, fees: &crate::sharding::ShardFees) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::CrossShard, Self::prefixed(Self::FEES_PREFIX, &(shard_id as u64).to_be_bytes()), bincode::serialize(&(shard_id, fees))?);
+        Ok(())
+    }
+
+    pub fn get_fees(&self) -> crate::error::BlockchainResult<Vec<(usize, crate::sharding::ShardFees)>> {
+        self.scan(Self::FEES_PREFIX)
+    }
+
+    /// Store a pooled same-shard transaction (keyed by hash: its shard follows
+    /// the ring when restored)
+    pub fn put_pool_transaction(&self, tx: &crate::blockchain::Transaction) -> crate::error::BlockchainResult<()> {
+        self.insert_record(Self::prefixed(Self::POOL_PREFIX, &tx.hash), tx)
+    }
+
+    /// Remove pooled transactions (mined or stale)
+    pub fn delete_pool_transactions(&self, hashes: &[crate::types::Hash]) -> crate::error::BlockchainResult<()> {
+        for hash in hashes {
+            self.db.delete(Namespace::CrossShard, Self::prefixed(Self::POOL_PREFIX, hash))?;
+        }
+        Ok(())
+    }
+
+    pub fn get_pool_transactions(&self) -> crate::error::BlockchainResult<Vec<crate::blockchain::Transaction>> {
+        self.scan_records(Self::POOL_PREFIX)
+    }
+
+    /// Store the sharding parameters the database was created with
+    pub fn put_genesis(&self, genesis: &crate::sharding::ShardGenesis) -> crate::error::BlockchainResult<()> {
+        self.insert(Self::GENESIS_KEY.to_vec(), genesis)
+    }
+
+    pub fn get_genesis(&self) -> crate::error::BlockchainResult<Option<crate::sharding::ShardGenesis>> {
+        self.get(Self::GENESIS_KEY)
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, key: &[u8]) -> crate::error::BlockchainResult<Option<T>> {
+        match self.db.get(Namespace::CrossShard, key)? {
+            Some(value) => Ok(Some(bincode::deserialize(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store the active shard assignment ring
+    pub fn put_ring(&self, ring: &crate::sharding::ring::HashRing) -> crate::error::BlockchainResult<()> {
+        self.insert(Self::RING_KEY.to_vec(), ring)
+    }
+
+    /// Stage the active shard assignment ring in a write batch
+    pub fn stage_ring(batch: &mut WriteBatch, ring: &crate::sharding::ring::HashRing) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::CrossShard, Self::RING_KEY.to_vec(), bincode::serialize(ring)?);
+        Ok(())
+    }
+
+    pub fn get_ring(&self) -> crate::error::BlockchainResult<Option<crate::sharding::ring::HashRing>> {
+        self.get(Self::RING_KEY)
+    }
+
+    /// Store the scheduled reshard (None clears it)
+    pub fn put_reshard_plan(&self, plan: Option<&crate::sharding::reshard::ReshardPlan>) -> crate::error::BlockchainResult<()> {
+        self.insert(Self::RESHARD_KEY.to_vec(), &plan)
+    }
+
+    /// Stage the scheduled reshard in a write batch (None clears it)
+    pub fn stage_reshard_plan(batch: &mut WriteBatch, plan: Option<&crate::sharding::reshard::ReshardPlan>) -> crate::error::BlockchainResult<()> {
+        batch.insert(Namespace::CrossShard, Self::RESHARD_KEY.to_vec(), bincode::serialize(&plan)?);
+        Ok(())
+    }
+
+    pub fn get_reshard_plan(&self) -> crate::error::BlockchainResult<Option<crate::sharding::reshard::ReshardPlan>> {
+        Ok(self.get::<Option<crate::sharding::reshard::ReshardPlan>>(Self::RESHARD_KEY)?.flatten())
+    }
+}
+
+/// Mining pool ledger store
+///
+/// One ledger per pool coinbase, so switching payout keys starts a new one.
+pub struct PoolStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> PoolStore<'a> {
+    const LEDGER_PREFIX: &'static [u8] = b"pool:ledger:";
+
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn key(address: &crate::types::Address) -> Vec<u8> {
+        let mut key = Vec::with_capacity(Self::LEDGER_PREFIX.len() + address.len());
+        key.extend_from_slice(Self::LEDGER_PREFIX);
+        key.extend_from_slice(address);
+        key
+    }
+
+    /// Store the ledger of the pool mining to `address`
+    pub fn put_ledger(&self, address: &crate::types::Address, ledger: &crate::mining::pool::PoolLedger) -> crate::error::BlockchainResult<()> {
+        self.db.put(Namespace::Pool, Self::key(address), bincode::serialize(ledger)?)?;
+        Ok(())
+    }
+
+    pub fn get_ledger(&self, address: &crate::types::Address) -> crate::error::BlockchainResult<Option<crate::mining::pool::PoolLedger>> {
+        match self.db.get(Namespace::Pool, Self::key(address))? {
+            Some(value) => Ok(Some(bincode::deserialize(&value)?)),
+            None => Ok(None),
+        }
+    }
+}
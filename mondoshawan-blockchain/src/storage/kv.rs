@@ -0,0 +1,142 @@
+//! Key-value backend interface
+//!
+//! Stores (`BlockStore`, `StateStore`, ...) are written against
+//! `KeyValueStore`, so the node runs on sled and tests can run on memory.
+//! Keys live in namespaces (sled trees, or separate maps in memory) so block
+//! hashes can no longer collide with prefixed keys such as `balance:`.
+
+use super::memory::MemoryStore;
+use crate::error::BlockchainResult;
+
+/// Column-family style keyspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Namespace {
+    /// Block bodies by hash
+    Blocks,
+    /// Acceptance-order block index, rewards and the tip marker
+    Chain,
+    /// Account balances and nonces
+    State,
+    /// Secondary indexes (height, transaction, address)
+    Indexes,
+    /// Per-block fairness records
+    Fairness,
+    /// Cross-shard journal, shard partitions and pools
+    CrossShard,
+    /// Database-wide metadata
+    Meta,
+    /// Mining pool ledger (pool-operator mode)
+    Pool,
+}
+
+impl Namespace {
+    pub const ALL: [Namespace; 8] = [
+        Namespace::Blocks,
+        Namespace::Chain,
+        Namespace::State,
+        Namespace::Indexes,
+        Namespace::Fairness,
+        Namespace::CrossShard,
+        Namespace::Meta,
+        Namespace::Pool,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Namespace::Blocks => "blocks",
+            Namespace::Chain => "chain",
+            Namespace::State => "state",
+            Namespace::Indexes => "indexes",
+            Namespace::Fairness => "fairness",
+            Namespace::CrossShard => "cross_shard",
+            Namespace::Meta => "meta",
+            Namespace::Pool => "pool",
+        }
+    }
+
+    /// Position in `ALL`
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Namespace of a key written by a release that kept everything in one keyspace
+    pub fn for_legacy_key(key: &[u8]) -> Namespace {
+        if key.starts_with(b"chain:") {
+            Namespace::Chain
+        } else if key.starts_with(b"balance:") || key.starts_with(b"nonce:") {
+            Namespace::State
+        } else if key.starts_with(b"idx:") {
+            Namespace::Indexes
+        } else if key.starts_with(b"fairness:") {
+            Namespace::Fairness
+        } else if key.starts_with(b"xshard:") {
+            Namespace::CrossShard
+        } else {
+            Namespace::Blocks
+        }
+    }
+}
+
+/// Key-value pairs of a scan, in key order
+pub type KvIter<'a> = Box<dyn DoubleEndedIterator<Item = BlockchainResult<(Vec<u8>, Vec<u8>)>> + 'a>;
+
+/// Storage backend
+pub trait KeyValueStore: Send + Sync {
+    fn get(&self, namespace: Namespace, key: &[u8]) -> BlockchainResult<Option<Vec<u8>>>;
+
+    fn put(&self, namespace: Namespace, key: &[u8], value: &[u8]) -> BlockchainResult<()>;
+
+    fn delete(&self, namespace: Namespace, key: &[u8]) -> BlockchainResult<()>;
+
+    /// Apply every write of the batch or none of them
+    fn apply(&self, batch: WriteBatch) -> BlockchainResult<()>;
+
+    /// Entries whose key starts with `prefix` (an empty prefix scans the namespace)
+    fn scan_prefix<'a>(&'a self, namespace: Namespace, prefix: &[u8]) -> KvIter<'a>;
+
+    /// Entries with keys in `start..=end`
+    fn scan_range<'a>(&'a self, namespace: Namespace, start: &[u8], end: &[u8]) -> KvIter<'a>;
+
+    /// Consistent in-memory copy of every namespace
+    fn snapshot(&self) -> BlockchainResult<MemoryStore>;
+
+    /// Make completed writes durable
+    fn flush(&self) -> BlockchainResult<()>;
+}
+
+/// One staged write; `None` removes the key
+pub type BatchOp = (Namespace, Vec<u8>, Option<Vec<u8>>);
+
+/// Writes applied atomically with `KeyValueStore::apply`
+#[derive(Debug, Default)]
+pub struct WriteBatch {
+    ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, namespace: Namespace, key: Vec<u8>, value: Vec<u8>) {
+        self.ops.push((namespace, key, Some(value)));
+    }
+
+    pub fn remove(&mut self, namespace: Namespace, key: Vec<u8>) {
+        self.ops.push((namespace, key, None));
+    }
+
+    /// Writes staged so far (a key written twice counts twice)
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Staged writes in order
+    pub fn into_ops(self) -> Vec<BatchOp> {
+        self.ops
+    }
+}
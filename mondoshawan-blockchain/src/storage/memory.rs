@@ -0,0 +1,136 @@
+//! In-memory backend (tests, snapshots)
+
+use super::kv::{KeyValueStore, KvIter, Namespace, WriteBatch};
+use crate::error::BlockchainResult;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+type Tables = Vec<BTreeMap<Vec<u8>, Vec<u8>>>;
+
+/// Ordered maps, one per namespace
+#[derive(Debug)]
+pub struct MemoryStore {
+    tables: RwLock<Tables>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self {
+            tables: RwLock::new(vec![BTreeMap::new(); Namespace::ALL.len()]),
+        }
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Tables> {
+        self.tables.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Tables> {
+        self.tables.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Entries stored in a namespace
+    pub fn len(&self, namespace: Namespace) -> usize {
+        self.read()[namespace.index()].len()
+    }
+}
+
+impl KeyValueStore for MemoryStore {
+    fn get(&self, namespace: Namespace, key: &[u8]) -> BlockchainResult<Option<Vec<u8>>> {
+        Ok(self.read()[namespace.index()].get(key).cloned())
+    }
+
+    fn put(&self, namespace: Namespace, key: &[u8], value: &[u8]) -> BlockchainResult<()> {
+        self.write()[namespace.index()].insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, namespace: Namespace, key: &[u8]) -> BlockchainResult<()> {
+        self.write()[namespace.index()].remove(key);
+        Ok(())
+    }
+
+    fn apply(&self, batch: WriteBatch) -> BlockchainResult<()> {
+        let mut tables = self.write();
+        for (namespace, key, value) in batch.into_ops() {
+            let table = &mut tables[namespace.index()];
+            match value {
+                Some(value) => {
+                    table.insert(key, value);
+                }
+                None => {
+                    table.remove(&key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn scan_prefix<'a>(&'a self, namespace: Namespace, prefix: &[u8]) -> KvIter<'a> {
+        let entries: Vec<_> = self.read()[namespace.index()]
+            .range(prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| Ok((key.clone(), value.clone())))
+            .collect();
+        Box::new(entries.into_iter())
+    }
+
+    fn scan_range<'a>(&'a self, namespace: Namespace, start: &[u8], end: &[u8]) -> KvIter<'a> {
+        if start > end {
+            return Box::new(std::iter::empty());
+        }
+        let entries: Vec<_> = self.read()[namespace.index()]
+            .range(start.to_vec()..=end.to_vec())
+            .map(|(key, value)| Ok((key.clone(), value.clone())))
+            .collect();
+        Box::new(entries.into_iter())
+    }
+
+    fn snapshot(&self) -> BlockchainResult<MemoryStore> {
+        Ok(MemoryStore {
+            tables: RwLock::new(self.read().clone()),
+        })
+    }
+
+    fn flush(&self) -> BlockchainResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaces_batches_and_snapshots() {
+        let store = MemoryStore::new();
+        let hash = [7u8; 32];
+        store.put(Namespace::Blocks, &hash, b"block").unwrap();
+        store.put(Namespace::State, b"balance:01", b"1").unwrap();
+        store.put(Namespace::State, b"balance:02", b"2").unwrap();
+        store.put(Namespace::State, b"nonce:01", b"3").unwrap();
+        // The same key in another namespace does not collide
+        store.put(Namespace::Chain, &hash, b"other").unwrap();
+        assert_eq!(store.get(Namespace::Blocks, &hash).unwrap(), Some(b"block".to_vec()));
+
+        let balances: Vec<_> = store.scan_prefix(Namespace::State, b"balance:").map(|item| item.unwrap().1).collect();
+        assert_eq!(balances, vec![b"1".to_vec(), b"2".to_vec()]);
+        let newest = store.scan_prefix(Namespace::State, b"balance:").next_back().unwrap().unwrap();
+        assert_eq!(newest.0, b"balance:02".to_vec());
+
+        let snapshot = store.snapshot().unwrap();
+        let mut batch = WriteBatch::new();
+        batch.remove(Namespace::Blocks, hash.to_vec());
+        batch.insert(Namespace::Meta, b"version".to_vec(), vec![1]);
+        store.apply(batch).unwrap();
+        assert_eq!(store.get(Namespace::Blocks, &hash).unwrap(), None);
+        assert_eq!(store.len(Namespace::Meta), 1);
+        assert_eq!(snapshot.get(Namespace::Blocks, &hash).unwrap(), Some(b"block".to_vec()));
+        assert_eq!(snapshot.len(Namespace::Meta), 0);
+    }
+}
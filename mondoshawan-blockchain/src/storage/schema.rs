@@ -0,0 +1,478 @@
+//! On-disk schema versioning and migrations
+//!
+//! The database records its schema version under `schema_version` in the meta
+//! namespace. Records that hold blocks or transactions are stored in an
+//! envelope, a big-endian u16 record version followed by the bincode payload,
+//! so a type can change its encoding and still decode older records through
+//! `Record::upgrade`. Changes that need every record rewritten bump
+//! `SCHEMA_VERSION` and register a step in `MIGRATIONS`, run when the
+//! database is opened.
+//!
+//! Version 1 stored raw bincode and skipped unset optional transaction
+//! fields, so records could only be decoded while every optional field of
+//! every transaction was unset. Its block headers have the legacy layout,
+//! decoded through the frozen `BlockHeaderV1` as header version 1. Version 2
+//! writes every field in envelopes. Version 3 numbers address index entries
+//! per address; the old entries are dropped and rebuilt on the next start.
+
+use super::{Database, KeyValueStore, Namespace, WriteBatch};
+use crate::blockchain::{Block, BlockHeader, Transaction};
+use crate::error::{BlockchainError, BlockchainResult};
+use crate::sharding::{CrossShardStatus, CrossShardTransaction};
+use crate::types::{Address, Hash, StreamType};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::Path;
+
+/// Schema version written by this release
+pub const SCHEMA_VERSION: u32 = 3;
+
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// A type stored in a versioned envelope
+pub trait Record: Serialize + DeserializeOwned {
+    /// Record version written by this release
+    const VERSION: u16;
+
+    /// Decode a payload written at an older record version
+    fn upgrade(version: u16, _payload: &[u8]) -> BlockchainResult<Self> {
+        Err(BlockchainError::Serialization(format!(
+            "Unsupported {} record version {} (expected {})",
+            std::any::type_name::<Self>(), version, Self::VERSION
+        )))
+    }
+}
+
+impl Record for Block {
+    const VERSION: u16 = 1;
+}
+
+impl Record for Transaction {
+    const VERSION: u16 = 1;
+}
+
+impl Record for CrossShardTransaction {
+    const VERSION: u16 = 1;
+}
+
+/// Encode a record in its envelope
+pub fn encode<T: Record>(value: &T) -> BlockchainResult<Vec<u8>> {
+    let mut bytes = T::VERSION.to_be_bytes().to_vec();
+    bincode::serialize_into(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+/// Decode an enveloped record, upgrading older record versions
+pub fn decode<T: Record>(bytes: &[u8]) -> BlockchainResult<T> {
+    if bytes.len() < 2 {
+        return Err(BlockchainError::Serialization("Record envelope is truncated".to_string()));
+    }
+    let version = u16::from_be_bytes([bytes[0], bytes[1]]);
+    if version == T::VERSION {
+        Ok(bincode::deserialize(&bytes[2..])?)
+    } else {
+        T::upgrade(version, &bytes[2..])
+    }
+}
+
+/// Schema version stored in the database (None if it was never stamped)
+pub fn stored_version(db: &Database) -> BlockchainResult<Option<u32>> {
+    match db.get(Namespace::Meta, SCHEMA_VERSION_KEY)? {
+        Some(value) if value.len() == 4 => Ok(Some(u32::from_be_bytes([value[0], value[1], value[2], value[3]]))),
+        Some(_) => Err(BlockchainError::Storage("Stored schema version is corrupt".to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Schema version `migrate` would upgrade from, without writing anything
+pub fn effective_version(db: &Database) -> BlockchainResult<u32> {
+    match stored_version(db)? {
+        Some(version) => Ok(version),
+        None if is_empty(db)? => Ok(SCHEMA_VERSION),
+        None => Ok(1),
+    }
+}
+
+fn stage_version(batch: &mut WriteBatch, version: u32) {
+    batch.insert(Namespace::Meta, SCHEMA_VERSION_KEY.to_vec(), version.to_be_bytes().to_vec());
+}
+
+/// Upgrade from `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    /// Stage the rewritten records; returns the records rewritten
+    pub run: fn(&Database, &mut WriteBatch) -> BlockchainResult<usize>,
+}
+
+/// Registered migrations, in version order
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "Store blocks and transactions in versioned envelopes with every field written",
+        run: envelope_records,
+    },
+    Migration {
+        from: 2,
+        description: "Drop the secondary indexes so addresses are reindexed with per-address counters",
+        run: drop_indexes,
+    },
+];
+
+/// How `migrate` runs
+#[derive(Debug, Clone, Default)]
+pub struct MigrationOptions<'a> {
+    /// Report the pending migrations without writing anything
+    pub dry_run: bool,
+    /// Copy the database here before the first migration
+    pub backup: Option<&'a Path>,
+}
+
+/// Outcome of `migrate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    /// (description, records rewritten) of each migration run
+    pub steps: Vec<(&'static str, usize)>,
+    pub dry_run: bool,
+}
+
+/// Bring the database to `SCHEMA_VERSION`
+///
+/// A database without a version is stamped with the current version when it
+/// holds no chain data and treated as version 1 otherwise. Each migration is
+/// committed in one batch with its version bump, so an interrupted upgrade
+/// resumes at the failed step.
+pub fn migrate(db: &Database, options: &MigrationOptions) -> BlockchainResult<MigrationReport> {
+    let from = effective_version(db)?;
+    if from == SCHEMA_VERSION && !options.dry_run && stored_version(db)?.is_none() {
+        let mut batch = WriteBatch::new();
+        stage_version(&mut batch, SCHEMA_VERSION);
+        db.apply(batch)?;
+    }
+    if from > SCHEMA_VERSION {
+        return Err(BlockchainError::Storage(format!(
+            "Database schema version {} is newer than this release supports ({})", from, SCHEMA_VERSION
+        )));
+    }
+
+    let mut report = MigrationReport { from, to: SCHEMA_VERSION, steps: Vec::new(), dry_run: options.dry_run };
+    if from == SCHEMA_VERSION {
+        return Ok(report);
+    }
+    if let (Some(path), false) = (options.backup, options.dry_run) {
+        backup(db, path)?;
+    }
+    for version in from..SCHEMA_VERSION {
+        let migration = MIGRATIONS.iter().find(|migration| migration.from == version).ok_or_else(|| {
+            BlockchainError::Storage(format!("No migration from schema version {}", version))
+        })?;
+        let mut batch = WriteBatch::new();
+        let rewritten = (migration.run)(db, &mut batch)?;
+        if !options.dry_run {
+            stage_version(&mut batch, version + 1);
+            db.apply(batch)?;
+        }
+        report.steps.push((migration.description, rewritten));
+    }
+    if !options.dry_run {
+        db.flush()?;
+    }
+    Ok(report)
+}
+
+fn is_empty(db: &Database) -> BlockchainResult<bool> {
+    for namespace in Namespace::ALL {
+        if namespace != Namespace::Meta && db.scan_prefix(namespace, b"").next().transpose()?.is_some() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Copy every namespace into a new sled database at `path`
+///
+/// An existing backup is kept: it was taken by an interrupted upgrade from the
+/// same version.
+fn backup(db: &Database, path: &Path) -> BlockchainResult<()> {
+    if path.exists() {
+        eprintln!("Keeping the existing database backup at {}", path.display());
+        return Ok(());
+    }
+    let target = super::SledStore::open(path)?;
+    for namespace in Namespace::ALL {
+        let mut batch = WriteBatch::new();
+        for item in db.scan_prefix(namespace, b"") {
+            let (key, value) = item?;
+            batch.insert(namespace, key, value);
+        }
+        target.apply(batch)?;
+    }
+    target.flush()?;
+    eprintln!("Backed up the database to {}", path.display());
+    Ok(())
+}
+
+/// Transaction as written by schema version 1 with every optional field unset
+/// (unset fields were skipped)
+#[derive(Deserialize)]
+struct TransactionV1 {
+    from: Address,
+    to: Address,
+    value: u128,
+    fee: u128,
+    nonce: u64,
+    data: Vec<u8>,
+    gas_limit: u64,
+    hash: Hash,
+    signature: Vec<u8>,
+    public_key: Vec<u8>,
+}
+
+impl From<TransactionV1> for Transaction {
+    fn from(v1: TransactionV1) -> Self {
+        let mut tx = Transaction::new(v1.from, v1.to, v1.value, v1.fee, v1.nonce);
+        tx.data = v1.data;
+        tx.gas_limit = v1.gas_limit;
+        tx.hash = v1.hash;
+        tx.signature = v1.signature;
+        tx.public_key = v1.public_key;
+        tx
+    }
+}
+
+/// Block header as written by schema version 1, frozen at that layout
+#[derive(Serialize, Deserialize)]
+struct BlockHeaderV1 {
+    parent_hashes: Vec<Hash>,
+    block_number: u64,
+    stream_type: StreamType,
+    difficulty: u64,
+    timestamp: u64,
+}
+
+impl From<BlockHeaderV1> for BlockHeader {
+    fn from(v1: BlockHeaderV1) -> Self {
+        let mut header = BlockHeader::new(v1.parent_hashes, v1.block_number, v1.stream_type, v1.difficulty);
+        header.version = 1;
+        header.ordering_policy = None;
+        header.timestamp = v1.timestamp;
+        header
+    }
+}
+
+/// Block as written by schema version 1, with transactions as `TransactionV1`
+/// (every optional field unset) or `Transaction` (every field set)
+#[derive(Deserialize)]
+struct BlockV1<T> {
+    header: BlockHeaderV1,
+    transactions: Vec<T>,
+    hash: Hash,
+}
+
+#[derive(Deserialize)]
+struct CrossShardTransactionV1 {
+    tx: TransactionV1,
+    source_shard: usize,
+    target_shard: usize,
+    status: CrossShardStatus,
+    id: Hash,
+    callback: Option<Address>,
+}
+
+/// Decode a version 1 record: first as `V`, written with every optional
+/// transaction field unset, then as `W`, written with every field set
+fn decode_v1<T, V, W>(bytes: &[u8], valid: impl Fn(&T) -> bool) -> Option<T>
+where
+    V: DeserializeOwned + Into<T>,
+    W: DeserializeOwned + Into<T>,
+{
+    bincode::deserialize::<V>(bytes).ok().map(Into::into).filter(&valid)
+        .or_else(|| bincode::deserialize::<W>(bytes).ok().map(Into::into).filter(&valid))
+}
+
+impl<T: Into<Transaction>> From<BlockV1<T>> for Block {
+    fn from(v1: BlockV1<T>) -> Self {
+        Block {
+            header: v1.header.into(),
+            transactions: v1.transactions.into_iter().map(Into::into).collect(),
+            hash: v1.hash,
+        }
+    }
+}
+
+impl From<CrossShardTransactionV1> for CrossShardTransaction {
+    fn from(v1: CrossShardTransactionV1) -> Self {
+        CrossShardTransaction {
+            tx: v1.tx.into(),
+            source_shard: v1.source_shard,
+            target_shard: v1.target_shard,
+            status: v1.status,
+            id: v1.id,
+            callback: v1.callback,
+        }
+    }
+}
+
+fn block_is_intact(block: &Block) -> bool {
+    block.hash == block.calculate_hash() && block.transactions.iter().all(|tx| tx.hash == tx.calculate_hash())
+}
+
+/// Schema 1 -> 2: re-encode block bodies and cross-shard blocks, queued
+/// transactions and pooled transactions
+fn envelope_records(db: &Database, batch: &mut WriteBatch) -> BlockchainResult<usize> {
+    let mut rewritten = 0;
+    let mut undecodable = Vec::new();
+
+    for item in db.scan_prefix(Namespace::Blocks, b"") {
+        let (key, value) = item?;
+        if key.len() != 32 {
+            continue;
+        }
+        match decode_v1::<Block, BlockV1<TransactionV1>, BlockV1<Transaction>>(&value, |block| block_is_intact(block) && block.hash.as_slice() == key.as_slice()) {
+            Some(block) => {
+                batch.insert(Namespace::Blocks, key, encode(&block)?);
+                rewritten += 1;
+            }
+            None => undecodable.push(key),
+        }
+    }
+    for item in db.scan_prefix(Namespace::CrossShard, b"xshard:block:") {
+        let (key, value) = item?;
+        match decode_v1::<Block, BlockV1<TransactionV1>, BlockV1<Transaction>>(&value, block_is_intact) {
+            Some(block) => {
+                batch.insert(Namespace::CrossShard, key, encode(&block)?);
+                rewritten += 1;
+            }
+            None => undecodable.push(key),
+        }
+    }
+    for item in db.scan_prefix(Namespace::CrossShard, b"xshard:tx:") {
+        let (key, value) = item?;
+        match decode_v1::<CrossShardTransaction, CrossShardTransactionV1, CrossShardTransaction>(&value, |cross_tx| cross_tx.tx.hash == cross_tx.tx.calculate_hash()) {
+            Some(cross_tx) => {
+                batch.insert(Namespace::CrossShard, key, encode(&cross_tx)?);
+                rewritten += 1;
+            }
+            None => undecodable.push(key),
+        }
+    }
+    for item in db.scan_prefix(Namespace::CrossShard, b"xshard:pool:") {
+        let (key, value) = item?;
+        match decode_v1::<Transaction, TransactionV1, Transaction>(&value, |tx| tx.hash == tx.calculate_hash()) {
+            Some(tx) => {
+                batch.insert(Namespace::CrossShard, key, encode(&tx)?);
+                rewritten += 1;
+            }
+            None => undecodable.push(key),
+        }
+    }
+
+    if let Some(first) = undecodable.first() {
+        return Err(BlockchainError::Storage(format!(
+            "{} record(s) cannot be decoded as schema version 1 (first key 0x{})",
+            undecodable.len(), hex::encode(first)
+        )));
+    }
+    Ok(rewritten)
+}
+
+/// Schema 2 -> 3: remove every secondary index entry; the chain is
+/// reindexed when it is loaded with no indexed blocks
+fn drop_indexes(db: &Database, batch: &mut WriteBatch) -> BlockchainResult<usize> {
+    let mut removed = 0;
+    for item in db.scan_prefix(Namespace::Indexes, b"idx:") {
+        let (key, _) = item?;
+        batch.remove(Namespace::Indexes, key);
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bincode of a transaction with unset optional fields as schema 1 wrote it
+    #[derive(Serialize)]
+    struct WrittenTransactionV1<'a> {
+        from: Address,
+        to: Address,
+        value: u128,
+        fee: u128,
+        nonce: u64,
+        data: &'a [u8],
+        gas_limit: u64,
+        hash: Hash,
+        signature: &'a [u8],
+        public_key: &'a [u8],
+    }
+
+    #[derive(Serialize)]
+    struct WrittenBlockV1<'a> {
+        header: BlockHeaderV1,
+        transactions: Vec<WrittenTransactionV1<'a>>,
+        hash: Hash,
+    }
+
+    fn write_v1(block: &Block) -> Vec<u8> {
+        let transactions = block.transactions.iter().map(|tx| WrittenTransactionV1 {
+            from: tx.from,
+            to: tx.to,
+            value: tx.value,
+            fee: tx.fee,
+            nonce: tx.nonce,
+            data: &tx.data,
+            gas_limit: tx.gas_limit,
+            hash: tx.hash,
+            signature: &tx.signature,
+            public_key: &tx.public_key,
+        }).collect();
+        let header = BlockHeaderV1 {
+            parent_hashes: block.header.parent_hashes.clone(),
+            block_number: block.header.block_number,
+            stream_type: block.header.stream_type,
+            difficulty: block.header.difficulty,
+            timestamp: block.header.timestamp,
+        };
+        bincode::serialize(&WrittenBlockV1 { header, transactions, hash: block.hash }).unwrap()
+    }
+
+    #[test]
+    fn test_version_1_records_are_migrated() {
+        let db = Database::in_memory();
+        let tx = Transaction::new([1u8; 20], [2u8; 20], 5, 1, 0);
+        let mut header = BlockHeader::new(vec![], 0, StreamType::StreamA, 4);
+        header.version = 1;
+        header.ordering_policy = None;
+        let block = Block::new(header, vec![tx.clone()], vec![]);
+        db.put(Namespace::Blocks, block.hash, write_v1(&block)).unwrap();
+        assert_eq!(stored_version(&db).unwrap(), None);
+
+        let report = migrate(&db, &MigrationOptions { dry_run: true, backup: None }).unwrap();
+        assert_eq!((report.from, report.steps[0].1), (1, 1));
+        assert_eq!(stored_version(&db).unwrap(), None);
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let backup_path = temp_dir.path().join("backup");
+        let report = migrate(&db, &MigrationOptions { dry_run: false, backup: Some(&backup_path) }).unwrap();
+        assert_eq!(report.to, SCHEMA_VERSION);
+        assert_eq!(stored_version(&db).unwrap(), Some(SCHEMA_VERSION));
+        let stored: Block = decode(&db.get(Namespace::Blocks, block.hash).unwrap().unwrap()).unwrap();
+        assert_eq!(stored.transactions[0].hash, tx.hash);
+        assert_eq!((stored.header.version, stored.hash), (1, block.hash));
+        // The backup holds the records as they were
+        // sled releases its file lock once its background flusher exits
+        let backup = (0..50)
+            .find_map(|_| crate::storage::SledStore::open(&backup_path).ok()
+                .or_else(|| { std::thread::sleep(std::time::Duration::from_millis(20)); None }))
+            .unwrap();
+        assert_eq!(backup.get(Namespace::Blocks, &block.hash).unwrap(), Some(write_v1(&block)));
+
+        // Optional fields now survive a round trip
+        let mut sponsored = tx;
+        sponsored.sponsor = Some([9u8; 20]);
+        assert_eq!(decode::<Transaction>(&encode(&sponsored).unwrap()).unwrap().sponsor, Some([9u8; 20]));
+        assert!(decode::<Block>(&[0, 9, 1]).is_err());
+    }
+}
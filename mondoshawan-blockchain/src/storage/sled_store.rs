@@ -0,0 +1,190 @@
+//! sled backend
+//!
+//! Each namespace is a sled tree. Batches spanning trees are applied in one
+//! multi-tree transaction.
+
+use super::kv::{KeyValueStore, KvIter, Namespace, WriteBatch};
+use super::memory::MemoryStore;
+use crate::error::{BlockchainError, BlockchainResult};
+use std::path::Path;
+use std::sync::RwLock;
+
+fn storage_error(e: sled::Error) -> BlockchainError {
+    BlockchainError::Storage(format!("Database error: {}", e))
+}
+
+pub struct SledStore {
+    db: sled::Db,
+    /// Trees in `Namespace::ALL` order
+    trees: Vec<sled::Tree>,
+    /// Shared by writers, held exclusively while a snapshot is copied
+    write_lock: RwLock<()>,
+}
+
+impl SledStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> BlockchainResult<Self> {
+        let store = Self::open_unsplit(path)?;
+        store.split_default_tree()?;
+        Ok(store)
+    }
+
+    /// Open without moving legacy keys out of the default tree
+    pub fn open_unsplit<P: AsRef<Path>>(path: P) -> BlockchainResult<Self> {
+        let db = sled::open(path)
+            .map_err(|e| BlockchainError::Storage(format!("Failed to open database: {}", e)))?;
+        let trees = Namespace::ALL.iter()
+            .map(|namespace| db.open_tree(namespace.name()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(storage_error)?;
+        Ok(Self { db, trees, write_lock: RwLock::new(()) })
+    }
+
+    /// Keys left in the default tree by older releases
+    pub fn legacy_key_count(&self) -> usize {
+        self.db.len()
+    }
+
+    fn tree(&self, namespace: Namespace) -> &sled::Tree {
+        &self.trees[namespace.index()]
+    }
+
+    /// Move keys left in the default tree by older releases into their namespaces
+    ///
+    /// Each key is copied before it is removed, so an interrupted move resumes
+    /// on the next open.
+    fn split_default_tree(&self) -> BlockchainResult<()> {
+        let mut moved = 0usize;
+        for item in self.db.iter() {
+            let (key, value) = item.map_err(storage_error)?;
+            self.tree(Namespace::for_legacy_key(&key)).insert(&key, value).map_err(storage_error)?;
+            self.db.remove(&key).map_err(storage_error)?;
+            moved += 1;
+        }
+        if moved > 0 {
+            self.db.flush().map_err(storage_error)?;
+            eprintln!("Moved {} legacy key(s) into storage namespaces", moved);
+        }
+        Ok(())
+    }
+
+    fn write_guard(&self) -> std::sync::RwLockReadGuard<'_, ()> {
+        self.write_lock.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl KeyValueStore for SledStore {
+    fn get(&self, namespace: Namespace, key: &[u8]) -> BlockchainResult<Option<Vec<u8>>> {
+        Ok(self.tree(namespace).get(key).map_err(storage_error)?.map(|value| value.to_vec()))
+    }
+
+    fn put(&self, namespace: Namespace, key: &[u8], value: &[u8]) -> BlockchainResult<()> {
+        let _guard = self.write_guard();
+        self.tree(namespace).insert(key, value).map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn delete(&self, namespace: Namespace, key: &[u8]) -> BlockchainResult<()> {
+        let _guard = self.write_guard();
+        self.tree(namespace).remove(key).map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn apply(&self, batch: WriteBatch) -> BlockchainResult<()> {
+        use sled::Transactional;
+        use sled::transaction::ConflictableTransactionResult;
+
+        let mut batches: Vec<Option<sled::Batch>> = self.trees.iter().map(|_| None).collect();
+        for (namespace, key, value) in batch.into_ops() {
+            let tree_batch = batches[namespace.index()].get_or_insert_with(sled::Batch::default);
+            match value {
+                Some(value) => tree_batch.insert(key, value),
+                None => tree_batch.remove(key),
+            }
+        }
+
+        let _guard = self.write_guard();
+        self.trees.as_slice()
+            .transaction(|trees| -> ConflictableTransactionResult<(), sled::Error> {
+                for (index, tree_batch) in batches.iter().enumerate() {
+                    if let Some(tree_batch) = tree_batch {
+                        trees[index].apply_batch(tree_batch)?;
+                    }
+                }
+                Ok(())
+            })
+            .map_err(|e| BlockchainError::Storage(format!("Database error: {:?}", e)))?;
+        Ok(())
+    }
+
+    fn scan_prefix<'a>(&'a self, namespace: Namespace, prefix: &[u8]) -> KvIter<'a> {
+        Box::new(self.tree(namespace).scan_prefix(prefix).map(|item| {
+            item.map(|(key, value)| (key.to_vec(), value.to_vec())).map_err(storage_error)
+        }))
+    }
+
+    fn scan_range<'a>(&'a self, namespace: Namespace, start: &[u8], end: &[u8]) -> KvIter<'a> {
+        if start > end {
+            return Box::new(std::iter::empty());
+        }
+        Box::new(self.tree(namespace).range(start.to_vec()..=end.to_vec()).map(|item| {
+            item.map(|(key, value)| (key.to_vec(), value.to_vec())).map_err(storage_error)
+        }))
+    }
+
+    fn snapshot(&self) -> BlockchainResult<MemoryStore> {
+        let _exclusive = self.write_lock.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let snapshot = MemoryStore::new();
+        let mut batch = WriteBatch::new();
+        for namespace in Namespace::ALL {
+            for item in self.tree(namespace).iter() {
+                let (key, value) = item.map_err(storage_error)?;
+                batch.insert(namespace, key.to_vec(), value.to_vec());
+            }
+        }
+        snapshot.apply(batch)?;
+        Ok(snapshot)
+    }
+
+    fn flush(&self) -> BlockchainResult<()> {
+        self.db.flush().map_err(storage_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_keys_move_into_namespaces() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("chain.db");
+        let hash = [9u8; 32];
+        {
+            let db = sled::open(&path).unwrap();
+            db.insert(hash, b"block".to_vec()).unwrap();
+            db.insert(b"balance:01", b"1".to_vec()).unwrap();
+            db.insert(b"chain:tip", b"tip".to_vec()).unwrap();
+            db.flush().unwrap();
+        }
+
+        // Opening for inspection leaves them in place
+        assert!(crate::storage::Database::open_existing_unmigrated(&path).is_err());
+        assert_eq!(SledStore::open_unsplit(&path).unwrap().legacy_key_count(), 3);
+
+        let store = SledStore::open(&path).unwrap();
+        assert_eq!(store.legacy_key_count(), 0);
+        assert_eq!(store.get(Namespace::Blocks, &hash).unwrap(), Some(b"block".to_vec()));
+        assert_eq!(store.get(Namespace::State, b"balance:01").unwrap(), Some(b"1".to_vec()));
+        assert_eq!(store.get(Namespace::Chain, b"chain:tip").unwrap(), Some(b"tip".to_vec()));
+        assert_eq!(store.get(Namespace::Blocks, b"balance:01").unwrap(), None);
+
+        // A batch spanning namespaces lands in both trees
+        let mut batch = WriteBatch::new();
+        batch.remove(Namespace::Blocks, hash.to_vec());
+        batch.insert(Namespace::Meta, b"version".to_vec(), vec![1]);
+        store.apply(batch).unwrap();
+        assert_eq!(store.get(Namespace::Blocks, &hash).unwrap(), None);
+        assert_eq!(store.snapshot().unwrap().get(Namespace::Meta, b"version").unwrap(), Some(vec![1]));
+    }
+}
@@ -302,6 +302,33 @@ impl VerkleState {
     pub fn size(&self) -> usize {
         self.tree.size()
     }
+    
+    /// Accounts as (address, balance, nonce), sorted by address
+    pub fn accounts(&self) -> Vec<(Address, u128, u64)> {
+        let mut accounts: Vec<_> = self.cache.iter()
+            .map(|(address, (balance, nonce))| (*address, *balance, *nonce))
+            .collect();
+        accounts.sort();
+        accounts
+    }
+    
+    /// Rebuild the tree from the live accounts
+    ///
+    /// The tree counts every write, so accounts updated many times inflate
+    /// `size`. The rebuilt tree holds one entry per account and has the same
+    /// root. Returns the entries released.
+    pub fn compact(&mut self) -> usize {
+        let before = self.tree.size();
+        let mut tree = VerkleTree::new();
+        for (address, (balance, nonce)) in &self.cache {
+            let mut value = Vec::with_capacity(24);
+            value.extend_from_slice(&balance.to_le_bytes());
+            value.extend_from_slice(&nonce.to_le_bytes());
+            tree.insert(address, value);
+        }
+        self.tree = tree;
+        before.saturating_sub(self.tree.size())
+    }
 }
 
 impl Default for VerkleState {
@@ -338,6 +365,22 @@ mod tests {
         assert_eq!(state.get_balance(address), 1000); // Balance preserved
     }
     
+    #[test]
+    fn test_compact_keeps_root() {
+        let mut state = VerkleState::new();
+        for (index, address) in [[1u8; 20], [2u8; 20]].into_iter().enumerate() {
+            state.set_balance(address, 1000 + index as u128);
+            state.set_nonce(address, 1);
+            state.set_nonce(address, 2);
+        }
+        let root = state.state_root();
+        
+        assert_eq!(state.compact(), 4);
+        assert_eq!(state.size(), 2);
+        assert_eq!(state.state_root(), root);
+        assert_eq!(state.get_nonce([2u8; 20]), 2);
+    }
+    
     #[test]
     fn test_proof_generation() {
         let mut state = VerkleState::new();
@@ -5,8 +5,10 @@
 use mondoshawan_blockchain::sharding::{
     ShardManager, ShardConfig, AssignmentStrategy, CrossShardStatus,
 };
-use mondoshawan_blockchain::blockchain::Transaction;
-use mondoshawan_blockchain::types::Address;
+use mondoshawan_blockchain::sharding::beacon::ShardCommitment;
+use mondoshawan_blockchain::sharding::messages::OutcomeStatus;
+use mondoshawan_blockchain::blockchain::{Block, BlockHeader, Transaction};
+use mondoshawan_blockchain::types::{Address, StreamType};
 
 /// Test shard creation
 #[tokio::test]
@@ -318,3 +320,440 @@ async fn test_sharding_disabled() {
     let shard_txs = manager.get_shard_transactions(from_shard, 10).await;
     assert!(shard_txs.len() >= 1);
 }
+
+/// Find a receiver that lives on a different shard than the sender
+fn cross_shard_pair(manager: &ShardManager) -> (Address, Address) {
+    let sender: Address = [1u8; 20];
+    let sender_shard = manager.get_shard_for_address(&sender);
+    let receiver = (2u8..=255)
+        .map(|b| [b; 20])
+        .find(|addr| manager.get_shard_for_address(addr) != sender_shard)
+        .unwrap();
+    (sender, receiver)
+}
+
+async fn shard_balance(manager: &ShardManager, shard_id: usize, address: Address) -> u128 {
+    let shard = manager.get_shard(shard_id).unwrap();
+    let shard = shard.read().await;
+    let balance = shard.blockchain.read().await.get_balance(address);
+    balance
+}
+
+/// Test receipt-based lock and commit across shards
+#[tokio::test]
+async fn test_cross_shard_receipt_commit() {
+    let config = ShardConfig {
+        shard_count: 4,
+        enable_cross_shard: true,
+        assignment_strategy: AssignmentStrategy::ConsistentHashing,
+    };
+    let manager = ShardManager::new(config);
+    let (sender, receiver) = cross_shard_pair(&manager);
+    let source = manager.get_shard_for_address(&sender);
+    let target = manager.get_shard_for_address(&receiver);
+    {
+        let shard = manager.get_shard(source).unwrap();
+        let shard = shard.read().await;
+        shard.blockchain.write().await.set_balance(sender, 1_000).unwrap();
+    }
+
+    let tx = Transaction::new(sender, receiver, 100, 1, 0);
+    let tx_hash = tx.hash;
+    manager.add_transaction(tx).await.unwrap();
+
+    // Queued cross-shard transfers are not handed to miners
+    assert!(manager.remove_shard_transactions(source, 10).await.is_empty());
+
+    let receipts = manager.lock_outgoing(source, 1_000).await.unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(manager.get_cross_shard_status(tx_hash).await, Some(CrossShardStatus::Locked));
+    assert_eq!(shard_balance(&manager, source, sender).await, 899);
+
+    let committed = manager.commit_incoming(target, 1_001).await.unwrap();
+    assert_eq!(committed, vec![tx_hash]);
+    assert_eq!(shard_balance(&manager, target, receiver).await, 100);
+    assert_eq!(manager.get_cross_shard_status(tx_hash).await, Some(CrossShardStatus::Committed));
+
+    // The fee is split on commit (an odd remainder stays with the source)
+    assert_eq!(manager.get_shard_fees(source).cross_shard_source, 1);
+    assert_eq!(manager.get_shard_fees(source).pending, 1);
+    assert_eq!(manager.get_shard_fees(target).cross_shard_target, 0);
+
+    // A committed receipt is never refunded
+    assert!(manager.abort_expired(u64::MAX).await.unwrap().is_empty());
+}
+
+/// Test refund of receipts that expire before the target commits them
+#[tokio::test]
+async fn test_cross_shard_receipt_timeout_refund() {
+    let config = ShardConfig {
+        shard_count: 4,
+        enable_cross_shard: true,
+        assignment_strategy: AssignmentStrategy::ConsistentHashing,
+    };
+    let manager = ShardManager::new(config).with_receipt_timeout(60);
+    let (sender, receiver) = cross_shard_pair(&manager);
+    let source = manager.get_shard_for_address(&sender);
+    let target = manager.get_shard_for_address(&receiver);
+    {
+        let shard = manager.get_shard(source).unwrap();
+        let shard = shard.read().await;
+        shard.blockchain.write().await.set_balance(sender, 1_000).unwrap();
+    }
+
+    let tx = Transaction::new(sender, receiver, 100, 1, 0);
+    let tx_hash = tx.hash;
+    manager.add_transaction(tx).await.unwrap();
+    manager.lock_outgoing(source, 1_000).await.unwrap();
+
+    // Past the timeout the target can no longer commit; the source refunds
+    assert!(manager.commit_incoming(target, 1_060).await.unwrap().is_empty());
+    assert_eq!(manager.abort_expired(1_060).await.unwrap(), vec![tx_hash]);
+    assert_eq!(shard_balance(&manager, source, sender).await, 1_000);
+    assert_eq!(shard_balance(&manager, target, receiver).await, 0);
+    assert_eq!(manager.get_cross_shard_status(tx_hash).await, Some(CrossShardStatus::Refunded));
+}
+
+/// Deploy a contract on `shard_id`, picking a deployer nonce whose contract address the shard owns
+async fn deploy_on_shard(manager: &ShardManager, shard_id: usize, deployer: Address) -> Address {
+    let shard = manager.get_shard(shard_id).unwrap();
+    let shard = shard.read().await;
+    let blockchain = shard.blockchain.read().await;
+    let executor = blockchain.evm_executor().unwrap();
+    for nonce in 0.. {
+        let (address, _) = executor.deploy_contract(deployer, vec![0x60, 0x00], 0, 100_000, nonce, 0, 0).unwrap();
+        if manager.get_shard_for_address(&address) == shard_id {
+            return address;
+        }
+    }
+    unreachable!()
+}
+
+/// Test asynchronous contract calls across shards with outcome callbacks
+#[tokio::test]
+async fn test_cross_shard_contract_call() {
+    let config = ShardConfig {
+        shard_count: 2,
+        enable_cross_shard: true,
+        assignment_strategy: AssignmentStrategy::ConsistentHashing,
+    };
+    let manager = ShardManager::new(config);
+    let caller = deploy_on_shard(&manager, 0, [9u8; 20]).await;
+    let token = deploy_on_shard(&manager, 1, [9u8; 20]).await;
+    let not_a_contract = (2u8..=255)
+        .map(|b| [b; 20])
+        .find(|addr| manager.get_shard_for_address(addr) == 1)
+        .unwrap();
+    manager.set_balance(caller, 1_000).await.unwrap();
+
+    // Successful call: the callee is credited and the caller's callback notified
+    let call = manager.emit_contract_call(caller, token, 100, vec![0xa9, 0x05, 0x9c, 0xbb], 100_000, Some(caller)).await.unwrap();
+    manager.process_cross_shard_round(1_000).await.unwrap();
+    let outcome = manager.get_call_outcome(call).await.unwrap();
+    assert!(outcome.outcome.success);
+    assert_eq!(outcome.status, OutcomeStatus::Delivered);
+    assert_eq!(shard_balance(&manager, 1, token).await, 100);
+    assert_eq!(shard_balance(&manager, 0, caller).await, 900);
+    assert_eq!(manager.get_cross_shard_status(call).await, Some(CrossShardStatus::Committed));
+
+    // Failed call: the value comes back to the caller
+    let call = manager.emit_contract_call(caller, not_a_contract, 50, vec![0x01], 100_000, Some(caller)).await.unwrap();
+    manager.process_cross_shard_round(1_001).await.unwrap();
+    let outcome = manager.get_call_outcome(call).await.unwrap();
+    assert!(!outcome.outcome.success);
+    assert_eq!(outcome.outcome.refund, 50);
+    assert_eq!(shard_balance(&manager, 1, not_a_contract).await, 0);
+    assert_eq!(shard_balance(&manager, 0, caller).await, 900);
+    assert_eq!(manager.get_cross_shard_status(call).await, Some(CrossShardStatus::Refunded));
+
+    // Only contracts emit calls, and only to other shards
+    assert!(manager.emit_contract_call([9u8; 20], token, 0, vec![0x01], 100_000, None).await.is_err());
+    assert!(manager.emit_contract_call(caller, caller, 0, vec![0x01], 100_000, None).await.is_err());
+}
+
+/// Test a contract call emitted over RPC through delivery and callback
+#[tokio::test]
+async fn test_contract_call_over_rpc() {
+    use mondoshawan_blockchain::blockchain::Blockchain;
+    use mondoshawan_blockchain::rpc::{JsonRpcRequest, RpcServer};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    let config = ShardConfig {
+        shard_count: 2,
+        enable_cross_shard: true,
+        assignment_strategy: AssignmentStrategy::ConsistentHashing,
+    };
+    let manager = Arc::new(ShardManager::new(config));
+    let caller = deploy_on_shard(&manager, 0, [9u8; 20]).await;
+    let token = deploy_on_shard(&manager, 1, [9u8; 20]).await;
+    manager.set_balance(caller, 1_000).await.unwrap();
+    let mut server = RpcServer::with_sharding(Arc::new(RwLock::new(Blockchain::new())), manager.clone());
+    let request = |method: &str, params: serde_json::Value| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        method: method.to_string(),
+        params: Some(params),
+        id: Some(json!(1)),
+    };
+    let caller_hex = format!("0x{}", hex::encode(caller));
+    let emit = json!([caller_hex, format!("0x{}", hex::encode(token)), "0x64", "0xa9059cbb", "0x186a0", caller_hex]);
+
+    // Unsigned calls are only served behind an API key
+    let response = server.handle_request(request("mds_emitContractCall", emit.clone()), None, None).await;
+    assert!(response.error.is_some());
+    server.set_api_key("secret".to_string());
+    let response = server.handle_request(request("mds_emitContractCall", emit.clone()), None, None).await;
+    assert!(response.error.is_some());
+
+    let response = server.handle_request(request("mds_emitContractCall", emit), Some("secret"), None).await;
+    let result = response.result.unwrap();
+    assert_eq!(result["sourceShard"], 0);
+    assert_eq!(result["targetShard"], 1);
+    let tx_hash = result["transactionHash"].as_str().unwrap().to_string();
+
+    // One round locks, delivers on the target and reports back to the callback
+    manager.process_cross_shard_round(1_000).await.unwrap();
+    let response = server.handle_request(request("mds_getCrossShardTransaction", json!([tx_hash])), Some("secret"), None).await;
+    let result = response.result.unwrap();
+    assert_eq!(result["status"], "Committed");
+    assert_eq!(result["call"]["callback"], json!(caller_hex));
+    assert_eq!(result["call"]["outcome"]["status"], "Delivered");
+    assert_eq!(result["call"]["outcome"]["success"], true);
+    assert_eq!(shard_balance(&manager, 1, token).await, 100);
+    assert_eq!(shard_balance(&manager, 0, caller).await, 900);
+}
+
+/// Test per-shard block production, state partitions and beacon commitments
+#[tokio::test]
+async fn test_shard_blocks_and_beacon_commitments() {
+    let config = ShardConfig {
+        shard_count: 4,
+        enable_cross_shard: true,
+        assignment_strategy: AssignmentStrategy::ConsistentHashing,
+    };
+    let manager = ShardManager::new(config.clone());
+    let secret = [7u8; 32];
+    let public_key = ed25519_dalek::SigningKey::from_bytes(&secret).verifying_key().to_bytes();
+    let sender = Transaction::derive_address_from_public_key(&public_key);
+    let shard_id = manager.get_shard_for_address(&sender);
+    let receiver = (2u8..=255)
+        .map(|b| [b; 20])
+        .find(|addr| manager.get_shard_for_address(addr) == shard_id)
+        .unwrap();
+    manager.set_balance(sender, 1_000).await.unwrap();
+
+    manager.add_transaction(Transaction::new(sender, receiver, 100, 1, 0).sign(&secret)).await.unwrap();
+    let block = manager.produce_shard_block(shard_id, 10, 1_000_000).await.unwrap().unwrap();
+    assert_eq!(block.transactions.len(), 1);
+    assert!(manager.produce_shard_block(shard_id, 10, 1_000_000).await.unwrap().is_none());
+
+    // The transfer only touched the owning shard's partition and DAG
+    assert_eq!(manager.get_balance(sender).await, 899);
+    assert_eq!(manager.get_balance(receiver).await, 100);
+    for other in (0..4).filter(|id| *id != shard_id) {
+        assert_eq!(manager.get_shard_stats(other).await.unwrap().block_count, 0);
+    }
+
+    // Beacon block commits the shard tip and state root
+    let commitments = manager.shard_commitments().await;
+    assert_eq!(commitments.len(), 1);
+    assert_eq!(commitments[0].block_hash, block.hash);
+    assert!(manager.has_new_commitments(&commitments).await);
+    assert_eq!(manager.get_shard_fees(shard_id).pending, 1);
+    let miner: Address = [0xee; 20];
+    let header = BlockHeader::new(vec![], 0, StreamType::StreamB, 4)
+        .with_shard_commitments(commitments.clone())
+        .with_coinbase(miner);
+    let beacon = Block::new(header, vec![], vec![]);
+    manager.verify_shard_commitments(&beacon).await.unwrap();
+    manager.record_beacon(&beacon).await;
+    assert!(!manager.has_new_commitments(&commitments).await);
+    assert_eq!(manager.get_beacon_commitments().await, commitments);
+
+    // The committed shard's fees are paid to the beacon block's coinbase
+    assert_eq!(manager.get_balance(miner).await, 1);
+    assert_eq!(manager.get_shard_fees(shard_id).pending, 0);
+    assert_eq!(manager.get_shard_fees(shard_id).collected, 1);
+
+    // A beacon block with a wrong state root for a tracked shard is rejected
+    let mut forged = commitments.clone();
+    forged[0].state_root = [0u8; 32];
+    let header = BlockHeader::new(vec![], 1, StreamType::StreamB, 4).with_shard_commitments(forged);
+    assert!(manager.verify_shard_commitments(&Block::new(header, vec![], vec![])).await.is_err());
+
+    // A node validating other shards refuses this shard's transactions
+    let partial = ShardManager::new(config.clone()).with_tracked_shards(vec![(shard_id + 1) % 4]);
+    partial.check_genesis().unwrap();
+    assert!(!partial.is_tracked(shard_id));
+    assert!(partial.add_transaction(Transaction::new(sender, receiver, 1, 1, 1)).await.is_err());
+
+    // Tracking shards that do not exist (or none at all) is refused at startup
+    assert!(ShardManager::new(config.clone()).with_tracked_shards(vec![1, 4]).check_genesis().is_err());
+    assert!(ShardManager::new(config).with_tracked_shards(Vec::new()).check_genesis().is_err());
+}
+
+/// Mine one transfer on every shard so each has a tip for beacon blocks to commit
+async fn mine_on_every_shard(manager: &ShardManager) {
+    for shard_id in 0..manager.shard_count() {
+        let (secret, sender) = (1u8..=255)
+            .map(|b| {
+                let secret = [b; 32];
+                let public_key = ed25519_dalek::SigningKey::from_bytes(&secret).verifying_key().to_bytes();
+                (secret, Transaction::derive_address_from_public_key(&public_key))
+            })
+            .find(|(_, sender)| manager.get_shard_for_address(sender) == shard_id)
+            .unwrap();
+        manager.set_balance(sender, 1_000).await.unwrap();
+        manager.add_transaction(Transaction::new(sender, sender, 1, 1, 0).sign(&secret)).await.unwrap();
+        manager.produce_shard_block(shard_id, 10, 1_000_000).await.unwrap().unwrap();
+    }
+}
+
+fn beacon_block(number: u64, commitments: Vec<ShardCommitment>) -> Block {
+    let header = BlockHeader::new(vec![], number, StreamType::StreamB, 4).with_shard_commitments(commitments);
+    Block::new(header, vec![], vec![])
+}
+
+/// Test epoch-activated resharding migrates only the accounts that change owner
+#[tokio::test]
+async fn test_reshard_at_epoch_boundary() {
+    use mondoshawan_blockchain::storage::Database;
+    use std::sync::Arc;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("shards.db");
+    let config = ShardConfig {
+        shard_count: 4,
+        enable_cross_shard: true,
+        assignment_strategy: AssignmentStrategy::ConsistentHashing,
+    };
+    let database = Arc::new(Database::open(&db_path).unwrap());
+    let manager = ShardManager::with_storage(config.clone(), database).unwrap().with_epoch_length(1);
+    let accounts: Vec<Address> = (0u8..100)
+        .map(|b| {
+            let mut address = [0xaa; 20];
+            address[0] = b;
+            address
+        })
+        .collect();
+    for (i, address) in accounts.iter().enumerate() {
+        manager.set_balance(*address, 1_000 + i as u128).await.unwrap();
+    }
+    let mut contracts = Vec::new();
+    for shard_id in 0..4 {
+        for deployer in 0u8..10 {
+            contracts.push(deploy_on_shard(&manager, shard_id, [0x10 + deployer; 20]).await);
+        }
+    }
+    mine_on_every_shard(&manager).await;
+    let owners_before: Vec<usize> = accounts.iter().map(|a| manager.get_shard_for_address(a)).collect();
+
+    // Growing the ring only moves accounts onto the new shard
+    let preview = manager.preview_reshard(5).await.unwrap();
+    assert!(preview.accounts_total >= 100);
+    assert!(preview.moves.len() < preview.accounts_total / 2);
+    assert!(preview.moves.iter().all(|account| account.to_shard == 4));
+
+    let plan = manager.schedule_reshard(5).await.unwrap();
+    assert_eq!(plan.activation_epoch, 1);
+    assert_eq!(manager.shard_count(), 4);
+
+    // State changed since the commitments does not match their roots: the
+    // reshard moves to the next epoch
+    let commitments = manager.shard_commitments().await;
+    assert_eq!(commitments.len(), 4);
+    let moving = accounts.iter().position(|a| preview.moves.iter().any(|m| &m.address == a)).unwrap();
+    manager.set_balance(accounts[moving], 1).await.unwrap();
+    manager.record_beacon(&beacon_block(0, commitments.clone())).await;
+    assert_eq!(manager.shard_count(), 4);
+    assert_eq!(manager.pending_reshard().unwrap().activation_epoch, 2);
+
+    // Back in line with the committed roots, the next beacon block activates it
+    manager.set_balance(accounts[moving], 1_000 + moving as u128).await.unwrap();
+    manager.record_beacon(&beacon_block(1, commitments)).await;
+    assert_eq!(manager.shard_count(), 5);
+    assert!(manager.pending_reshard().is_none());
+
+    let mut moved = 0;
+    for (i, address) in accounts.iter().enumerate() {
+        assert_eq!(manager.get_balance(*address).await, 1_000 + i as u128);
+        if manager.get_shard_for_address(address) != owners_before[i] {
+            moved += 1;
+        }
+    }
+    assert!(moved > 0);
+
+    // Contract code follows its account
+    let mut contracts_moved = Vec::new();
+    for contract in contracts {
+        let owner = manager.get_shard_for_address(&contract);
+        if owner == 4 {
+            contracts_moved.push(contract);
+        }
+        for shard_id in 0..5 {
+            let shard = manager.get_shard(shard_id).unwrap();
+            let shard = shard.read().await;
+            let code = shard.blockchain.read().await.contract_code(&contract);
+            assert_eq!(code.is_some(), shard_id == owner);
+        }
+    }
+    assert!(!contracts_moved.is_empty());
+
+    // The journaled layout is what a restart restores
+    drop(manager);
+    let manager = ShardManager::with_storage(config, Arc::new(Database::open(&db_path).unwrap())).unwrap();
+    assert_eq!(manager.shard_count(), 5);
+    assert_eq!(manager.get_balance(accounts[moving]).await, 1_000 + moving as u128);
+    let shard = manager.get_shard(4).unwrap();
+    let shard = shard.read().await;
+    let blockchain = shard.blockchain.read().await;
+    assert!(contracts_moved.iter().all(|contract| blockchain.contract_code(contract).is_some()));
+}
+
+/// Test shard pools and sharding parameters survive a restart
+#[tokio::test]
+async fn test_shard_pool_and_genesis_persist() {
+    use mondoshawan_blockchain::storage::Database;
+    use std::sync::Arc;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("shards.db");
+    let config = ShardConfig {
+        shard_count: 4,
+        enable_cross_shard: true,
+        assignment_strategy: AssignmentStrategy::ConsistentHashing,
+    };
+    let sender: Address = [1u8; 20];
+    let shard_id;
+    let tx_hash;
+    {
+        let database = Arc::new(Database::open(&db_path).unwrap());
+        let manager = ShardManager::with_storage(config.clone(), database).unwrap();
+        manager.check_genesis().unwrap();
+        shard_id = manager.get_shard_for_address(&sender);
+        let receiver = (2u8..=255)
+            .map(|b| [b; 20])
+            .find(|addr| manager.get_shard_for_address(addr) == shard_id)
+            .unwrap();
+        let tx = Transaction::new(sender, receiver, 100, 1, 0);
+        tx_hash = tx.hash;
+        manager.add_transaction(tx).await.unwrap();
+    }
+
+    let database = Arc::new(Database::open(&db_path).unwrap());
+    let manager = ShardManager::with_storage(config, database.clone()).unwrap();
+    manager.check_genesis().unwrap();
+    let pooled = manager.get_shard_transactions(shard_id, 10).await;
+    assert_eq!(pooled.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![tx_hash]);
+    drop(manager);
+
+    // A different shard count than the database was created with is refused
+    let resized = ShardConfig {
+        shard_count: 8,
+        enable_cross_shard: true,
+        assignment_strategy: AssignmentStrategy::ConsistentHashing,
+    };
+    let manager = ShardManager::with_storage(resized, database).unwrap();
+    assert!(manager.check_genesis().is_err());
+}